@@ -52,6 +52,33 @@ fn main() -> Result<(), io::Error> {
             "paint-bucket-regular",
             "page-fit-regular",
             "resize-large-regular",
+            "arrow-maximize-regular",
+            "eraser",
+            "select",
+            "color-fill-regular",
+            "layer-diagonal-regular",
+            "border-all-regular",
+            "text-bold-regular",
+            "text-italic-regular",
+            "text-align-left-regular",
+            "text-align-center-regular",
+            "text-align-right-regular",
+            "ruler-regular",
+            "table-regular",
+            "braces-regular",
+            "scan-text-regular",
+            "eyedropper-regular",
+            "re-order-dots-vertical-regular",
+            "zoom-in-regular",
+            "zoom-out-regular",
+            "zoom",
+            "camera-regular",
+            "arrow-previous-regular",
+            "arrow-next-regular",
+            "image-multiple-regular",
+            "column-single-compare-regular",
+            "document-arrow-up-regular",
+            "document-arrow-down-regular",
         ],
     );
 
@@ -1,4 +1,6 @@
-use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -11,13 +13,32 @@ pub struct CommandLine {
     #[arg(short, long)]
     pub filename: Option<String>,
 
+    /// Additional input images as paths or URIs, e.g. as passed by file managers (NEXTRELEASE).
+    /// Images are opened one after another, the next one is shown when exiting the current.
+    /// Ctrl+Page Down and Ctrl+Page Up switch between them, each keeps its annotations. Quoted
+    /// patterns like `'shots/*.png'` are expanded (NEXTRELEASE)
+    #[arg(value_name = "INPUT")]
+    pub inputs: Vec<String>,
+
     /// Start Satty in fullscreen mode
     #[arg(long)]
     pub fullscreen: bool,
 
+    /// Show the image in an overlay covering an output, so annotations are drawn right on the
+    /// screen. Needs gtk4-layer-shell and a compositor with wlr-layer-shell (NEXTRELEASE)
+    #[arg(long)]
+    pub layer_shell: bool,
+
+    /// Connector name of the output the layer shell overlay covers, e.g. `DP-1`. The
+    /// compositor picks one if omitted (NEXTRELEASE)
+    #[arg(long, value_name = "OUTPUT")]
+    pub layer_shell_output: Option<String>,
+
     /// Filename to use for saving action or '-' to print to stdout. Omit to disable saving to file. Might contain format
     /// specifiers: <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>.
-    /// Since 0.20.0, can contain tilde (~) for home dir
+    /// Since 0.20.0, can contain tilde (~) for home dir.
+    /// {cwd} is replaced by the current working directory (NEXTRELEASE)
+    /// {name} is replaced by the name of the input file without extension (NEXTRELEASE)
     #[arg(short, long)]
     pub output_filename: Option<String>,
 
@@ -34,10 +55,31 @@ pub struct CommandLine {
     #[arg(long, value_name = "TOOL", visible_alias = "init-tool")]
     pub initial_tool: Option<Tools>,
 
+    /// Tool used when dragging with the right mouse button, right clicks without dragging
+    /// still trigger the right click actions (NEXTRELEASE)
+    #[arg(long, value_name = "TOOL")]
+    pub secondary_tool: Option<Tools>,
+
     /// Configure the command to be called on copy, for example `wl-copy`
     #[arg(long)]
     pub copy_command: Option<String>,
 
+    /// Shell command the PNG is piped through before it is saved, for example
+    /// `pngquant -`. The unfiltered image is saved if the command fails (NEXTRELEASE)
+    #[arg(long)]
+    pub export_filter_command: Option<String>,
+
+    /// Shell command writing a new screenshot to stdout, e.g. `grim -g "10,20 640x480" -` for
+    /// the region just captured. Ctrl+R runs it and replaces the image, the annotations stay
+    /// in place (NEXTRELEASE)
+    #[arg(long)]
+    pub recapture_command: Option<String>,
+
+    /// Seconds to count down before re-capturing, e.g. to open a menu or hover over something
+    /// that should be in the screenshot (NEXTRELEASE)
+    #[arg(long, value_name = "SECONDS")]
+    pub delay: Option<u32>,
+
     /// Increase or decrease the size of the annotations
     #[arg(long)]
     pub annotation_size_factor: Option<f32>,
@@ -79,14 +121,40 @@ pub struct CommandLine {
     #[arg(long)]
     pub font_style: Option<String>,
 
+    /// Text size in image pixels for text annotations, instead of following the annotation
+    /// size (NEXTRELEASE)
+    #[arg(long)]
+    pub font_size: Option<f32>,
+
     /// The primary highlighter to use, secondary is accessible with CTRL
     #[arg(long)]
     pub primary_highlighter: Option<Highlighters>,
 
+    /// Opacity of highlights, from 0 to 1 (NEXTRELEASE)
+    #[arg(long)]
+    pub highlight_opacity: Option<f32>,
+
+    /// How highlights blend with the image below, multiply keeps dark text dark (NEXTRELEASE)
+    #[arg(long)]
+    pub highlight_blend: Option<HighlightBlend>,
+
+    /// Length of an image pixel in the ruler unit, the ruler tool shows it next to the pixels,
+    /// e.g. 0.2646 for millimeters at 96 dpi (NEXTRELEASE)
+    #[arg(long)]
+    pub ruler_scale: Option<f32>,
+
+    /// Unit of the ruler scale, "mm" by default (NEXTRELEASE)
+    #[arg(long)]
+    pub ruler_unit: Option<String>,
+
     /// Disable notifications
     #[arg(long)]
     pub disable_notifications: bool,
 
+    /// Show a tray icon while running as daemon, to open an image or quit it (NEXTRELEASE)
+    #[arg(long)]
+    pub tray_icon: bool,
+
     /// Print profiling
     #[arg(long)]
     pub profile_startup: bool,
@@ -125,11 +193,128 @@ pub struct CommandLine {
     #[arg(long)]
     pub pan_step_size: Option<f32>,
 
+    /// Maximum size in MiB of images downloaded from http(s) URLs (NEXTRELEASE).
+    /// defaults to 50
+    #[arg(long, value_name = "MIB")]
+    pub max_download_size: Option<u64>,
+
+    /// Center exported images on a canvas of a fixed size (NEXTRELEASE).
+    /// Accepts WIDTHxHEIGHT or one of the presets 720p, 1080p, 1440p and 4k.
+    /// Larger images are scaled down to fit.
+    #[arg(long, value_name = "SIZE")]
+    pub export_canvas: Option<String>,
+
+    /// Split saved images into numbered tiles next to the output filename, with a JSON
+    /// summary listing them (NEXTRELEASE). Accepts COLUMNSxROWS for a grid, e.g. 2x2, or a
+    /// single number for full width slices of at most that height.
+    #[arg(long, value_name = "SPLIT")]
+    pub export_split: Option<String>,
+
+    /// Set a variable for `{name}` placeholders in text annotations, can be given
+    /// multiple times (NEXTRELEASE). Builtin are {date}, {time} and {file}.
+    #[arg(long = "var", value_name = "NAME=VALUE")]
+    pub vars: Vec<String>,
+
     /// Start Satty in daemon mode to reduce subsequent startups time
     /// defaults to falss
     #[arg(long, action)]
     pub daemon: bool,
     // ---
+    /// Install the desktop entry, file manager integration and icon for the current user
+    /// to the XDG data directory and exit (NEXTRELEASE)
+    #[arg(long)]
+    pub install_desktop_files: bool,
+
+    /// Print the effective configuration, merged from defaults, config file and command
+    /// line, as a config file and exit (NEXTRELEASE)
+    #[arg(long)]
+    pub dump_config: bool,
+
+    /// Print the version, protocol version, GL renderer and open sessions of the running
+    /// daemon as JSON and exit, fails if no daemon is running (NEXTRELEASE)
+    #[arg(long)]
+    pub ping: bool,
+
+    /// Look for a newer release and exit, with status 2 if there is one (NEXTRELEASE)
+    #[arg(long)]
+    pub check_update: bool,
+
+    /// Restore the annotations of the last session that did not end normally, e.g. after a
+    /// crash, even onto a different image (NEXTRELEASE)
+    #[arg(long)]
+    pub recover: bool,
+
+    /// Read several PNG images one after another from stdin, e.g. frames of a capture
+    /// script, and switch between them as they arrive (NEXTRELEASE)
+    #[arg(long)]
+    pub stdin_stream: bool,
+
+    /// Compare the two input images side by side, a toolbar button shows a heatmap of their
+    /// differences over the second one (NEXTRELEASE)
+    #[arg(long)]
+    pub diff: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Manage the access tokens of the upload action in the Secret Service (NEXTRELEASE)
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+    /// Print how often each tool and action got used, as counted locally with
+    /// `usage-statistics` (NEXTRELEASE)
+    Stats {
+        /// Delete the counters instead
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Draw the annotations of a script onto an image and save the result without opening
+    /// any window, e.g. to stamp screenshots in CI pipelines (NEXTRELEASE)
+    Render(Render),
+    /// Measure startup, rendering and PNG encoding times without a window and print them as
+    /// JSON, to track performance across versions (NEXTRELEASE)
+    Bench(Bench),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct Render {
+    /// Path to the image or '-' to read from stdin
+    #[arg(long)]
+    pub input: String,
+    /// JSON file with a list of annotations in image coordinates, e.g.
+    /// `[{"type": "arrow", "start": {"x": 10, "y": 10}, "end": {"x": 90, "y": 40}}]`, or
+    /// annotations exported from an image of the same size
+    #[arg(long)]
+    pub script: PathBuf,
+    /// Where the result is saved, the extension picks the format. '-' writes a PNG to stdout
+    #[arg(long)]
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct Bench {
+    /// Path to the image to annotate or '-' to read from stdin, a synthetic 1920×1080 image
+    /// if not given
+    #[arg(long)]
+    pub input: Option<String>,
+    /// Number of synthetic annotations committed and rendered
+    #[arg(long, default_value_t = 1000)]
+    pub annotations: usize,
+    /// How often the annotations are rendered, the fastest, median and slowest run are printed
+    #[arg(long, default_value_t = 5)]
+    pub runs: usize,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum AuthAction {
+    /// Store the token for a service like "gitlab" or "github", read from stdin
+    Set { service: String },
+    /// Remove the stored token of a service
+    Clear { service: String },
 }
 
 #[derive(Debug, Clone, Copy, Default, ValueEnum)]
@@ -146,12 +331,27 @@ pub enum Tools {
     Blur,
     Highlight,
     Brush,
+    Eraser,
+    Select,
+    Ruler,
+    Table,
+    Brace,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum Action {
     SaveToClipboard,
     SaveToFile,
+    /// Copy the text annotations as text (NEXTRELEASE)
+    CopyText,
+    /// Show the content of QR codes and barcodes in the image (NEXTRELEASE)
+    ScanCodes,
+    /// Upload to the project configured in the [upload] section and copy the Markdown link
+    /// (NEXTRELEASE)
+    Upload,
+    /// Save the image without and with the annotations as `…-before` and `…-after` of the
+    /// output filename (NEXTRELEASE)
+    SaveBeforeAfter,
     Exit,
 }
 
@@ -160,6 +360,15 @@ pub enum Highlighters {
     #[default]
     Block,
     Freehand,
+    /// straight strip from the start of the drag to the pointer (NEXTRELEASE)
+    Line,
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum HighlightBlend {
+    #[default]
+    Normal,
+    Multiply,
 }
 
 impl std::fmt::Display for Tools {
@@ -177,6 +386,11 @@ impl std::fmt::Display for Tools {
             Blur => "blur",
             Highlight => "highlight",
             Brush => "brush",
+            Eraser => "eraser",
+            Select => "select",
+            Ruler => "ruler",
+            Table => "table",
+            Brace => "brace",
         };
         f.write_str(s)
     }
@@ -0,0 +1,57 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::tools::Annotation;
+
+/// Annotations exported from an image, in its coordinates. They are only imported onto images
+/// of the same size.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Document {
+    width: i32,
+    height: i32,
+    annotations: Vec<Annotation>,
+}
+
+/// A file as written by [`write`], or a plain list of annotations for any image size as
+/// scripts of `satty render` are
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AnnotationFile {
+    Document(Document),
+    List(Vec<Annotation>),
+}
+
+/// Write `annotations` of an image of the given size as JSON
+pub fn write(path: &Path, (width, height): (i32, i32), annotations: Vec<Annotation>) -> Result<()> {
+    let document = Document {
+        width,
+        height,
+        annotations,
+    };
+    fs::write(path, serde_json::to_vec_pretty(&document)?)
+        .with_context(|| format!("couldn't write '{}'", path.display()))
+}
+
+/// The annotations of the file at `path` for an image of `size`, fails if they were exported
+/// from an image of another size
+pub fn read(path: &Path, size: (i32, i32)) -> Result<Vec<Annotation>> {
+    let data = fs::read(path).with_context(|| format!("couldn't read '{}'", path.display()))?;
+    let file = serde_json::from_slice(&data)
+        .with_context(|| format!("invalid annotations in '{}'", path.display()))?;
+    match file {
+        AnnotationFile::Document(document) if (document.width, document.height) != size => {
+            bail!(
+                "the annotations are for an image of {}×{}, not {}×{}",
+                document.width,
+                document.height,
+                size.0,
+                size.1
+            )
+        }
+        AnnotationFile::Document(document) => Ok(document.annotations),
+        AnnotationFile::List(annotations) => Ok(annotations),
+    }
+}
@@ -0,0 +1,46 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::Result;
+use gdk_pixbuf::Pixbuf;
+use image::RgbaImage;
+
+use crate::{headless::Renderer, tools::Annotation};
+
+/// Draws annotations onto an image without a window, in an OpenGL context of its own. Needs
+/// EGL that can create contexts without a surface, as Mesa's.
+pub struct Annotator {
+    image: Pixbuf,
+    annotations: Vec<Annotation>,
+}
+
+impl Annotator {
+    pub fn new(image: Pixbuf) -> Self {
+        Self {
+            image,
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Draw `annotation` on top of the ones added before
+    pub fn with_annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    pub fn extend(mut self, annotations: impl IntoIterator<Item = Annotation>) -> Self {
+        self.annotations.extend(annotations);
+        self
+    }
+
+    /// The image with the annotations, in its size
+    pub fn render(&self) -> Result<RgbaImage> {
+        let marker_numbers = Rc::new(RefCell::new(1));
+        let drawables: Vec<_> = self
+            .annotations
+            .iter()
+            .cloned()
+            .map(|annotation| annotation.into_drawable(&marker_numbers))
+            .collect();
+        Renderer::new()?.render(&self.image, &drawables)
+    }
+}
@@ -0,0 +1,82 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use anyhow::{anyhow, Result};
+use femtovg::{renderer::OpenGl, Canvas, FontId};
+use fontconfig::Fontconfig;
+use resource::resource;
+
+use crate::{configuration::DRAWING_CONFIG, style::TextFont};
+
+/// Fonts loaded into the canvas. The configured font is loaded upfront, the ones picked
+/// in the style toolbar on first use.
+pub struct Fonts {
+    default: FontId,
+    loaded: RefCell<HashMap<FontKey, FontId>>,
+}
+
+// family and emphasis, the size is set on the paint
+type FontKey = (Option<&'static str>, bool, bool);
+
+impl Fonts {
+    pub fn new(canvas: &mut Canvas<OpenGl>) -> Self {
+        let config = DRAWING_CONFIG.read();
+        let font = config
+            .font_family
+            .as_deref()
+            .map(|family| Self::load(canvas, family, config.font_style.as_deref()))
+            .transpose()
+            .unwrap_or_else(|e| {
+                println!("Error while loading font. Using default font: {e}");
+                None
+            });
+
+        Self {
+            default: font.unwrap_or_else(|| {
+                canvas
+                    .add_font_mem(&resource!("src/assets/Roboto-Regular.ttf"))
+                    .expect("Cannot add font")
+            }),
+            loaded: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The configured font
+    pub fn default_font(&self) -> FontId {
+        self.default
+    }
+
+    /// Font for `font`, falls back to the configured one if it can't be found
+    pub fn get(&self, canvas: &mut Canvas<OpenGl>, font: &TextFont) -> FontId {
+        let style = font.fontconfig_style();
+        if font.family.is_none() && style.is_none() {
+            return self.default;
+        }
+
+        *self
+            .loaded
+            .borrow_mut()
+            .entry((font.family, font.bold, font.italic))
+            .or_insert_with(|| {
+                let config = DRAWING_CONFIG.read();
+                let family = font
+                    .family
+                    .or(config.font_family.as_deref())
+                    .unwrap_or("sans-serif");
+                // failures are remembered as the default font, so they are reported once
+                Self::load(canvas, family, style.or(config.font_style.as_deref())).unwrap_or_else(
+                    |e| {
+                        println!("Error while loading font {family}. Using default font: {e}");
+                        self.default
+                    },
+                )
+            })
+    }
+
+    fn load(canvas: &mut Canvas<OpenGl>, family: &str, style: Option<&str>) -> Result<FontId> {
+        let font = Fontconfig::new()
+            .ok_or_else(|| anyhow!("Error while initializing fontconfig"))?
+            .find(family, style)
+            .ok_or_else(|| anyhow!("Can not find font"))?;
+        Ok(canvas.add_font(font.path)?)
+    }
+}
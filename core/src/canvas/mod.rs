@@ -0,0 +1,35 @@
+//! What drawing on a femtovg canvas needs besides the drawables: fonts, cached stamps and
+//! images larger than a texture
+mod fonts;
+mod stamps;
+mod tiles;
+
+pub use fonts::Fonts;
+pub use stamps::{draw_stamp, prepare_stamps};
+pub use tiles::{max_texture_size, render_tiled, render_to_image, ImageTiles};
+
+use std::sync::OnceLock;
+
+/// `GL_RENDERER` string of the OpenGL context, known once a canvas was set up
+pub static GL_RENDERER: OnceLock<String> = OnceLock::new();
+/// `GL_MAX_TEXTURE_SIZE` of the OpenGL context, known once a canvas was set up
+pub static MAX_TEXTURE_SIZE: OnceLock<u32> = OnceLock::new();
+
+/// Resolve the OpenGL functions through libepoxy, as GTK does, before any canvas is set up
+pub fn load_gl() -> anyhow::Result<()> {
+    #[cfg(target_os = "macos")]
+    let library = unsafe { libloading::os::unix::Library::new("libepoxy.0.dylib") }?;
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let library = unsafe { libloading::os::unix::Library::new("libepoxy.so.0") }?;
+    #[cfg(windows)]
+    let library = libloading::os::windows::Library::open_already_loaded("libepoxy-0.dll")
+        .or_else(|_| libloading::os::windows::Library::open_already_loaded("epoxy-0.dll"))?;
+
+    epoxy::load_with(|name| {
+        unsafe { library.get::<_>(name.as_bytes()) }
+            .map(|symbol| *symbol)
+            .unwrap_or(std::ptr::null())
+    });
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Mutex,
+};
+
+use femtovg::{renderer::OpenGl, Canvas, Color, ImageFlags, ImageId, Paint, Path, PixelFormat};
+
+use crate::math::Vec2D;
+
+/// cached images are all dropped beyond this, zooming renders new ones for the new scale
+const MAX_STAMPS: usize = 256;
+
+type DrawStamp = Box<dyn Fn(&mut Canvas<OpenGl>) + Send>;
+
+/// Images of parts looking the same in many drawables, e.g. the rings of markers. Drawing
+/// an image keeps frame times flat with hundreds of them, tessellating each ring along with
+/// its shadow and outline does not.
+#[derive(Default)]
+struct Stamps {
+    images: HashMap<u64, (ImageId, f32)>,
+    // requested while drawing, rendered into images before the next frame
+    pending: HashMap<u64, (f32, f32, DrawStamp)>,
+}
+
+static STAMPS: Mutex<Option<Stamps>> = Mutex::new(None);
+
+/// Draw a stamp centered at `center`. `draw` paints it around the origin, within `extent`
+/// in each direction, and is only called again if `key` or the zoom changes. The first time
+/// it draws directly, the image is rendered before the next frame.
+pub fn draw_stamp(
+    canvas: &mut Canvas<OpenGl>,
+    key: impl Hash,
+    center: Vec2D,
+    extent: f32,
+    draw: impl Fn(&mut Canvas<OpenGl>) + Send + 'static,
+) {
+    let scale = canvas.transform().average_scale();
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    // the image has the resolution of the screen, a new one is needed after zooming
+    ((scale * 100.0).round() as u32).hash(&mut hasher);
+    let key = hasher.finish();
+
+    let mut stamps = STAMPS.lock().unwrap();
+    let stamps = stamps.get_or_insert_with(Stamps::default);
+    match stamps.images.get(&key) {
+        Some(&(image, extent)) => {
+            let (x, y, size) = (center.x - extent, center.y - extent, 2.0 * extent);
+            let mut path = Path::new();
+            path.rect(x, y, size, size);
+            canvas.fill_path(&path, &Paint::image(image, x, y, size, size, 0.0, 1.0));
+        }
+        None => {
+            canvas.save();
+            canvas.translate(center.x, center.y);
+            draw(canvas);
+            canvas.restore();
+            stamps
+                .pending
+                .entry(key)
+                .or_insert_with(|| (extent, scale, Box::new(draw)));
+        }
+    }
+}
+
+/// Render the stamps requested by the last frame, call before setting the render target
+pub fn prepare_stamps(canvas: &mut Canvas<OpenGl>) {
+    let mut stamps = STAMPS.lock().unwrap();
+    let Some(stamps) = stamps.as_mut() else {
+        return;
+    };
+    if stamps.pending.is_empty() {
+        return;
+    }
+    if stamps.images.len() + stamps.pending.len() > MAX_STAMPS {
+        for (_, (image, _)) in stamps.images.drain() {
+            canvas.delete_image(image);
+        }
+    }
+
+    canvas.save();
+    for (key, (extent, scale, draw)) in stamps.pending.drain() {
+        let size = (2.0 * extent * scale).ceil().max(1.0);
+        // rendered images are upside down and premultiplied
+        let Ok(image) = canvas.create_image_empty(
+            size as usize,
+            size as usize,
+            PixelFormat::Rgba8,
+            ImageFlags::PREMULTIPLIED | ImageFlags::FLIP_Y,
+        ) else {
+            continue;
+        };
+        canvas.set_render_target(femtovg::RenderTarget::Image(image));
+        canvas.clear_rect(
+            0,
+            0,
+            size as u32,
+            size as u32,
+            Color::rgbaf(0.0, 0.0, 0.0, 0.0),
+        );
+        // rounding up the size leaves a bit more room around the stamp
+        let extent = size / scale / 2.0;
+        canvas.reset_transform();
+        canvas.scale(scale, scale);
+        canvas.translate(extent, extent);
+        draw(canvas);
+        stamps.images.insert(key, (image, extent));
+    }
+    canvas.restore();
+    canvas.set_render_target(femtovg::RenderTarget::Screen);
+}
@@ -0,0 +1,263 @@
+use anyhow::Result;
+use femtovg::{
+    imgref::{Img, ImgVec},
+    renderer::OpenGl,
+    rgb::{RGB, RGBA, RGBA8},
+    Canvas, Color, ImageFlags, ImageId, ImageSource, Paint, Path, PixelFormat, RenderTarget,
+    Transform2D,
+};
+use gdk_pixbuf::Pixbuf;
+
+use crate::math::Vec2D;
+
+use super::MAX_TEXTURE_SIZE;
+
+/// `GL_MAX_TEXTURE_SIZE` guaranteed by OpenGL 3, used until a context told its own
+const MIN_MAX_TEXTURE_SIZE: u32 = 1024;
+
+/// tiles of a rendering reach this far into their neighbours, so blurs near their border see
+/// what is next to it, more than the radius of the largest blur of the default sizes
+const TILE_OVERLAP: u32 = 128;
+
+/// Largest width and height of textures and render targets
+pub fn max_texture_size() -> u32 {
+    MAX_TEXTURE_SIZE
+        .get()
+        .copied()
+        .unwrap_or(MIN_MAX_TEXTURE_SIZE)
+}
+
+/// Offsets and sizes of the tiles covering `length`, none larger than the maximum texture size
+fn spans(length: u32) -> impl Iterator<Item = (u32, u32)> {
+    spans_of(length, max_texture_size(), 0).map(|(start, size, _, _)| (start, size))
+}
+
+/// Tiles covering `length` that are at most `max` long and reach `overlap` into their
+/// neighbours, as offset and size of the rendered tile and of the part of it that is kept
+fn spans_of(length: u32, max: u32, overlap: u32) -> impl Iterator<Item = (u32, u32, u32, u32)> {
+    // a tile must keep something
+    let overlap = overlap.min(max / 4);
+    let step = max.saturating_sub(2 * overlap).max(1);
+    (0..length).step_by(step as usize).map(move |start| {
+        let size = step.min(length - start);
+        let rendered = start.saturating_sub(overlap);
+        let end = (start + size + overlap).min(length);
+        (rendered, end - rendered, start - rendered, size)
+    })
+}
+
+/// Upload `image` into a single texture, it must not be larger than the maximum texture size
+fn upload_tile(canvas: &mut Canvas<OpenGl>, image: &Pixbuf) -> Result<ImageId> {
+    let format = if image.has_alpha() {
+        PixelFormat::Rgba8
+    } else {
+        PixelFormat::Rgb8
+    };
+
+    let background_image_id = canvas.create_image_empty(
+        image.width() as usize,
+        image.height() as usize,
+        format,
+        ImageFlags::empty(),
+    )?;
+
+    // extract values
+    let width = image.width() as usize;
+    let stride = image.rowstride() as usize; // stride is in bytes per row
+    let height = image.height() as usize;
+    let bytes_per_pixel = if image.has_alpha() { 4 } else { 3 }; // pixbuf supports rgb or rgba
+
+    unsafe {
+        let src_buffer = image.pixels();
+
+        let row_length = width * bytes_per_pixel;
+        let mut dst_buffer = if row_length == stride {
+            // stride == row_length, there are no additional bytes after the end of each row
+            src_buffer.to_vec()
+        } else {
+            // stride != row_length, there are additional bytes after the end of each row that
+            // need to be truncated. We copy row by row..
+            let mut dst_buffer = Vec::<u8>::with_capacity(width * height * bytes_per_pixel);
+
+            for row in 0..height {
+                let src_offset = row * stride;
+                dst_buffer.extend_from_slice(&src_buffer[src_offset..src_offset + row_length]);
+            }
+            dst_buffer
+        };
+
+        // in almost all cases, that should be a no-op. Buf we might have additional elements after the
+        // end of the buffer, e.g. after width * height * bytes_per_pixel
+        dst_buffer.truncate(width * height * bytes_per_pixel);
+
+        if image.has_alpha() {
+            let img = Img::new_stride(
+                dst_buffer.align_to::<RGBA<u8>>().1.to_vec(),
+                width,
+                height,
+                width,
+            );
+
+            canvas.update_image(background_image_id, ImageSource::Rgba(img.as_ref()), 0, 0)?;
+        } else {
+            let img = Img::new_stride(
+                dst_buffer.align_to::<RGB<u8>>().1.to_owned(),
+                width,
+                height,
+                width,
+            );
+
+            canvas.update_image(background_image_id, ImageSource::Rgb(img.as_ref()), 0, 0)?;
+        }
+    }
+
+    Ok(background_image_id)
+}
+
+/// An image uploaded in tiles, as images larger than the maximum texture size need
+pub struct ImageTiles {
+    // texture, position in the image and size
+    tiles: Vec<(ImageId, Vec2D, Vec2D)>,
+}
+
+impl ImageTiles {
+    pub fn upload(canvas: &mut Canvas<OpenGl>, image: &Pixbuf) -> Result<Self> {
+        let mut tiles = Vec::new();
+        for (y, height) in spans(image.height() as u32) {
+            for (x, width) in spans(image.width() as u32) {
+                let tile = image.new_subpixbuf(x as i32, y as i32, width as i32, height as i32);
+                let id = match upload_tile(canvas, &tile) {
+                    Ok(id) => id,
+                    Err(e) => {
+                        Self { tiles }.delete(canvas);
+                        return Err(e);
+                    }
+                };
+                tiles.push((
+                    id,
+                    Vec2D::new(x as f32, y as f32),
+                    Vec2D::new(width as f32, height as f32),
+                ));
+            }
+        }
+        Ok(Self { tiles })
+    }
+
+    /// Draw the image at the origin in its size, with `alpha`
+    pub fn draw(&self, canvas: &mut Canvas<OpenGl>, alpha: f32) {
+        for &(id, pos, size) in &self.tiles {
+            let mut path = Path::new();
+            path.rect(pos.x, pos.y, size.x, size.y);
+            canvas.fill_path(
+                &path,
+                &Paint::image(id, pos.x, pos.y, size.x, size.y, 0.0, alpha),
+            );
+        }
+    }
+
+    pub fn delete(self, canvas: &mut Canvas<OpenGl>) {
+        for (id, _, _) in self.tiles {
+            canvas.delete_image(id);
+        }
+    }
+}
+
+/// Render `draw` into a new transparent image of `width` × `height` pixels, to be drawn with
+/// [`Paint::image`]. `draw` starts from the identity transform, the canvas renders to the screen
+/// again afterwards with its transform as before.
+pub fn render_to_image(
+    canvas: &mut Canvas<OpenGl>,
+    (width, height): (u32, u32),
+    draw: impl FnOnce(&mut Canvas<OpenGl>) -> Result<()>,
+) -> Result<ImageId> {
+    // rendered images are upside down and premultiplied
+    let image = canvas.create_image_empty(
+        width as usize,
+        height as usize,
+        PixelFormat::Rgba8,
+        ImageFlags::PREMULTIPLIED | ImageFlags::FLIP_Y,
+    )?;
+
+    // what is drawn so far belongs to the screen
+    canvas.flush();
+    canvas.save();
+    canvas.set_render_target(RenderTarget::Image(image));
+    canvas.reset_transform();
+    canvas.clear_rect(0, 0, width, height, Color::rgbaf(0.0, 0.0, 0.0, 0.0));
+    let drawn = draw(canvas);
+    canvas.flush();
+    canvas.set_render_target(RenderTarget::Screen);
+    canvas.restore();
+
+    if let Err(e) = drawn {
+        canvas.delete_image(image);
+        return Err(e);
+    }
+    Ok(image)
+}
+
+/// Render the area at `origin` with `size` into an image, in tiles no larger than the maximum
+/// texture size. `draw` is called for each tile, with the transform set to the area. The tiles
+/// overlap by [`TILE_OVERLAP`], only their inner part is kept.
+pub fn render_tiled(
+    canvas: &mut Canvas<OpenGl>,
+    origin: Vec2D,
+    (width, height): (u32, u32),
+    mut draw: impl FnMut(&mut Canvas<OpenGl>) -> Result<()>,
+) -> Result<ImgVec<RGBA8>> {
+    let mut result = vec![RGBA8::default(); width as usize * height as usize];
+    let max = max_texture_size();
+    for (y, tile_height, skip_y, keep_height) in spans_of(height, max, TILE_OVERLAP) {
+        for (x, tile_width, skip_x, keep_width) in spans_of(width, max, TILE_OVERLAP) {
+            let target = canvas.create_image_empty(
+                tile_width as usize,
+                tile_height as usize,
+                PixelFormat::Rgba8,
+                ImageFlags::empty(),
+            )?;
+            canvas.set_render_target(RenderTarget::Image(target));
+            let mut transform = Transform2D::identity();
+            transform.translate(-origin.x - x as f32, -origin.y - y as f32);
+            canvas.reset_transform();
+            canvas.set_transform(&transform);
+
+            let tile = draw(canvas).and_then(|()| Ok(canvas.screenshot()?));
+            canvas.set_render_target(RenderTarget::Screen);
+            canvas.delete_image(target);
+
+            let tile = tile?;
+            let rows = tile.rows().skip(skip_y as usize).take(keep_height as usize);
+            for (row, pixels) in rows.enumerate() {
+                let pixels = &pixels[skip_x as usize..(skip_x + keep_width) as usize];
+                let start = (y + skip_y) as usize + row;
+                let start = start * width as usize + (x + skip_x) as usize;
+                result[start..start + pixels.len()].copy_from_slice(pixels);
+            }
+        }
+    }
+    Ok(Img::new(result, width as usize, height as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_keep_every_pixel_once() {
+        for (length, max, overlap) in [(0, 1024, 128), (1000, 1024, 128), (5000, 1024, 128)] {
+            let mut next = 0;
+            for (start, size, skip, keep) in spans_of(length, max, overlap) {
+                assert!(size <= max);
+                assert_eq!(start + skip, next);
+                assert!(skip + keep <= size);
+                next += keep;
+            }
+            assert_eq!(next, length);
+        }
+    }
+
+    #[test]
+    fn spans_without_texture_size() {
+        assert_eq!(spans_of(3, 0, 128).count(), 3);
+    }
+}
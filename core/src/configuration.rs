@@ -0,0 +1,155 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    style::Color,
+    tools::{HighlightBlend, Highlighters},
+};
+
+pub static DRAWING_CONFIG: SharedDrawingConfiguration = SharedDrawingConfiguration::new();
+
+/// The drawing options shared by all threads. Reading returns an immutable snapshot, a
+/// replaced configuration is seen by the next read.
+pub struct SharedDrawingConfiguration {
+    current: RwLock<Option<Arc<DrawingConfiguration>>>,
+}
+
+impl SharedDrawingConfiguration {
+    const fn new() -> Self {
+        Self {
+            current: RwLock::new(None),
+        }
+    }
+
+    /// The current options, the built-in defaults until they are replaced
+    pub fn read(&self) -> Arc<DrawingConfiguration> {
+        if let Some(config) = self.current.read().unwrap().as_ref() {
+            return config.clone();
+        }
+        self.current
+            .write()
+            .unwrap()
+            .get_or_insert_with(|| Arc::new(DrawingConfiguration::default()))
+            .clone()
+    }
+
+    pub fn replace(&self, config: DrawingConfiguration) {
+        *self.current.write().unwrap() = Some(Arc::new(config));
+    }
+}
+
+/// How annotations are drawn where their style leaves it open
+#[derive(Debug, Clone)]
+pub struct DrawingConfiguration {
+    pub corner_roundness: f32,
+    pub annotation_size_factor: f32,
+    /// Color of new annotations
+    pub color: Color,
+    pub font_family: Option<String>,
+    pub font_style: Option<String>,
+    /// Text size in image pixels, `None` follows the annotation size
+    pub font_size: Option<f32>,
+    pub fill_shapes: bool,
+    /// Whether annotations get a drop shadow
+    pub shadow: bool,
+    /// Whether annotations get a contrasting outline
+    pub outline: bool,
+    /// Color of the background behind filled text annotations, `None` picks a translucent
+    /// one contrasting with the text
+    pub text_background_color: Option<Color>,
+    pub primary_highlighter: Highlighters,
+    /// Opacity of highlights, from 0 to 1
+    pub highlight_opacity: f32,
+    pub highlight_blend: HighlightBlend,
+    /// Length of an image pixel in `ruler_unit`, the ruler only shows pixels if unset
+    pub ruler_scale: Option<f32>,
+    pub ruler_unit: String,
+    /// Rows of new tables, unless they follow the cell size
+    pub table_rows: u32,
+    /// Columns of new tables, unless they follow the cell size
+    pub table_columns: u32,
+    /// Size of table cells in image pixels, tables get as many cells as fit if set
+    pub table_cell_size: Option<f32>,
+    pub brush_smooth_history_size: usize,
+    /// Values of `{name}` placeholders in texts
+    pub variables: HashMap<String, String>,
+    /// The annotated file, for the `{file}` placeholder
+    pub input_filename: Option<String>,
+}
+
+impl DrawingConfiguration {
+    /// Expand `{name}` placeholders in annotation texts. Variables take precedence over the
+    /// builtin `{date}`, `{time}` and `{file}`, unknown placeholders are kept as they are.
+    pub fn expand_variables<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !text.contains('{') {
+            return Cow::Borrowed(text);
+        }
+
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let placeholder = &rest[start..start + len + 1];
+            match self.variable(&placeholder[1..len]) {
+                Some(value) => result.push_str(&value),
+                None => result.push_str(placeholder),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        result.push_str(rest);
+        Cow::Owned(result)
+    }
+
+    fn variable(&self, name: &str) -> Option<Cow<'_, str>> {
+        if let Some(v) = self.variables.get(name) {
+            return Some(Cow::Borrowed(v));
+        }
+        match name {
+            "date" => Some(Cow::Owned(
+                chrono::Local::now().format("%Y-%m-%d").to_string(),
+            )),
+            "time" => Some(Cow::Owned(chrono::Local::now().format("%H:%M").to_string())),
+            "file" => self
+                .input_filename
+                .as_deref()
+                .and_then(|f| Path::new(f).file_name())
+                .map(|f| f.to_string_lossy()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for DrawingConfiguration {
+    fn default() -> Self {
+        Self {
+            corner_roundness: 12.0,
+            annotation_size_factor: 1.0,
+            color: Color::orange(),
+            font_family: None,
+            font_style: None,
+            font_size: None,
+            fill_shapes: false,
+            shadow: false,
+            outline: false,
+            text_background_color: None,
+            primary_highlighter: Highlighters::Block,
+            highlight_opacity: 0.4,
+            highlight_blend: HighlightBlend::Normal,
+            ruler_scale: None,
+            ruler_unit: "mm".to_string(),
+            table_rows: 3,
+            table_columns: 3,
+            table_cell_size: None,
+            brush_smooth_history_size: 0,
+            variables: HashMap::new(),
+            input_filename: None,
+        }
+    }
+}
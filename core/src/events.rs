@@ -0,0 +1,109 @@
+//! Input the tools handle, as the widget receives it
+use gtk::{
+    self,
+    gdk::{Key, ModifierType},
+};
+use keycode::{KeyMap, KeyMappingId};
+
+use crate::{ime::preedit::PreeditSpan, math::Vec2D};
+
+#[derive(Debug, Clone)]
+pub enum InputEvent {
+    Mouse(MouseEventMsg),
+    Key(KeyEventMsg),
+    KeyRelease(KeyEventMsg),
+    Text(TextEventMsg),
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum MouseButton {
+    Primary,
+    Secondary,
+    Middle,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct KeyEventMsg {
+    pub key: Key,
+    pub code: u32,
+    pub modifier: ModifierType,
+}
+
+#[derive(Debug, Clone)]
+pub enum TextEventMsg {
+    Commit(String),
+    Preedit {
+        text: String,
+        cursor_chars: Option<usize>,
+        spans: Vec<PreeditSpan>,
+    },
+    PreeditEnd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MouseEventType {
+    BeginDrag,
+    EndDrag,
+    UpdateDrag,
+    Click,
+    Scroll,
+    PointerPos,
+    Release,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MouseEventMsg {
+    pub type_: MouseEventType,
+    pub button: MouseButton,
+    pub modifier: ModifierType,
+    pub pos: Vec2D,
+    pub n_pressed: i32,
+    pub release: bool,
+    /// pen pressure between 0.0 and 1.0, only set for input from a stylus
+    pub pressure: Option<f32>,
+}
+
+impl From<u32> for MouseButton {
+    fn from(value: u32) -> Self {
+        match value {
+            gtk::gdk::BUTTON_PRIMARY => MouseButton::Primary,
+            gtk::gdk::BUTTON_MIDDLE => MouseButton::Middle,
+            gtk::gdk::BUTTON_SECONDARY => MouseButton::Secondary,
+            _ => MouseButton::Primary,
+        }
+    }
+}
+
+impl MouseEventMsg {
+    /// Rectangle as (pos, size) spanned by a drag from `origin`, `pos` being relative to it.
+    /// Ctrl draws from the center outwards and Shift constrains it to a square.
+    pub fn drag_rect(&self, origin: Vec2D) -> (Vec2D, Vec2D) {
+        let mut size = self.pos;
+        if self.modifier.contains(ModifierType::SHIFT_MASK) {
+            let max_size = size.x.abs().max(size.y.abs());
+            size = Vec2D::new(max_size * size.x.signum(), max_size * size.y.signum());
+        }
+
+        if self.modifier.contains(ModifierType::CONTROL_MASK) {
+            (origin - size, size * 2.0)
+        } else {
+            (origin, size)
+        }
+    }
+}
+
+impl KeyEventMsg {
+    pub fn new(key: Key, code: u32, modifier: ModifierType) -> Self {
+        Self {
+            key,
+            code,
+            modifier,
+        }
+    }
+
+    /// Whether this is `key`, or the key at the position of `code` on a US layout
+    pub fn is_one_of(&self, key: Key, code: KeyMappingId) -> bool {
+        let keymap = KeyMap::from(code);
+        self.key == key || self.code as u16 - 8 == keymap.evdev
+    }
+}
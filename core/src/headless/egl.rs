@@ -0,0 +1,154 @@
+use std::ffi::{c_int, c_uint, c_void};
+use std::ptr;
+
+use anyhow::{bail, Result};
+
+const LIBRARY: &str = "libEGL.so.1";
+
+// values of the EGL headers
+const PLATFORM_SURFACELESS_MESA: c_uint = 0x31dd;
+const SURFACE_TYPE: c_int = 0x3033;
+const PBUFFER_BIT: c_int = 0x0001;
+const RENDERABLE_TYPE: c_int = 0x3040;
+const OPENGL_BIT: c_int = 0x0008;
+const OPENGL_API: c_uint = 0x30a2;
+const CONTEXT_MAJOR_VERSION: c_int = 0x3098;
+const CONTEXT_MINOR_VERSION: c_int = 0x30fb;
+const CONTEXT_OPENGL_PROFILE_MASK: c_int = 0x30fd;
+const CONTEXT_OPENGL_CORE_PROFILE_BIT: c_int = 0x0001;
+const NONE: c_int = 0x3038;
+
+type Display = *mut c_void;
+type Config = *mut c_void;
+type EglContext = *mut c_void;
+
+type GetPlatformDisplay = unsafe extern "C" fn(c_uint, *mut c_void, *const isize) -> Display;
+type GetDisplay = unsafe extern "C" fn(*mut c_void) -> Display;
+type Initialize = unsafe extern "C" fn(Display, *mut c_int, *mut c_int) -> c_uint;
+type BindApi = unsafe extern "C" fn(c_uint) -> c_uint;
+type ChooseConfig =
+    unsafe extern "C" fn(Display, *const c_int, *mut Config, c_int, *mut c_int) -> c_uint;
+type CreateContext = unsafe extern "C" fn(Display, Config, EglContext, *const c_int) -> EglContext;
+type MakeCurrent = unsafe extern "C" fn(Display, *mut c_void, *mut c_void, EglContext) -> c_uint;
+type DestroyContext = unsafe extern "C" fn(Display, EglContext) -> c_uint;
+type Terminate = unsafe extern "C" fn(Display) -> c_uint;
+type GetError = unsafe extern "C" fn() -> c_int;
+
+/// An OpenGL context current on this thread without any window or surface, rendering goes
+/// to framebuffers only. Needs EGL with surfaceless contexts, as Mesa has them even without
+/// a GPU.
+pub struct Context {
+    library: libloading::Library,
+    display: Display,
+    context: EglContext,
+}
+
+impl Context {
+    pub fn new() -> Result<Self> {
+        let library = unsafe { libloading::Library::new(LIBRARY) }?;
+        unsafe {
+            let display = Self::display(&library)?;
+            if display.is_null()
+                || library.get::<Initialize>(b"eglInitialize")?(
+                    display,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                ) == 0
+            {
+                bail!("no EGL display, {}", Self::error(&library));
+            }
+            if library.get::<BindApi>(b"eglBindAPI")?(OPENGL_API) == 0 {
+                bail!("EGL can't use OpenGL, {}", Self::error(&library));
+            }
+
+            let attributes = [SURFACE_TYPE, PBUFFER_BIT, RENDERABLE_TYPE, OPENGL_BIT, NONE];
+            let mut config = ptr::null_mut();
+            let mut count = 0;
+            let chosen = library.get::<ChooseConfig>(b"eglChooseConfig")?(
+                display,
+                attributes.as_ptr(),
+                &mut config,
+                1,
+                &mut count,
+            );
+            if chosen == 0 || count == 0 {
+                bail!("no EGL config for OpenGL, {}", Self::error(&library));
+            }
+
+            let attributes = [
+                CONTEXT_MAJOR_VERSION,
+                3,
+                CONTEXT_MINOR_VERSION,
+                3,
+                CONTEXT_OPENGL_PROFILE_MASK,
+                CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                NONE,
+            ];
+            let context = library.get::<CreateContext>(b"eglCreateContext")?(
+                display,
+                config,
+                ptr::null_mut(),
+                attributes.as_ptr(),
+            );
+            if context.is_null() {
+                bail!("cannot create an OpenGL context, {}", Self::error(&library));
+            }
+            let context = Self {
+                library,
+                display,
+                context,
+            };
+            // without a surface, needs EGL_KHR_surfaceless_context
+            let make_current = context.library.get::<MakeCurrent>(b"eglMakeCurrent")?;
+            if make_current(display, ptr::null_mut(), ptr::null_mut(), context.context) == 0 {
+                bail!(
+                    "cannot use the OpenGL context without a surface, {}",
+                    Self::error(&context.library)
+                );
+            }
+            Ok(context)
+        }
+    }
+
+    /// The surfaceless platform of Mesa, or the default display where it is missing
+    unsafe fn display(library: &libloading::Library) -> Result<Display> {
+        if let Ok(get_platform_display) =
+            library.get::<GetPlatformDisplay>(b"eglGetPlatformDisplay")
+        {
+            let display =
+                get_platform_display(PLATFORM_SURFACELESS_MESA, ptr::null_mut(), ptr::null());
+            if !display.is_null() {
+                return Ok(display);
+            }
+        }
+        Ok(library.get::<GetDisplay>(b"eglGetDisplay")?(ptr::null_mut()))
+    }
+
+    unsafe fn error(library: &libloading::Library) -> String {
+        match library.get::<GetError>(b"eglGetError") {
+            Ok(get_error) => format!("error 0x{:x}", get_error()),
+            Err(e) => e.to_string(),
+        }
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe {
+            if let Ok(make_current) = self.library.get::<MakeCurrent>(b"eglMakeCurrent") {
+                make_current(
+                    self.display,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                );
+            }
+            if let Ok(destroy) = self.library.get::<DestroyContext>(b"eglDestroyContext") {
+                destroy(self.display, self.context);
+            }
+            if let Ok(terminate) = self.library.get::<Terminate>(b"eglTerminate") {
+                terminate(self.display);
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+mod egl;
+
+use anyhow::{Context, Result};
+use femtovg::{renderer::OpenGl, rgb::ComponentBytes, Canvas, Color};
+use gdk_pixbuf::Pixbuf;
+use glow::HasContext;
+use image::RgbaImage;
+
+use crate::{
+    canvas::{self, max_texture_size, render_tiled, Fonts, ImageTiles},
+    math::Vec2D,
+    tools::Drawable,
+};
+
+/// Draws images with annotations into an OpenGL context of its own, without a window or a
+/// display
+pub struct Renderer {
+    canvas: Canvas<OpenGl>,
+    fonts: Fonts,
+    // dropped last, the canvas cleans up in it
+    _context: egl::Context,
+}
+
+impl Renderer {
+    pub fn new() -> Result<Self> {
+        let context = egl::Context::new().context("cannot render without a window")?;
+        canvas::load_gl()?;
+        static LOAD_FN: fn(&str) -> *const std::ffi::c_void =
+            |s| epoxy::get_proc_addr(s) as *const _;
+        let renderer = unsafe { OpenGl::new_from_function(LOAD_FN) }?;
+        let gl = unsafe { glow::Context::from_loader_function(LOAD_FN) };
+        canvas::GL_RENDERER.get_or_init(|| unsafe { gl.get_parameter_string(glow::RENDERER) });
+        canvas::MAX_TEXTURE_SIZE
+            .get_or_init(|| unsafe { gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE) as u32 });
+        let mut canvas = Canvas::new(renderer)?;
+        let fonts = Fonts::new(&mut canvas);
+        Ok(Self {
+            canvas,
+            fonts,
+            _context: context,
+        })
+    }
+
+    /// `image` with `drawables` on top, in the size of the image
+    pub fn render(&mut self, image: &Pixbuf, drawables: &[Box<dyn Drawable>]) -> Result<RgbaImage> {
+        let (width, height) = (image.width() as u32, image.height() as u32);
+        let canvas = &mut self.canvas;
+        let max = max_texture_size();
+        canvas.set_size(width.min(max), height.min(max), 1.0);
+        let background = ImageTiles::upload(canvas, image)?;
+
+        let bounds = (Vec2D::zero(), Vec2D::new(width as f32, height as f32));
+        let fonts = &self.fonts;
+        // render targets are textures too, large images are rendered in tiles
+        let rendered = render_tiled(canvas, Vec2D::zero(), (width, height), |canvas| {
+            canvas.clear_rect(0, 0, max, max, Color::rgbaf(0.0, 0.0, 0.0, 0.0));
+            background.draw(canvas, 1.0);
+            drawables
+                .iter()
+                .try_for_each(|d| d.draw(canvas, fonts, bounds))?;
+            canvas.flush();
+            Ok(())
+        });
+        background.delete(canvas);
+
+        let (buf, w, h) = rendered?.into_contiguous_buf();
+        RgbaImage::from_raw(w as u32, h as u32, buf.as_bytes().to_vec())
+            .context("the rendered image has the wrong size")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use femtovg::{Paint, Path};
+
+    use super::*;
+    use crate::canvas::render_to_image;
+
+    #[test]
+    fn rendered_images_are_drawn_upright() {
+        // only read when the first context is created
+        std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+        let Ok(mut renderer) = Renderer::new() else {
+            eprintln!("skipped, no OpenGL context without a display");
+            return;
+        };
+        let canvas = &mut renderer.canvas;
+        canvas.set_size(8, 8, 1.0);
+
+        // red in the upper half only
+        let image = render_to_image(canvas, (8, 8), |canvas| {
+            let mut path = Path::new();
+            path.rect(0.0, 0.0, 8.0, 4.0);
+            canvas.fill_path(&path, &Paint::color(Color::rgb(255, 0, 0)));
+            Ok(())
+        })
+        .unwrap();
+        let rendered = render_tiled(canvas, Vec2D::zero(), (8, 8), |canvas| {
+            canvas.clear_rect(0, 0, 8, 8, Color::rgbaf(0.0, 0.0, 0.0, 0.0));
+            let mut path = Path::new();
+            path.rect(0.0, 0.0, 8.0, 8.0);
+            canvas.fill_path(&path, &Paint::image(image, 0.0, 0.0, 8.0, 8.0, 0.0, 1.0));
+            canvas.flush();
+            Ok(())
+        })
+        .unwrap();
+
+        let pixels = rendered.buf();
+        assert_eq!((pixels[0].r, pixels[0].a), (255, 255), "top is red");
+        assert_eq!(pixels[7 * 8].a, 0, "bottom is transparent");
+    }
+}
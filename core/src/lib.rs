@@ -0,0 +1,41 @@
+//! The annotation engine of Satty, for drawing Satty's annotations onto images from other
+//! applications: the tools and drawables, rendering them and the drawing options they follow.
+//! The `satty` binary is the GTK application built on it.
+//!
+//! [`Annotator`] renders [`Annotation`]s onto an image into an image buffer, without any
+//! window:
+//!
+//! ```no_run
+//! use satty_core::{Annotation, AnnotationStyle, Annotator, Vec2D};
+//!
+//! let image = gdk_pixbuf::Pixbuf::from_file("screenshot.png")?;
+//! let annotated = Annotator::new(image)
+//!     .with_annotation(Annotation::Arrow {
+//!         start: Vec2D::new(10.0, 10.0),
+//!         end: Vec2D::new(90.0, 40.0),
+//!         style: AnnotationStyle::default(),
+//!     })
+//!     .render()?;
+//! annotated.save("annotated.png")?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Styles left at their defaults follow the options in [`DRAWING_CONFIG`], which holds the
+//! built-in defaults unless it is replaced.
+
+pub mod annotation_file;
+mod annotator;
+pub mod canvas;
+pub mod configuration;
+pub mod events;
+pub mod headless;
+pub mod ime;
+pub mod math;
+pub mod style;
+pub mod tools;
+
+pub use annotator::Annotator;
+pub use configuration::DRAWING_CONFIG;
+pub use math::Vec2D;
+pub use style::{Color, Size, TextAlign};
+pub use tools::{Annotation, AnnotationStyle};
@@ -0,0 +1,526 @@
+use std::{
+    f32::consts::PI,
+    fmt::Display,
+    ops::{Add, AddAssign, Mul, Sub, SubAssign},
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Default, Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Vec2D {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Default, Debug, Copy, Clone, PartialEq)]
+pub struct Angle {
+    pub radians: f32,
+}
+impl Angle {
+    pub fn from_radians(radians: f32) -> Self {
+        Self { radians }
+    }
+
+    pub fn from_degrees(degrees: f32) -> Self {
+        Self {
+            radians: degrees * PI / 180.0,
+        }
+    }
+
+    pub fn cos(&self) -> f32 {
+        self.radians.cos()
+    }
+
+    pub fn sin(&self) -> f32 {
+        self.radians.sin()
+    }
+}
+
+impl Mul<f32> for Angle {
+    type Output = Angle;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Angle::from_radians(self.radians * rhs)
+    }
+}
+
+impl Vec2D {
+    pub fn zero() -> Self {
+        Self { x: 0.0, y: 0.0 }
+    }
+
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn norm(&self) -> f32 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    pub fn norm2(&self) -> f32 {
+        self.x * self.x + self.y * self.y
+    }
+
+    /**
+     * Get the angle of the vector.
+     * Angle of 0 is the positive x-axis.
+     * Angle of PI/2 is the positive y-axis.
+     */
+    pub fn angle(&self) -> Angle {
+        Angle::from_radians(self.y.atan2(self.x))
+    }
+
+    /**
+     * Create a vector from an angle.
+     * Angle of 0 is the positive x-axis.
+     * Angle of PI/2 is the positive y-axis.
+     */
+    pub fn from_angle(angle: Angle) -> Vec2D {
+        Vec2D::new(angle.cos(), angle.sin())
+    }
+
+    pub fn snapped_vector_15deg(&self) -> Vec2D {
+        let current_angle = (self.y / self.x).atan();
+        let current_norm2 = self.norm2();
+        let new_angle = (current_angle / 0.261_799_4).round() * 0.261_799_4;
+
+        let (a, b) = if new_angle.abs() < PI / 4.0
+        // 45°
+        {
+            let b = (current_norm2 / ((PI / 2.0 - new_angle).tan().powi(2) + 1.0)).sqrt();
+            let a = (current_norm2 - b * b).sqrt();
+            (a, b)
+        } else {
+            let a = (current_norm2 / (new_angle.tan().powi(2) + 1.0)).sqrt();
+            let b = (current_norm2 - a * a).sqrt();
+            (a, b)
+        };
+
+        if self.x >= 0.0 && self.y >= 0.0 {
+            Vec2D::new(a, b)
+        } else if self.x < 0.0 && self.y >= 0.0 {
+            Vec2D::new(-a, b)
+        } else if self.x >= 0.0 && self.y < 0.0 {
+            Vec2D::new(a, -b)
+        } else {
+            Vec2D::new(-a, -b)
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.x.abs() < f32::EPSILON && self.y.abs() < f32::EPSILON
+    }
+
+    pub fn distance_to(&self, other: &Vec2D) -> f32 {
+        let dx = self.x - other.x;
+        let dy = self.y - other.y;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+impl Add for Vec2D {
+    type Output = Vec2D;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::Output {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+impl AddAssign for Vec2D {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs
+    }
+}
+
+impl Sub for Vec2D {
+    type Output = Vec2D;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::Output {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl SubAssign for Vec2D {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<f32> for Vec2D {
+    type Output = Vec2D;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Vec2D::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Display for Vec2D {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({},{})", self.x, self.y)
+    }
+}
+
+pub fn rect_ensure_positive_size(pos: Vec2D, size: Vec2D) -> (Vec2D, Vec2D) {
+    let (pos_x, size_x) = if size.x > 0.0 {
+        (pos.x, size.x)
+    } else {
+        ((pos.x + size.x), size.x.abs())
+    };
+
+    let (pos_y, size_y) = if size.y > 0.0 {
+        (pos.y, size.y)
+    } else {
+        ((pos.y + size.y), size.y.abs())
+    };
+
+    (Vec2D::new(pos_x, pos_y), Vec2D::new(size_x, size_y))
+}
+
+pub fn rect_ensure_in_bounds(rect: (Vec2D, Vec2D), bounds: (Vec2D, Vec2D)) -> (Vec2D, Vec2D) {
+    let (mut pos, mut size) = rect;
+
+    if pos.x < bounds.0.x {
+        pos.x = bounds.0.x;
+        size.x -= bounds.0.x - pos.x;
+    }
+
+    if pos.y < bounds.0.y {
+        pos.y = bounds.0.y;
+        size.y -= bounds.0.y - pos.y;
+    }
+
+    if pos.x + size.x > bounds.1.x {
+        size.x = bounds.1.x - pos.x;
+    }
+
+    if pos.y + size.y > bounds.1.y {
+        size.y = bounds.1.y - pos.y;
+    }
+
+    (pos, size)
+}
+
+/// Grow `bounds` (given as min and max corner) so that it also contains `rect`
+pub fn rect_extend_bounds(rect: (Vec2D, Vec2D), bounds: (Vec2D, Vec2D)) -> (Vec2D, Vec2D) {
+    let (pos, size) = rect;
+    let (mut min, mut max) = bounds;
+
+    min.x = min.x.min(pos.x);
+    min.y = min.y.min(pos.y);
+    max.x = max.x.max(pos.x + size.x);
+    max.y = max.y.max(pos.y + size.y);
+
+    (min, max)
+}
+
+pub fn rect_round(rect: (Vec2D, Vec2D)) -> (Vec2D, Vec2D) {
+    let (mut pos, mut size) = rect;
+
+    pos.x = pos.x.round();
+    pos.y = pos.y.round();
+    size.x = size.x.round();
+    size.y = size.y.round();
+
+    (pos, size)
+}
+
+/// Check whether `point` lies within the rectangle (given as position and size), grown by
+/// `tolerance` on every side
+pub fn rect_contains(rect: (Vec2D, Vec2D), point: Vec2D, tolerance: f32) -> bool {
+    let (pos, size) = rect_ensure_positive_size(rect.0, rect.1);
+
+    point.x >= pos.x - tolerance
+        && point.x <= pos.x + size.x + tolerance
+        && point.y >= pos.y - tolerance
+        && point.y <= pos.y + size.y + tolerance
+}
+
+/// Shortest distance from `point` to the line segment between `start` and `end`
+pub fn distance_to_segment(point: Vec2D, start: Vec2D, end: Vec2D) -> f32 {
+    let segment = end - start;
+    let length2 = segment.norm2();
+    if length2 < f32::EPSILON {
+        return point.distance_to(&start);
+    }
+
+    let to_point = point - start;
+    let t = ((to_point.x * segment.x + to_point.y * segment.y) / length2).clamp(0.0, 1.0);
+    point.distance_to(&(start + segment * t))
+}
+
+/// Shortest distance from `point` to a polyline through `points`
+pub fn distance_to_polyline(point: Vec2D, points: impl IntoIterator<Item = Vec2D>) -> f32 {
+    let mut points = points.into_iter();
+    let Some(mut previous) = points.next() else {
+        return f32::INFINITY;
+    };
+
+    let mut distance = point.distance_to(&previous);
+    for p in points {
+        distance = distance.min(distance_to_segment(point, previous, p));
+        previous = p;
+    }
+    distance
+}
+
+/// Bounds (min and max corner) of a rectangle given as position and size
+pub fn rect_to_bounds(pos: Vec2D, size: Vec2D) -> (Vec2D, Vec2D) {
+    let (pos, size) = rect_ensure_positive_size(pos, size);
+    (pos, pos + size)
+}
+
+/// Smallest bounds (min and max corner) containing all `points`, `None` if there are none
+pub fn bounds_of_points(points: impl IntoIterator<Item = Vec2D>) -> Option<(Vec2D, Vec2D)> {
+    points.into_iter().fold(None, |bounds, p| match bounds {
+        None => Some((p, p)),
+        Some((min, max)) => Some((
+            Vec2D::new(min.x.min(p.x), min.y.min(p.y)),
+            Vec2D::new(max.x.max(p.x), max.y.max(p.y)),
+        )),
+    })
+}
+
+/// Move, resize or rotation applied to committed drawables when editing them
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Transformation {
+    Translate(Vec2D),
+    /// scale both axes independently, `origin` stays in place
+    Scale {
+        origin: Vec2D,
+        factor: Vec2D,
+    },
+    Rotate {
+        center: Vec2D,
+        angle: Angle,
+    },
+}
+
+impl Transformation {
+    /// Transform a position
+    pub fn apply(&self, point: Vec2D) -> Vec2D {
+        match *self {
+            Self::Translate(offset) => point + offset,
+            Self::Scale { origin, .. } => origin + self.apply_vector(point - origin),
+            Self::Rotate { center, .. } => center + self.apply_vector(point - center),
+        }
+    }
+
+    /// Transform a direction or size, these are not affected by translations
+    pub fn apply_vector(&self, vector: Vec2D) -> Vec2D {
+        match *self {
+            Self::Translate(_) => vector,
+            Self::Scale { factor, .. } => Vec2D::new(vector.x * factor.x, vector.y * factor.y),
+            Self::Rotate { angle, .. } => Vec2D::new(
+                vector.x * angle.cos() - vector.y * angle.sin(),
+                vector.x * angle.sin() + vector.y * angle.cos(),
+            ),
+        }
+    }
+}
+
+/// Zoom and pan of the image on the canvas. Image coordinates are scaled by `scale` and then
+/// moved by `offset` to get device pixels, canvas coordinates of events are in logical pixels
+/// of `dpi_scale` device pixels each.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ViewTransform {
+    pub scale: f32,
+    pub offset: Vec2D,
+    pub dpi_scale: f32,
+}
+
+impl ViewTransform {
+    /// Image coordinates of a position on the canvas
+    pub fn abs_to_image(&self, pos: Vec2D) -> Vec2D {
+        Vec2D::new(
+            (pos.x * self.dpi_scale - self.offset.x) / self.scale,
+            (pos.y * self.dpi_scale - self.offset.y) / self.scale,
+        )
+    }
+
+    /// Image coordinates of a distance on the canvas, e.g. of a drag
+    pub fn rel_to_image(&self, vector: Vec2D) -> Vec2D {
+        Vec2D::new(
+            vector.x * self.dpi_scale / self.scale,
+            vector.y * self.dpi_scale / self.scale,
+        )
+    }
+}
+
+/// Offset centering the image area from `origin` to `extent` at `scale` in `viewport`, all
+/// offsets and the viewport in device pixels
+pub fn center_offset(viewport: Vec2D, origin: Vec2D, extent: Vec2D, scale: f32) -> Vec2D {
+    (viewport - (extent - origin) * scale) * 0.5 - origin * scale
+}
+
+/// Offset on top of the centering offset `centered` keeping the image point under `pointer`
+/// in place when zooming from `offset` and `previous_scale` to `scale`
+pub fn zoom_drag_offset(
+    pointer: Vec2D,
+    offset: Vec2D,
+    previous_scale: f32,
+    scale: f32,
+    centered: Vec2D,
+) -> Vec2D {
+    let image_pos = Vec2D::new(
+        (pointer.x - offset.x) / previous_scale,
+        (pointer.y - offset.y) / previous_scale,
+    );
+    pointer - image_pos * scale - centered
+}
+
+/// Offset on top of the centering offset `centered` showing `center` (image coordinates) in
+/// the middle of `viewport`
+pub fn center_drag_offset(viewport: Vec2D, center: Vec2D, scale: f32, centered: Vec2D) -> Vec2D {
+    viewport * 0.5 - center * scale - centered
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn vec2d(range: f32) -> impl Strategy<Value = Vec2D> {
+        (-range..range, -range..range).prop_map(|(x, y)| Vec2D::new(x, y))
+    }
+
+    fn view() -> impl Strategy<Value = ViewTransform> {
+        (0.05f32..32.0, vec2d(10_000.0), 1.0f32..3.0).prop_map(|(scale, offset, dpi_scale)| {
+            ViewTransform {
+                scale,
+                offset,
+                dpi_scale,
+            }
+        })
+    }
+
+    /// Inverse of `abs_to_image`
+    fn image_to_abs(view: &ViewTransform, pos: Vec2D) -> Vec2D {
+        Vec2D::new(
+            (pos.x * view.scale + view.offset.x) / view.dpi_scale,
+            (pos.y * view.scale + view.offset.y) / view.dpi_scale,
+        )
+    }
+
+    /// Inverse of `rel_to_image`
+    fn image_to_rel(view: &ViewTransform, vector: Vec2D) -> Vec2D {
+        Vec2D::new(
+            vector.x * view.scale / view.dpi_scale,
+            vector.y * view.scale / view.dpi_scale,
+        )
+    }
+
+    /// Equal up to rounding, relative to the magnitude of the values involved
+    fn assert_close(a: Vec2D, b: Vec2D, magnitude: f32) {
+        let tolerance = magnitude.max(1.0) * 1e-4;
+        assert!(
+            (a - b).norm() <= tolerance,
+            "{a} != {b} (tolerance {tolerance})"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn abs_image_round_trip(view in view(), pos in vec2d(10_000.0)) {
+            let image = view.abs_to_image(pos);
+            let magnitude = pos.norm() * view.dpi_scale + view.offset.norm();
+            assert_close(image_to_abs(&view, image), pos, magnitude);
+        }
+
+        #[test]
+        fn image_abs_round_trip(view in view(), pos in vec2d(10_000.0)) {
+            let canvas = image_to_abs(&view, pos);
+            let magnitude = pos.norm() + view.offset.norm() / view.scale;
+            assert_close(view.abs_to_image(canvas), pos, magnitude);
+        }
+
+        #[test]
+        fn rel_image_round_trip(view in view(), vector in vec2d(10_000.0)) {
+            let image = view.rel_to_image(vector);
+            assert_close(image_to_rel(&view, image), vector, vector.norm());
+        }
+
+        #[test]
+        fn rel_is_difference_of_abs(view in view(), a in vec2d(10_000.0), b in vec2d(10_000.0)) {
+            let difference = view.abs_to_image(b) - view.abs_to_image(a);
+            let magnitude = (a.norm() + b.norm()) * view.dpi_scale + view.offset.norm();
+            assert_close(
+                difference * view.scale,
+                view.rel_to_image(b - a) * view.scale,
+                magnitude,
+            );
+        }
+
+        #[test]
+        fn zoom_keeps_point_under_pointer(
+            view in view(),
+            pointer in vec2d(5_000.0),
+            scale in 0.05f32..32.0,
+            origin in vec2d(1_000.0),
+            size in vec2d(5_000.0),
+        ) {
+            let viewport = Vec2D::new(1920.0, 1080.0);
+            let extent = origin + Vec2D::new(size.x.abs(), size.y.abs());
+            let before = (pointer - view.offset) * (1.0 / view.scale);
+
+            let centered = center_offset(viewport, origin, extent, scale);
+            let drag = zoom_drag_offset(pointer, view.offset, view.scale, scale, centered);
+            let zoomed = ViewTransform {
+                scale,
+                offset: centered + drag,
+                dpi_scale: 1.0,
+            };
+            let magnitude = (pointer.norm() + view.offset.norm()) * scale / view.scale;
+            assert_close(zoomed.abs_to_image(pointer) * scale, before * scale, magnitude);
+        }
+
+        #[test]
+        fn center_on_shows_center_in_the_middle(
+            center in vec2d(10_000.0),
+            scale in 0.05f32..32.0,
+            origin in vec2d(1_000.0),
+            size in vec2d(5_000.0),
+            viewport in vec2d(4_000.0),
+        ) {
+            let viewport = Vec2D::new(viewport.x.abs() + 1.0, viewport.y.abs() + 1.0);
+            let extent = origin + Vec2D::new(size.x.abs(), size.y.abs());
+
+            let centered = center_offset(viewport, origin, extent, scale);
+            let drag = center_drag_offset(viewport, center, scale, centered);
+            let view = ViewTransform {
+                scale,
+                offset: centered + drag,
+                dpi_scale: 1.0,
+            };
+            let magnitude = center.norm() * scale + viewport.norm() + extent.norm() * scale;
+            assert_close(image_to_abs(&view, center), viewport * 0.5, magnitude);
+        }
+
+        #[test]
+        fn centered_area_is_symmetric(
+            scale in 0.05f32..32.0,
+            origin in vec2d(1_000.0),
+            size in vec2d(5_000.0),
+            viewport in vec2d(4_000.0),
+        ) {
+            let viewport = Vec2D::new(viewport.x.abs(), viewport.y.abs());
+            let extent = origin + Vec2D::new(size.x.abs(), size.y.abs());
+            let view = ViewTransform {
+                scale,
+                offset: center_offset(viewport, origin, extent, scale),
+                dpi_scale: 1.0,
+            };
+            // the margins left and right, top and bottom of the area are the same
+            let start = image_to_abs(&view, origin);
+            let end = viewport - image_to_abs(&view, extent);
+            let magnitude = viewport.norm() + (origin.norm() + extent.norm()) * scale;
+            assert_close(start, end, magnitude);
+        }
+    }
+}
@@ -0,0 +1,596 @@
+use std::{borrow::Cow, fmt::Display, sync::Mutex};
+
+use femtovg::{renderer::OpenGl, Canvas, ErrorKind, Paint, Path};
+use gdk_pixbuf::{
+    glib::{Variant, VariantTy},
+    prelude::{StaticVariantType, ToVariant},
+};
+use glib::variant::FromVariant;
+use gtk::gdk::RGBA;
+use hex_color::HexColor;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::configuration::DRAWING_CONFIG;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Style {
+    pub color: Color,
+    pub size: Size,
+    pub fill: bool,
+    pub annotation_size_factor: f32,
+    pub shadow: bool,
+    pub outline: bool,
+    pub font: TextFont,
+    pub text_align: TextAlign,
+}
+
+/// Alignment of the lines of text annotations
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Font of text annotations as picked in the style toolbar
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextFont {
+    /// `None` uses the configured font family
+    pub family: Option<&'static str>,
+    pub bold: bool,
+    pub italic: bool,
+    /// text size in image pixels, `None` follows the annotation size
+    pub size: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+/// Color vision deficiencies the annotations can be previewed with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorBlindness {
+    Deuteranopia,
+    Protanopia,
+}
+
+#[derive(
+    Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Size {
+    Small = 0,
+    #[default]
+    Medium = 1,
+    Large = 2,
+}
+
+/// The part of a [`Style`] remembered for each tool
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ToolStyle {
+    pub color: Color,
+    pub size: Size,
+    pub fill: bool,
+}
+
+impl Style {
+    pub fn tool_style(&self) -> ToolStyle {
+        ToolStyle {
+            color: self.color,
+            size: self.size,
+            fill: self.fill,
+        }
+    }
+
+    pub fn apply_tool_style(&mut self, tool_style: ToolStyle) {
+        self.color = tool_style.color;
+        self.size = tool_style.size;
+        self.fill = tool_style.fill;
+    }
+
+    /// Take over the properties that differ between `previous` and `current`, so a single
+    /// change in the style toolbar doesn't reset the other properties
+    pub fn apply_change(&mut self, previous: &Style, current: &Style) {
+        if previous.color != current.color {
+            self.color = current.color;
+        }
+        if previous.size != current.size {
+            self.size = current.size;
+        }
+        if previous.fill != current.fill {
+            self.fill = current.fill;
+        }
+        if previous.annotation_size_factor != current.annotation_size_factor {
+            self.annotation_size_factor = current.annotation_size_factor;
+        }
+        if previous.shadow != current.shadow {
+            self.shadow = current.shadow;
+        }
+        if previous.outline != current.outline {
+            self.outline = current.outline;
+        }
+        if previous.font != current.font {
+            self.font = current.font;
+        }
+        if previous.text_align != current.text_align {
+            self.text_align = current.text_align;
+        }
+    }
+
+    pub fn text_size(&self) -> f32 {
+        self.font
+            .size
+            .unwrap_or_else(|| self.size.to_text_size(self.annotation_size_factor) as f32)
+    }
+
+    /// Stroke `path` with `paint`, the enabled shadow and outline are drawn below it
+    pub fn stroke_path(&self, canvas: &mut Canvas<OpenGl>, path: &Path, paint: &Paint) {
+        self.stroke_paths(canvas, &[(path, paint)]);
+    }
+
+    /// Stroke several paths forming one drawable, their shadows and outlines are drawn below
+    /// all of them
+    pub fn stroke_paths(&self, canvas: &mut Canvas<OpenGl>, paths: &[(&Path, &Paint)]) {
+        let widened = |paint: &Paint| {
+            paint
+                .clone()
+                .with_line_width(paint.line_width() + 2.0 * self.outline_width())
+        };
+        if self.shadow {
+            self.with_shadow_offset(canvas, |canvas| {
+                for (path, paint) in paths {
+                    canvas.stroke_path(path, &self.shadow_paint(&widened(paint)));
+                }
+            });
+        }
+        if self.outline {
+            for (path, paint) in paths {
+                canvas.stroke_path(path, &self.outline_paint(&widened(paint)));
+            }
+        }
+        for (path, paint) in paths {
+            canvas.stroke_path(path, paint);
+        }
+    }
+
+    /// Fill `path` with `paint`, the enabled shadow and outline are drawn below it
+    pub fn fill_path(&self, canvas: &mut Canvas<OpenGl>, path: &Path, paint: &Paint) {
+        let border = paint.clone().with_line_width(2.0 * self.outline_width());
+        if self.shadow {
+            self.with_shadow_offset(canvas, |canvas| {
+                canvas.fill_path(path, &self.shadow_paint(paint));
+                if self.outline {
+                    canvas.stroke_path(path, &self.shadow_paint(&border));
+                }
+            });
+        }
+        if self.outline {
+            canvas.stroke_path(path, &self.outline_paint(&border));
+        }
+        canvas.fill_path(path, paint);
+    }
+
+    /// Draw `text` with `paint`, the enabled shadow and outline are drawn below it
+    pub fn fill_text(
+        &self,
+        canvas: &mut Canvas<OpenGl>,
+        pos: (f32, f32),
+        text: &str,
+        paint: &Paint,
+    ) -> Result<(), ErrorKind> {
+        let (x, y) = pos;
+        let border = paint.clone().with_line_width(2.0 * self.outline_width());
+        if self.shadow {
+            self.with_shadow_offset(canvas, |canvas| {
+                canvas.fill_text(x, y, text, &self.shadow_paint(paint))?;
+                if self.outline {
+                    canvas.stroke_text(x, y, text, &self.shadow_paint(&border))?;
+                }
+                Ok::<_, ErrorKind>(())
+            })?;
+        }
+        if self.outline {
+            canvas.stroke_text(x, y, text, &self.outline_paint(&border))?;
+        }
+        canvas.fill_text(x, y, text, paint)?;
+        Ok(())
+    }
+
+    fn outline_width(&self) -> f32 {
+        if self.outline {
+            self.size.to_outline_width(self.annotation_size_factor)
+        } else {
+            0.0
+        }
+    }
+
+    fn with_shadow_offset<T>(
+        &self,
+        canvas: &mut Canvas<OpenGl>,
+        draw: impl FnOnce(&mut Canvas<OpenGl>) -> T,
+    ) -> T {
+        let offset = self.size.to_shadow_offset(self.annotation_size_factor);
+        // the shadow falls to the bottom right of the image, also for rotated drawables
+        let transform = canvas.transform();
+        let device_offset = offset * transform.average_scale();
+        let inverse = transform.inverse();
+        let (x0, y0) = inverse.transform_point(0.0, 0.0);
+        let (x1, y1) = inverse.transform_point(device_offset, device_offset);
+
+        canvas.save();
+        canvas.translate(x1 - x0, y1 - y0);
+        let result = draw(canvas);
+        canvas.restore();
+        result
+    }
+
+    fn shadow_paint(&self, paint: &Paint) -> Paint {
+        paint
+            .clone()
+            .with_color(femtovg::Color::rgba(0, 0, 0, self.color.a / 2))
+    }
+
+    fn outline_paint(&self, paint: &Paint) -> Paint {
+        paint.clone().with_color(self.color.contrasting().into())
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        let config = DRAWING_CONFIG.read();
+        Self {
+            color: Color::default(),
+            size: Size::default(),
+            fill: config.fill_shapes,
+            annotation_size_factor: config.annotation_size_factor,
+            shadow: config.shadow,
+            outline: config.outline,
+            font: TextFont::default(),
+            text_align: TextAlign::default(),
+        }
+    }
+}
+
+impl TextFont {
+    /// Family names live as long as the program so the style stays `Copy`, only a handful
+    /// of them get picked in a session
+    pub fn intern_family(family: &str) -> &'static str {
+        static FAMILIES: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+        let mut families = FAMILIES.lock().unwrap();
+        if let Some(interned) = families.iter().find(|f| **f == family) {
+            return interned;
+        }
+        let interned: &'static str = Box::leak(family.to_owned().into_boxed_str());
+        families.push(interned);
+        interned
+    }
+
+    /// Fontconfig style for the emphasis, `None` keeps the configured style
+    pub fn fontconfig_style(&self) -> Option<&'static str> {
+        match (self.bold, self.italic) {
+            (false, false) => None,
+            (true, false) => Some("Bold"),
+            (false, true) => Some("Italic"),
+            (true, true) => Some("Bold Italic"),
+        }
+    }
+}
+
+impl Default for TextFont {
+    fn default() -> Self {
+        Self {
+            family: None,
+            bold: false,
+            italic: false,
+            size: DRAWING_CONFIG.read().font_size,
+        }
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        DRAWING_CONFIG.read().color
+    }
+}
+
+impl StaticVariantType for Color {
+    fn static_variant_type() -> Cow<'static, VariantTy> {
+        Cow::Borrowed(VariantTy::TUPLE)
+    }
+}
+impl ToVariant for Color {
+    fn to_variant(&self) -> Variant {
+        (self.r, self.g, self.b, self.a).to_variant()
+    }
+}
+
+impl FromVariant for Color {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        <(u8, u8, u8, u8)>::from_variant(variant).map(|(r, g, b, a)| Self { r, g, b, a })
+    }
+}
+
+impl Color {
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn from_gdk(rgba: RGBA) -> Self {
+        Self::from(rgba)
+    }
+
+    pub fn orange() -> Self {
+        Self::new(240, 147, 43, 255)
+    }
+    pub fn red() -> Self {
+        Self::new(235, 77, 75, 255)
+    }
+    pub fn green() -> Self {
+        Self::new(106, 176, 76, 255)
+    }
+    pub fn blue() -> Self {
+        Self::new(34, 166, 179, 255)
+    }
+    pub fn cove() -> Self {
+        Self::new(19, 15, 64, 255)
+    }
+
+    pub fn pink() -> Self {
+        Self::new(200, 37, 184, 255)
+    }
+
+    /// How this color looks with the given deficiency, using the matrices of Machado et al.
+    /// (2009) for full severity on linear RGB
+    pub fn simulate(self, deficiency: ColorBlindness) -> Self {
+        let matrix = match deficiency {
+            ColorBlindness::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorBlindness::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+        };
+        let to_srgb = |c: f32| {
+            let c = c.clamp(0.0, 1.0);
+            let c = if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            };
+            (c * 255.0).round() as u8
+        };
+
+        let rgb = [to_linear(self.r), to_linear(self.g), to_linear(self.b)];
+        let [r, g, b] =
+            matrix.map(|row| to_srgb(row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]));
+        Self::new(r, g, b, self.a)
+    }
+
+    /// Relative luminance as defined by WCAG, ignoring alpha
+    pub fn relative_luminance(self) -> f32 {
+        0.2126 * to_linear(self.r) + 0.7152 * to_linear(self.g) + 0.0722 * to_linear(self.b)
+    }
+
+    /// WCAG contrast ratio between this and `other`, from 1 (none) to 21 (black on white)
+    pub fn contrast_ratio(self, other: Self) -> f32 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Black or white, whichever stands out more against this color
+    pub fn contrasting(self) -> Self {
+        let luminance = 0.299 * self.r as f32 + 0.587 * self.g as f32 + 0.114 * self.b as f32;
+        if luminance > 150.0 {
+            Self::new(0, 0, 0, self.a)
+        } else {
+            Self::new(255, 255, 255, self.a)
+        }
+    }
+
+    pub fn to_rgba_f64(self) -> (f64, f64, f64, f64) {
+        (
+            (self.r as f64) / 255.0,
+            (self.g as f64) / 255.0,
+            (self.b as f64) / 255.0,
+            (self.a as f64) / 255.0,
+        )
+    }
+    pub fn to_rgba_u32(self) -> u32 {
+        ((self.r as u32) << 24) | ((self.g as u32) << 16) | ((self.b as u32) << 8) | (self.a as u32)
+    }
+}
+
+/// sRGB channel to linear light
+fn to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+impl ColorBlindness {
+    /// Preview following `current` when cycling through them, `None` shows the real colors
+    pub fn cycle(current: Option<Self>) -> Option<Self> {
+        match current {
+            None => Some(Self::Deuteranopia),
+            Some(Self::Deuteranopia) => Some(Self::Protanopia),
+            Some(Self::Protanopia) => None,
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Deuteranopia => "Deuteranopia",
+            Self::Protanopia => "Protanopia",
+        }
+    }
+}
+
+impl From<RGBA> for Color {
+    fn from(value: RGBA) -> Self {
+        // rounded, so a translucent color from the config survives the color picker
+        Self::new(
+            (value.red() * 255.0).round() as u8,
+            (value.green() * 255.0).round() as u8,
+            (value.blue() * 255.0).round() as u8,
+            (value.alpha() * 255.0).round() as u8,
+        )
+    }
+}
+
+/// `#rrggbb`, or `#rrggbbaa` for translucent colors, as in the config file
+impl Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+        if self.a != 255 {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Color> for RGBA {
+    fn from(color: Color) -> Self {
+        Self::new(
+            color.r as f32 / 255.0,
+            color.g as f32 / 255.0,
+            color.b as f32 / 255.0,
+            color.a as f32 / 255.0,
+        )
+    }
+}
+
+impl From<Color> for femtovg::Color {
+    fn from(value: Color) -> Self {
+        femtovg::Color {
+            r: value.r as f32 / 255.0,
+            g: value.g as f32 / 255.0,
+            b: value.b as f32 / 255.0,
+            a: value.a as f32 / 255.0,
+        }
+    }
+}
+
+impl From<HexColor> for Color {
+    fn from(value: HexColor) -> Self {
+        Self::new(value.r, value.g, value.b, value.a)
+    }
+}
+
+impl From<Color> for HexColor {
+    fn from(value: Color) -> Self {
+        HexColor::rgba(value.r, value.g, value.b, value.a)
+    }
+}
+
+impl From<Style> for Paint {
+    fn from(value: Style) -> Self {
+        Paint::default()
+            .with_anti_alias(true)
+            .with_font_size(value.text_size())
+            .with_color(value.color.into())
+            .with_line_width(value.size.to_line_width(value.annotation_size_factor))
+    }
+}
+
+impl StaticVariantType for Size {
+    fn static_variant_type() -> Cow<'static, VariantTy> {
+        Cow::Borrowed(VariantTy::UINT32)
+    }
+}
+
+impl ToVariant for Size {
+    fn to_variant(&self) -> Variant {
+        Variant::from(*self as u32)
+    }
+}
+
+impl FromVariant for Size {
+    fn from_variant(variant: &Variant) -> Option<Self> {
+        variant.get::<u32>().and_then(|v| match v {
+            0 => Some(Size::Small),
+            1 => Some(Size::Medium),
+            2 => Some(Size::Large),
+            _ => None,
+        })
+    }
+}
+
+impl Size {
+    pub fn to_text_size(self, size_factor: f32) -> i32 {
+        match self {
+            Size::Small => (36.0 * size_factor) as i32,
+            Size::Medium => (54.0 * size_factor) as i32,
+            Size::Large => (96.0 * size_factor) as i32,
+        }
+    }
+
+    pub fn to_line_width(self, size_factor: f32) -> f32 {
+        match self {
+            Size::Small => 3.0 * size_factor,
+            Size::Medium => 5.0 * size_factor,
+            Size::Large => 7.0 * size_factor,
+        }
+    }
+
+    pub fn to_shadow_offset(self, size_factor: f32) -> f32 {
+        match self {
+            Size::Small => 2.0 * size_factor,
+            Size::Medium => 3.0 * size_factor,
+            Size::Large => 5.0 * size_factor,
+        }
+    }
+
+    pub fn to_outline_width(self, size_factor: f32) -> f32 {
+        match self {
+            Size::Small => 1.5 * size_factor,
+            Size::Medium => 2.0 * size_factor,
+            Size::Large => 3.0 * size_factor,
+        }
+    }
+
+    pub fn to_arrow_tail_width(self, size_factor: f32) -> f32 {
+        match self {
+            Size::Small => 3.0 * size_factor,
+            Size::Medium => 10.0 * size_factor,
+            Size::Large => 25.0 * size_factor,
+        }
+    }
+
+    pub fn to_arrow_head_length(self, size_factor: f32) -> f32 {
+        match self {
+            Size::Small => 15.0 * size_factor,
+            Size::Medium => 30.0 * size_factor,
+            Size::Large => 60.0 * size_factor,
+        }
+    }
+
+    pub fn to_blur_factor(self, size_factor: f32) -> f32 {
+        match self {
+            Size::Small => 10.0 * size_factor,
+            Size::Medium => 20.0 * size_factor,
+            Size::Large => 30.0 * size_factor,
+        }
+    }
+
+    pub fn to_highlight_width(self, size_factor: f32) -> f32 {
+        match self {
+            Size::Small => 15.0 * size_factor,
+            Size::Medium => 30.0 * size_factor,
+            Size::Large => 45.0 * size_factor,
+        }
+    }
+}
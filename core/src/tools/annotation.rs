@@ -0,0 +1,247 @@
+use std::{cell::RefCell, rc::Rc};
+
+use hex_color::HexColor;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    math::Vec2D,
+    style::{Size, Style, TextAlign, TextFont},
+};
+
+use super::{
+    arrow::Arrow, brace::Brace, brush::BrushDrawable, ellipse::Ellipse, highlight::HighlightKind,
+    line::Line, marker::Marker, rectangle::Rectangle, ruler::Ruler, table::Table, text::Text, Blur,
+    Drawable,
+};
+
+/// A committed drawable as plain data, in image coordinates. This is the JSON format
+/// annotations are stored and exchanged in, it doesn't change with the drawables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "kebab-case",
+    rename_all_fields = "kebab-case",
+    deny_unknown_fields
+)]
+pub enum Annotation {
+    Line {
+        start: Vec2D,
+        end: Vec2D,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Arrow {
+        start: Vec2D,
+        end: Vec2D,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Ruler {
+        start: Vec2D,
+        end: Vec2D,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Brace {
+        start: Vec2D,
+        end: Vec2D,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Rectangle {
+        top_left: Vec2D,
+        size: Vec2D,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Ellipse {
+        center: Vec2D,
+        radii: Vec2D,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Table {
+        top_left: Vec2D,
+        size: Vec2D,
+        rows: u32,
+        columns: u32,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Blur {
+        top_left: Vec2D,
+        size: Vec2D,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Highlight {
+        top_left: Vec2D,
+        size: Vec2D,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    HighlightLine {
+        start: Vec2D,
+        end: Vec2D,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    HighlightFreehand {
+        points: Vec<Vec2D>,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Brush {
+        points: Vec<Vec2D>,
+        /// stylus pressure of each point, empty for devices without pressure
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pressures: Vec<f32>,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Marker {
+        pos: Vec2D,
+        number: u16,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+    Text {
+        pos: Vec2D,
+        /// as typed, `{name}` placeholders are expanded when drawn
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        wrap_width: Option<f32>,
+        #[serde(default)]
+        style: AnnotationStyle,
+    },
+}
+
+/// The [`Style`] of an [`Annotation`], left out fields take the configured defaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct AnnotationStyle {
+    pub color: HexColor,
+    pub size: Size,
+    pub fill: bool,
+    pub annotation_size_factor: f32,
+    pub shadow: bool,
+    pub outline: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_family: Option<String>,
+    pub bold: bool,
+    pub italic: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<f32>,
+    pub text_align: TextAlign,
+}
+
+impl Default for AnnotationStyle {
+    fn default() -> Self {
+        Style::default().into()
+    }
+}
+
+impl From<Style> for AnnotationStyle {
+    fn from(style: Style) -> Self {
+        Self {
+            color: style.color.into(),
+            size: style.size,
+            fill: style.fill,
+            annotation_size_factor: style.annotation_size_factor,
+            shadow: style.shadow,
+            outline: style.outline,
+            font_family: style.font.family.map(str::to_string),
+            bold: style.font.bold,
+            italic: style.font.italic,
+            font_size: style.font.size,
+            text_align: style.text_align,
+        }
+    }
+}
+
+impl From<AnnotationStyle> for Style {
+    fn from(style: AnnotationStyle) -> Self {
+        Self {
+            color: style.color.into(),
+            size: style.size,
+            fill: style.fill,
+            annotation_size_factor: style.annotation_size_factor,
+            shadow: style.shadow,
+            outline: style.outline,
+            font: TextFont {
+                family: style.font_family.as_deref().map(TextFont::intern_family),
+                bold: style.bold,
+                italic: style.italic,
+                size: style.font_size,
+            },
+            text_align: style.text_align,
+        }
+    }
+}
+
+impl Annotation {
+    /// The drawable this describes. Markers share `marker_numbers` with the marker tool,
+    /// it continues after the highest restored number.
+    pub fn into_drawable(self, marker_numbers: &Rc<RefCell<u16>>) -> Box<dyn Drawable> {
+        match self {
+            Self::Line { start, end, style } => Box::new(Line::new(start, end, style.into())),
+            Self::Arrow { start, end, style } => Box::new(Arrow::new(start, end, style.into())),
+            Self::Ruler { start, end, style } => Box::new(Ruler::new(start, end, style.into())),
+            Self::Brace { start, end, style } => Box::new(Brace::new(start, end, style.into())),
+            Self::Rectangle {
+                top_left,
+                size,
+                style,
+            } => Box::new(Rectangle::new(top_left, size, style.into())),
+            Self::Ellipse {
+                center,
+                radii,
+                style,
+            } => Box::new(Ellipse::new(center, radii, style.into())),
+            Self::Table {
+                top_left,
+                size,
+                rows,
+                columns,
+                style,
+            } => Box::new(Table::new(top_left, size, rows, columns, style.into())),
+            Self::Blur {
+                top_left,
+                size,
+                style,
+            } => Box::new(Blur::new(top_left, size, style.into())),
+            Self::Highlight {
+                top_left,
+                size,
+                style,
+            } => Box::new(HighlightKind::block(top_left, size, style.into())),
+            Self::HighlightLine { start, end, style } => {
+                Box::new(HighlightKind::line(start, end, style.into()))
+            }
+            Self::HighlightFreehand { points, style } => {
+                Box::new(HighlightKind::freehand(&points, style.into()))
+            }
+            Self::Brush {
+                points,
+                pressures,
+                style,
+            } => Box::new(BrushDrawable::new(&points, pressures, style.into())),
+            Self::Marker { pos, number, style } => {
+                let mut next_number = marker_numbers.borrow_mut();
+                *next_number = (*next_number).max(number.saturating_add(1));
+                Box::new(Marker::new(
+                    pos,
+                    number,
+                    style.into(),
+                    marker_numbers.clone(),
+                ))
+            }
+            Self::Text {
+                pos,
+                text,
+                wrap_width,
+                style,
+            } => Box::new(Text::committed(pos, &text, wrap_width, style.into())),
+        }
+    }
+}
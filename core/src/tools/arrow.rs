@@ -1,17 +1,15 @@
 use anyhow::Result;
-use femtovg::{FontId, Path};
-use relm4::{
-    gtk::gdk::{Key, ModifierType},
-    Sender,
-};
+use femtovg::Path;
+use gtk::gdk::{Key, ModifierType};
 
 use crate::{
-    math::{Angle, Vec2D},
-    sketch_board::{MouseButton, MouseEventMsg, MouseEventType, SketchBoardInput},
+    canvas::Fonts,
+    events::{MouseButton, MouseEventMsg, MouseEventType},
+    math::{self, Angle, Transformation, Vec2D},
     style::Style,
 };
 
-use super::{Drawable, DrawableClone, Tool, ToolUpdateResult, Tools};
+use super::{Annotation, Drawable, DrawableClone, Tool, ToolSender, ToolUpdateResult, Tools};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Arrow {
@@ -25,7 +23,7 @@ pub struct ArrowTool {
     arrow: Option<Arrow>,
     style: Style,
     input_enabled: bool,
-    sender: Option<Sender<SketchBoardInput>>,
+    sender: Option<ToolSender>,
 }
 
 impl Tool for ArrowTool {
@@ -41,6 +39,10 @@ impl Tool for ArrowTool {
         Tools::Arrow
     }
 
+    fn snaps_to_guides(&self) -> bool {
+        true
+    }
+
     fn handle_mouse_event(&mut self, event: MouseEventMsg) -> ToolUpdateResult {
         match event.type_ {
             MouseEventType::BeginDrag => {
@@ -106,7 +108,7 @@ impl Tool for ArrowTool {
         }
     }
 
-    fn handle_key_event(&mut self, event: crate::sketch_board::KeyEventMsg) -> ToolUpdateResult {
+    fn handle_key_event(&mut self, event: crate::events::KeyEventMsg) -> ToolUpdateResult {
         if event.key == Key::Escape && self.arrow.is_some() {
             self.arrow = None;
             ToolUpdateResult::Redraw
@@ -127,16 +129,26 @@ impl Tool for ArrowTool {
         ToolUpdateResult::Unmodified
     }
 
-    fn set_sender(&mut self, sender: Sender<SketchBoardInput>) {
+    fn set_sender(&mut self, sender: ToolSender) {
         self.sender = Some(sender);
     }
 }
 
+impl Arrow {
+    pub fn new(start: Vec2D, end: Vec2D, style: Style) -> Self {
+        Self {
+            start,
+            end: Some(end),
+            style,
+        }
+    }
+}
+
 impl Drawable for Arrow {
     fn draw(
         &self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        _font: FontId,
+        _fonts: &Fonts,
         _bounds: (Vec2D, Vec2D),
     ) -> Result<()> {
         let end = match self.end {
@@ -225,7 +237,7 @@ impl Drawable for Arrow {
             }
             path.close();
 
-            canvas.fill_path(&path, &self.style.into());
+            self.style.fill_path(canvas, &path, &self.style.into());
         } else {
             // Draw a 'thin' arrow head.
             let mut path = Path::new();
@@ -236,10 +248,53 @@ impl Drawable for Arrow {
             path.move_to(0.0, 0.0); // A
             path.line_to(arrow_length, 0.0); // B
 
-            canvas.stroke_path(&path, &self.style.into());
+            self.style.stroke_path(canvas, &path, &self.style.into());
         }
 
         canvas.restore();
         Ok(())
     }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let Some(end) = self.end else {
+            return false;
+        };
+        // the head is the widest part, use its width for the whole arrow
+        let half_width = self
+            .style
+            .size
+            .to_arrow_head_length(self.style.annotation_size_factor)
+            / 2.0;
+
+        math::distance_to_segment(pos, self.start, end) <= half_width + tolerance
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        math::bounds_of_points([self.start, self.end?])
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.start = transformation.apply(self.start);
+        self.end = self.end.map(|e| transformation.apply(e));
+    }
+
+    fn can_rotate(&self) -> bool {
+        true
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(Annotation::Arrow {
+            start: self.start,
+            end: self.end?,
+            style: self.style.into(),
+        })
+    }
 }
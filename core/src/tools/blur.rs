@@ -0,0 +1,367 @@
+use std::cell::RefCell;
+
+use anyhow::Result;
+use femtovg::{imgref::Img, Color, ImageFilter, ImageFlags, ImageId, Paint, Path, Transform2D};
+
+use gtk::gdk::Key;
+
+use crate::{
+    canvas::Fonts,
+    configuration::DRAWING_CONFIG,
+    events::{MouseButton, MouseEventMsg, MouseEventType},
+    math::{self, Transformation, Vec2D},
+    style::Style,
+};
+
+use super::{Annotation, Drawable, DrawableClone, Tool, ToolSender, ToolUpdateResult, Tools};
+
+/// The blurred part of the area, the canvas may only show some of it
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct BlurredArea {
+    image: ImageId,
+    // bounds of the image in canvas coordinates
+    bounds: (Vec2D, Vec2D),
+    // what was asked to be blurred, the image is blurred again once this changes
+    key: ((Vec2D, Vec2D), f32),
+}
+
+#[derive(Clone, Debug)]
+pub struct Blur {
+    top_left: Vec2D,
+    size: Option<Vec2D>,
+    style: Style,
+    editing: bool,
+    cached_image: RefCell<Option<BlurredArea>>,
+}
+
+impl Blur {
+    /// A finished blur of the area at `top_left` with `size`
+    pub fn new(top_left: Vec2D, size: Vec2D, style: Style) -> Self {
+        Self {
+            top_left,
+            size: Some(size),
+            style,
+            editing: false,
+            cached_image: RefCell::new(None),
+        }
+    }
+
+    /// Bounds of `(min, max)` in pixels of the render target, cut to `width` × `height`
+    fn to_pixels(
+        transform: Transform2D,
+        (min, max): (Vec2D, Vec2D),
+        (width, height): (f32, f32),
+    ) -> Option<(Vec2D, Vec2D)> {
+        let (x0, y0) = transform.transform_point(min.x, min.y);
+        let (x1, y1) = transform.transform_point(max.x, max.y);
+        let (x0, x1) = (x0.min(x1).max(0.0).floor(), x0.max(x1).min(width).ceil());
+        let (y0, y1) = (y0.min(y1).max(0.0).floor(), y0.max(y1).min(height).ceil());
+        (x1 > x0 && y1 > y0).then(|| (Vec2D::new(x0, y0), Vec2D::new(x1, y1)))
+    }
+
+    /// Blur the part of `bounds` the render target holds, a tile of a large export or a
+    /// zoomed in view may only hold some of it. `None` if it holds nothing of it.
+    fn blur(
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        bounds: (Vec2D, Vec2D),
+        sigma: f32,
+    ) -> Result<Option<BlurredArea>> {
+        let img = canvas.screenshot()?;
+
+        let transform = canvas.transform();
+        let Some((min, max)) =
+            Self::to_pixels(transform, bounds, (img.width() as f32, img.height() as f32))
+        else {
+            return Ok(None);
+        };
+        let (buf, width, height) = img
+            .sub_image(
+                min.x as usize,
+                min.y as usize,
+                (max.x - min.x) as usize,
+                (max.y - min.y) as usize,
+            )
+            .to_contiguous_buf();
+        let sub = Img::new(buf.into_owned(), width, height);
+
+        let src_image_id = canvas.create_image(sub.as_ref(), ImageFlags::empty())?;
+        let dst_image_id = canvas.create_image_empty(
+            sub.width(),
+            sub.height(),
+            femtovg::PixelFormat::Rgba8,
+            ImageFlags::empty(),
+        )?;
+
+        canvas.filter_image(
+            dst_image_id,
+            ImageFilter::GaussianBlur { sigma },
+            src_image_id,
+        );
+        //canvas.delete_image(src_image_id);
+
+        let inverse = transform.inverse();
+        let (min_x, min_y) = inverse.transform_point(min.x, min.y);
+        let (max_x, max_y) = inverse.transform_point(max.x, max.y);
+        Ok(Some(BlurredArea {
+            image: dst_image_id,
+            bounds: (Vec2D::new(min_x, min_y), Vec2D::new(max_x, max_y)),
+            key: (bounds, transform.average_scale()),
+        }))
+    }
+}
+
+impl Drawable for Blur {
+    fn draw(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        _fonts: &Fonts,
+        bounds: (Vec2D, Vec2D),
+    ) -> Result<()> {
+        let size = match self.size {
+            Some(s) => s,
+            None => return Ok(()), // early exit if none
+        };
+        let (pos, size) = math::rect_ensure_in_bounds(
+            math::rect_ensure_positive_size(self.top_left, size),
+            bounds,
+        );
+        if self.editing {
+            // set style
+            let mut color = Color::black();
+            color.set_alphaf(0.6);
+            let paint = Paint::color(color);
+
+            // make rect
+            let mut path = Path::new();
+            path.rounded_rect(
+                pos.x,
+                pos.y,
+                size.x,
+                size.y,
+                DRAWING_CONFIG.read().corner_roundness,
+            );
+
+            // draw
+            canvas.fill_path(&path, &paint);
+        } else {
+            if size.x <= 0.0 || size.y <= 0.0 {
+                return Ok(());
+            }
+
+            // only the part of the area on the canvas is blurred, again once another part or
+            // another zoom is shown
+            let transform = canvas.transform();
+            let canvas_size = (canvas.width() as f32, canvas.height() as f32);
+            let Some(pixels) = Self::to_pixels(transform, (pos, pos + size), canvas_size) else {
+                return Ok(());
+            };
+            let inverse = transform.inverse();
+            let corner = |p: Vec2D| {
+                let (x, y) = inverse.transform_point(p.x, p.y);
+                Vec2D::new(x, y)
+            };
+            let key = (
+                (corner(pixels.0), corner(pixels.1)),
+                transform.average_scale(),
+            );
+
+            canvas.save();
+            canvas.flush();
+
+            let cached = *self.cached_image.borrow();
+            let area = match cached {
+                Some(area) if area.key == key => area,
+                _ => {
+                    let sigma = self
+                        .style
+                        .size
+                        .to_blur_factor(self.style.annotation_size_factor);
+                    let Some(area) = Self::blur(canvas, key.0, sigma)? else {
+                        canvas.restore();
+                        return Ok(());
+                    };
+                    self.cached_image.replace(Some(area));
+                    area
+                }
+            };
+            let (min, max) = area.bounds;
+
+            // the rounded corners belong to the whole area, the image only covers what is shown
+            let mut path = Path::new();
+            path.rounded_rect(
+                pos.x,
+                pos.y,
+                size.x,
+                size.y,
+                DRAWING_CONFIG.read().corner_roundness,
+            );
+
+            canvas.fill_path(
+                &path,
+                &Paint::image(
+                    area.image,
+                    min.x,
+                    min.y,
+                    max.x - min.x,
+                    max.y - min.y,
+                    0f32,
+                    1f32,
+                ),
+            );
+            canvas.restore();
+        }
+        Ok(())
+    }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        self.size
+            .is_some_and(|size| math::rect_contains((self.top_left, size), pos, tolerance))
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        Some(math::rect_to_bounds(self.top_left, self.size?))
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.top_left = transformation.apply(self.top_left);
+        self.size = self.size.map(|s| transformation.apply_vector(s));
+        // the cached image shows the old area
+        self.cached_image.replace(None);
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+        self.cached_image.replace(None);
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(Annotation::Blur {
+            top_left: self.top_left,
+            size: self.size?,
+            style: self.style.into(),
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct BlurTool {
+    blur: Option<Blur>,
+    // start of the current drag
+    origin: Vec2D,
+    style: Style,
+    input_enabled: bool,
+    sender: Option<ToolSender>,
+}
+
+impl Tool for BlurTool {
+    fn input_enabled(&self) -> bool {
+        self.input_enabled
+    }
+
+    fn set_input_enabled(&mut self, value: bool) {
+        self.input_enabled = value;
+    }
+
+    fn get_tool_type(&self) -> super::Tools {
+        Tools::Blur
+    }
+
+    fn snaps_to_guides(&self) -> bool {
+        true
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEventMsg) -> ToolUpdateResult {
+        match event.type_ {
+            MouseEventType::BeginDrag => {
+                if event.button == MouseButton::Middle {
+                    return ToolUpdateResult::Unmodified;
+                }
+
+                // start new
+                self.origin = event.pos;
+                self.blur = Some(Blur {
+                    top_left: event.pos,
+                    size: None,
+                    style: self.style,
+                    editing: true,
+                    cached_image: RefCell::new(None),
+                });
+
+                ToolUpdateResult::Redraw
+            }
+            MouseEventType::EndDrag => {
+                if event.button == MouseButton::Middle {
+                    return ToolUpdateResult::Unmodified;
+                }
+
+                if let Some(a) = &mut self.blur {
+                    if event.pos == Vec2D::zero() {
+                        self.blur = None;
+
+                        ToolUpdateResult::Redraw
+                    } else {
+                        let (top_left, size) = event.drag_rect(self.origin);
+                        a.top_left = top_left;
+                        a.size = Some(size);
+                        a.editing = false;
+
+                        let result = a.clone_box();
+                        self.blur = None;
+
+                        ToolUpdateResult::Commit(result)
+                    }
+                } else {
+                    ToolUpdateResult::Unmodified
+                }
+            }
+            MouseEventType::UpdateDrag => {
+                if event.button == MouseButton::Middle {
+                    return ToolUpdateResult::Unmodified;
+                }
+
+                if let Some(a) = &mut self.blur {
+                    if event.pos == Vec2D::zero() {
+                        return ToolUpdateResult::Unmodified;
+                    }
+                    let (top_left, size) = event.drag_rect(self.origin);
+                    a.top_left = top_left;
+                    a.size = Some(size);
+
+                    ToolUpdateResult::Redraw
+                } else {
+                    ToolUpdateResult::Unmodified
+                }
+            }
+            _ => ToolUpdateResult::Unmodified,
+        }
+    }
+
+    fn handle_key_event(&mut self, event: crate::events::KeyEventMsg) -> ToolUpdateResult {
+        if event.key == Key::Escape && self.blur.is_some() {
+            self.blur = None;
+            ToolUpdateResult::Redraw
+        } else {
+            ToolUpdateResult::Unmodified
+        }
+    }
+
+    fn handle_style_event(&mut self, style: Style) -> ToolUpdateResult {
+        self.style = style;
+        ToolUpdateResult::Unmodified
+    }
+
+    fn get_drawable(&self) -> Option<&dyn Drawable> {
+        match &self.blur {
+            Some(d) => Some(d),
+            None => None,
+        }
+    }
+
+    fn set_sender(&mut self, sender: ToolSender) {
+        self.sender = Some(sender);
+    }
+}
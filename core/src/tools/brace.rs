@@ -0,0 +1,256 @@
+use anyhow::Result;
+use femtovg::Path;
+use gtk::gdk::{Key, ModifierType};
+
+use crate::{
+    canvas::Fonts,
+    events::{KeyEventMsg, MouseButton, MouseEventMsg, MouseEventType},
+    math::{self, Transformation, Vec2D},
+    style::Style,
+};
+
+use super::{Annotation, Drawable, DrawableClone, Tool, ToolSender, ToolUpdateResult, Tools};
+
+/// depth of the brace relative to its length, limited to a few line widths
+const DEPTH_RATIO: f32 = 0.08;
+const MIN_DEPTH: f32 = 3.0;
+const MAX_DEPTH: f32 = 10.0;
+
+#[derive(Default)]
+pub struct BraceTool {
+    brace: Option<Brace>,
+    style: Style,
+    input_enabled: bool,
+    sender: Option<ToolSender>,
+}
+
+/// Curly brace spanning the line from `start` along `direction`, with its tip on the left
+/// of the drag direction. A short stub continues from the tip and ends in the label anchor,
+/// which is part of the bounds so labels snap to it.
+#[derive(Clone, Copy, Debug)]
+pub struct Brace {
+    start: Vec2D,
+    direction: Option<Vec2D>,
+    style: Style,
+}
+
+impl Brace {
+    pub fn new(start: Vec2D, end: Vec2D, style: Style) -> Self {
+        Self {
+            start,
+            direction: Some(end - start),
+            style,
+        }
+    }
+
+    fn line_width(&self) -> f32 {
+        self.style
+            .size
+            .to_line_width(self.style.annotation_size_factor)
+    }
+
+    /// Point `along` the brace and `across` it towards the tip
+    fn point(&self, along: f32, across: f32) -> Option<Vec2D> {
+        let direction = self.direction?;
+        let length = direction.norm();
+        if length == 0.0 {
+            return None;
+        }
+        let axis = direction * (1.0 / length);
+        let normal = Vec2D::new(-axis.y, axis.x);
+        Some(self.start + axis * along + normal * across)
+    }
+
+    fn length(&self) -> f32 {
+        self.direction.map_or(0.0, |d| d.norm())
+    }
+
+    fn depth(&self) -> f32 {
+        let width = self.line_width();
+        (self.length() * DEPTH_RATIO).clamp(MIN_DEPTH * width, MAX_DEPTH * width)
+    }
+
+    /// Corners of the brace outline from start over the tip to the end, followed by the anchor
+    fn key_points(&self) -> Option<[Vec2D; 6]> {
+        let (length, depth) = (self.length(), self.depth());
+        Some([
+            self.point(0.0, 0.0)?,
+            self.point(0.0, depth / 2.0)?,
+            self.point(length / 2.0, depth)?,
+            self.point(length, depth / 2.0)?,
+            self.point(length, 0.0)?,
+            self.point(length / 2.0, depth * 2.0)?,
+        ])
+    }
+}
+
+impl Drawable for Brace {
+    fn draw(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        _fonts: &Fonts,
+        _bounds: (Vec2D, Vec2D),
+    ) -> Result<()> {
+        let (length, depth) = (self.length(), self.depth());
+        let half = depth / 2.0;
+        // radius of the rounded corners, shorter braces get tighter ones
+        let radius = half.min(length / 4.0);
+        let Some(points) = [
+            (0.0, 0.0),
+            (0.0, half),
+            (radius, half),
+            (length / 2.0 - radius, half),
+            (length / 2.0, half),
+            (length / 2.0, depth),
+            (length / 2.0 + radius, half),
+            (length - radius, half),
+            (length, half),
+            (length, 0.0),
+            (length / 2.0, depth * 2.0),
+        ]
+        .into_iter()
+        .map(|(along, across)| self.point(along, across))
+        .collect::<Option<Vec<_>>>() else {
+            return Ok(());
+        };
+
+        let mut path = Path::new();
+        path.move_to(points[0].x, points[0].y);
+        path.quad_to(points[1].x, points[1].y, points[2].x, points[2].y);
+        path.line_to(points[3].x, points[3].y);
+        path.quad_to(points[4].x, points[4].y, points[5].x, points[5].y);
+        path.quad_to(points[4].x, points[4].y, points[6].x, points[6].y);
+        path.line_to(points[7].x, points[7].y);
+        path.quad_to(points[8].x, points[8].y, points[9].x, points[9].y);
+        // the stub towards the label anchor
+        path.move_to(points[5].x, points[5].y);
+        path.line_to(points[10].x, points[10].y);
+
+        canvas.save();
+        self.style.stroke_path(canvas, &path, &self.style.into());
+        canvas.restore();
+        Ok(())
+    }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let Some([start, shoulder, tip, other_shoulder, end, anchor]) = self.key_points() else {
+            return false;
+        };
+        let half_width = self.line_width() / 2.0;
+        math::distance_to_polyline(pos, [start, shoulder, tip, other_shoulder, end])
+            .min(math::distance_to_segment(pos, tip, anchor))
+            <= half_width + tolerance
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        math::bounds_of_points(self.key_points()?)
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.start = transformation.apply(self.start);
+        self.direction = self.direction.map(|d| transformation.apply_vector(d));
+    }
+
+    fn can_rotate(&self) -> bool {
+        true
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(Annotation::Brace {
+            start: self.start,
+            end: self.start + self.direction?,
+            style: self.style.into(),
+        })
+    }
+}
+
+impl Tool for BraceTool {
+    fn input_enabled(&self) -> bool {
+        self.input_enabled
+    }
+
+    fn set_input_enabled(&mut self, value: bool) {
+        self.input_enabled = value;
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEventMsg) -> ToolUpdateResult {
+        if event.button == MouseButton::Middle {
+            return ToolUpdateResult::Unmodified;
+        }
+
+        match event.type_ {
+            MouseEventType::BeginDrag => {
+                self.brace = Some(Brace {
+                    start: event.pos,
+                    direction: None,
+                    style: self.style,
+                });
+                ToolUpdateResult::Redraw
+            }
+            MouseEventType::UpdateDrag | MouseEventType::EndDrag => {
+                let Some(brace) = &mut self.brace else {
+                    return ToolUpdateResult::Unmodified;
+                };
+                if event.type_ == MouseEventType::EndDrag && event.pos == Vec2D::zero() {
+                    self.brace = None;
+                    return ToolUpdateResult::Redraw;
+                }
+
+                brace.direction = Some(if event.modifier.intersects(ModifierType::SHIFT_MASK) {
+                    event.pos.snapped_vector_15deg()
+                } else {
+                    event.pos
+                });
+
+                if event.type_ == MouseEventType::UpdateDrag {
+                    return ToolUpdateResult::Redraw;
+                }
+                let result = brace.clone_box();
+                self.brace = None;
+                ToolUpdateResult::Commit(result)
+            }
+            _ => ToolUpdateResult::Unmodified,
+        }
+    }
+
+    fn handle_key_event(&mut self, event: KeyEventMsg) -> ToolUpdateResult {
+        if event.key == Key::Escape && self.brace.is_some() {
+            self.brace = None;
+            ToolUpdateResult::Redraw
+        } else {
+            ToolUpdateResult::Unmodified
+        }
+    }
+
+    fn handle_style_event(&mut self, style: Style) -> ToolUpdateResult {
+        self.style = style;
+        ToolUpdateResult::Unmodified
+    }
+
+    fn get_drawable(&self) -> Option<&dyn Drawable> {
+        match &self.brace {
+            Some(d) => Some(d),
+            None => None,
+        }
+    }
+
+    fn get_tool_type(&self) -> super::Tools {
+        Tools::Brace
+    }
+
+    fn snaps_to_guides(&self) -> bool {
+        true
+    }
+
+    fn set_sender(&mut self, sender: ToolSender) {
+        self.sender = Some(sender);
+    }
+}
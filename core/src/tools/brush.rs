@@ -1,23 +1,24 @@
 use std::time::Instant;
 
-use femtovg::{FontId, Path};
+use femtovg::{LineCap, Paint, Path};
 
 use crate::{
-    configuration::APP_CONFIG,
-    math::Vec2D,
-    sketch_board::{MouseButton, MouseEventMsg, MouseEventType, SketchBoardInput},
+    canvas::Fonts,
+    configuration::DRAWING_CONFIG,
+    events::{KeyEventMsg, MouseButton, MouseEventMsg, MouseEventType},
+    math::{self, Transformation, Vec2D},
     style::Style,
 };
 
-use super::{Drawable, DrawableClone, Tool, ToolUpdateResult, Tools};
-use relm4::Sender;
+use super::{Annotation, Drawable, DrawableClone, Tool, ToolSender, ToolUpdateResult, Tools};
+use gtk::gdk::Key;
 
 #[derive(Default)]
 pub struct BrushTool {
     drawable: Option<BrushDrawable>,
     style: Style,
     input_enabled: bool,
-    sender: Option<Sender<SketchBoardInput>>,
+    sender: Option<ToolSender>,
 }
 
 #[derive(Debug, Clone)]
@@ -26,13 +27,89 @@ pub struct BrushDrawable {
     // after this the points are relative to the start point
     start_point: Option<Vec2D>,
     points: Vec<Vec2D>,
+    // stylus pressure for each point, stays empty for devices without pressure
+    pressures: Vec<f32>,
     smoother: Smoother,
     style: Style,
 }
 
+// lightest touch still draws a visible line
+const MIN_PRESSURE: f32 = 0.2;
+
 impl BrushDrawable {
-    fn add_point(&mut self, point: Vec2D) {
+    /// A finished stroke through the `points` in image coordinates
+    pub fn new(points: &[Vec2D], pressures: Vec<f32>, style: Style) -> Self {
+        // all but the first point are kept relative to it, the first entry is not drawn
+        let start_point = points.first().copied();
+        let points = points
+            .iter()
+            .map(|p| *p - start_point.unwrap_or_default())
+            .collect::<Vec<_>>();
+        let pressures = if pressures.len() == points.len() {
+            pressures
+        } else {
+            Vec::new()
+        };
+        Self {
+            start_point,
+            points,
+            pressures,
+            smoother: Smoother::new(0),
+            style,
+        }
+    }
+
+    fn add_point(&mut self, point: Vec2D, pressure: Option<f32>) {
         self.points.push(self.smoother.update(point));
+
+        match (pressure, self.pressures.last().copied()) {
+            (Some(p), None) => {
+                // first pressure reading, use it for the points we already have
+                self.pressures = vec![p; self.points.len()];
+            }
+            (p, Some(last)) => self.pressures.push(p.unwrap_or(last)),
+            (None, None) => (),
+        }
+    }
+
+    fn absolute_points(&self, start_point: Vec2D) -> impl Iterator<Item = Vec2D> + '_ {
+        std::iter::once(start_point)
+            .chain(self.points.iter().skip(1).map(move |p| start_point + *p))
+    }
+
+    /// Stroke every segment on its own so width and opacity can follow the pen pressure.
+    /// Butt caps keep the overlap of neighbouring segments small, stylus input is dense
+    /// enough that the joints don't show.
+    fn draw_with_pressure(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        start_point: Vec2D,
+    ) {
+        let line_width = self
+            .style
+            .size
+            .to_line_width(self.style.annotation_size_factor);
+
+        let points: Vec<Vec2D> = self.absolute_points(start_point).collect();
+        let mut segments = Vec::new();
+        for (segment, pressure) in points.windows(2).zip(self.pressures.iter().skip(1)) {
+            let pressure = pressure.clamp(MIN_PRESSURE, 1.0);
+
+            let mut path = Path::new();
+            path.move_to(segment[0].x, segment[0].y);
+            path.line_to(segment[1].x, segment[1].y);
+
+            let mut style = self.style;
+            style.color.a = (style.color.a as f32 * pressure) as u8;
+            let mut paint: Paint = style.into();
+            paint.set_line_width(line_width * pressure);
+            paint.set_line_cap(LineCap::Butt);
+            segments.push((path, paint));
+        }
+
+        // decorate the stroke as a whole, so outlines don't cover neighbouring segments
+        let segments: Vec<_> = segments.iter().map(|(path, paint)| (path, paint)).collect();
+        self.style.stroke_paths(canvas, &segments);
     }
 }
 
@@ -40,7 +117,7 @@ impl Drawable for BrushDrawable {
     fn draw(
         &self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        _font: FontId,
+        _fonts: &Fonts,
         _bounds: (Vec2D, Vec2D),
     ) -> anyhow::Result<()> {
         if self.points.is_empty() {
@@ -52,17 +129,65 @@ impl Drawable for BrushDrawable {
         };
 
         canvas.save();
-        let mut path = Path::new();
+        if self.pressures.len() == self.points.len() {
+            self.draw_with_pressure(canvas, start_point);
+        } else {
+            let mut path = Path::new();
 
-        path.move_to(start_point.x, start_point.y);
-        for p in self.points.iter().skip(1) {
-            path.line_to(start_point.x + p.x, start_point.y + p.y);
-        }
+            path.move_to(start_point.x, start_point.y);
+            for p in self.points.iter().skip(1) {
+                path.line_to(start_point.x + p.x, start_point.y + p.y);
+            }
 
-        canvas.stroke_path(&path, &self.style.into());
+            self.style.stroke_path(canvas, &path, &self.style.into());
+        }
         canvas.restore();
         Ok(())
     }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let Some(start_point) = self.start_point else {
+            return false;
+        };
+        let half_width = self
+            .style
+            .size
+            .to_line_width(self.style.annotation_size_factor)
+            / 2.0;
+
+        math::distance_to_polyline(pos, self.absolute_points(start_point)) <= half_width + tolerance
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        math::bounds_of_points(self.absolute_points(self.start_point?))
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.start_point = self.start_point.map(|p| transformation.apply(p));
+        for p in &mut self.points {
+            *p = transformation.apply_vector(*p);
+        }
+    }
+
+    fn can_rotate(&self) -> bool {
+        true
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(Annotation::Brush {
+            points: self.absolute_points(self.start_point?).collect(),
+            pressures: self.pressures.clone(),
+            style: self.style.into(),
+        })
+    }
 }
 
 impl Tool for BrushTool {
@@ -99,7 +224,7 @@ impl Tool for BrushTool {
                 let Some(brush) = &mut self.drawable else {
                     return ToolUpdateResult::Unmodified;
                 };
-                brush.add_point(event.pos);
+                brush.add_point(event.pos, event.pressure);
 
                 // commit
                 let result = brush.clone_box();
@@ -115,7 +240,7 @@ impl Tool for BrushTool {
                 let Some(brush) = &mut self.drawable else {
                     return ToolUpdateResult::Unmodified;
                 };
-                brush.add_point(event.pos);
+                brush.add_point(event.pos, event.pressure);
                 ToolUpdateResult::Redraw
             }
             MouseEventType::Click => {
@@ -124,8 +249,9 @@ impl Tool for BrushTool {
                 }
                 self.drawable = Some(BrushDrawable {
                     start_point: None,
-                    smoother: Smoother::new(APP_CONFIG.read().brush_smooth_history_size()),
+                    smoother: Smoother::new(DRAWING_CONFIG.read().brush_smooth_history_size),
                     points: vec![event.pos],
+                    pressures: event.pressure.into_iter().collect(),
                     style: self.style,
                 });
                 ToolUpdateResult::Unmodified
@@ -134,6 +260,15 @@ impl Tool for BrushTool {
         }
     }
 
+    fn handle_key_event(&mut self, event: KeyEventMsg) -> ToolUpdateResult {
+        if event.key == Key::Escape && self.drawable.is_some() {
+            self.drawable = None;
+            ToolUpdateResult::Redraw
+        } else {
+            ToolUpdateResult::Unmodified
+        }
+    }
+
     fn get_drawable(&self) -> Option<&dyn Drawable> {
         match &self.drawable {
             Some(d) => Some(d),
@@ -146,7 +281,7 @@ impl Tool for BrushTool {
         ToolUpdateResult::Unmodified
     }
 
-    fn set_sender(&mut self, sender: Sender<SketchBoardInput>) {
+    fn set_sender(&mut self, sender: ToolSender) {
         self.sender = Some(sender);
     }
 }
@@ -1,14 +1,15 @@
 use std::f32::consts::PI;
 
 use crate::{
+    canvas::Fonts,
+    events::{KeyEventMsg, MouseButton, MouseEventMsg, MouseEventType},
     math::{self, Vec2D},
-    sketch_board::{KeyEventMsg, MouseButton, MouseEventMsg, MouseEventType, SketchBoardInput},
 };
 use anyhow::Result;
 use femtovg::{Color, Paint, Path};
-use relm4::{gtk::gdk::Key, Sender};
+use gtk::gdk::Key;
 
-use super::{Drawable, Tool, ToolUpdateResult, Tools};
+use super::{Drawable, Tool, ToolSender, ToolUpdateResult, Tools};
 
 #[derive(Debug, Clone)]
 pub struct Crop {
@@ -22,7 +23,7 @@ pub struct CropTool {
     crop: Option<Crop>,
     action: Option<CropToolAction>,
     input_enabled: bool,
-    sender: Option<Sender<SketchBoardInput>>,
+    sender: Option<ToolSender>,
 }
 
 impl Crop {
@@ -37,7 +38,7 @@ impl Crop {
         }
     }
 
-    fn draw_single_handle(
+    pub fn draw_single_handle(
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
         center: Vec2D,
         scale: f32,
@@ -107,20 +108,17 @@ impl Drawable for Crop {
     fn draw(
         &self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        _font: femtovg::FontId,
-        _bounds: (Vec2D, Vec2D),
+        _fonts: &Fonts,
+        bounds: (Vec2D, Vec2D),
     ) -> Result<()> {
         let size = self.size;
         let scale = canvas.transform().average_scale();
-        let dimensions = Vec2D::new(
-            canvas.width() as f32 / scale,
-            canvas.height() as f32 / scale,
-        );
+        let (min, max) = bounds;
 
         let shadow_paint = Paint::color(Color::rgbaf(0.0, 0.0, 0.0, 0.5))
             .with_fill_rule(femtovg::FillRule::EvenOdd);
         let mut shadow_path = Path::new();
-        shadow_path.rect(0.0, 0.0, dimensions.x, dimensions.y);
+        shadow_path.rect(min.x, min.y, max.x - min.x, max.y - min.y);
         shadow_path.rect(self.pos.x, self.pos.y, size.x, size.y);
 
         let border_paint = Paint::color(Color::rgbf(0.1, 0.1, 0.1)).with_line_width(2.0);
@@ -436,7 +434,7 @@ impl Tool for CropTool {
         None
     }
 
-    fn set_sender(&mut self, sender: Sender<SketchBoardInput>) {
+    fn set_sender(&mut self, sender: ToolSender) {
         self.sender = Some(sender);
     }
 }
@@ -1,17 +1,15 @@
 use anyhow::Result;
-use femtovg::{FontId, Path};
-use relm4::{
-    gtk::gdk::{Key, ModifierType},
-    Sender,
-};
+use femtovg::Path;
+use gtk::gdk::{Key, ModifierType};
 
 use crate::{
-    math::Vec2D,
-    sketch_board::{MouseButton, MouseEventMsg, MouseEventType, SketchBoardInput},
+    canvas::Fonts,
+    events::{MouseButton, MouseEventMsg, MouseEventType},
+    math::{Transformation, Vec2D},
     style::Style,
 };
 
-use super::{Drawable, DrawableClone, Tool, ToolUpdateResult, Tools};
+use super::{Annotation, Drawable, DrawableClone, Tool, ToolSender, ToolUpdateResult, Tools};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Ellipse {
@@ -27,7 +25,7 @@ impl Drawable for Ellipse {
     fn draw(
         &self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        _font: FontId,
+        _fonts: &Fonts,
         _bounds: (Vec2D, Vec2D),
     ) -> Result<()> {
         let radii = match self.radii {
@@ -44,7 +42,12 @@ impl Drawable for Ellipse {
             if self.centered {
                 helpers.circle(self.middle.x, self.middle.y, 2.0);
             } else {
-                helpers.rect(self.origin.x, self.origin.y, radii.x * 2.0, radii.y * 2.0);
+                helpers.rect(
+                    self.middle.x - radii.x,
+                    self.middle.y - radii.y,
+                    radii.x * 2.0,
+                    radii.y * 2.0,
+                );
             }
             canvas.stroke_path(
                 &helpers,
@@ -54,51 +57,93 @@ impl Drawable for Ellipse {
         }
 
         if self.style.fill {
-            canvas.fill_path(&path, &self.style.into());
+            self.style.fill_path(canvas, &path, &self.style.into());
         } else {
-            canvas.stroke_path(&path, &self.style.into());
+            self.style.stroke_path(canvas, &path, &self.style.into());
         }
         canvas.restore();
 
         Ok(())
     }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let Some(radii) = self.radii else {
+            return false;
+        };
+        let margin = self
+            .style
+            .size
+            .to_line_width(self.style.annotation_size_factor)
+            / 2.0
+            + tolerance;
+        let offset = pos - self.middle;
+
+        // normalized distance to the middle, 1.0 is on the ellipse
+        let distance = |rx: f32, ry: f32| {
+            if rx <= 0.0 || ry <= 0.0 {
+                return f32::INFINITY;
+            }
+            ((offset.x / rx).powi(2) + (offset.y / ry).powi(2)).sqrt()
+        };
+        let (rx, ry) = (radii.x.abs(), radii.y.abs());
+
+        if distance(rx + margin, ry + margin) > 1.0 {
+            return false;
+        }
+        // outlines are only hit on the border, not in the empty inside
+        self.style.fill || distance(rx - margin, ry - margin) >= 1.0
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        let radii = self.radii?;
+        let radii = Vec2D::new(radii.x.abs(), radii.y.abs());
+        Some((self.middle - radii, self.middle + radii))
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.origin = transformation.apply(self.origin);
+        self.middle = transformation.apply(self.middle);
+        self.radii = self.radii.map(|r| {
+            let r = transformation.apply_vector(r);
+            Vec2D::new(r.x.abs(), r.y.abs())
+        });
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(Annotation::Ellipse {
+            center: self.middle,
+            radii: self.radii?,
+            style: self.style.into(),
+        })
+    }
 }
 
 impl Ellipse {
-    fn calculate_shape(&mut self, event: &MouseEventMsg) {
-        self.centered = event.modifier & ModifierType::ALT_MASK == ModifierType::ALT_MASK;
-        match event.modifier & (ModifierType::ALT_MASK | ModifierType::SHIFT_MASK) {
-            v if v == ModifierType::ALT_MASK | ModifierType::SHIFT_MASK => {
-                self.middle = self.origin;
-                let max_size = event.pos.x.abs().max(event.pos.y.abs());
-                self.radii = Some(Vec2D {
-                    x: max_size * event.pos.x.signum(),
-                    y: max_size * event.pos.y.signum(),
-                });
-            }
-            ModifierType::ALT_MASK => {
-                self.middle = self.origin;
-                self.radii = Some(event.pos);
-            }
-            ModifierType::SHIFT_MASK => {
-                let max_size = (event.pos.x / 2.0).abs().max((event.pos.y / 2.0).abs());
-                self.radii = Some(Vec2D {
-                    x: max_size * event.pos.x.signum(),
-                    y: max_size * event.pos.y.signum(),
-                });
-                self.middle.x = self.origin.x + max_size * event.pos.x.signum();
-                self.middle.y = self.origin.y + max_size * event.pos.y.signum();
-            }
-            _ => {
-                self.radii = Some(Vec2D {
-                    x: event.pos.x / 2.0,
-                    y: event.pos.y / 2.0,
-                });
-                self.middle.x = self.origin.x + event.pos.x / 2.0;
-                self.middle.y = self.origin.y + event.pos.y / 2.0;
-            }
+    pub fn new(center: Vec2D, radii: Vec2D, style: Style) -> Self {
+        Self {
+            origin: center - radii,
+            middle: center,
+            radii: Some(radii),
+            style,
+            centered: false,
+            finishing: true,
         }
     }
+
+    fn calculate_shape(&mut self, event: &MouseEventMsg) {
+        self.centered = event.modifier.contains(ModifierType::CONTROL_MASK);
+        let (top_left, size) = event.drag_rect(self.origin);
+        self.radii = Some(size * 0.5);
+        self.middle = top_left + size * 0.5;
+    }
 }
 
 #[derive(Default)]
@@ -106,7 +151,7 @@ pub struct EllipseTool {
     ellipse: Option<Ellipse>,
     style: Style,
     input_enabled: bool,
-    sender: Option<Sender<SketchBoardInput>>,
+    sender: Option<ToolSender>,
 }
 
 impl Tool for EllipseTool {
@@ -122,6 +167,10 @@ impl Tool for EllipseTool {
         Tools::Ellipse
     }
 
+    fn snaps_to_guides(&self) -> bool {
+        true
+    }
+
     fn handle_mouse_event(&mut self, event: MouseEventMsg) -> ToolUpdateResult {
         match event.type_ {
             MouseEventType::BeginDrag => {
@@ -181,7 +230,7 @@ impl Tool for EllipseTool {
         }
     }
 
-    fn handle_key_event(&mut self, event: crate::sketch_board::KeyEventMsg) -> ToolUpdateResult {
+    fn handle_key_event(&mut self, event: crate::events::KeyEventMsg) -> ToolUpdateResult {
         if event.key == Key::Escape && self.ellipse.is_some() {
             self.ellipse = None;
             ToolUpdateResult::Redraw
@@ -202,7 +251,7 @@ impl Tool for EllipseTool {
         }
     }
 
-    fn set_sender(&mut self, sender: Sender<SketchBoardInput>) {
+    fn set_sender(&mut self, sender: ToolSender) {
         self.sender = Some(sender);
     }
 }
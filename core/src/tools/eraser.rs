@@ -0,0 +1,72 @@
+use crate::{
+    events::{MouseButton, MouseEventMsg, MouseEventType},
+    math::Vec2D,
+};
+
+use super::{Drawable, Tool, ToolRequest, ToolSender, ToolUpdateResult, Tools};
+
+/// Removes committed drawables under the cursor, the actual removal is done by the
+/// sketch board as only the renderer knows about committed drawables.
+#[derive(Default)]
+pub struct EraserTool {
+    // start of the current drag, drag updates are relative to it
+    start: Option<Vec2D>,
+    input_enabled: bool,
+    sender: Option<ToolSender>,
+}
+
+impl EraserTool {
+    fn erase_at(&self, pos: Vec2D) -> ToolUpdateResult {
+        if let Some(sender) = &self.sender {
+            sender.emit(ToolRequest::EraseAt(pos));
+        }
+        ToolUpdateResult::Unmodified
+    }
+}
+
+impl Tool for EraserTool {
+    fn input_enabled(&self) -> bool {
+        self.input_enabled
+    }
+
+    fn set_input_enabled(&mut self, value: bool) {
+        self.input_enabled = value;
+    }
+
+    fn get_tool_type(&self) -> super::Tools {
+        Tools::Eraser
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEventMsg) -> ToolUpdateResult {
+        if event.button != MouseButton::Primary {
+            return ToolUpdateResult::Unmodified;
+        }
+
+        match event.type_ {
+            // every press also begins a drag, only erase once at the start position
+            MouseEventType::Click => self.erase_at(event.pos),
+            MouseEventType::BeginDrag => {
+                self.start = Some(event.pos);
+                ToolUpdateResult::Unmodified
+            }
+            MouseEventType::UpdateDrag | MouseEventType::EndDrag => {
+                let Some(start) = self.start else {
+                    return ToolUpdateResult::Unmodified;
+                };
+                if event.type_ == MouseEventType::EndDrag {
+                    self.start = None;
+                }
+                self.erase_at(start + event.pos)
+            }
+            _ => ToolUpdateResult::Unmodified,
+        }
+    }
+
+    fn get_drawable(&self) -> Option<&dyn Drawable> {
+        None
+    }
+
+    fn set_sender(&mut self, sender: ToolSender) {
+        self.sender = Some(sender);
+    }
+}
@@ -0,0 +1,577 @@
+use std::ops::{Add, Sub};
+
+use anyhow::Result;
+use femtovg::{BlendFactor, Paint, Path};
+
+use gtk::gdk::{Key, ModifierType};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    canvas::Fonts,
+    configuration::DRAWING_CONFIG,
+    events::{MouseButton, MouseEventMsg, MouseEventType},
+    math::{self, Transformation, Vec2D},
+    style::Style,
+    tools::DrawableClone,
+};
+
+use super::{Annotation, Drawable, Tool, ToolSender, ToolUpdateResult, Tools};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Highlighters {
+    Block = 0,
+    Freehand = 1,
+    Line = 2,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightBlend {
+    Normal,
+    /// darkens the image like a highlighter pen, dark text stays dark
+    Multiply,
+}
+
+#[derive(Clone, Debug)]
+pub struct BlockHighlight {
+    top_left: Vec2D,
+    size: Option<Vec2D>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FreehandHighlight {
+    points: Vec<Vec2D>,
+    shift_pressed: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct LineHighlight {
+    start: Vec2D,
+    // relative to start
+    end: Vec2D,
+}
+
+#[derive(Clone, Debug)]
+pub struct Highlighter<T> {
+    data: T,
+    style: Style,
+}
+
+trait Highlight {
+    fn highlight(&self, canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>) -> Result<()>;
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool;
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)>;
+    fn transform(&mut self, transformation: Transformation);
+}
+
+impl<T> Highlighter<T> {
+    fn paint(&self) -> Paint {
+        // a translucent color brings its own opacity
+        let alpha = if self.style.color.a < 255 {
+            self.style.color.a
+        } else {
+            (255.0 * DRAWING_CONFIG.read().highlight_opacity).round() as u8
+        };
+        Paint::color(femtovg::Color::rgba(
+            self.style.color.r,
+            self.style.color.g,
+            self.style.color.b,
+            alpha,
+        ))
+    }
+
+    fn stroke_paint(&self) -> Paint {
+        let mut paint = self.paint();
+        paint.set_line_width(self.width());
+        paint.set_line_join(femtovg::LineJoin::Round);
+        paint
+    }
+
+    fn width(&self) -> f32 {
+        self.style
+            .size
+            .to_highlight_width(self.style.annotation_size_factor)
+    }
+}
+
+impl Highlight for Highlighter<FreehandHighlight> {
+    fn highlight(&self, canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>) -> Result<()> {
+        canvas.save();
+
+        let mut path = Path::new();
+        let first = self
+            .data
+            .points
+            .first()
+            .expect("should exist at least one point in highlight instance.");
+
+        path.move_to(first.x, first.y);
+        for p in self.data.points.iter().skip(1) {
+            path.line_to(first.x + p.x, first.y + p.y);
+        }
+
+        let mut paint = self.stroke_paint();
+        paint.set_line_cap(femtovg::LineCap::Square);
+
+        canvas.stroke_path(&path, &paint);
+        canvas.restore();
+        Ok(())
+    }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let Some(first) = self.data.points.first().copied() else {
+            return false;
+        };
+        let half_width = self.width() / 2.0;
+        let points =
+            std::iter::once(first).chain(self.data.points.iter().skip(1).map(|p| first + *p));
+        math::distance_to_polyline(pos, points) <= half_width + tolerance
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        let first = self.data.points.first().copied()?;
+        math::bounds_of_points(
+            std::iter::once(first).chain(self.data.points.iter().skip(1).map(|p| first + *p)),
+        )
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        // the first point is absolute, all others are relative to it
+        let mut points = self.data.points.iter_mut();
+        if let Some(first) = points.next() {
+            *first = transformation.apply(*first);
+        }
+        for p in points {
+            *p = transformation.apply_vector(*p);
+        }
+    }
+}
+
+impl Highlight for Highlighter<BlockHighlight> {
+    fn highlight(&self, canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>) -> Result<()> {
+        let size = match self.data.size {
+            Some(s) => s,
+            None => return Ok(()), // early exit if size is none
+        };
+
+        let (pos, size) = math::rect_ensure_positive_size(self.data.top_left, size);
+
+        let mut shadow_path = Path::new();
+        shadow_path.rounded_rect(
+            pos.x,
+            pos.y,
+            size.x,
+            size.y,
+            DRAWING_CONFIG.read().corner_roundness,
+        );
+
+        canvas.fill_path(&shadow_path, &self.paint());
+        Ok(())
+    }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        self.data
+            .size
+            .is_some_and(|size| math::rect_contains((self.data.top_left, size), pos, tolerance))
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        Some(math::rect_to_bounds(self.data.top_left, self.data.size?))
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.data.top_left = transformation.apply(self.data.top_left);
+        self.data.size = self.data.size.map(|s| transformation.apply_vector(s));
+    }
+}
+
+impl Highlight for Highlighter<LineHighlight> {
+    fn highlight(&self, canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>) -> Result<()> {
+        let end = self.data.start + self.data.end;
+        let mut path = Path::new();
+        path.move_to(self.data.start.x, self.data.start.y);
+        path.line_to(end.x, end.y);
+
+        // butt caps, so the strip ends where the drag did
+        let mut paint = self.stroke_paint();
+        paint.set_line_cap(femtovg::LineCap::Butt);
+        canvas.stroke_path(&path, &paint);
+        Ok(())
+    }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let points = [self.data.start, self.data.start + self.data.end];
+        math::distance_to_polyline(pos, points) <= self.width() / 2.0 + tolerance
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        math::bounds_of_points([self.data.start, self.data.start + self.data.end])
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.data.start = transformation.apply(self.data.start);
+        self.data.end = transformation.apply_vector(self.data.end);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum HighlightKind {
+    Block(Highlighter<BlockHighlight>),
+    Freehand(Highlighter<FreehandHighlight>),
+    Line(Highlighter<LineHighlight>),
+}
+
+impl HighlightKind {
+    pub fn block(top_left: Vec2D, size: Vec2D, style: Style) -> Self {
+        Self::Block(Highlighter {
+            data: BlockHighlight {
+                top_left,
+                size: Some(size),
+            },
+            style,
+        })
+    }
+
+    pub fn line(start: Vec2D, end: Vec2D, style: Style) -> Self {
+        Self::Line(Highlighter {
+            data: LineHighlight {
+                start,
+                end: end - start,
+            },
+            style,
+        })
+    }
+
+    /// A freehand highlight through the `points` in image coordinates
+    pub fn freehand(points: &[Vec2D], style: Style) -> Self {
+        let first = points.first().copied().unwrap_or_default();
+        Self::Freehand(Highlighter {
+            data: FreehandHighlight {
+                points: std::iter::once(first)
+                    .chain(points.iter().skip(1).map(|p| *p - first))
+                    .collect(),
+                shift_pressed: false,
+            },
+            style,
+        })
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct HighlightTool {
+    highlighter: Option<HighlightKind>,
+    style: Style,
+    input_enabled: bool,
+    sender: Option<ToolSender>,
+}
+
+impl Drawable for HighlightKind {
+    fn draw(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        _fonts: &Fonts,
+        _bounds: (Vec2D, Vec2D),
+    ) -> Result<()> {
+        canvas.save();
+        if DRAWING_CONFIG.read().highlight_blend == HighlightBlend::Multiply {
+            // multiply the colors, alpha composes as usual so transparent areas stay intact
+            canvas.global_composite_blend_func_separate(
+                BlendFactor::DstColor,
+                BlendFactor::OneMinusSrcAlpha,
+                BlendFactor::One,
+                BlendFactor::OneMinusSrcAlpha,
+            );
+        }
+        let result = match self {
+            HighlightKind::Block(highlighter) => highlighter.highlight(canvas),
+            HighlightKind::Freehand(highlighter) => highlighter.highlight(canvas),
+            HighlightKind::Line(highlighter) => highlighter.highlight(canvas),
+        };
+        canvas.restore();
+        result
+    }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        match self {
+            HighlightKind::Block(highlighter) => highlighter.hit_test(pos, tolerance),
+            HighlightKind::Freehand(highlighter) => highlighter.hit_test(pos, tolerance),
+            HighlightKind::Line(highlighter) => highlighter.hit_test(pos, tolerance),
+        }
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        match self {
+            HighlightKind::Block(highlighter) => highlighter.bounds(),
+            HighlightKind::Freehand(highlighter) => highlighter.bounds(),
+            HighlightKind::Line(highlighter) => highlighter.bounds(),
+        }
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        match self {
+            HighlightKind::Block(highlighter) => highlighter.transform(transformation),
+            HighlightKind::Freehand(highlighter) => highlighter.transform(transformation),
+            HighlightKind::Line(highlighter) => highlighter.transform(transformation),
+        }
+    }
+
+    fn can_rotate(&self) -> bool {
+        matches!(self, HighlightKind::Freehand(_) | HighlightKind::Line(_))
+    }
+
+    fn style(&self) -> Option<Style> {
+        match self {
+            HighlightKind::Block(highlighter) => Some(highlighter.style),
+            HighlightKind::Freehand(highlighter) => Some(highlighter.style),
+            HighlightKind::Line(highlighter) => Some(highlighter.style),
+        }
+    }
+
+    fn set_style(&mut self, style: Style) {
+        match self {
+            HighlightKind::Block(highlighter) => highlighter.style = style,
+            HighlightKind::Freehand(highlighter) => highlighter.style = style,
+            HighlightKind::Line(highlighter) => highlighter.style = style,
+        }
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(match self {
+            HighlightKind::Block(highlighter) => Annotation::Highlight {
+                top_left: highlighter.data.top_left,
+                size: highlighter.data.size?,
+                style: highlighter.style.into(),
+            },
+            HighlightKind::Freehand(highlighter) => {
+                let first = highlighter.data.points.first().copied()?;
+                Annotation::HighlightFreehand {
+                    points: std::iter::once(first)
+                        .chain(highlighter.data.points.iter().skip(1).map(|p| first + *p))
+                        .collect(),
+                    style: highlighter.style.into(),
+                }
+            }
+            HighlightKind::Line(highlighter) => Annotation::HighlightLine {
+                start: highlighter.data.start,
+                end: highlighter.data.start + highlighter.data.end,
+                style: highlighter.style.into(),
+            },
+        })
+    }
+}
+
+impl Tool for HighlightTool {
+    fn input_enabled(&self) -> bool {
+        self.input_enabled
+    }
+
+    fn set_input_enabled(&mut self, value: bool) {
+        self.input_enabled = value;
+    }
+
+    fn get_tool_type(&self) -> super::Tools {
+        Tools::Highlight
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEventMsg) -> ToolUpdateResult {
+        let shift_pressed = event.modifier.intersects(ModifierType::SHIFT_MASK);
+        let ctrl_pressed = event.modifier.intersects(ModifierType::CONTROL_MASK);
+        let primary_highlighter = DRAWING_CONFIG.read().primary_highlighter;
+        match event.type_ {
+            MouseEventType::BeginDrag => {
+                if event.button == MouseButton::Middle {
+                    return ToolUpdateResult::Unmodified;
+                }
+                // There exists three types of highlighting modes currently: freehand, block and
+                // line. A user may set a primary highlighter mode, with a secondary one being
+                // accessible by clicking CTRL when starting a highlight (doesn't need to be held).
+                match (primary_highlighter, ctrl_pressed) {
+                    // This matches when CTRL is not pressed and the primary highlighting mode
+                    // is block, along with its inverse, CTRL pressed with the freehand or line
+                    // mode being their primary highlighting mode.
+                    (Highlighters::Block, false)
+                    | (Highlighters::Freehand | Highlighters::Line, true) => {
+                        self.highlighter =
+                            Some(HighlightKind::Block(Highlighter::<BlockHighlight> {
+                                data: BlockHighlight {
+                                    top_left: event.pos,
+                                    size: None,
+                                },
+                                style: self.style,
+                            }))
+                    }
+                    // This matches the remaining two cases, which is when the user has the
+                    // freehand mode as the primary mode and CTRL is not pressed, and conversely,
+                    // when CTRL is pressed and the users primary mode is block.
+                    (Highlighters::Freehand, false) | (Highlighters::Block, true) => {
+                        self.highlighter =
+                            Some(HighlightKind::Freehand(Highlighter::<FreehandHighlight> {
+                                data: FreehandHighlight {
+                                    points: vec![event.pos],
+                                    shift_pressed,
+                                },
+                                style: self.style,
+                            }))
+                    }
+                    (Highlighters::Line, false) => {
+                        self.highlighter = Some(HighlightKind::Line(Highlighter::<LineHighlight> {
+                            data: LineHighlight {
+                                start: event.pos,
+                                end: Vec2D::zero(),
+                            },
+                            style: self.style,
+                        }))
+                    }
+                }
+
+                ToolUpdateResult::Redraw
+            }
+            MouseEventType::UpdateDrag | MouseEventType::EndDrag => {
+                if event.button == MouseButton::Middle {
+                    return ToolUpdateResult::Unmodified;
+                }
+
+                if self.highlighter.is_none() {
+                    return ToolUpdateResult::Unmodified;
+                }
+                let mut highlighter_kind = self.highlighter.as_mut().unwrap();
+                let update: ToolUpdateResult = match &mut highlighter_kind {
+                    HighlightKind::Block(highlighter) => {
+                        // When shift is pressed when using the block highlighter, it transforms
+                        // the area into a perfect square (in the direction they intended).
+                        if shift_pressed {
+                            let max_size = event.pos.x.abs().max(event.pos.y.abs());
+                            highlighter.data.size = Some(Vec2D {
+                                x: max_size * event.pos.x.signum(),
+                                y: max_size * event.pos.y.signum(),
+                            });
+                        } else {
+                            highlighter.data.size = Some(event.pos);
+                        };
+                        ToolUpdateResult::Redraw
+                    }
+                    HighlightKind::Freehand(highlighter) => {
+                        if event.pos == Vec2D::zero() {
+                            return ToolUpdateResult::Unmodified;
+                        };
+
+                        // The freehand highlighter has a more complex shift model:
+                        // when pressing shift it begins a straight line, which is aligned
+                        // from the point after shift was pressed, to any 15*n degree rotation.
+                        //
+                        // After releasing shift, it creates an extra point, this is useful since
+                        // it means that users do not need to move their mouse to achieve perfectly
+                        // aligned turns, since they can release, then hold shift again to continue
+                        // another aligned line.
+                        // This extra point can be removed by releasing shift again (if the cursor
+                        // hasn't moved)
+                        if shift_pressed {
+                            // if shift was pressed before we remove an extra point which would
+                            // have been the previous aligned point. However ignore if there is
+                            // only one point which means the highlight has just started.
+                            if highlighter.data.shift_pressed && highlighter.data.points.len() >= 2
+                            {
+                                highlighter
+                                    .data
+                                    .points
+                                    .pop()
+                                    .expect("at least 2 points in highlight path.");
+                            };
+                            // use the last point to position the snapping guide, or 0 if the point
+                            // is the first one.
+                            let last = if highlighter.data.points.len() == 1 {
+                                Vec2D::zero()
+                            } else {
+                                *highlighter
+                                    .data
+                                    .points
+                                    .last_mut()
+                                    .expect("at least one point")
+                            };
+                            let snapped_pos = event.pos.sub(last).snapped_vector_15deg().add(last);
+                            highlighter.data.points.push(snapped_pos);
+                        } else {
+                            highlighter.data.points.push(event.pos);
+                        }
+
+                        highlighter.data.shift_pressed = shift_pressed;
+                        ToolUpdateResult::Redraw
+                    }
+                    HighlightKind::Line(highlighter) => {
+                        // shift aligns the strip to 15 degree steps, like lines
+                        highlighter.data.end = if shift_pressed {
+                            event.pos.snapped_vector_15deg()
+                        } else {
+                            event.pos
+                        };
+                        ToolUpdateResult::Redraw
+                    }
+                };
+                // a click without dragging leaves no line to show
+                if event.type_ == MouseEventType::EndDrag
+                    && event.pos == Vec2D::zero()
+                    && matches!(highlighter_kind, HighlightKind::Line(_))
+                {
+                    self.highlighter = None;
+                    return ToolUpdateResult::Redraw;
+                }
+                if event.type_ == MouseEventType::UpdateDrag {
+                    return update;
+                };
+                let result = highlighter_kind.clone_box();
+                self.highlighter = None;
+                ToolUpdateResult::Commit(result)
+            }
+
+            _ => ToolUpdateResult::Unmodified,
+        }
+    }
+
+    fn handle_key_event(&mut self, event: crate::events::KeyEventMsg) -> ToolUpdateResult {
+        if event.key == Key::Escape && self.highlighter.is_some() {
+            self.highlighter = None;
+            return ToolUpdateResult::Redraw;
+        }
+        ToolUpdateResult::Unmodified
+    }
+
+    fn handle_key_release_event(&mut self, event: crate::events::KeyEventMsg) -> ToolUpdateResult {
+        // Adds an extra point when shift is released in the freehand mode, this
+        // allows for users to make sharper turns. Release shift a second time
+        // to remove the added point (only if the cursor has not moved).
+        if event.key == Key::Shift_L || event.key == Key::Shift_R {
+            if let Some(HighlightKind::Freehand(highlighter)) = &mut self.highlighter {
+                let points = &mut highlighter.data.points;
+                let last = points
+                    .last()
+                    .expect("line highlight must have at least one point");
+                if points.len() >= 2 {
+                    if *last == points[points.len() - 2] {
+                        points.pop();
+                    } else {
+                        points.push(*last);
+                    }
+                    return ToolUpdateResult::Redraw;
+                };
+            };
+        }
+        ToolUpdateResult::Unmodified
+    }
+
+    fn handle_style_event(&mut self, style: Style) -> ToolUpdateResult {
+        self.style = style;
+        ToolUpdateResult::Unmodified
+    }
+
+    fn get_drawable(&self) -> Option<&dyn Drawable> {
+        match &self.highlighter {
+            Some(d) => Some(d),
+            None => None,
+        }
+    }
+
+    fn set_sender(&mut self, sender: ToolSender) {
+        self.sender = Some(sender);
+    }
+}
@@ -1,24 +1,22 @@
 use anyhow::Result;
-use femtovg::{FontId, Path};
-use relm4::{
-    gtk::gdk::{Key, ModifierType},
-    Sender,
-};
+use femtovg::Path;
+use gtk::gdk::{Key, ModifierType};
 
 use crate::{
-    math::Vec2D,
-    sketch_board::{MouseButton, MouseEventMsg, MouseEventType, SketchBoardInput},
+    canvas::Fonts,
+    events::{MouseButton, MouseEventMsg, MouseEventType},
+    math::{self, Transformation, Vec2D},
     style::Style,
 };
 
-use super::{Drawable, DrawableClone, Tool, ToolUpdateResult, Tools};
+use super::{Annotation, Drawable, DrawableClone, Tool, ToolSender, ToolUpdateResult, Tools};
 
 #[derive(Default)]
 pub struct LineTool {
     line: Option<Line>,
     style: Style,
     input_enabled: bool,
-    sender: Option<Sender<SketchBoardInput>>,
+    sender: Option<ToolSender>,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -28,11 +26,21 @@ pub struct Line {
     style: Style,
 }
 
+impl Line {
+    pub fn new(start: Vec2D, end: Vec2D, style: Style) -> Self {
+        Self {
+            start,
+            direction: Some(end - start),
+            style,
+        }
+    }
+}
+
 impl Drawable for Line {
     fn draw(
         &self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        _font: FontId,
+        _fonts: &Fonts,
         _bounds: (Vec2D, Vec2D),
     ) -> Result<()> {
         let direction = match self.direction {
@@ -46,12 +54,55 @@ impl Drawable for Line {
         path.move_to(self.start.x, self.start.y);
         path.line_to(self.start.x + direction.x, self.start.y + direction.y);
 
-        canvas.stroke_path(&path, &self.style.into());
+        self.style.stroke_path(canvas, &path, &self.style.into());
 
         canvas.restore();
 
         Ok(())
     }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let Some(direction) = self.direction else {
+            return false;
+        };
+        let half_width = self
+            .style
+            .size
+            .to_line_width(self.style.annotation_size_factor)
+            / 2.0;
+
+        math::distance_to_segment(pos, self.start, self.start + direction) <= half_width + tolerance
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        let direction = self.direction?;
+        math::bounds_of_points([self.start, self.start + direction])
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.start = transformation.apply(self.start);
+        self.direction = self.direction.map(|d| transformation.apply_vector(d));
+    }
+
+    fn can_rotate(&self) -> bool {
+        true
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(Annotation::Line {
+            start: self.start,
+            end: self.start + self.direction?,
+            style: self.style.into(),
+        })
+    }
 }
 
 impl Tool for LineTool {
@@ -124,7 +175,7 @@ impl Tool for LineTool {
         }
     }
 
-    fn handle_key_event(&mut self, event: crate::sketch_board::KeyEventMsg) -> ToolUpdateResult {
+    fn handle_key_event(&mut self, event: crate::events::KeyEventMsg) -> ToolUpdateResult {
         if event.key == Key::Escape && self.line.is_some() {
             self.line = None;
             ToolUpdateResult::Redraw
@@ -149,7 +200,11 @@ impl Tool for LineTool {
         Tools::Line
     }
 
-    fn set_sender(&mut self, sender: Sender<SketchBoardInput>) {
+    fn snaps_to_guides(&self) -> bool {
+        true
+    }
+
+    fn set_sender(&mut self, sender: ToolSender) {
         self.sender = Some(sender);
     }
 }
@@ -0,0 +1,251 @@
+use std::cell::{Cell, RefCell};
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use femtovg::{Color, Paint, Path};
+
+use crate::events::{MouseButton, MouseEventType};
+use crate::style::Style;
+use crate::{
+    canvas::{draw_stamp, Fonts},
+    events::MouseEventMsg,
+    math::{Transformation, Vec2D},
+};
+
+use super::{Annotation, Drawable, DrawableClone, Tool, ToolSender, ToolUpdateResult, Tools};
+
+pub struct MarkerTool {
+    style: Style,
+    next_number: Rc<RefCell<u16>>,
+    input_enabled: bool,
+    sender: Option<ToolSender>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Marker {
+    pos: Vec2D,
+    number: u16,
+    style: Style,
+    tool_next_number: Rc<RefCell<u16>>,
+    // outer radius, only known after the text has been measured in draw
+    radius: Cell<f32>,
+}
+
+impl Marker {
+    pub fn new(pos: Vec2D, number: u16, style: Style, tool_next_number: Rc<RefCell<u16>>) -> Self {
+        Self {
+            pos,
+            number,
+            style,
+            tool_next_number,
+            radius: Cell::new(0.0),
+        }
+    }
+}
+
+impl Drawable for Marker {
+    fn draw(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        fonts: &Fonts,
+        _bounds: (Vec2D, Vec2D),
+    ) -> anyhow::Result<()> {
+        let text = format!("{}", self.number);
+
+        let marker_color: Color = self.style.color.into();
+        // https://en.wikipedia.org/wiki/Luma_(video)
+        let luminance = 0.2126 * marker_color.r + 0.7152 * marker_color.g + 0.0722 * marker_color.b;
+        let text_color = if luminance > 0.5 {
+            Color::black()
+        } else {
+            Color::white()
+        };
+
+        let mut paint = Paint::color(text_color);
+
+        paint.set_font(&[fonts.default_font()]);
+        paint.set_font_size(
+            (self
+                .style
+                .size
+                .to_text_size(self.style.annotation_size_factor)) as f32,
+        );
+        paint.set_text_align(femtovg::Align::Center);
+        paint.set_text_baseline(femtovg::Baseline::Middle);
+
+        let text_metrics = canvas.measure_text(self.pos.x, self.pos.y, &text, &paint)?;
+
+        let circle_radius = (text_metrics.width() * text_metrics.width()
+            + text_metrics.height() * text_metrics.height())
+        .sqrt();
+        self.radius.set(circle_radius);
+
+        // rings look the same for all markers of a style with the same number of digits,
+        // they are drawn from a cached image
+        let style = self.style;
+        let factor = style.annotation_size_factor;
+        let line_width = style.size.to_line_width(factor);
+        let extent = circle_radius
+            + line_width
+            + style.size.to_outline_width(factor)
+            + style.size.to_shadow_offset(factor)
+            + 1.0;
+        let key = (
+            Tools::Marker,
+            style.color,
+            style.size,
+            factor.to_bits(),
+            style.shadow,
+            style.outline,
+            (circle_radius * 4.0).round() as u32,
+        );
+
+        canvas.save();
+        draw_stamp(canvas, key, self.pos, extent, move |canvas| {
+            let mut inner_circle_path = Path::new();
+            inner_circle_path.arc(
+                0.0,
+                0.0,
+                circle_radius * 0.8,
+                0.0,
+                2.0 * PI as f32,
+                femtovg::Solidity::Solid,
+            );
+
+            let mut outer_circle_path = Path::new();
+            outer_circle_path.arc(
+                0.0,
+                0.0,
+                circle_radius,
+                0.0,
+                2.0 * PI as f32,
+                femtovg::Solidity::Solid,
+            );
+
+            let circle_paint = Paint::color(marker_color).with_line_width(line_width * 2.0);
+
+            // the inner circle covers the inner side of the ring's outline and shadow
+            style.stroke_path(canvas, &outer_circle_path, &circle_paint);
+            canvas.fill_path(&inner_circle_path, &circle_paint);
+        });
+        canvas.fill_text(self.pos.x, self.pos.y, &text, &paint)?;
+        canvas.restore();
+        Ok(())
+    }
+
+    fn handle_undo(&mut self) {
+        *self.tool_next_number.borrow_mut() = self.number;
+    }
+
+    fn handle_redo(&mut self) {
+        *self.tool_next_number.borrow_mut() = self.number + 1;
+    }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let border = self
+            .style
+            .size
+            .to_line_width(self.style.annotation_size_factor);
+        pos.distance_to(&self.pos) <= self.radius.get() + border + tolerance
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        let border = self
+            .style
+            .size
+            .to_line_width(self.style.annotation_size_factor);
+        let radius = self.radius.get() + border;
+        Some((
+            self.pos - Vec2D::new(radius, radius),
+            self.pos + Vec2D::new(radius, radius),
+        ))
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        // markers keep their size, only the position follows
+        self.pos = transformation.apply(self.pos);
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(Annotation::Marker {
+            pos: self.pos,
+            number: self.number,
+            style: self.style.into(),
+        })
+    }
+}
+
+impl Tool for MarkerTool {
+    fn input_enabled(&self) -> bool {
+        self.input_enabled
+    }
+
+    fn set_input_enabled(&mut self, value: bool) {
+        self.input_enabled = value;
+    }
+
+    fn get_tool_type(&self) -> super::Tools {
+        Tools::Marker
+    }
+
+    fn get_drawable(&self) -> Option<&dyn Drawable> {
+        None
+    }
+
+    fn handle_style_event(&mut self, style: Style) -> ToolUpdateResult {
+        self.style = style;
+        ToolUpdateResult::Unmodified
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEventMsg) -> ToolUpdateResult {
+        match event.type_ {
+            MouseEventType::Click => {
+                if event.button == MouseButton::Primary {
+                    let marker = Marker::new(
+                        event.pos,
+                        *self.next_number.borrow(),
+                        self.style,
+                        self.next_number.clone(),
+                    );
+
+                    // increment for next
+                    *self.next_number.borrow_mut() += 1;
+
+                    ToolUpdateResult::Commit(marker.clone_box())
+                } else {
+                    ToolUpdateResult::Unmodified
+                }
+            }
+            _ => ToolUpdateResult::Unmodified,
+        }
+    }
+
+    fn set_sender(&mut self, sender: ToolSender) {
+        self.sender = Some(sender);
+    }
+}
+
+impl MarkerTool {
+    pub fn numbers(&self) -> Rc<RefCell<u16>> {
+        self.next_number.clone()
+    }
+}
+
+impl Default for MarkerTool {
+    fn default() -> Self {
+        Self {
+            style: Default::default(),
+            next_number: Rc::new(RefCell::new(1)),
+            input_enabled: true,
+            sender: None,
+        }
+    }
+}
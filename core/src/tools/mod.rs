@@ -1,4 +1,5 @@
 use std::{
+    any::Any,
     borrow::Cow,
     cell::RefCell,
     collections::HashMap,
@@ -7,37 +8,39 @@ use std::{
 };
 
 use anyhow::Result;
-use femtovg::{renderer::OpenGl, Canvas, FontId};
+use femtovg::{renderer::OpenGl, Canvas};
 use gdk_pixbuf::{
     glib::{Variant, VariantTy},
     prelude::{StaticVariantType, ToVariant},
 };
 
 use glib::variant::FromVariant;
-use relm4::{
-    gtk::{self, IMMulticontext},
-    Sender,
-};
-use serde_derive::Deserialize;
+use gtk::{self, IMMulticontext};
+use serde_derive::{Deserialize, Serialize};
 
 use crate::{
-    math::Vec2D,
-    sketch_board::{InputEvent, KeyEventMsg, MouseEventMsg, SketchBoardInput, TextEventMsg},
+    canvas::Fonts,
+    events::{InputEvent, KeyEventMsg, MouseEventMsg, TextEventMsg},
+    math::{Transformation, Vec2D},
     style::Style,
 };
 
-use satty_cli::command_line;
-
+mod annotation;
 mod arrow;
 mod blur;
+mod brace;
 mod brush;
 mod crop;
 mod ellipse;
+mod eraser;
 mod highlight;
 mod line;
 mod marker;
 mod pointer;
 mod rectangle;
+mod ruler;
+mod select;
+mod table;
 mod text;
 
 pub enum ToolEvent {
@@ -117,11 +120,48 @@ pub trait Tool {
 
     fn set_im_context(&mut self, _context: Option<InputContext>) {}
 
+    /// Whether dragging with this tool snaps to the edges and centers of other drawables
+    fn snaps_to_guides(&self) -> bool {
+        false
+    }
+
     fn get_drawable(&self) -> Option<&dyn Drawable>;
 
     fn get_tool_type(&self) -> Tools;
 
-    fn set_sender(&mut self, sender: Sender<SketchBoardInput>);
+    fn set_sender(&mut self, sender: ToolSender);
+}
+
+/// What tools ask of whoever owns the committed drawables, the sketch board in the application
+#[derive(Debug, Clone)]
+pub enum ToolRequest {
+    /// draw again, e.g. after the clipboard got pasted asynchronously
+    Refresh,
+    /// remove the topmost drawable at the position, in image coordinates
+    EraseAt(Vec2D),
+    Select(SelectEvent),
+    /// continue editing the committed text at the position, in image coordinates
+    EditTextAt(Vec2D),
+}
+
+/// Delivers the [`ToolRequest`]s of the tools, in the order they are emitted
+#[derive(Clone)]
+pub struct ToolSender(Rc<dyn Fn(ToolRequest)>);
+
+impl ToolSender {
+    pub fn new(emit: impl Fn(ToolRequest) + 'static) -> Self {
+        Self(Rc::new(emit))
+    }
+
+    pub fn emit(&self, request: ToolRequest) {
+        (self.0)(request)
+    }
+}
+
+impl Debug for ToolSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ToolSender")
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -145,11 +185,71 @@ where
     }
 }
 
-pub trait Drawable: DrawableClone + Debug {
-    fn draw(&self, canvas: &mut Canvas<OpenGl>, font: FontId, bounds: (Vec2D, Vec2D))
-        -> Result<()>;
+/// Gives access to the concrete type of a drawable, e.g. to edit committed texts again
+pub trait DrawableAny {
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T> DrawableAny for T
+where
+    T: 'static + Drawable,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub trait Drawable: DrawableClone + DrawableAny + Debug {
+    fn draw(
+        &self,
+        canvas: &mut Canvas<OpenGl>,
+        fonts: &Fonts,
+        bounds: (Vec2D, Vec2D),
+    ) -> Result<()>;
     fn handle_undo(&mut self) {}
     fn handle_redo(&mut self) {}
+
+    /// Whether `pos` (in image coordinates) touches this drawable, `tolerance` widens the
+    /// hit area so thin strokes can be picked as well
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let _ = (pos, tolerance);
+        false
+    }
+
+    /// Bounding box (min and max corner) in image coordinates. Drawables without bounds can't
+    /// be selected for editing.
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        None
+    }
+
+    /// Move, resize or rotate the drawable. Rotations are only requested if `can_rotate` is set.
+    fn transform(&mut self, transformation: Transformation) {
+        let _ = transformation;
+    }
+
+    fn can_rotate(&self) -> bool {
+        false
+    }
+
+    /// The style of the drawable, if it can be changed after committing it
+    fn style(&self) -> Option<Style> {
+        None
+    }
+
+    fn set_style(&mut self, style: Style) {
+        let _ = style;
+    }
+
+    /// Text shown by the drawable, for searching annotations
+    fn text(&self) -> Option<String> {
+        None
+    }
+
+    /// The drawable as plain data to store it, `None` while it is unfinished or if it can't
+    /// be stored
+    fn annotation(&self) -> Option<Annotation> {
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -161,18 +261,26 @@ pub enum ToolUpdateResult {
     RedrawAndStopPropagation,
 }
 
+pub use annotation::{Annotation, AnnotationStyle};
 pub use arrow::ArrowTool;
-pub use blur::BlurTool;
+pub use blur::{Blur, BlurTool};
+pub use brace::BraceTool;
 pub use crop::CropTool;
 pub use ellipse::EllipseTool;
-pub use highlight::{HighlightTool, Highlighters};
+pub use highlight::{HighlightBlend, HighlightTool, Highlighters};
 pub use line::LineTool;
 pub use rectangle::RectangleTool;
+pub use ruler::RulerTool;
+pub use select::{SelectEvent, Selection};
+pub use table::TableTool;
 pub use text::TextTool;
 
-use self::{brush::BrushTool, marker::MarkerTool, pointer::PointerTool};
+use self::{
+    brush::BrushTool, eraser::EraserTool, marker::MarkerTool, pointer::PointerTool,
+    select::SelectTool,
+};
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Deserialize)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Tools {
     Pointer = 0,
@@ -186,9 +294,66 @@ pub enum Tools {
     Blur = 8,
     Highlight = 9,
     Brush = 10,
+    Eraser = 11,
+    Select = 12,
+    Ruler = 13,
+    Table = 14,
+    Brace = 15,
 }
 
 impl Tools {
+    /// All tools in toolbar order
+    pub const ALL: [Tools; 16] = [
+        Tools::Pointer,
+        Tools::Select,
+        Tools::Crop,
+        Tools::Brush,
+        Tools::Line,
+        Tools::Arrow,
+        Tools::Rectangle,
+        Tools::Ellipse,
+        Tools::Table,
+        Tools::Text,
+        Tools::Marker,
+        Tools::Blur,
+        Tools::Highlight,
+        Tools::Ruler,
+        Tools::Brace,
+        Tools::Eraser,
+    ];
+
+    /// The tool next to this one in toolbar order, wrapping around at both ends
+    pub fn cycle(self, forward: bool) -> Tools {
+        let index = Self::ALL.iter().position(|t| *t == self).unwrap_or(0);
+        let len = Self::ALL.len();
+        if forward {
+            Self::ALL[(index + 1) % len]
+        } else {
+            Self::ALL[(index + len - 1) % len]
+        }
+    }
+
+    /// Name of the pointer cursor while the tool is active
+    pub fn cursor_name(&self) -> &'static str {
+        match self {
+            Tools::Pointer | Tools::Select => "default",
+            Tools::Text => "text",
+            Tools::Eraser => "cell",
+            Tools::Crop
+            | Tools::Line
+            | Tools::Arrow
+            | Tools::Rectangle
+            | Tools::Ellipse
+            | Tools::Marker
+            | Tools::Blur
+            | Tools::Highlight
+            | Tools::Brush
+            | Tools::Ruler
+            | Tools::Table
+            | Tools::Brace => "crosshair",
+        }
+    }
+
     pub fn display_name(&self) -> &'static str {
         match self {
             Tools::Pointer => "Pointer",
@@ -202,6 +367,11 @@ impl Tools {
             Tools::Marker => "Numbered Marker",
             Tools::Blur => "Blur",
             Tools::Highlight => "Highlight",
+            Tools::Eraser => "Eraser",
+            Tools::Select => "Select",
+            Tools::Ruler => "Ruler",
+            Tools::Table => "Table",
+            Tools::Brace => "Brace",
         }
     }
 }
@@ -221,6 +391,11 @@ impl Display for Tools {
             Self::Blur => write!(f, "blur"),
             Self::Highlight => write!(f, "highlight"),
             Self::Brush => write!(f, "brush"),
+            Self::Eraser => write!(f, "eraser"),
+            Self::Select => write!(f, "select"),
+            Self::Ruler => write!(f, "ruler"),
+            Self::Table => write!(f, "table"),
+            Self::Brace => write!(f, "brace"),
         }
     }
 }
@@ -228,6 +403,14 @@ impl Display for Tools {
 pub struct ToolsManager {
     tools: HashMap<Tools, Rc<RefCell<dyn Tool>>>,
     crop_tool: Rc<RefCell<CropTool>>,
+    text_tool: Rc<RefCell<TextTool>>,
+    marker_tool: Rc<RefCell<MarkerTool>>,
+}
+
+impl Default for ToolsManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ToolsManager {
@@ -248,17 +431,29 @@ impl ToolsManager {
             Tools::Ellipse,
             Rc::new(RefCell::new(EllipseTool::default())),
         );
-        tools.insert(Tools::Text, Rc::new(RefCell::new(TextTool::default())));
+        let text_tool = Rc::new(RefCell::new(TextTool::default()));
+        tools.insert(Tools::Text, text_tool.clone());
         tools.insert(Tools::Blur, Rc::new(RefCell::new(BlurTool::default())));
         tools.insert(
             Tools::Highlight,
             Rc::new(RefCell::new(HighlightTool::default())),
         );
-        tools.insert(Tools::Marker, Rc::new(RefCell::new(MarkerTool::default())));
+        let marker_tool = Rc::new(RefCell::new(MarkerTool::default()));
+        tools.insert(Tools::Marker, marker_tool.clone());
         tools.insert(Tools::Brush, Rc::new(RefCell::new(BrushTool::default())));
+        tools.insert(Tools::Eraser, Rc::new(RefCell::new(EraserTool::default())));
+        tools.insert(Tools::Select, Rc::new(RefCell::new(SelectTool::default())));
+        tools.insert(Tools::Ruler, Rc::new(RefCell::new(RulerTool::default())));
+        tools.insert(Tools::Table, Rc::new(RefCell::new(TableTool::default())));
+        tools.insert(Tools::Brace, Rc::new(RefCell::new(BraceTool::default())));
 
         let crop_tool = Rc::new(RefCell::new(CropTool::default()));
-        Self { tools, crop_tool }
+        Self {
+            tools,
+            crop_tool,
+            text_tool,
+            marker_tool,
+        }
     }
 
     pub fn get(&self, tool: &Tools) -> Rc<RefCell<dyn Tool>> {
@@ -277,6 +472,15 @@ impl ToolsManager {
     pub fn get_crop_tool(&self) -> Rc<RefCell<CropTool>> {
         self.crop_tool.clone()
     }
+
+    pub fn get_text_tool(&self) -> Rc<RefCell<TextTool>> {
+        self.text_tool.clone()
+    }
+
+    /// Number the next marker gets, shared by the marker tool and its markers
+    pub fn marker_numbers(&self) -> Rc<RefCell<u16>> {
+        self.marker_tool.borrow().numbers()
+    }
 }
 
 impl StaticVariantType for Tools {
@@ -305,25 +509,12 @@ impl FromVariant for Tools {
             8 => Some(Tools::Blur),
             9 => Some(Tools::Highlight),
             10 => Some(Tools::Brush),
+            11 => Some(Tools::Eraser),
+            12 => Some(Tools::Select),
+            13 => Some(Tools::Ruler),
+            14 => Some(Tools::Table),
+            15 => Some(Tools::Brace),
             _ => None,
         })
     }
 }
-
-impl From<command_line::Tools> for Tools {
-    fn from(tool: command_line::Tools) -> Self {
-        match tool {
-            command_line::Tools::Pointer => Self::Pointer,
-            command_line::Tools::Crop => Self::Crop,
-            command_line::Tools::Line => Self::Line,
-            command_line::Tools::Arrow => Self::Arrow,
-            command_line::Tools::Rectangle => Self::Rectangle,
-            command_line::Tools::Ellipse => Self::Ellipse,
-            command_line::Tools::Text => Self::Text,
-            command_line::Tools::Marker => Self::Marker,
-            command_line::Tools::Blur => Self::Blur,
-            command_line::Tools::Highlight => Self::Highlight,
-            command_line::Tools::Brush => Self::Brush,
-        }
-    }
-}
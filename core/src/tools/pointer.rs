@@ -1,11 +1,9 @@
-use super::{Tool, Tools};
-use crate::sketch_board::SketchBoardInput;
-use relm4::Sender;
+use super::{Tool, ToolSender, Tools};
 
 #[derive(Default)]
 pub struct PointerTool {
     input_enabled: bool,
-    sender: Option<Sender<SketchBoardInput>>,
+    sender: Option<ToolSender>,
 }
 
 impl Tool for PointerTool {
@@ -25,7 +23,7 @@ impl Tool for PointerTool {
         self.input_enabled = value;
     }
 
-    fn set_sender(&mut self, sender: Sender<SketchBoardInput>) {
+    fn set_sender(&mut self, sender: ToolSender) {
         self.sender = Some(sender);
     }
 }
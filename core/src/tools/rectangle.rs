@@ -1,18 +1,16 @@
 use anyhow::Result;
-use femtovg::{FontId, Path};
-use relm4::{
-    gtk::gdk::{Key, ModifierType},
-    Sender,
-};
+use femtovg::Path;
+use gtk::gdk::{Key, ModifierType};
 
 use crate::{
-    configuration::APP_CONFIG,
-    math::Vec2D,
-    sketch_board::{MouseButton, MouseEventMsg, MouseEventType, SketchBoardInput},
+    canvas::Fonts,
+    configuration::DRAWING_CONFIG,
+    events::{MouseButton, MouseEventMsg, MouseEventType},
+    math::{self, Transformation, Vec2D},
     style::Style,
 };
 
-use super::{Drawable, DrawableClone, Tool, ToolUpdateResult, Tools};
+use super::{Annotation, Drawable, DrawableClone, Tool, ToolSender, ToolUpdateResult, Tools};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Rectangle {
@@ -28,7 +26,7 @@ impl Drawable for Rectangle {
     fn draw(
         &self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        _font: FontId,
+        _fonts: &Fonts,
         _bounds: (Vec2D, Vec2D),
     ) -> Result<()> {
         let size = match self.size {
@@ -43,7 +41,7 @@ impl Drawable for Rectangle {
             self.top_left.y,
             size.x,
             size.y,
-            APP_CONFIG.read().corner_roundness(),
+            DRAWING_CONFIG.read().corner_roundness,
         );
 
         if !self.finishing && self.centered {
@@ -56,48 +54,84 @@ impl Drawable for Rectangle {
         }
 
         if self.style.fill {
-            canvas.fill_path(&path, &self.style.into());
+            self.style.fill_path(canvas, &path, &self.style.into());
         } else {
-            canvas.stroke_path(&path, &self.style.into());
+            self.style.stroke_path(canvas, &path, &self.style.into());
         }
         canvas.restore();
 
         Ok(())
     }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let Some(size) = self.size else {
+            return false;
+        };
+        if self.style.fill {
+            return math::rect_contains((self.top_left, size), pos, tolerance);
+        }
+
+        // outlines are only hit on the border, not in the empty inside
+        let margin = self
+            .style
+            .size
+            .to_line_width(self.style.annotation_size_factor)
+            / 2.0
+            + tolerance;
+        let (top_left, size) = math::rect_ensure_positive_size(self.top_left, size);
+        let inner = (
+            top_left + Vec2D::new(margin, margin),
+            size - Vec2D::new(2.0 * margin, 2.0 * margin),
+        );
+        math::rect_contains((top_left, size), pos, margin)
+            && (inner.1.x <= 0.0 || inner.1.y <= 0.0 || !math::rect_contains(inner, pos, 0.0))
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        Some(math::rect_to_bounds(self.top_left, self.size?))
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.origin = transformation.apply(self.origin);
+        self.top_left = transformation.apply(self.top_left);
+        self.size = self.size.map(|s| transformation.apply_vector(s));
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(Annotation::Rectangle {
+            top_left: self.top_left,
+            size: self.size?,
+            style: self.style.into(),
+        })
+    }
 }
 
 impl Rectangle {
-    fn calculate_shape(&mut self, event: &MouseEventMsg) {
-        self.centered = event.modifier & ModifierType::ALT_MASK == ModifierType::ALT_MASK;
-        match event.modifier & (ModifierType::ALT_MASK | ModifierType::SHIFT_MASK) {
-            v if v == ModifierType::ALT_MASK | ModifierType::SHIFT_MASK => {
-                let max_size = event.pos.x.abs().max(event.pos.y.abs());
-                self.top_left.x = self.origin.x - max_size * event.pos.x.signum() / 2.0;
-                self.top_left.y = self.origin.y - max_size * event.pos.y.signum() / 2.0;
-                self.size = Some(Vec2D {
-                    x: max_size * event.pos.x.signum(),
-                    y: max_size * event.pos.y.signum(),
-                });
-            }
-            ModifierType::ALT_MASK => {
-                self.top_left.x = self.origin.x - event.pos.x / 2.0;
-                self.top_left.y = self.origin.y - event.pos.y / 2.0;
-                self.size = Some(event.pos);
-            }
-            ModifierType::SHIFT_MASK => {
-                self.top_left = self.origin;
-                let max_size = event.pos.x.abs().max(event.pos.y.abs());
-                self.size = Some(Vec2D {
-                    x: max_size * event.pos.x.signum(),
-                    y: max_size * event.pos.y.signum(),
-                });
-            }
-            _ => {
-                self.top_left = self.origin;
-                self.size = Some(event.pos);
-            }
+    pub fn new(top_left: Vec2D, size: Vec2D, style: Style) -> Self {
+        Self {
+            origin: top_left,
+            top_left,
+            size: Some(size),
+            style,
+            centered: false,
+            finishing: true,
         }
     }
+
+    fn calculate_shape(&mut self, event: &MouseEventMsg) {
+        self.centered = event.modifier.contains(ModifierType::CONTROL_MASK);
+        let (top_left, size) = event.drag_rect(self.origin);
+        self.top_left = top_left;
+        self.size = Some(size);
+    }
 }
 
 #[derive(Default)]
@@ -105,7 +139,7 @@ pub struct RectangleTool {
     rectangle: Option<Rectangle>,
     style: Style,
     input_enabled: bool,
-    sender: Option<Sender<SketchBoardInput>>,
+    sender: Option<ToolSender>,
 }
 
 impl Tool for RectangleTool {
@@ -175,7 +209,7 @@ impl Tool for RectangleTool {
         }
     }
 
-    fn handle_key_event(&mut self, event: crate::sketch_board::KeyEventMsg) -> ToolUpdateResult {
+    fn handle_key_event(&mut self, event: crate::events::KeyEventMsg) -> ToolUpdateResult {
         if event.key == Key::Escape && self.rectangle.is_some() {
             self.rectangle = None;
             ToolUpdateResult::Redraw
@@ -200,7 +234,11 @@ impl Tool for RectangleTool {
         Tools::Rectangle
     }
 
-    fn set_sender(&mut self, sender: Sender<SketchBoardInput>) {
+    fn snaps_to_guides(&self) -> bool {
+        true
+    }
+
+    fn set_sender(&mut self, sender: ToolSender) {
         self.sender = Some(sender);
     }
 }
@@ -0,0 +1,239 @@
+use anyhow::Result;
+use femtovg::{Paint, Path};
+use gtk::gdk::{Key, ModifierType};
+
+use crate::{
+    canvas::Fonts,
+    configuration::DRAWING_CONFIG,
+    events::{MouseButton, MouseEventMsg, MouseEventType},
+    math::{self, Transformation, Vec2D},
+    style::Style,
+};
+
+use super::{Annotation, Drawable, DrawableClone, Tool, ToolSender, ToolUpdateResult, Tools};
+
+/// length of the ticks at both ends, in line widths
+const TICK_LENGTH: f32 = 4.0;
+
+#[derive(Default)]
+pub struct RulerTool {
+    ruler: Option<Ruler>,
+    style: Style,
+    input_enabled: bool,
+    sender: Option<ToolSender>,
+}
+
+/// Line labeled with its length in image pixels, and in the configured unit if there is one
+#[derive(Clone, Copy, Debug)]
+pub struct Ruler {
+    start: Vec2D,
+    direction: Option<Vec2D>,
+    style: Style,
+}
+
+impl Ruler {
+    pub fn new(start: Vec2D, end: Vec2D, style: Style) -> Self {
+        Self {
+            start,
+            direction: Some(end - start),
+            style,
+        }
+    }
+
+    fn label(length: f32) -> String {
+        let config = DRAWING_CONFIG.read();
+        match config.ruler_scale {
+            Some(scale) => format!(
+                "{length:.0} px · {:.1} {}",
+                length * scale,
+                config.ruler_unit
+            ),
+            None => format!("{length:.0} px"),
+        }
+    }
+
+    fn line_width(&self) -> f32 {
+        self.style
+            .size
+            .to_line_width(self.style.annotation_size_factor)
+    }
+}
+
+impl Drawable for Ruler {
+    fn draw(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        fonts: &Fonts,
+        _bounds: (Vec2D, Vec2D),
+    ) -> Result<()> {
+        let Some(direction) = self.direction else {
+            return Ok(());
+        };
+        let end = self.start + direction;
+        let length = direction.norm();
+
+        canvas.save();
+
+        let mut path = Path::new();
+        path.move_to(self.start.x, self.start.y);
+        path.line_to(end.x, end.y);
+
+        // ticks across both ends mark where the measurement starts and stops
+        if length > 0.0 {
+            let normal = Vec2D::new(-direction.y, direction.x)
+                * (TICK_LENGTH * self.line_width() / 2.0 / length);
+            for p in [self.start, end] {
+                path.move_to(p.x - normal.x, p.y - normal.y);
+                path.line_to(p.x + normal.x, p.y + normal.y);
+            }
+        }
+        self.style.stroke_path(canvas, &path, &self.style.into());
+
+        // the label stays horizontal on a plate in the line color, centered on the line
+        let center = self.start + direction * 0.5;
+        let label = Self::label(length);
+        let mut text_paint = Paint::color(self.style.color.contrasting().into());
+        text_paint.set_font(&[fonts.default_font()]);
+        text_paint.set_font_size(self.style.text_size());
+        text_paint.set_text_align(femtovg::Align::Center);
+        text_paint.set_text_baseline(femtovg::Baseline::Middle);
+
+        let metrics = canvas.measure_text(center.x, center.y, &label, &text_paint)?;
+        let padding = metrics.height() * 0.25;
+        let mut plate = Path::new();
+        plate.rounded_rect(
+            metrics.x - padding,
+            metrics.y - padding,
+            metrics.width() + 2.0 * padding,
+            metrics.height() + 2.0 * padding,
+            padding,
+        );
+        canvas.fill_path(&plate, &Paint::color(self.style.color.into()));
+        canvas.fill_text(center.x, center.y, &label, &text_paint)?;
+
+        canvas.restore();
+        Ok(())
+    }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        let Some(direction) = self.direction else {
+            return false;
+        };
+        math::distance_to_segment(pos, self.start, self.start + direction)
+            <= self.line_width() / 2.0 + tolerance
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        let direction = self.direction?;
+        math::bounds_of_points([self.start, self.start + direction])
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.start = transformation.apply(self.start);
+        self.direction = self.direction.map(|d| transformation.apply_vector(d));
+    }
+
+    fn can_rotate(&self) -> bool {
+        true
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(Annotation::Ruler {
+            start: self.start,
+            end: self.start + self.direction?,
+            style: self.style.into(),
+        })
+    }
+}
+
+impl Tool for RulerTool {
+    fn input_enabled(&self) -> bool {
+        self.input_enabled
+    }
+
+    fn set_input_enabled(&mut self, value: bool) {
+        self.input_enabled = value;
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEventMsg) -> ToolUpdateResult {
+        if event.button == MouseButton::Middle {
+            return ToolUpdateResult::Unmodified;
+        }
+
+        match event.type_ {
+            MouseEventType::BeginDrag => {
+                self.ruler = Some(Ruler {
+                    start: event.pos,
+                    direction: None,
+                    style: self.style,
+                });
+                ToolUpdateResult::Redraw
+            }
+            MouseEventType::UpdateDrag | MouseEventType::EndDrag => {
+                let Some(ruler) = &mut self.ruler else {
+                    return ToolUpdateResult::Unmodified;
+                };
+                if event.type_ == MouseEventType::EndDrag && event.pos == Vec2D::zero() {
+                    self.ruler = None;
+                    return ToolUpdateResult::Redraw;
+                }
+
+                // shift measures along 15 degree steps, e.g. exactly horizontal distances
+                ruler.direction = Some(if event.modifier.intersects(ModifierType::SHIFT_MASK) {
+                    event.pos.snapped_vector_15deg()
+                } else {
+                    event.pos
+                });
+
+                if event.type_ == MouseEventType::UpdateDrag {
+                    return ToolUpdateResult::Redraw;
+                }
+                let result = ruler.clone_box();
+                self.ruler = None;
+                ToolUpdateResult::Commit(result)
+            }
+            _ => ToolUpdateResult::Unmodified,
+        }
+    }
+
+    fn handle_key_event(&mut self, event: crate::events::KeyEventMsg) -> ToolUpdateResult {
+        if event.key == Key::Escape && self.ruler.is_some() {
+            self.ruler = None;
+            ToolUpdateResult::Redraw
+        } else {
+            ToolUpdateResult::Unmodified
+        }
+    }
+
+    fn handle_style_event(&mut self, style: Style) -> ToolUpdateResult {
+        self.style = style;
+        ToolUpdateResult::Unmodified
+    }
+
+    fn get_drawable(&self) -> Option<&dyn Drawable> {
+        match &self.ruler {
+            Some(d) => Some(d),
+            None => None,
+        }
+    }
+
+    fn get_tool_type(&self) -> super::Tools {
+        Tools::Ruler
+    }
+
+    fn snaps_to_guides(&self) -> bool {
+        true
+    }
+
+    fn set_sender(&mut self, sender: ToolSender) {
+        self.sender = Some(sender);
+    }
+}
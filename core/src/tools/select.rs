@@ -0,0 +1,443 @@
+use anyhow::Result;
+use femtovg::{Color, Paint, Path};
+
+use crate::{
+    events::{MouseButton, MouseEventMsg, MouseEventType},
+    math::{bounds_of_points, rect_to_bounds, Angle, Transformation, Vec2D},
+    style::Style,
+};
+
+use super::{crop::Crop, Drawable, Tool, ToolRequest, ToolSender, ToolUpdateResult, Tools};
+
+/// Edits committed drawables. The drawables and the selection are owned by the renderer,
+/// this tool only forwards the user input to it.
+#[derive(Default)]
+pub struct SelectTool {
+    // start of the current drag, drag updates are relative to it
+    start: Option<Vec2D>,
+    style: Style,
+    input_enabled: bool,
+    sender: Option<ToolSender>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SelectEvent {
+    /// grab a handle of the selected drawable or select the drawable at the position
+    Begin(Vec2D),
+    /// drag whatever got grabbed to the position
+    Update(Vec2D),
+    End,
+    /// apply a change made in the style toolbar to the selected drawable
+    Restyle {
+        previous: Style,
+        current: Style,
+    },
+    Clear,
+}
+
+/// Committed drawables selected for editing, identified by their index in the drawables list.
+/// Several drawables are transformed together around their common bounds.
+pub struct Selection {
+    indices: Vec<usize>,
+    drag: Option<SelectionDrag>,
+    // start and current corner of a rubber band selecting all drawables inside it
+    rubber_band: Option<(Vec2D, Vec2D)>,
+}
+
+struct SelectionDrag {
+    // `None` moves the whole selection
+    handle: Option<SelectionHandle>,
+    start: Vec2D,
+    bounds: (Vec2D, Vec2D),
+    // the drawables as they were before the drag, every update transforms fresh copies
+    originals: Vec<(usize, Box<dyn Drawable>)>,
+    changed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SelectionHandle {
+    /// position of the handle relative to the bounds, 0.0, 0.5 or 1.0 on each axis
+    Resize(Vec2D),
+    Rotate,
+}
+
+impl Selection {
+    /// radius around the pointer in which drawables get selected, in screen pixels
+    pub const PICK_RADIUS: f32 = 6.0;
+    /// offset of duplicated drawables on both axes, in screen pixels
+    pub const DUPLICATE_OFFSET: f32 = 12.0;
+    // in screen pixels as well
+    const GRAB_RADIUS: f32 = 10.0;
+    const ROTATE_HANDLE_DISTANCE: f32 = 24.0;
+
+    pub fn new(indices: Vec<usize>) -> Self {
+        Self {
+            indices,
+            drag: None,
+            rubber_band: None,
+        }
+    }
+
+    /// Start a rubber band at `pos`, nothing is selected until it gets released
+    pub fn rubber_band(pos: Vec2D) -> Self {
+        Self {
+            indices: Vec::new(),
+            drag: None,
+            rubber_band: Some((pos, pos)),
+        }
+    }
+
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Whether the selected drawables are being moved, resized or rotated
+    pub fn dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.indices.contains(&index)
+    }
+
+    /// Common bounds of the selected drawables
+    pub fn bounds(&self, drawables: &[Box<dyn Drawable>]) -> Option<(Vec2D, Vec2D)> {
+        bounds_of_points(
+            self.indices
+                .iter()
+                .filter_map(|index| drawables[*index].bounds())
+                .flat_map(|(min, max)| [min, max]),
+        )
+    }
+
+    fn can_rotate(&self, drawables: &[Box<dyn Drawable>]) -> bool {
+        self.indices
+            .iter()
+            .all(|index| drawables[*index].can_rotate())
+    }
+
+    fn handles(
+        bounds: (Vec2D, Vec2D),
+        can_rotate: bool,
+        pixel: f32,
+    ) -> Vec<(SelectionHandle, Vec2D)> {
+        let (min, max) = bounds;
+        let size = max - min;
+
+        let mut handles: Vec<_> = [
+            (0.0, 0.0),
+            (0.5, 0.0),
+            (1.0, 0.0),
+            (1.0, 0.5),
+            (1.0, 1.0),
+            (0.5, 1.0),
+            (0.0, 1.0),
+            (0.0, 0.5),
+        ]
+        .into_iter()
+        .map(|(x, y)| {
+            (
+                SelectionHandle::Resize(Vec2D::new(x, y)),
+                min + Vec2D::new(size.x * x, size.y * y),
+            )
+        })
+        .collect();
+
+        if can_rotate {
+            handles.push((
+                SelectionHandle::Rotate,
+                Vec2D::new(
+                    min.x + size.x / 2.0,
+                    min.y - Self::ROTATE_HANDLE_DISTANCE * pixel,
+                ),
+            ));
+        }
+        handles
+    }
+
+    /// Start dragging a handle of the selection, returns false if there is no handle at
+    /// `pos`. `pixel` is the size of a screen pixel in image coordinates.
+    pub fn grab_handle(&mut self, drawables: &[Box<dyn Drawable>], pos: Vec2D, pixel: f32) -> bool {
+        let Some(bounds) = self.bounds(drawables) else {
+            return false;
+        };
+
+        let handle = Self::handles(bounds, self.can_rotate(drawables), pixel)
+            .into_iter()
+            .find(|(_, handle_pos)| handle_pos.distance_to(&pos) <= Self::GRAB_RADIUS * pixel);
+
+        match handle {
+            Some((handle, _)) => {
+                self.start_drag(drawables, Some(handle), pos, bounds);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Start moving the selected drawables
+    pub fn grab(&mut self, drawables: &[Box<dyn Drawable>], pos: Vec2D) {
+        if let Some(bounds) = self.bounds(drawables) {
+            self.start_drag(drawables, None, pos, bounds);
+        }
+    }
+
+    fn start_drag(
+        &mut self,
+        drawables: &[Box<dyn Drawable>],
+        handle: Option<SelectionHandle>,
+        start: Vec2D,
+        bounds: (Vec2D, Vec2D),
+    ) {
+        self.drag = Some(SelectionDrag {
+            handle,
+            start,
+            bounds,
+            originals: self
+                .indices
+                .iter()
+                .map(|index| (*index, drawables[*index].clone_box()))
+                .collect(),
+            changed: false,
+        });
+    }
+
+    /// The selected drawables with their indices as they look after dragging to `pos`
+    pub fn drag_to(&mut self, pos: Vec2D) -> Vec<(usize, Box<dyn Drawable>)> {
+        if let Some(rubber_band) = &mut self.rubber_band {
+            rubber_band.1 = pos;
+            return Vec::new();
+        }
+        let Some(drag) = self.drag.as_mut() else {
+            return Vec::new();
+        };
+        drag.changed |= pos != drag.start;
+
+        let transformation = drag.transformation(pos);
+        drag.originals
+            .iter()
+            .map(|(index, original)| {
+                let mut drawable = original.clone_box();
+                drawable.transform(transformation);
+                (*index, drawable)
+            })
+            .collect()
+    }
+
+    /// Finish the current drag, returns the drawables as they were before if they got
+    /// changed. A rubber band selects all drawables completely inside of it.
+    pub fn release(&mut self, drawables: &[Box<dyn Drawable>]) -> Vec<(usize, Box<dyn Drawable>)> {
+        if let Some((start, end)) = self.rubber_band.take() {
+            let (min, max) = rect_to_bounds(start, end - start);
+            self.indices = drawables
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| {
+                    d.bounds().is_some_and(|(d_min, d_max)| {
+                        d_min.x >= min.x && d_min.y >= min.y && d_max.x <= max.x && d_max.y <= max.y
+                    })
+                })
+                .map(|(index, _)| index)
+                .collect();
+            return Vec::new();
+        }
+
+        self.drag
+            .take()
+            .filter(|drag| drag.changed)
+            .map(|drag| drag.originals)
+            .unwrap_or_default()
+    }
+
+    /// Points that snap to guides when dragging to `pos`, the grabbed edges and the
+    /// center when moving the whole selection
+    pub fn snap_anchors(&self, pos: Vec2D) -> Vec<Vec2D> {
+        let Some(drag) = &self.drag else {
+            return Vec::new();
+        };
+        let (min, max) = drag.bounds;
+        let offset = pos - drag.start;
+
+        match drag.handle {
+            None => vec![min + offset, (min + max) * 0.5 + offset, max + offset],
+            Some(SelectionHandle::Resize(relative)) => {
+                let size = max - min;
+                vec![min + Vec2D::new(size.x * relative.x, size.y * relative.y) + offset]
+            }
+            Some(SelectionHandle::Rotate) => Vec::new(),
+        }
+    }
+
+    pub fn draw(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        drawables: &[Box<dyn Drawable>],
+    ) -> Result<()> {
+        let scale = canvas.transform().average_scale();
+        let pixel = 1.0 / scale;
+        let color = Color::rgbf(0.2, 0.5, 0.9);
+        let border_paint = Paint::color(color).with_line_width(1.5 * pixel);
+
+        canvas.save();
+        if let Some((start, end)) = self.rubber_band {
+            let mut path = Path::new();
+            let size = end - start;
+            path.rect(start.x, start.y, size.x, size.y);
+            canvas.fill_path(&path, &Paint::color(Color::rgbaf(0.2, 0.5, 0.9, 0.15)));
+            canvas.stroke_path(&path, &border_paint);
+        }
+
+        // outline every drawable of a group, the handles belong to the common bounds
+        if self.indices.len() > 1 {
+            let mut path = Path::new();
+            for (min, max) in self
+                .indices
+                .iter()
+                .filter_map(|index| drawables[*index].bounds())
+            {
+                path.rect(min.x, min.y, max.x - min.x, max.y - min.y);
+            }
+            canvas.stroke_path(&path, &Paint::color(color).with_line_width(pixel));
+        }
+
+        if let Some((min, max)) = self.bounds(drawables) {
+            let size = max - min;
+            let mut border_path = Path::new();
+            border_path.rect(min.x, min.y, size.x, size.y);
+
+            let handles = Self::handles((min, max), self.can_rotate(drawables), pixel);
+            if let Some((_, rotate_pos)) = handles
+                .iter()
+                .find(|(handle, _)| *handle == SelectionHandle::Rotate)
+            {
+                border_path.move_to(rotate_pos.x, rotate_pos.y);
+                border_path.line_to(rotate_pos.x, min.y);
+            }
+            canvas.stroke_path(&border_path, &border_paint);
+
+            for (_, pos) in handles {
+                Crop::draw_single_handle(canvas, pos, scale);
+            }
+        }
+        canvas.restore();
+        Ok(())
+    }
+}
+
+impl SelectionDrag {
+    fn transformation(&self, pos: Vec2D) -> Transformation {
+        let (min, max) = self.bounds;
+        let size = max - min;
+
+        match self.handle {
+            None => Transformation::Translate(pos - self.start),
+            Some(SelectionHandle::Rotate) => {
+                let center = min + size * 0.5;
+                Transformation::Rotate {
+                    center,
+                    angle: Angle::from_radians(
+                        (pos - center).angle().radians - (self.start - center).angle().radians,
+                    ),
+                }
+            }
+            Some(SelectionHandle::Resize(relative)) => {
+                // the opposite side stays in place, edge handles only scale one axis
+                let handle = min + Vec2D::new(size.x * relative.x, size.y * relative.y);
+                let origin =
+                    min + Vec2D::new(size.x * (1.0 - relative.x), size.y * (1.0 - relative.y));
+                let target = handle + (pos - self.start);
+                let factor = |relative: f32, handle: f32, target: f32, origin: f32| {
+                    if relative == 0.5 || (handle - origin).abs() < f32::EPSILON {
+                        1.0
+                    } else {
+                        (target - origin) / (handle - origin)
+                    }
+                };
+
+                Transformation::Scale {
+                    origin,
+                    factor: Vec2D::new(
+                        factor(relative.x, handle.x, target.x, origin.x),
+                        factor(relative.y, handle.y, target.y, origin.y),
+                    ),
+                }
+            }
+        }
+    }
+}
+
+impl SelectTool {
+    fn emit(&self, event: SelectEvent) {
+        if let Some(sender) = &self.sender {
+            sender.emit(ToolRequest::Select(event));
+        }
+    }
+}
+
+impl Tool for SelectTool {
+    fn input_enabled(&self) -> bool {
+        self.input_enabled
+    }
+
+    fn set_input_enabled(&mut self, value: bool) {
+        self.input_enabled = value;
+    }
+
+    fn get_tool_type(&self) -> super::Tools {
+        Tools::Select
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEventMsg) -> ToolUpdateResult {
+        if event.button != MouseButton::Primary {
+            return ToolUpdateResult::Unmodified;
+        }
+
+        match event.type_ {
+            MouseEventType::BeginDrag => {
+                self.start = Some(event.pos);
+                self.emit(SelectEvent::Begin(event.pos));
+            }
+            MouseEventType::UpdateDrag | MouseEventType::EndDrag => {
+                let Some(start) = self.start else {
+                    return ToolUpdateResult::Unmodified;
+                };
+                self.emit(SelectEvent::Update(start + event.pos));
+                if event.type_ == MouseEventType::EndDrag {
+                    self.start = None;
+                    self.emit(SelectEvent::End);
+                }
+            }
+            MouseEventType::Click if event.n_pressed == 2 => {
+                if let Some(sender) = &self.sender {
+                    sender.emit(ToolRequest::EditTextAt(event.pos));
+                }
+            }
+            _ => (),
+        }
+        ToolUpdateResult::Unmodified
+    }
+
+    fn handle_style_event(&mut self, style: Style) -> ToolUpdateResult {
+        let previous = std::mem::replace(&mut self.style, style);
+        self.emit(SelectEvent::Restyle {
+            previous,
+            current: style,
+        });
+        ToolUpdateResult::Unmodified
+    }
+
+    fn handle_deactivated(&mut self) -> ToolUpdateResult {
+        self.start = None;
+        self.emit(SelectEvent::Clear);
+        ToolUpdateResult::Unmodified
+    }
+
+    fn get_drawable(&self) -> Option<&dyn Drawable> {
+        // the selection is drawn by the renderer, it owns the committed drawables
+        None
+    }
+
+    fn set_sender(&mut self, sender: ToolSender) {
+        self.sender = Some(sender);
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::Result;
+use femtovg::Path;
+use gtk::gdk::Key;
+
+use crate::{
+    canvas::Fonts,
+    configuration::DRAWING_CONFIG,
+    events::{KeyEventMsg, MouseButton, MouseEventMsg, MouseEventType},
+    math::{self, Transformation, Vec2D},
+    style::Style,
+};
+
+use super::{Annotation, Drawable, DrawableClone, Tool, ToolSender, ToolUpdateResult, Tools};
+
+/// upper limit for rows and columns changed with the arrow keys
+const MAX_CELLS: u32 = 50;
+
+/// Grid of `rows` times `columns` equally sized cells
+#[derive(Clone, Copy, Debug)]
+pub struct Table {
+    top_left: Vec2D,
+    size: Option<Vec2D>,
+    rows: u32,
+    columns: u32,
+    style: Style,
+}
+
+impl Table {
+    pub fn new(top_left: Vec2D, size: Vec2D, rows: u32, columns: u32, style: Style) -> Self {
+        Self {
+            top_left,
+            size: Some(size),
+            rows,
+            columns,
+            style,
+        }
+    }
+}
+
+impl Drawable for Table {
+    fn draw(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        _fonts: &Fonts,
+        _bounds: (Vec2D, Vec2D),
+    ) -> Result<()> {
+        let Some(size) = self.size else {
+            return Ok(());
+        };
+        let (top_left, size) = math::rect_ensure_positive_size(self.top_left, size);
+
+        let mut path = Path::new();
+        path.rect(top_left.x, top_left.y, size.x, size.y);
+        for row in 1..self.rows {
+            let y = top_left.y + size.y * row as f32 / self.rows as f32;
+            path.move_to(top_left.x, y);
+            path.line_to(top_left.x + size.x, y);
+        }
+        for column in 1..self.columns {
+            let x = top_left.x + size.x * column as f32 / self.columns as f32;
+            path.move_to(x, top_left.y);
+            path.line_to(x, top_left.y + size.y);
+        }
+
+        canvas.save();
+        self.style.stroke_path(canvas, &path, &self.style.into());
+        canvas.restore();
+        Ok(())
+    }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        self.size
+            .is_some_and(|size| math::rect_contains((self.top_left, size), pos, tolerance))
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        Some(math::rect_to_bounds(self.top_left, self.size?))
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        self.top_left = transformation.apply(self.top_left);
+        self.size = self.size.map(|s| transformation.apply_vector(s));
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        Some(Annotation::Table {
+            top_left: self.top_left,
+            size: self.size?,
+            rows: self.rows,
+            columns: self.columns,
+            style: self.style.into(),
+        })
+    }
+}
+
+/// Draws a table in one drag. The dragged area is split into the configured number of rows
+/// and columns, which the arrow keys change while dragging. With a configured cell size the
+/// area is filled with as many cells as fit instead.
+pub struct TableTool {
+    table: Option<(Vec2D, Table)>,
+    rows: u32,
+    columns: u32,
+    style: Style,
+    input_enabled: bool,
+    sender: Option<ToolSender>,
+}
+
+impl Default for TableTool {
+    fn default() -> Self {
+        let config = DRAWING_CONFIG.read();
+        Self {
+            table: None,
+            rows: config.table_rows,
+            columns: config.table_columns,
+            style: Style::default(),
+            input_enabled: false,
+            sender: None,
+        }
+    }
+}
+
+impl TableTool {
+    fn update_shape(&mut self, event: &MouseEventMsg) {
+        let Some((origin, table)) = &mut self.table else {
+            return;
+        };
+        let (top_left, mut size) = event.drag_rect(*origin);
+
+        match DRAWING_CONFIG.read().table_cell_size {
+            Some(cell) => {
+                // whole cells only, at least one in each direction
+                let cells = |length: f32| (length.abs() / cell).round().max(1.0);
+                let (columns, rows) = (cells(size.x), cells(size.y));
+                size = Vec2D::new(
+                    columns * cell * size.x.signum(),
+                    rows * cell * size.y.signum(),
+                );
+                table.columns = columns as u32;
+                table.rows = rows as u32;
+            }
+            None => {
+                table.columns = self.columns;
+                table.rows = self.rows;
+            }
+        }
+        table.top_left = top_left;
+        table.size = Some(size);
+    }
+}
+
+impl Tool for TableTool {
+    fn input_enabled(&self) -> bool {
+        self.input_enabled
+    }
+
+    fn set_input_enabled(&mut self, value: bool) {
+        self.input_enabled = value;
+    }
+
+    fn handle_mouse_event(&mut self, event: MouseEventMsg) -> ToolUpdateResult {
+        if event.button == MouseButton::Middle {
+            return ToolUpdateResult::Unmodified;
+        }
+
+        match event.type_ {
+            MouseEventType::BeginDrag => {
+                self.table = Some((
+                    event.pos,
+                    Table {
+                        top_left: event.pos,
+                        size: None,
+                        rows: self.rows,
+                        columns: self.columns,
+                        style: self.style,
+                    },
+                ));
+                ToolUpdateResult::Redraw
+            }
+            MouseEventType::UpdateDrag => {
+                if self.table.is_none() || event.pos == Vec2D::zero() {
+                    return ToolUpdateResult::Unmodified;
+                }
+                self.update_shape(&event);
+                ToolUpdateResult::Redraw
+            }
+            MouseEventType::EndDrag => {
+                if self.table.is_none() {
+                    return ToolUpdateResult::Unmodified;
+                }
+                if event.pos == Vec2D::zero() {
+                    self.table = None;
+                    return ToolUpdateResult::Redraw;
+                }
+                self.update_shape(&event);
+                match self.table.take() {
+                    Some((_, table)) => ToolUpdateResult::Commit(table.clone_box()),
+                    None => ToolUpdateResult::Unmodified,
+                }
+            }
+            _ => ToolUpdateResult::Unmodified,
+        }
+    }
+
+    fn handle_key_event(&mut self, event: KeyEventMsg) -> ToolUpdateResult {
+        let Some((_, table)) = &mut self.table else {
+            return ToolUpdateResult::Unmodified;
+        };
+
+        // rows and columns stick for the next tables, the cell size decides them otherwise
+        let fixed_cells = DRAWING_CONFIG.read().table_cell_size.is_some();
+        match event.key {
+            Key::Escape => {
+                self.table = None;
+                return ToolUpdateResult::Redraw;
+            }
+            Key::Up if !fixed_cells => self.rows = (self.rows + 1).min(MAX_CELLS),
+            Key::Down if !fixed_cells => self.rows = (self.rows - 1).max(1),
+            Key::Right if !fixed_cells => self.columns = (self.columns + 1).min(MAX_CELLS),
+            Key::Left if !fixed_cells => self.columns = (self.columns - 1).max(1),
+            _ => return ToolUpdateResult::Unmodified,
+        }
+        table.rows = self.rows;
+        table.columns = self.columns;
+        ToolUpdateResult::RedrawAndStopPropagation
+    }
+
+    fn handle_style_event(&mut self, style: Style) -> ToolUpdateResult {
+        self.style = style;
+        ToolUpdateResult::Unmodified
+    }
+
+    fn get_drawable(&self) -> Option<&dyn Drawable> {
+        match &self.table {
+            Some((_, d)) => Some(d),
+            None => None,
+        }
+    }
+
+    fn get_tool_type(&self) -> super::Tools {
+        Tools::Table
+    }
+
+    fn snaps_to_guides(&self) -> bool {
+        true
+    }
+
+    fn set_sender(&mut self, sender: ToolSender) {
+        self.sender = Some(sender);
+    }
+}
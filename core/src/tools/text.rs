@@ -1,33 +1,38 @@
 use anyhow::Result;
 use femtovg::{Color, FontId, Paint, Path};
-use relm4::gtk::prelude::IMContextExt;
-use relm4::gtk::{
+use gtk::prelude::IMContextExt;
+use gtk::{
     gdk::{Key, ModifierType, Rectangle},
     TextBuffer,
 };
 use std::{borrow::Cow, ops::Range};
 
-use relm4::gtk::prelude::*;
+use gtk::prelude::*;
 
 use crate::{
+    canvas::Fonts,
+    configuration::DRAWING_CONFIG,
+    events::{KeyEventMsg, MouseButton, MouseEventMsg, MouseEventType, TextEventMsg},
     ime::preedit::{Preedit, UnderlineKind},
-    math::Vec2D,
-    sketch_board::{KeyEventMsg, MouseButton, MouseEventMsg, MouseEventType, TextEventMsg},
-    style::Style,
+    math::{self, Transformation, Vec2D},
+    style::{Style, TextAlign},
 };
 
-use super::{Drawable, DrawableClone, InputContext, Tool, ToolUpdateResult, Tools};
-use crate::sketch_board::SketchBoardInput;
-use relm4::gtk::gdk::DisplayManager;
-use relm4::Sender;
-use std::cell::RefCell;
+use super::{
+    Annotation, Drawable, DrawableClone, InputContext, Tool, ToolRequest, ToolSender,
+    ToolUpdateResult, Tools,
+};
+use gtk::gdk::DisplayManager;
+use std::cell::{OnceCell, RefCell};
 use std::rc::Rc;
 
 #[derive(Clone, Debug)]
 pub struct Text {
     pos: Vec2D,
     editing: bool,
-    text_buffer: TextBuffer,
+    // created on first edit, drawing a committed text only needs `initial_text`
+    buffer: OnceCell<TextBuffer>,
+    initial_text: String,
     style: Style,
     preedit: Option<Preedit>,
     im_context: Option<InputContext>,
@@ -36,6 +41,8 @@ pub struct Text {
     line_ranges: RefCell<Vec<Range<usize>>>,
     cursor_visible: RefCell<bool>,
     draw_rect: RefCell<bool>,
+    // lines wrap at this width if the text box got dragged open, at the image edge otherwise
+    wrap_width: Option<f32>,
 }
 
 struct DisplayContent<'a> {
@@ -47,12 +54,16 @@ struct DisplayContent<'a> {
 struct LineLayout {
     range: Range<usize>,
     baseline: f32,
+    // start of the line, depends on the alignment
+    x: f32,
 }
 
 struct TextDrawingContext<'a> {
     paint: &'a Paint,
     text: &'a str,
     lines: &'a [LineLayout],
+    // width the lines are aligned within
+    align_width: f32,
 }
 
 #[derive(Clone, Copy)]
@@ -64,12 +75,10 @@ struct CursorMetrics {
 
 impl Text {
     fn new(pos: Vec2D, style: Style, im_context: Option<InputContext>) -> Self {
-        let text_buffer = TextBuffer::new(None);
-        text_buffer.set_enable_undo(true);
-
         Self {
             pos,
-            text_buffer,
+            buffer: OnceCell::new(),
+            initial_text: String::new(),
             editing: true,
             style,
             preedit: None,
@@ -79,9 +88,103 @@ impl Text {
             line_ranges: RefCell::new(Vec::new()),
             cursor_visible: RefCell::new(true),
             draw_rect: RefCell::new(true),
+            wrap_width: None,
         }
     }
 
+    /// A text as committed by the text tool
+    pub fn committed(pos: Vec2D, text: &str, wrap_width: Option<f32>, style: Style) -> Self {
+        let mut committed = Self::new(pos, style, None);
+        committed.initial_text = text.to_string();
+        committed.editing = false;
+        committed.wrap_width = wrap_width;
+        *committed.draw_rect.borrow_mut() = false;
+        committed
+    }
+
+    /// The buffer the text gets edited in, created with the initial text on first use.
+    /// Needs GTK, so only the editing paths may call it.
+    fn text_buffer(&self) -> &TextBuffer {
+        self.buffer.get_or_init(|| {
+            let buffer = TextBuffer::new(None);
+            buffer.set_enable_undo(true);
+            if !self.initial_text.is_empty() {
+                // undoing the first edit must not clear the initial text
+                buffer.begin_irreversible_action();
+                buffer.set_text(&self.initial_text);
+                buffer.end_irreversible_action();
+            }
+            buffer
+        })
+    }
+
+    /// Current content, read without creating the buffer
+    fn content(&self) -> String {
+        match self.buffer.get() {
+            Some(buffer) => buffer
+                .text(&buffer.start_iter(), &buffer.end_iter(), false)
+                .to_string(),
+            None => self.initial_text.clone(),
+        }
+    }
+
+    /// Start of a line of `line_width` aligned within `align_width`
+    fn line_x(&self, line_width: f32, align_width: f32) -> f32 {
+        match self.style.text_align {
+            TextAlign::Left => self.pos.x,
+            TextAlign::Center => self.pos.x + (align_width - line_width) / 2.0,
+            TextAlign::Right => self.pos.x + align_width - line_width,
+        }
+    }
+
+    /// Index of the character closest to `pos`, based on the glyphs of the last draw
+    fn char_index_at(&self, pos: Vec2D) -> i32 {
+        let mut index = 0;
+        let mut find_index = false;
+
+        let glyphs = self.glyphs.borrow();
+        for line in 0..glyphs.len() {
+            let line_rect = glyphs.get(line).unwrap();
+
+            for glyph in line_rect.iter() {
+                if glyph.contains_point(pos.x as i32, pos.y as i32) {
+                    find_index = true;
+                    if pos.x > glyph.x() as f32 + glyph.width() as f32 / 2.0 {
+                        index += 1;
+                    }
+                    break;
+                }
+                index += 1;
+            }
+
+            if find_index {
+                break;
+            }
+
+            let Some(first_ele) = line_rect.iter().next() else {
+                continue;
+            };
+            if pos.y <= (first_ele.y() + first_ele.height()) as f32 && line != glyphs.len() - 1 {
+                index -= 1;
+                break;
+            }
+        }
+        index
+    }
+
+    /// Copy of a committed text to continue editing it. The text buffer is not shared
+    /// with the committed text, so undoing the edit restores the original content.
+    fn reopen(&self, im_context: Option<InputContext>) -> Self {
+        let mut text = Self::new(self.pos, self.style, im_context);
+        text.wrap_width = self.wrap_width;
+        text.initial_text = self.content();
+
+        *text.rect.borrow_mut() = *self.rect.borrow();
+        *text.glyphs.borrow_mut() = self.glyphs.borrow().clone();
+        *text.line_ranges.borrow_mut() = self.line_ranges.borrow().clone();
+        text
+    }
+
     fn byte_index_from_char_index(text: &str, char_index: usize) -> usize {
         text.char_indices()
             .nth(char_index)
@@ -90,7 +193,12 @@ impl Text {
     }
 
     fn display_text<'a>(&self, base_text: &'a str) -> DisplayContent<'a> {
-        let cursor_char_index = self.text_buffer.cursor_position() as usize;
+        let cursor_char_index = self
+            .buffer
+            .get()
+            .map_or(base_text.chars().count(), |buffer| {
+                buffer.cursor_position() as usize
+            });
         let base_cursor_byte = Self::byte_index_from_char_index(base_text, cursor_char_index);
 
         if self.editing {
@@ -143,15 +251,19 @@ impl Drawable for Text {
     fn draw(
         &self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        font: FontId,
+        fonts: &Fonts,
         _bounds: (Vec2D, Vec2D),
     ) -> Result<()> {
-        let gtext = self.text_buffer.text(
-            &self.text_buffer.start_iter(),
-            &self.text_buffer.end_iter(),
-            false,
-        );
-        let base_text = gtext.as_str();
+        let font = fonts.get(canvas, &self.style.font);
+        let content = self.content();
+        // placeholders are shown as typed while editing
+        let expanded = (!self.editing).then(|| {
+            DRAWING_CONFIG
+                .read()
+                .expand_variables(content.as_str())
+                .into_owned()
+        });
+        let base_text = expanded.as_deref().unwrap_or(content.as_str());
         let display = self.display_text(base_text);
         let text = display.text.as_ref();
 
@@ -161,7 +273,7 @@ impl Drawable for Text {
         let transform = canvas.transform();
         let canva_scale = transform.average_scale();
 
-        let width = _bounds.1.x - self.pos.x;
+        let width = self.wrap_width.unwrap_or(_bounds.1.x - self.pos.x);
 
         let lines = canvas.break_text_vec(width, text, &base_paint)?;
         self.line_ranges.replace(lines.clone());
@@ -192,12 +304,27 @@ impl Drawable for Text {
             (font_metrics.height() / canva_scale).abs()
         };
 
+        let line_widths = lines
+            .iter()
+            .map(|range| {
+                Self::text_width(
+                    canvas,
+                    &base_paint,
+                    text[range.clone()].trim_end_matches('\n'),
+                )
+            })
+            .collect::<Vec<_>>();
+        let align_width = self
+            .wrap_width
+            .unwrap_or_else(|| line_widths.iter().copied().fold(0.0, f32::max));
+
         let mut line_layouts: Vec<LineLayout> = Vec::with_capacity(lines.len());
         let mut baseline = self.pos.y;
-        for line_range in &lines {
+        for (line_range, line_width) in lines.iter().zip(line_widths) {
             line_layouts.push(LineLayout {
                 range: line_range.clone(),
                 baseline,
+                x: self.line_x(line_width, align_width),
             });
             baseline += line_height;
         }
@@ -212,6 +339,7 @@ impl Drawable for Text {
             paint: &base_paint,
             text,
             lines: &line_layouts,
+            align_width,
         };
 
         if self.editing {
@@ -228,7 +356,11 @@ impl Drawable for Text {
 
         let mut cursor_visible = self.cursor_visible.borrow_mut();
         //draw selection
-        if let Some((sel_start_iter, sel_end_iter)) = self.text_buffer.selection_bounds() {
+        if let Some((sel_start_iter, sel_end_iter)) = self
+            .buffer
+            .get()
+            .and_then(|buffer| buffer.selection_bounds())
+        {
             let sel_start = sel_start_iter.offset() as usize;
             let sel_end = sel_end_iter.offset() as usize;
 
@@ -270,16 +402,13 @@ impl Drawable for Text {
         }
 
         //calculate rect and glyphs
-        let mut draw_baseline = self.pos.y;
         let mut rect = self.rect.borrow_mut();
         let mut glyphs = self.glyphs.borrow_mut();
 
         glyphs.clear();
         {
-            let mut top = 0;
-            let mut left = 0;
-            let mut width = 0;
-            let mut height = 0;
+            // left, top, right and bottom of all glyphs, aligned lines start at different x
+            let mut extent: Option<(i32, i32, i32, i32)> = None;
 
             for line in &line_layouts {
                 let mut line_glyphs = Vec::new();
@@ -299,36 +428,42 @@ impl Drawable for Text {
                         let w = (end_x - start_x) as i32;
                         line_glyphs.push(Rectangle::new(x, y, w, h));
 
-                        if top == 0 {
-                            top = y;
-                        }
-
-                        if left == 0 {
-                            left = x;
-                        }
-
-                        width = (end_x as i32 - left).max(width);
-                        height = y + h - top;
+                        let (left, top, right, bottom) = extent.unwrap_or((x, y, x + w, y + h));
+                        extent = Some((
+                            left.min(x),
+                            top.min(y),
+                            right.max(end_x as i32),
+                            bottom.max(y + h),
+                        ));
                     }
                 }
 
                 glyphs.push(line_glyphs);
+            }
 
-                rect.set_height(height);
-                rect.set_width(width);
-                rect.set_x(left);
-                rect.set_y(top);
+            if let Some((left, top, right, bottom)) = extent {
+                *rect = Rectangle::new(left, top, right - left, bottom - top);
+            } else if !line_layouts.is_empty() {
+                *rect = Rectangle::new(0, 0, 0, 0);
             }
         }
 
+        //draw background pill
+        if self.style.fill && rect.width() > 0 && rect.height() > 0 {
+            self.draw_background(canvas, &rect, cursor_metrics.height);
+        }
+
         //draw rect
         if *self.draw_rect.borrow() {
             let mut rect_paint = Path::new();
             rect_paint.move_to(self.pos.x, self.pos.y);
             let y = rect.y() as f32;
             let h = rect.height() as f32;
-            let x = rect.x() as f32;
-            let w = rect.width() as f32;
+            // a dragged open text box is shown in full
+            let (x, w) = match self.wrap_width {
+                Some(wrap_width) => (self.pos.x, wrap_width),
+                None => (rect.x() as f32, rect.width() as f32),
+            };
 
             rect_paint.rect(x, y, w, h);
             let mut paint = Paint::color(Color::rgbaf(1.0, 0.5, 0.3, 0.3)); // transparent orange
@@ -337,14 +472,13 @@ impl Drawable for Text {
             canvas.stroke_path(&rect_paint, &paint);
         }
 
-        for line_range in &lines {
-            canvas.fill_text(
-                self.pos.x,
-                draw_baseline,
-                &text[line_range.clone()],
+        for line in &line_layouts {
+            self.style.fill_text(
+                canvas,
+                (line.x, line.baseline),
+                &text[line.range.clone()],
                 &base_paint,
             )?;
-            draw_baseline += line_height;
         }
 
         if self.editing {
@@ -373,9 +507,107 @@ impl Drawable for Text {
 
         Ok(())
     }
+
+    fn hit_test(&self, pos: Vec2D, tolerance: f32) -> bool {
+        // the rect is computed while drawing, it is empty for texts never drawn
+        let rect = self.rect.borrow();
+        rect.width() > 0
+            && rect.height() > 0
+            && math::rect_contains(
+                (
+                    Vec2D::new(rect.x() as f32, rect.y() as f32),
+                    Vec2D::new(rect.width() as f32, rect.height() as f32),
+                ),
+                pos,
+                tolerance,
+            )
+    }
+
+    fn text(&self) -> Option<String> {
+        let content = self.content();
+        Some(
+            DRAWING_CONFIG
+                .read()
+                .expand_variables(content.as_str())
+                .into_owned(),
+        )
+    }
+
+    fn bounds(&self) -> Option<(Vec2D, Vec2D)> {
+        let rect = self.rect.borrow();
+        (rect.width() > 0 && rect.height() > 0).then(|| {
+            math::rect_to_bounds(
+                Vec2D::new(rect.x() as f32, rect.y() as f32),
+                Vec2D::new(rect.width() as f32, rect.height() as f32),
+            )
+        })
+    }
+
+    fn transform(&mut self, transformation: Transformation) {
+        // text keeps its size, only the position follows. The cached rect is moved along so
+        // the bounds are correct before the next draw.
+        let pos = transformation.apply(self.pos);
+        let offset = pos - self.pos;
+        self.pos = pos;
+
+        let mut rect = self.rect.borrow_mut();
+        *rect = Rectangle::new(
+            rect.x() + offset.x.round() as i32,
+            rect.y() + offset.y.round() as i32,
+            rect.width(),
+            rect.height(),
+        );
+    }
+
+    fn style(&self) -> Option<Style> {
+        Some(self.style)
+    }
+
+    fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+
+    fn annotation(&self) -> Option<Annotation> {
+        let text = self.content();
+        Some(Annotation::Text {
+            pos: self.pos,
+            text,
+            wrap_width: self.wrap_width,
+            style: self.style.into(),
+        })
+    }
 }
 
 impl Text {
+    /// Rounded background behind the text `rect`, it is fully round for single lines
+    fn draw_background(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        rect: &Rectangle,
+        line_height: f32,
+    ) {
+        let color = DRAWING_CONFIG
+            .read()
+            .text_background_color
+            .unwrap_or_else(|| {
+                let mut color = self.style.color.contrasting();
+                color.a = 153;
+                color
+            });
+
+        let padding = line_height * 0.25;
+        let x = rect.x() as f32 - padding;
+        let y = rect.y() as f32 - padding;
+        let w = rect.width() as f32 + 2.0 * padding;
+        let h = rect.height() as f32 + 2.0 * padding;
+
+        let mut path = Path::new();
+        path.rounded_rect(x, y, w, h, (line_height * 0.5 + padding).min(h * 0.5));
+        let mut paint = Paint::color(color.into());
+        paint.set_anti_alias(true);
+        canvas.fill_path(&path, &paint);
+    }
+
     fn draw_preedit_background(
         &self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
@@ -457,7 +689,7 @@ impl Text {
                             (cursor.height + 2.0).ceil(),
                         );
                         canvas.fill_text(
-                            self.pos.x,
+                            line.x,
                             line.baseline,
                             &context.text[line.range.clone()],
                             &overlay_paint,
@@ -572,7 +804,7 @@ impl Text {
         let prefix = &line_text[..start_byte];
         let selected = &line_text[start_byte..end_byte].replace("\n", "");
 
-        let start_x: f32 = self.pos.x + Self::text_width(canvas, context.paint, prefix);
+        let start_x: f32 = line.x + Self::text_width(canvas, context.paint, prefix);
         let width = Self::text_width(canvas, context.paint, selected);
 
         vec![(start_x, start_x + width.max(0.0))]
@@ -586,7 +818,10 @@ impl Text {
         cursor: CursorMetrics,
     ) -> (f32, f32) {
         if context.lines.is_empty() {
-            return (self.pos.x, self.pos.y + cursor.top_offset);
+            return (
+                self.line_x(0.0, context.align_width),
+                self.pos.y + cursor.top_offset,
+            );
         }
 
         let mut newline_pending_baseline: Option<f32> = None;
@@ -600,7 +835,7 @@ impl Text {
                     .min(line_text.len());
                 let prefix = &line_text[..prefix_len];
                 let offset = Self::text_width(canvas, context.paint, prefix);
-                return (self.pos.x + offset, line.baseline + cursor.top_offset);
+                return (line.x + offset, line.baseline + cursor.top_offset);
             }
 
             if cursor_byte_pos == line.range.end {
@@ -612,23 +847,26 @@ impl Text {
                     continue;
                 }
                 let offset = Self::text_width(canvas, context.paint, line_text);
-                return (self.pos.x + offset, line.baseline + cursor.top_offset);
+                return (line.x + offset, line.baseline + cursor.top_offset);
             }
         }
 
         if let Some(baseline) = newline_pending_baseline {
-            return (self.pos.x, baseline);
+            return (self.line_x(0.0, context.align_width), baseline);
         }
 
         if let Some(last_line) = context.lines.last() {
             let line_text = &context.text[last_line.range.clone()];
             let offset = Self::text_width(canvas, context.paint, line_text);
             (
-                self.pos.x + offset,
+                last_line.x + offset,
                 last_line.baseline + cursor.top_offset + cursor.line_height,
             )
         } else {
-            (self.pos.x, self.pos.y + cursor.top_offset)
+            (
+                self.line_x(0.0, context.align_width),
+                self.pos.y + cursor.top_offset,
+            )
         }
     }
 
@@ -693,9 +931,16 @@ pub struct TextTool {
     style: Style,
     input_enabled: bool,
     im_context: Option<InputContext>,
-    sender: Option<Sender<SketchBoardInput>>,
+    sender: Option<ToolSender>,
     drag_start_pos: Vec2D,
     dragged: Rc<RefCell<bool>>,
+    // whether the current drag opens a text box for the new text
+    sizing_box: bool,
+}
+
+impl TextTool {
+    /// narrower drags are taken as a click, the text wraps at the image edge then
+    const MIN_BOX_WIDTH: f32 = 10.0;
 }
 
 impl Tool for TextTool {
@@ -735,7 +980,7 @@ impl Tool for TextTool {
         }
     }
 
-    fn handle_text_event(&mut self, event: crate::sketch_board::TextEventMsg) -> ToolUpdateResult {
+    fn handle_text_event(&mut self, event: crate::events::TextEventMsg) -> ToolUpdateResult {
         if let Some(t) = &mut self.text {
             match event {
                 TextEventMsg::Commit(text) => {
@@ -743,7 +988,7 @@ impl Tool for TextTool {
                     Self::handle_text_buffer_action(t, Action::Delete, ActionScope::None);
                     //update input text
                     t.preedit = None;
-                    t.text_buffer.insert_at_cursor(&text);
+                    t.text_buffer().insert_at_cursor(&text);
                     ToolUpdateResult::Redraw
                 }
                 TextEventMsg::Preedit {
@@ -787,15 +1032,17 @@ impl Tool for TextTool {
                     ModifierType::SHIFT_MASK => {
                         //delete selection
                         Self::handle_text_buffer_action(t, Action::Delete, ActionScope::None);
-                        t.text_buffer.insert_at_cursor("\n");
+                        t.text_buffer().insert_at_cursor("\n");
                         tool_update_result = ToolUpdateResult::RedrawAndStopPropagation;
                     }
                     _ => {
                         t.preedit = None;
                         t.editing = false;
                         t.im_context = None;
-                        t.text_buffer
-                            .select_range(&t.text_buffer.start_iter(), &t.text_buffer.start_iter());
+                        t.text_buffer().select_range(
+                            &t.text_buffer().start_iter(),
+                            &t.text_buffer().start_iter(),
+                        );
                         *t.draw_rect.borrow_mut() = false;
                         let result = t.clone_box();
                         self.text = None;
@@ -915,7 +1162,7 @@ impl Tool for TextTool {
                         return ToolUpdateResult::StopPropagation;
                     }
                     let clipboard = display.unwrap().clipboard();
-                    let buffer = t.text_buffer.clone();
+                    let buffer = t.text_buffer().clone();
 
                     Self::handle_text_buffer_action(t, Action::Delete, ActionScope::None);
 
@@ -927,7 +1174,7 @@ impl Tool for TextTool {
                             Ok(Some(text)) => {
                                 buffer.insert_at_cursor(&text);
                                 if let Some(sender) = sender {
-                                    sender.emit(SketchBoardInput::Refresh);
+                                    sender.emit(ToolRequest::Refresh);
                                 }
                             }
                             Ok(None) => {
@@ -942,7 +1189,7 @@ impl Tool for TextTool {
                 Key::c | Key::C => {
                     if event.modifier == ModifierType::CONTROL_MASK {
                         if let Some(text) = &self.text {
-                            let buffer = text.text_buffer.clone();
+                            let buffer = text.text_buffer().clone();
                             if let Some((start, end)) = buffer.selection_bounds() {
                                 let selected_text = buffer.text(&start, &end, false);
 
@@ -961,7 +1208,7 @@ impl Tool for TextTool {
                 Key::x | Key::X => {
                     if event.modifier == ModifierType::CONTROL_MASK {
                         if let Some(text) = &mut self.text {
-                            let buffer = text.text_buffer.clone();
+                            let buffer = text.text_buffer().clone();
                             if let Some((start, end)) = buffer.selection_bounds() {
                                 let selected_text = buffer.text(&start, &end, false);
 
@@ -992,7 +1239,7 @@ impl Tool for TextTool {
                             return ToolUpdateResult::StopPropagation;
                         }
                         let selection_clipboard = display.unwrap().primary_clipboard();
-                        let buffer = t.text_buffer.clone();
+                        let buffer = t.text_buffer().clone();
 
                         Self::handle_text_buffer_action(t, Action::Delete, ActionScope::None);
 
@@ -1003,7 +1250,7 @@ impl Tool for TextTool {
                                 Ok(Some(text)) => {
                                     buffer.insert_at_cursor(&text);
                                     if let Some(sender) = sender {
-                                        sender.emit(SketchBoardInput::Refresh);
+                                        sender.emit(ToolRequest::Refresh);
                                     }
                                 }
                                 Ok(None) => {
@@ -1033,52 +1280,31 @@ impl Tool for TextTool {
                     MouseButton::Primary => {
                         let pos = event.pos;
                         if let Some(t) = &mut self.text {
+                            // a double click next to an untouched new text re-edits the
+                            // committed text below it, if any
+                            if event.n_pressed == 2 && t.text_buffer().char_count() == 0 {
+                                if let Some(sender) = &self.sender {
+                                    sender.emit(ToolRequest::EditTextAt(pos));
+                                }
+                                return ToolUpdateResult::StopPropagation;
+                            }
+
                             let rect = t.rect.borrow();
                             if rect.contains_point(pos.x as i32, pos.y as i32) {
                                 //calculate text cursor position
-                                let mut index = 0;
-                                let mut find_index = false;
-
-                                let glyphs = t.glyphs.borrow();
-                                for line in 0..glyphs.len() {
-                                    let line_rect = glyphs.get(line).unwrap();
-
-                                    for glyph in line_rect.iter() {
-                                        if glyph.contains_point(pos.x as i32, pos.y as i32) {
-                                            find_index = true;
-                                            if pos.x > glyph.x() as f32 + glyph.width() as f32 / 2.0
-                                            {
-                                                index += 1;
-                                            }
-                                            break;
-                                        }
-                                        index += 1;
-                                    }
-
-                                    if find_index {
-                                        break;
-                                    }
-
-                                    let first_ele = line_rect.iter().next().unwrap();
-                                    if pos.y <= (first_ele.y() + first_ele.height()) as f32
-                                        && line != glyphs.len() - 1
-                                    {
-                                        index -= 1;
-                                        break;
-                                    }
-                                }
+                                let index = t.char_index_at(pos);
 
-                                let buffer = &t.text_buffer;
+                                let buffer = t.text_buffer();
                                 let mut cursor_iter = buffer.iter_at_mark(&buffer.get_insert());
                                 cursor_iter.set_offset(index);
-                                t.text_buffer.place_cursor(&cursor_iter);
+                                t.text_buffer().place_cursor(&cursor_iter);
 
                                 if event.n_pressed == 2 {
                                     let mut start_itr = cursor_iter;
                                     let mut end_itr = start_itr;
                                     start_itr.backward_word_start();
                                     end_itr.forward_word_end();
-                                    t.text_buffer.select_range(&start_itr, &end_itr);
+                                    t.text_buffer().select_range(&start_itr, &end_itr);
                                 } else if event.n_pressed == 3 {
                                     let mut start_itr = cursor_iter;
                                     let mut end_itr = start_itr;
@@ -1086,7 +1312,7 @@ impl Tool for TextTool {
                                         start_itr.backward_line();
                                     }
                                     end_itr.forward_to_end();
-                                    t.text_buffer.select_range(&start_itr, &end_itr);
+                                    t.text_buffer().select_range(&start_itr, &end_itr);
                                 }
 
                                 return ToolUpdateResult::RedrawAndStopPropagation;
@@ -1099,9 +1325,9 @@ impl Tool for TextTool {
                                 l.preedit = None;
                                 l.editing = false;
                                 l.im_context = None;
-                                l.text_buffer.select_range(
-                                    &l.text_buffer.start_iter(),
-                                    &l.text_buffer.start_iter(),
+                                l.text_buffer().select_range(
+                                    &l.text_buffer().start_iter(),
+                                    &l.text_buffer().start_iter(),
                                 );
                                 *l.draw_rect.borrow_mut() = false;
                                 ToolUpdateResult::Commit(l.clone_box())
@@ -1128,7 +1354,7 @@ impl Tool for TextTool {
                             return ToolUpdateResult::StopPropagation;
                         }
                         let selection_clipboard = display.unwrap().primary_clipboard();
-                        let buffer = t.text_buffer.clone();
+                        let buffer = t.text_buffer().clone();
 
                         Self::handle_text_buffer_action(t, Action::Delete, ActionScope::None);
 
@@ -1141,7 +1367,7 @@ impl Tool for TextTool {
                                     if !*dragged.borrow() {
                                         buffer.insert_at_cursor(&text);
                                         if let Some(sender) = sender {
-                                            sender.emit(SketchBoardInput::Refresh);
+                                            sender.emit(ToolRequest::Refresh);
                                         }
                                     }
                                 }
@@ -1161,6 +1387,14 @@ impl Tool for TextTool {
             },
             MouseEventType::BeginDrag => {
                 self.drag_start_pos = event.pos;
+                // dragging from where a new text got placed opens a box to wrap it in
+                self.sizing_box = event.button == MouseButton::Primary
+                    && self.text.as_ref().is_some_and(|t| {
+                        t.text_buffer().char_count() == 0 && t.pos.distance_to(&event.pos) < 1.0
+                    });
+                if self.sizing_box {
+                    return ToolUpdateResult::StopPropagation;
+                }
                 if let Some(t) = &mut self.text {
                     let rect = t.rect.borrow();
                     if rect.contains_point(event.pos.x as i32, event.pos.y as i32) {
@@ -1169,6 +1403,17 @@ impl Tool for TextTool {
                 }
                 ToolUpdateResult::Unmodified
             }
+            MouseEventType::UpdateDrag | MouseEventType::EndDrag if self.sizing_box => {
+                if let Some(t) = &mut self.text {
+                    let width = event.pos.x.abs();
+                    t.wrap_width = (width >= Self::MIN_BOX_WIDTH).then_some(width);
+                    t.pos.x = self.drag_start_pos.x + event.pos.x.min(0.0);
+                }
+                if event.type_ == MouseEventType::EndDrag {
+                    self.sizing_box = false;
+                }
+                ToolUpdateResult::RedrawAndStopPropagation
+            }
             MouseEventType::UpdateDrag => {
                 self.dragged = Rc::new(RefCell::new(true));
                 if event.button == MouseButton::Primary {
@@ -1205,7 +1450,7 @@ impl Tool for TextTool {
                                 }
                             }
 
-                            let buffer = &t.text_buffer;
+                            let buffer = t.text_buffer();
                             let mut cursor_iter = buffer.iter_at_mark(&buffer.get_insert());
                             cursor_iter.set_offset(index);
 
@@ -1239,8 +1484,8 @@ impl Tool for TextTool {
             t.preedit = None;
             t.editing = false;
             t.im_context = None;
-            t.text_buffer
-                .select_range(&t.text_buffer.start_iter(), &t.text_buffer.start_iter());
+            t.text_buffer()
+                .select_range(&t.text_buffer().start_iter(), &t.text_buffer().start_iter());
             *t.draw_rect.borrow_mut() = false;
             let result = t.clone_box();
             self.text = None;
@@ -1257,7 +1502,7 @@ impl Tool for TextTool {
 
     fn handle_undo(&mut self) -> ToolUpdateResult {
         if let Some(t) = &self.text {
-            t.text_buffer.undo();
+            t.text_buffer().undo();
             ToolUpdateResult::Redraw
         } else {
             ToolUpdateResult::Unmodified
@@ -1266,14 +1511,14 @@ impl Tool for TextTool {
 
     fn handle_redo(&mut self) -> ToolUpdateResult {
         if let Some(t) = &self.text {
-            t.text_buffer.redo();
+            t.text_buffer().redo();
             ToolUpdateResult::Redraw
         } else {
             ToolUpdateResult::Unmodified
         }
     }
 
-    fn set_sender(&mut self, sender: Sender<SketchBoardInput>) {
+    fn set_sender(&mut self, sender: ToolSender) {
         self.sender = Some(sender);
     }
 }
@@ -1299,12 +1544,40 @@ enum Action {
 }
 
 impl TextTool {
+    /// Whether `drawable` is a text that can be edited again
+    pub fn can_edit(drawable: &dyn Drawable) -> bool {
+        drawable.as_any().is::<Text>()
+    }
+
+    /// Continue editing a committed text with the cursor placed at `pos`. The text
+    /// currently being edited is committed first, unless it is empty.
+    pub fn edit(&mut self, drawable: &dyn Drawable, pos: Vec2D) -> ToolUpdateResult {
+        let Some(committed) = drawable.as_any().downcast_ref::<Text>() else {
+            return ToolUpdateResult::Unmodified;
+        };
+
+        let result = match &self.text {
+            Some(t) if t.text_buffer().char_count() > 0 => self.handle_deactivated(),
+            _ => ToolUpdateResult::Redraw,
+        };
+
+        let text = committed.reopen(self.im_context.clone());
+        let buffer = text.text_buffer();
+        let cursor_iter = buffer.iter_at_offset(committed.char_index_at(pos));
+        buffer.place_cursor(&cursor_iter);
+
+        self.text = Some(text);
+        self.set_input_enabled(true);
+
+        result
+    }
+
     fn handle_text_buffer_action(
         text: &mut Text,
         action: Action,
         action_scope: ActionScope,
     ) -> ToolUpdateResult {
-        let text_buffer = &text.text_buffer;
+        let text_buffer = text.text_buffer();
         let mut start_cursor_itr = text_buffer.iter_at_mark(&text_buffer.get_insert());
 
         match action {
@@ -1387,9 +1660,9 @@ impl TextTool {
                         if has_selection {
                             cursor_itr = end_iter.unwrap();
                         } else {
-                            let content = &text.text_buffer.text(
-                                &text.text_buffer.start_iter(),
-                                &text.text_buffer.end_iter(),
+                            let content = &text.text_buffer().text(
+                                &text.text_buffer().start_iter(),
+                                &text.text_buffer().end_iter(),
                                 false,
                             );
                             let current_offset = cursor_itr.offset();
@@ -1448,9 +1721,9 @@ impl TextTool {
                         if has_selection {
                             cursor_itr = start_iter.unwrap();
                         } else {
-                            let content = &text.text_buffer.text(
-                                &text.text_buffer.start_iter(),
-                                &text.text_buffer.end_iter(),
+                            let content = &text.text_buffer().text(
+                                &text.text_buffer().start_iter(),
+                                &text.text_buffer().end_iter(),
                                 false,
                             );
                             let current_offset = cursor_itr.offset();
@@ -1555,9 +1828,9 @@ impl TextTool {
                         }
                     }
                     ActionScope::ForwardLineAndWord => {
-                        let content = &text.text_buffer.text(
-                            &text.text_buffer.start_iter(),
-                            &text.text_buffer.end_iter(),
+                        let content = &text.text_buffer().text(
+                            &text.text_buffer().start_iter(),
+                            &text.text_buffer().end_iter(),
                             false,
                         );
                         let current_offset = end_cursor_itr.offset();
@@ -1606,9 +1879,9 @@ impl TextTool {
                         end_cursor_itr.set_offset(move_offset);
                     }
                     ActionScope::BackwardLineAndWord => {
-                        let content = &text.text_buffer.text(
-                            &text.text_buffer.start_iter(),
-                            &text.text_buffer.end_iter(),
+                        let content = &text.text_buffer().text(
+                            &text.text_buffer().start_iter(),
+                            &text.text_buffer().end_iter(),
                             false,
                         );
                         let current_offset = end_cursor_itr.offset();
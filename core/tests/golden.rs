@@ -0,0 +1,253 @@
+//! Renders the annotations of each tool with fixed inputs and compares them with the reference
+//! images in `tests/golden`. Runs without a display through EGL, forced to Mesa's software
+//! rasterizer so the results don't depend on the GPU.
+//!
+//! Run with `SATTY_BLESS=1 cargo test -p satty_core --test golden` to write the references
+//! after an intended change of the rendering, images that don't match are written to the target
+//! directory for comparison. Skipped where no EGL context can be created.
+
+use std::path::{Path, PathBuf};
+
+use gdk_pixbuf::{Colorspace, Pixbuf};
+use image::RgbaImage;
+use satty_core::{headless::Renderer, Annotation, AnnotationStyle, Annotator, Color, Size, Vec2D};
+
+const WIDTH: i32 = 160;
+const HEIGHT: i32 = 120;
+
+/// channels may differ this much, antialiasing isn't exact across Mesa versions
+const TOLERANCE: u8 = 8;
+/// fraction of the pixels that may differ by more than the tolerance
+const MAX_DIFFERENT: f64 = 0.002;
+
+fn style() -> AnnotationStyle {
+    AnnotationStyle {
+        color: Color::red().into(),
+        size: Size::Medium,
+        shadow: false,
+        ..AnnotationStyle::default()
+    }
+}
+
+fn filled() -> AnnotationStyle {
+    AnnotationStyle {
+        fill: true,
+        ..style()
+    }
+}
+
+fn v(x: f32, y: f32) -> Vec2D {
+    Vec2D::new(x, y)
+}
+
+/// Diagonal stripes, so blur and highlight have something to work on
+fn background() -> Pixbuf {
+    let image = Pixbuf::new(Colorspace::Rgb, true, 8, WIDTH, HEIGHT).unwrap();
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let shade = if (x + y) / 8 % 2 == 0 { 0xf0 } else { 0x30 };
+            image.put_pixel(x as u32, y as u32, shade, shade, shade, 0xff);
+        }
+    }
+    image
+}
+
+fn cases() -> Vec<(&'static str, Annotation)> {
+    vec![
+        (
+            "line",
+            Annotation::Line {
+                start: v(20.0, 20.0),
+                end: v(140.0, 100.0),
+                style: style(),
+            },
+        ),
+        (
+            "arrow",
+            Annotation::Arrow {
+                start: v(20.0, 100.0),
+                end: v(140.0, 20.0),
+                style: style(),
+            },
+        ),
+        (
+            "ruler",
+            Annotation::Ruler {
+                start: v(20.0, 60.0),
+                end: v(140.0, 60.0),
+                style: style(),
+            },
+        ),
+        (
+            "brace",
+            Annotation::Brace {
+                start: v(20.0, 40.0),
+                end: v(140.0, 40.0),
+                style: style(),
+            },
+        ),
+        (
+            "rectangle",
+            Annotation::Rectangle {
+                top_left: v(20.0, 20.0),
+                size: v(120.0, 80.0),
+                style: style(),
+            },
+        ),
+        (
+            "rectangle-filled",
+            Annotation::Rectangle {
+                top_left: v(20.0, 20.0),
+                size: v(120.0, 80.0),
+                style: filled(),
+            },
+        ),
+        (
+            "ellipse",
+            Annotation::Ellipse {
+                center: v(80.0, 60.0),
+                radii: v(60.0, 40.0),
+                style: style(),
+            },
+        ),
+        (
+            "table",
+            Annotation::Table {
+                top_left: v(20.0, 20.0),
+                size: v(120.0, 80.0),
+                rows: 3,
+                columns: 4,
+                style: style(),
+            },
+        ),
+        (
+            "blur",
+            Annotation::Blur {
+                top_left: v(30.0, 30.0),
+                size: v(100.0, 60.0),
+                style: style(),
+            },
+        ),
+        (
+            "highlight",
+            Annotation::Highlight {
+                top_left: v(30.0, 30.0),
+                size: v(100.0, 60.0),
+                style: style(),
+            },
+        ),
+        (
+            "highlight-line",
+            Annotation::HighlightLine {
+                start: v(20.0, 60.0),
+                end: v(140.0, 60.0),
+                style: style(),
+            },
+        ),
+        (
+            "highlight-freehand",
+            Annotation::HighlightFreehand {
+                points: vec![v(20.0, 80.0), v(60.0, 40.0), v(100.0, 80.0), v(140.0, 40.0)],
+                style: style(),
+            },
+        ),
+        (
+            "brush",
+            Annotation::Brush {
+                points: vec![v(20.0, 80.0), v(60.0, 40.0), v(100.0, 80.0), v(140.0, 40.0)],
+                pressures: Vec::new(),
+                style: style(),
+            },
+        ),
+        (
+            "brush-pressure",
+            Annotation::Brush {
+                points: vec![v(20.0, 80.0), v(60.0, 40.0), v(100.0, 80.0), v(140.0, 40.0)],
+                pressures: vec![0.1, 0.4, 0.7, 1.0],
+                style: style(),
+            },
+        ),
+        (
+            "text",
+            Annotation::Text {
+                pos: v(20.0, 40.0),
+                text: "Satty\nwrapped text".to_string(),
+                wrap_width: Some(80.0),
+                style: style(),
+            },
+        ),
+        (
+            "marker",
+            Annotation::Marker {
+                pos: v(80.0, 60.0),
+                number: 7,
+                style: style(),
+            },
+        ),
+    ]
+}
+
+fn reference_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"))
+}
+
+/// Why `actual` doesn't match `expected`, if it doesn't
+fn compare(actual: &RgbaImage, expected: &RgbaImage) -> Option<String> {
+    if actual.dimensions() != expected.dimensions() {
+        return Some(format!(
+            "size {:?} instead of {:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        ));
+    }
+    let different = actual
+        .pixels()
+        .zip(expected.pixels())
+        .filter(|(a, e)| a.0.iter().zip(e.0).any(|(a, e)| a.abs_diff(e) > TOLERANCE))
+        .count();
+    let fraction = different as f64 / (actual.width() * actual.height()) as f64;
+    (fraction > MAX_DIFFERENT).then(|| format!("{different} pixels differ"))
+}
+
+#[test]
+fn tools_match_references() {
+    // only read when the first context is created
+    std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+    if Renderer::new().is_err() {
+        eprintln!("skipped, no OpenGL context without a display");
+        return;
+    }
+    let bless = std::env::var_os("SATTY_BLESS").is_some();
+    let actual_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+
+    let mut failures = Vec::new();
+    for (name, annotation) in cases() {
+        let actual = Annotator::new(background())
+            .with_annotation(annotation)
+            .render()
+            .unwrap_or_else(|e| panic!("rendering {name} failed: {e:#}"));
+        let reference = reference_path(name);
+        if bless {
+            std::fs::create_dir_all(reference.parent().unwrap()).unwrap();
+            actual.save(&reference).unwrap();
+            continue;
+        }
+        let failure = match image::open(&reference) {
+            Ok(expected) => compare(&actual, &expected.to_rgba8()),
+            Err(e) => Some(format!("no reference: {e}")),
+        };
+        if let Some(failure) = failure {
+            std::fs::create_dir_all(&actual_dir).unwrap();
+            let path = actual_dir.join(format!("{name}.png"));
+            actual.save(&path).unwrap();
+            failures.push(format!("{name}: {failure}, rendered to {}", path.display()));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "renderings differ from tests/golden, run with SATTY_BLESS=1 if intended:\n{}",
+        failures.join("\n")
+    );
+}
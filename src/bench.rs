@@ -0,0 +1,193 @@
+use std::{
+    cell::RefCell,
+    io::Cursor,
+    rc::Rc,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use gdk_pixbuf::{glib::Bytes, Colorspace, Pixbuf};
+use image::ImageFormat;
+use serde_json::json;
+
+use crate::{
+    canvas,
+    headless::Renderer,
+    input,
+    math::Vec2D,
+    tools::{Annotation, AnnotationStyle},
+};
+use satty_cli::command_line::Bench;
+
+/// Phases of the startup as timed for `--profile-startup`, in ms since the start
+static STARTUP: Mutex<Vec<(String, i64)>> = Mutex::new(Vec::new());
+
+/// Note the end of a startup phase for the benchmark
+pub fn record_startup_phase(phase: &str, elapsed_ms: i64) {
+    STARTUP
+        .lock()
+        .unwrap()
+        .push((phase.to_string(), elapsed_ms));
+}
+
+/// `satty bench`, time the stages of annotating an image and print them as JSON
+pub fn run(bench: &Bench) -> Result<()> {
+    let start = Instant::now();
+    let image = match &bench.input {
+        Some(input) => input::load_image(input)?,
+        None => synthetic_image(1920, 1080),
+    };
+    let load_image = start.elapsed();
+
+    let start = Instant::now();
+    let mut renderer = Renderer::new()?;
+    let context = start.elapsed();
+
+    // compiles the shaders, as the first frame of the editor does
+    let start = Instant::now();
+    renderer.render(&image, &[])?;
+    let first_frame = start.elapsed();
+
+    let annotations = synthetic_annotations(bench.annotations, image.width(), image.height());
+    let start = Instant::now();
+    let marker_numbers = Rc::new(RefCell::new(1));
+    let drawables: Vec<_> = annotations
+        .into_iter()
+        .map(|annotation| annotation.into_drawable(&marker_numbers))
+        .collect();
+    let commit = start.elapsed();
+
+    let mut renders = Vec::new();
+    let mut rendered = None;
+    for _ in 0..bench.runs.max(1) {
+        let start = Instant::now();
+        rendered = Some(renderer.render(&image, &drawables)?);
+        renders.push(start.elapsed());
+    }
+    renders.sort_unstable();
+
+    let start = Instant::now();
+    let mut png = Cursor::new(Vec::new());
+    if let Some(rendered) = rendered {
+        rendered.write_to(&mut png, ImageFormat::Png)?;
+    }
+    let png_encode = start.elapsed();
+
+    let startup: Vec<_> = STARTUP
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(phase, ms)| json!({ "phase": phase, "ms": ms }))
+        .collect();
+    let report = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "renderer": canvas::GL_RENDERER.get(),
+        "width": image.width(),
+        "height": image.height(),
+        "annotations": bench.annotations,
+        "startup": startup,
+        "load-image-ms": ms(load_image),
+        "context-ms": ms(context),
+        "first-frame-ms": ms(first_frame),
+        "commit-ms": ms(commit),
+        "render-ms": {
+            "min": ms(renders[0]),
+            "median": ms(renders[renders.len() / 2]),
+            "max": ms(renders[renders.len() - 1]),
+        },
+        "png-encode-ms": ms(png_encode),
+        "png-bytes": png.get_ref().len(),
+    });
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// A gradient, so blurs and highlights have something to work on
+fn synthetic_image(width: i32, height: i32) -> Pixbuf {
+    let data: Vec<u8> = (0..height)
+        .flat_map(|y| {
+            (0..width).flat_map(move |x| {
+                [
+                    (x * 255 / width) as u8,
+                    (y * 255 / height) as u8,
+                    ((x + y) % 256) as u8,
+                    255,
+                ]
+            })
+        })
+        .collect();
+    Pixbuf::from_bytes(
+        &Bytes::from_owned(data),
+        Colorspace::Rgb,
+        true,
+        8,
+        width,
+        height,
+        width * 4,
+    )
+}
+
+/// `count` annotations of the common tools spread over the image, the same on every run
+fn synthetic_annotations(count: usize, width: i32, height: i32) -> Vec<Annotation> {
+    // linear congruential generator, good enough for positions
+    let mut state: u32 = 0x5a77;
+    let mut next = move |max: i32| {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        ((state >> 16) % max.max(1) as u32) as f32
+    };
+    (0..count)
+        .map(|i| {
+            let start = Vec2D::new(next(width), next(height));
+            let end = Vec2D::new(next(width), next(height));
+            let size = Vec2D::new(next(width / 4), next(height / 4));
+            let style = AnnotationStyle::default();
+            match i % 9 {
+                0 => Annotation::Line { start, end, style },
+                1 => Annotation::Arrow { start, end, style },
+                2 => Annotation::Rectangle {
+                    top_left: start,
+                    size,
+                    style,
+                },
+                3 => Annotation::Ellipse {
+                    center: start,
+                    radii: size * 0.5,
+                    style,
+                },
+                4 => Annotation::Brush {
+                    points: (0..16)
+                        .map(|_| Vec2D::new(next(width), next(height)))
+                        .collect(),
+                    pressures: Vec::new(),
+                    style,
+                },
+                5 => Annotation::Highlight {
+                    top_left: start,
+                    size,
+                    style,
+                },
+                6 => Annotation::Blur {
+                    top_left: start,
+                    size,
+                    style,
+                },
+                7 => Annotation::Text {
+                    pos: start,
+                    text: format!("annotation {i}"),
+                    wrap_width: None,
+                    style,
+                },
+                _ => Annotation::Marker {
+                    pos: start,
+                    number: i as u16,
+                    style,
+                },
+            }
+        })
+        .collect()
+}
@@ -1,25 +1,88 @@
 use std::{
+    cell::RefCell,
     collections::HashMap,
+    fmt::Display,
     fs,
     io::{self, Write},
-    path::Path,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
 };
 
 use clap::Parser;
 use hex_color::HexColor;
-use relm4::SharedState;
-use serde_derive::Deserialize;
+use relm4::gtk::gdk::{Key, ModifierType};
+use serde_derive::{Deserialize, Serialize};
 use thiserror::Error;
 use xdg::{BaseDirectories, BaseDirectoriesError};
 
 use crate::{
-    style::Color,
-    tools::{Highlighters, Tools},
+    style::{Color, Size, ToolStyle},
+    tools::{HighlightBlend, Highlighters, Tools},
 };
 
-use satty_cli::command_line::{Action as CommandLineAction, CommandLine};
+use satty_cli::command_line::{
+    self, Action as CommandLineAction, AuthAction, Bench, Command, CommandLine, Render,
+};
+use satty_core::configuration::{DrawingConfiguration, DRAWING_CONFIG};
+
+pub static APP_CONFIG: SharedConfiguration = SharedConfiguration::new();
+
+thread_local! {
+    // the snapshot last read on this thread along with its generation
+    static SNAPSHOT: RefCell<Option<(u64, Arc<Configuration>)>> = const { RefCell::new(None) };
+}
+
+/// The configuration shared by all threads. Reading it returns an immutable snapshot that is
+/// cached per thread until the configuration is replaced, so hot paths like input handling and
+/// rendering take no lock. A snapshot stays unchanged while it is held, a replaced
+/// configuration is seen by the next read.
+pub struct SharedConfiguration {
+    current: RwLock<Option<Arc<Configuration>>>,
+    generation: AtomicU64,
+}
+
+impl SharedConfiguration {
+    const fn new() -> Self {
+        Self {
+            current: RwLock::new(None),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn read(&self) -> Arc<Configuration> {
+        let generation = self.generation.load(Ordering::Acquire);
+        SNAPSHOT.with_borrow_mut(|snapshot| match snapshot {
+            Some((cached, config)) if *cached == generation => config.clone(),
+            _ => {
+                let config = self.current();
+                *snapshot = Some((generation, config.clone()));
+                config
+            }
+        })
+    }
 
-pub static APP_CONFIG: SharedState<Configuration> = SharedState::new();
+    /// Replace the configuration, e.g. once it is loaded. The drawing follows its options.
+    pub fn replace(&self, config: Configuration) {
+        DRAWING_CONFIG.replace(config.drawing());
+        let mut current = self.current.write().unwrap();
+        *current = Some(Arc::new(config));
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    fn current(&self) -> Arc<Configuration> {
+        if let Some(config) = self.current.read().unwrap().as_ref() {
+            return config.clone();
+        }
+        self.current
+            .write()
+            .unwrap()
+            .get_or_insert_with(|| Arc::new(Configuration::default()))
+            .clone()
+    }
+}
 
 #[derive(Error, Debug)]
 enum ConfigurationFileError {
@@ -35,12 +98,21 @@ enum ConfigurationFileError {
 
 pub struct Configuration {
     input_filename: String,
+    inputs: Vec<String>,
     output_filename: Option<String>,
     fullscreen: bool,
+    layer_shell: bool,
+    layer_shell_output: Option<String>,
     early_exit: bool,
     corner_roundness: f32,
     initial_tool: Tools,
+    secondary_tool: Option<Tools>,
     copy_command: Option<String>,
+    export_filter_command: Option<String>,
+    recapture_command: Option<String>,
+    capture_delay: u32,
+    export_snippet: Option<String>,
+    code_scan_command: String,
     annotation_size_factor: f32,
     save_after_copy: bool,
     actions_on_enter: Vec<Action>,
@@ -50,20 +122,90 @@ pub struct Configuration {
     default_hide_toolbars: bool,
     focus_toggles_toolbars: bool,
     default_fill_shapes: bool,
+    default_shadow: bool,
+    default_outline: bool,
+    text_background_color: Option<Color>,
     font: FontConfiguration,
+    mouse: MouseBindings,
+    auto_redact: AutoRedact,
+    stitch: Stitch,
+    global_shortcuts: GlobalShortcuts,
+    hooks: Hooks,
+    toolbars: Toolbars,
+    upload: Option<Upload>,
+    variables: HashMap<String, String>,
     primary_highlighter: Highlighters,
+    highlight_opacity: f32,
+    highlight_blend: HighlightBlend,
+    ruler_scale: Option<f32>,
+    ruler_unit: String,
+    table_rows: u32,
+    table_columns: u32,
+    table_cell_size: Option<f32>,
     disable_notifications: bool,
+    tray_icon: bool,
+    check_for_updates: bool,
+    usage_statistics: bool,
+    reduce_motion: Option<bool>,
+    theme: String,
+    toast_position: ToastPosition,
+    toast_duration: f32,
     profile_startup: bool,
     no_window_decoration: bool,
+    window_opacity: f32,
     brush_smooth_history_size: usize,
     keybinds: Keybinds,
     zoom_factor: f32,
     pan_step_size: f32,
+    scroll_action: ScrollAction,
+    tool_style_memory: ToolStyleMemory,
+    latency_mode: LatencyMode,
+    copy_text_order: TextOrder,
+    copy_text_format: TextFormat,
+    invert_scroll: bool,
+    canvas_padding: f32,
+    canvas_padding_color: Color,
+    max_download_size: u64,
+    snap_to_guides: bool,
+    snap_threshold: f32,
+    crosshair_guides: bool,
+    show_minimap: bool,
+    show_status_bar: bool,
+    warn_low_contrast: bool,
+    autosnapshot_interval: u32,
+    recovery_interval: u32,
+    export_split: Option<ExportSplit>,
+    export_canvas: Option<ExportCanvas>,
+    export_canvas_presets: Vec<ExportCanvas>,
+    export_canvas_color: Color,
+    size_rules: Vec<SizeRule>,
+    presets: Vec<Preset>,
     daemon: bool,
+    install_desktop_files: bool,
+    dump_config: bool,
+    ping: bool,
+    check_update: bool,
+    recover: bool,
+    stdin_stream: bool,
+    diff: bool,
+    auth: Option<AuthAction>,
+    stats: Option<bool>,
+    render: Option<Render>,
+    bench: Option<Bench>,
 }
 
 pub struct Keybinds {
     shortcuts: HashMap<char, Tools>,
+    previous_tool: Key,
+    cycle_next: char,
+    cycle_previous: char,
+    cycle_preset: char,
+    spring_loaded: Vec<Tools>,
+    toggle_minimap: char,
+    color_blindness_preview: char,
+    keyboard_placement: char,
+    auto_redact: char,
+    actions: Vec<(ModifierType, Key, Vec<Action>)>,
 }
 
 impl Keybinds {
@@ -75,6 +217,130 @@ impl Keybinds {
         &self.shortcuts
     }
 
+    /// Key switching back to the previously used tool
+    pub fn previous_tool(&self) -> Key {
+        self.previous_tool
+    }
+
+    /// Whether holding the key of `tool` only selects it until the key is released
+    pub fn is_spring_loaded(&self, tool: Tools) -> bool {
+        self.spring_loaded.contains(&tool)
+    }
+
+    /// Key selecting the next tool in toolbar order
+    pub fn cycle_next(&self) -> char {
+        self.cycle_next
+    }
+
+    /// Key selecting the previous tool in toolbar order
+    pub fn cycle_previous(&self) -> char {
+        self.cycle_previous
+    }
+
+    /// Key applying the next of the `[[presets]]`
+    pub fn cycle_preset(&self) -> char {
+        self.cycle_preset
+    }
+
+    /// Key showing or hiding the minimap
+    pub fn toggle_minimap(&self) -> char {
+        self.toggle_minimap
+    }
+
+    /// Key cycling through previews of the annotations as seen with color blindness
+    pub fn color_blindness_preview(&self) -> char {
+        self.color_blindness_preview
+    }
+
+    /// Key toggling the crosshair placing annotations with the arrow keys and Enter
+    pub fn keyboard_placement(&self) -> char {
+        self.keyboard_placement
+    }
+
+    /// Key proposing blurs for the text found in the image
+    pub fn auto_redact(&self) -> char {
+        self.auto_redact
+    }
+
+    /// Actions bound to `key` pressed with exactly `modifier`, if any
+    pub fn actions(&self, modifier: ModifierType, key: Key) -> Option<&[Action]> {
+        self.actions
+            .iter()
+            .find(|(m, k, _)| *m == modifier && k.to_lower() == key.to_lower())
+            .map(|(_, _, actions)| actions.as_slice())
+    }
+
+    /// Key selecting `tool`, if it has one
+    pub fn tool_key(&self, tool: Tools) -> Option<char> {
+        self.shortcuts
+            .iter()
+            .find(|(_, t)| **t == tool)
+            .map(|(key, _)| *key)
+    }
+
+    /// Key combination running exactly `actions`, from `[keybinds.actions]` or one of the
+    /// built in shortcuts it doesn't take over, as shown to the user
+    pub fn actions_shortcut(&self, actions: &[Action]) -> Option<String> {
+        // as handled by the sketch board when nothing is bound to the keys
+        let ctrl = ModifierType::CONTROL_MASK;
+        let ctrl_shift = ModifierType::CONTROL_MASK | ModifierType::SHIFT_MASK;
+        let builtin = [
+            (ctrl, Key::c, Action::SaveToClipboard),
+            (ctrl, Key::s, Action::SaveToFile(None)),
+            (ctrl_shift, Key::s, Action::SaveToFileAs),
+            (ctrl_shift, Key::q, Action::ScanCodes),
+        ];
+        self.actions
+            .iter()
+            .find(|(_, _, bound)| bound.as_slice() == actions)
+            .map(|(modifier, key, _)| (*modifier, *key))
+            .or_else(|| {
+                builtin
+                    .into_iter()
+                    .find(|(modifier, key, action)| {
+                        actions == std::slice::from_ref(action)
+                            && self.actions(*modifier, *key).is_none()
+                    })
+                    .map(|(modifier, key, _)| (modifier, key))
+            })
+            .and_then(|(modifier, key)| Self::format_key_combination(modifier, key.to_upper()))
+    }
+
+    /// Parse a key combination like `Ctrl+Shift+Return`, the key named as in GDK
+    fn parse_key_combination(combination: &str) -> Option<(ModifierType, Key)> {
+        let mut parts: Vec<&str> = combination.split('+').map(str::trim).collect();
+        let key = Key::from_name(parts.pop()?)?;
+        let mut modifier = ModifierType::empty();
+        for part in parts {
+            modifier |= match part.to_lowercase().as_str() {
+                "ctrl" | "control" => ModifierType::CONTROL_MASK,
+                "shift" => ModifierType::SHIFT_MASK,
+                "alt" => ModifierType::ALT_MASK,
+                "super" => ModifierType::SUPER_MASK,
+                _ => return None,
+            };
+        }
+        Some((modifier, key))
+    }
+
+    /// Format a key combination the way `parse_key_combination` reads it
+    fn format_key_combination(modifier: ModifierType, key: Key) -> Option<String> {
+        let mut combination = String::new();
+        for (mask, name) in [
+            (ModifierType::CONTROL_MASK, "Ctrl"),
+            (ModifierType::SHIFT_MASK, "Shift"),
+            (ModifierType::ALT_MASK, "Alt"),
+            (ModifierType::SUPER_MASK, "Super"),
+        ] {
+            if modifier.contains(mask) {
+                combination.push_str(name);
+                combination.push('+');
+            }
+        }
+        combination.push_str(&key.name()?);
+        Some(combination)
+    }
+
     /// Update a single keybind, only if it is valid
     fn update_keybind(&mut self, key: Option<String>, tool: Tools) {
         if let Some(key_str) = key {
@@ -86,7 +352,7 @@ impl Keybinds {
     }
 
     /// A shortcut keybinding is only valid if it is one char
-    fn validate_keybind(key: &str, tool: Tools) -> Option<char> {
+    fn validate_keybind(key: &str, tool: impl Display) -> Option<char> {
         let mut chars = key.chars();
         match (chars.next(), chars.next()) {
             (Some(c), None) => Some(c),
@@ -97,6 +363,51 @@ impl Keybinds {
         }
     }
 
+    fn to_file(&self) -> KeybindsFile {
+        let tool = |tool: Tools| {
+            self.shortcuts
+                .iter()
+                .find(|(_, t)| **t == tool)
+                .map(|(key, _)| key.to_string())
+        };
+        KeybindsFile {
+            pointer: tool(Tools::Pointer),
+            crop: tool(Tools::Crop),
+            brush: tool(Tools::Brush),
+            line: tool(Tools::Line),
+            arrow: tool(Tools::Arrow),
+            rectangle: tool(Tools::Rectangle),
+            ellipse: tool(Tools::Ellipse),
+            text: tool(Tools::Text),
+            marker: tool(Tools::Marker),
+            blur: tool(Tools::Blur),
+            highlight: tool(Tools::Highlight),
+            eraser: tool(Tools::Eraser),
+            select: tool(Tools::Select),
+            ruler: tool(Tools::Ruler),
+            table: tool(Tools::Table),
+            brace: tool(Tools::Brace),
+            previous_tool: self.previous_tool.name().map(|name| name.to_string()),
+            cycle_next: Some(self.cycle_next.to_string()),
+            cycle_previous: Some(self.cycle_previous.to_string()),
+            cycle_preset: Some(self.cycle_preset.to_string()),
+            spring_loaded: Some(self.spring_loaded.clone()),
+            toggle_minimap: Some(self.toggle_minimap.to_string()),
+            color_blindness_preview: Some(self.color_blindness_preview.to_string()),
+            keyboard_placement: Some(self.keyboard_placement.to_string()),
+            auto_redact: Some(self.auto_redact.to_string()),
+            actions: Some(
+                self.actions
+                    .iter()
+                    .filter_map(|(modifier, key, actions)| {
+                        Self::format_key_combination(*modifier, *key)
+                            .map(|combination| (combination, actions.clone()))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
     /// Merge keybindings with default
     /// Only replaces defaults if they are set
     fn merge(&mut self, file_keybinds: KeybindsFile) {
@@ -111,6 +422,76 @@ impl Keybinds {
         self.update_keybind(file_keybinds.marker, Tools::Marker);
         self.update_keybind(file_keybinds.blur, Tools::Blur);
         self.update_keybind(file_keybinds.highlight, Tools::Highlight);
+        self.update_keybind(file_keybinds.eraser, Tools::Eraser);
+        self.update_keybind(file_keybinds.select, Tools::Select);
+        self.update_keybind(file_keybinds.ruler, Tools::Ruler);
+        self.update_keybind(file_keybinds.table, Tools::Table);
+        self.update_keybind(file_keybinds.brace, Tools::Brace);
+        if let Some(name) = file_keybinds.previous_tool {
+            match Key::from_name(&name) {
+                Some(key) => self.previous_tool = key,
+                None => eprintln!("Warning: Invalid keybind: 'previous-tool = {name}'. Using default keybind instead."),
+            }
+        }
+        if let Some(key) = file_keybinds
+            .cycle_next
+            .and_then(|key| Self::validate_keybind(&key, "cycle-next"))
+        {
+            self.cycle_next = key;
+        }
+        if let Some(v) = file_keybinds.spring_loaded {
+            self.spring_loaded = v;
+        }
+        if let Some(key) = file_keybinds
+            .cycle_previous
+            .and_then(|key| Self::validate_keybind(&key, "cycle-previous"))
+        {
+            self.cycle_previous = key;
+        }
+        if let Some(key) = file_keybinds
+            .cycle_preset
+            .and_then(|key| Self::validate_keybind(&key, "cycle-preset"))
+        {
+            self.cycle_preset = key;
+        }
+        if let Some(key) = file_keybinds
+            .toggle_minimap
+            .and_then(|key| Self::validate_keybind(&key, "toggle-minimap"))
+        {
+            self.toggle_minimap = key;
+        }
+        if let Some(key) = file_keybinds
+            .color_blindness_preview
+            .and_then(|key| Self::validate_keybind(&key, "color-blindness-preview"))
+        {
+            self.color_blindness_preview = key;
+        }
+        if let Some(key) = file_keybinds
+            .keyboard_placement
+            .and_then(|key| Self::validate_keybind(&key, "keyboard-placement"))
+        {
+            self.keyboard_placement = key;
+        }
+        if let Some(key) = file_keybinds
+            .auto_redact
+            .and_then(|key| Self::validate_keybind(&key, "auto-redact"))
+        {
+            self.auto_redact = key;
+        }
+        if let Some(v) = file_keybinds.actions {
+            self.actions = v
+                .into_iter()
+                .filter_map(|(combination, actions)| {
+                    match Self::parse_key_combination(&combination) {
+                        Some((modifier, key)) => Some((modifier, key, actions)),
+                        None => {
+                            eprintln!("Warning: Invalid keybind: '{combination}' in keybinds.actions. Ignoring it.");
+                            None
+                        }
+                    }
+                })
+                .collect();
+        }
     }
 }
 
@@ -128,8 +509,25 @@ impl Default for Keybinds {
         shortcuts.insert('m', Tools::Marker);
         shortcuts.insert('u', Tools::Blur);
         shortcuts.insert('g', Tools::Highlight);
+        shortcuts.insert('x', Tools::Eraser);
+        shortcuts.insert('v', Tools::Select);
+        shortcuts.insert('l', Tools::Ruler);
+        shortcuts.insert('a', Tools::Table);
+        shortcuts.insert('o', Tools::Brace);
 
-        Self { shortcuts }
+        Self {
+            shortcuts,
+            previous_tool: Key::Tab,
+            cycle_next: ']',
+            cycle_previous: '[',
+            cycle_preset: 'w',
+            spring_loaded: Vec::new(),
+            toggle_minimap: 'n',
+            color_blindness_preview: 'k',
+            keyboard_placement: 'j',
+            auto_redact: 'd',
+            actions: Vec::new(),
+        }
     }
 }
 
@@ -137,14 +535,19 @@ impl Default for Keybinds {
 pub struct FontConfiguration {
     family: Option<String>,
     style: Option<String>,
+    size: Option<f32>,
 }
 
 impl FontConfiguration {
     pub fn family(&self) -> Option<&str> {
         self.family.as_deref()
     }
-    pub fn style(&self) -> Option<&str> {
-        self.style.as_deref()
+    fn to_file(&self) -> FontFile {
+        FontFile {
+            family: self.family.clone(),
+            style: self.style.clone(),
+            size: self.size,
+        }
     }
     fn merge(&mut self, file_font: FontFile) {
         if let Some(v) = file_font.family {
@@ -153,26 +556,472 @@ impl FontConfiguration {
         if let Some(v) = file_font.style {
             self.style = Some(v);
         }
+        if let Some(v) = file_font.size.filter(|size| *size > 0.0) {
+            self.size = Some(v);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToastPosition {
+    Top,
+    Center,
+    Bottom,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrollAction {
+    Zoom,
+    Pan,
+}
+
+/// How frames are presented, low latency gives up vsync for less lag behind a pen
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LatencyMode {
+    Default,
+    Low,
+}
+
+/// Order of the texts copied by the copy-text action
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextOrder {
+    /// top to bottom, lines left to right
+    Reading,
+    /// bottom to top as stacked, i.e. usually as written
+    ZOrder,
+}
+
+/// How the copy-text action joins the texts
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextFormat {
+    /// separated by empty lines
+    Plain,
+    /// a bullet list
+    Markdown,
+}
+
+/// Whether color, size and fill are remembered for each tool, and for how long
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolStyleMemory {
+    /// all tools share one style
+    Shared,
+    Session,
+    /// kept across sessions in the state directory
+    Persistent,
+}
+
+/// Physical mouse button taking over the role of the middle or right button
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseButtonBinding {
+    Middle,
+    Right,
+    #[serde(rename = "none")]
+    Disabled,
+}
+
+/// Which buttons pan and which one triggers the right click actions, secondary tool and
+/// selection menu
+pub struct MouseBindings {
+    pan: MouseButtonBinding,
+    actions: MouseButtonBinding,
+}
+
+impl Default for MouseBindings {
+    fn default() -> Self {
+        Self {
+            pan: MouseButtonBinding::Middle,
+            actions: MouseButtonBinding::Right,
+        }
+    }
+}
+
+impl MouseBindings {
+    pub fn pan(&self) -> MouseButtonBinding {
+        self.pan
+    }
+
+    pub fn actions(&self) -> MouseButtonBinding {
+        self.actions
+    }
+
+    fn to_file(&self) -> MouseFile {
+        MouseFile {
+            pan: Some(self.pan),
+            actions: Some(self.actions),
+        }
+    }
+
+    fn merge(&mut self, file_mouse: MouseFile) {
+        let pan = file_mouse.pan.unwrap_or(self.pan);
+        let actions = file_mouse.actions.unwrap_or(self.actions);
+        if pan == actions && pan != MouseButtonBinding::Disabled {
+            eprintln!("Warning: pan and actions can't use the same mouse button. Using default mouse buttons instead.");
+            return;
+        }
+        self.pan = pan;
+        self.actions = actions;
+    }
+}
+
+/// How the auto-redact key finds text regions to blur
+pub struct AutoRedact {
+    threshold: u8,
+    max_line_height: u32,
+    padding: u32,
+}
+
+impl Default for AutoRedact {
+    fn default() -> Self {
+        Self {
+            threshold: 48,
+            max_line_height: 64,
+            padding: 2,
+        }
+    }
+}
+
+impl AutoRedact {
+    /// Brightness difference between neighboring pixels counting as the edge of a glyph
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Regions higher than this are no line of text, e.g. photos or icons
+    pub fn max_line_height(&self) -> u32 {
+        self.max_line_height
+    }
+
+    /// Space added around the text of each region
+    pub fn padding(&self) -> u32 {
+        self.padding
+    }
+
+    fn to_file(&self) -> AutoRedactFile {
+        AutoRedactFile {
+            threshold: Some(self.threshold),
+            max_line_height: Some(self.max_line_height),
+            padding: Some(self.padding),
+        }
+    }
+
+    fn merge(&mut self, file_auto_redact: AutoRedactFile) {
+        if let Some(v) = file_auto_redact.threshold {
+            self.threshold = v;
+        }
+        if let Some(v) = file_auto_redact.max_line_height {
+            self.max_line_height = v;
+        }
+        if let Some(v) = file_auto_redact.padding {
+            self.padding = v;
+        }
+    }
+}
+
+/// Where the append image action places the other image
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StitchDirection {
+    /// the side that keeps the result closer to a square
+    Auto,
+    Right,
+    Below,
+}
+
+/// How the append image action joins the images
+pub struct Stitch {
+    direction: StitchDirection,
+    separator_width: u32,
+    separator_color: Color,
+}
+
+impl Default for Stitch {
+    fn default() -> Self {
+        Self {
+            direction: StitchDirection::Auto,
+            separator_width: 4,
+            separator_color: Color::new(255, 255, 255, 255),
+        }
+    }
+}
+
+impl Stitch {
+    pub fn direction(&self) -> StitchDirection {
+        self.direction
+    }
+
+    /// Width of the line between the images, 0 for none
+    pub fn separator_width(&self) -> u32 {
+        self.separator_width
+    }
+
+    pub fn separator_color(&self) -> Color {
+        self.separator_color
+    }
+
+    fn to_file(&self) -> StitchFile {
+        StitchFile {
+            direction: Some(self.direction),
+            separator_width: Some(self.separator_width),
+            separator_color: Some(self.separator_color.into()),
+        }
+    }
+
+    fn merge(&mut self, file_stitch: StitchFile) {
+        if let Some(v) = file_stitch.direction {
+            self.direction = v;
+        }
+        if let Some(v) = file_stitch.separator_width {
+            self.separator_width = v;
+        }
+        if let Some(v) = file_stitch.separator_color {
+            self.separator_color = v.into();
+        }
+    }
+}
+
+/// Shortcuts the daemon registers with the desktop, their preferred triggers
+#[derive(Default)]
+pub struct GlobalShortcuts {
+    capture: Option<String>,
+    paste: Option<String>,
+}
+
+impl GlobalShortcuts {
+    /// Run the `recapture-command` and annotate its screenshot
+    pub fn capture(&self) -> Option<&String> {
+        self.capture.as_ref()
+    }
+
+    /// Annotate the image in the clipboard
+    pub fn paste(&self) -> Option<&String> {
+        self.paste.as_ref()
+    }
+
+    fn to_file(&self) -> GlobalShortcutsFile {
+        GlobalShortcutsFile {
+            capture: self.capture.clone(),
+            paste: self.paste.clone(),
+        }
+    }
+
+    fn merge(&mut self, file_shortcuts: GlobalShortcutsFile) {
+        if let Some(v) = file_shortcuts.capture {
+            self.capture = Some(v);
+        }
+        if let Some(v) = file_shortcuts.paste {
+            self.paste = Some(v);
+        }
+    }
+}
+
+/// Rhai script whose functions run on events of the editor, they get the annotations and may
+/// change them
+#[derive(Default)]
+pub struct Hooks {
+    script: Option<String>,
+}
+
+impl Hooks {
+    /// Path of the script, may start with `~/`
+    pub fn script(&self) -> Option<&String> {
+        self.script.as_ref()
+    }
+
+    fn to_file(&self) -> HooksFile {
+        HooksFile {
+            script: self.script.clone(),
+        }
+    }
+
+    fn merge(&mut self, file_hooks: HooksFile) {
+        if let Some(v) = file_hooks.script {
+            self.script = Some(v);
+        }
+    }
+}
+
+/// Items of the toolbars in order, the built-in layout is kept for a toolbar without a list
+#[derive(Default)]
+pub struct Toolbars {
+    top: Option<Vec<String>>,
+    bottom: Option<Vec<String>>,
+}
+
+impl Toolbars {
+    /// Names of the items of the top toolbar, "separator" adds a separator
+    pub fn top(&self) -> Option<&[String]> {
+        self.top.as_deref()
+    }
+
+    /// Names of the items of the bottom toolbar, "separator" adds a separator
+    pub fn bottom(&self) -> Option<&[String]> {
+        self.bottom.as_deref()
+    }
+
+    fn to_file(&self) -> ToolbarsFile {
+        ToolbarsFile {
+            top: self.top.clone(),
+            bottom: self.bottom.clone(),
+        }
+    }
+
+    fn merge(&mut self, file_toolbars: ToolbarsFile) {
+        if let Some(v) = file_toolbars.top {
+            self.top = Some(v);
+        }
+        if let Some(v) = file_toolbars.bottom {
+            self.bottom = Some(v);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadProvider {
+    /// the uploads endpoint of a project, as used for issue attachments
+    GitLab,
+    /// an asset attached to a release of the repository
+    GitHub,
+}
+
+/// Where the upload action puts the exported image
+#[derive(Clone)]
+pub struct Upload {
+    provider: UploadProvider,
+    project: String,
+    url: Option<String>,
+    token_env: String,
+    token_service: Option<String>,
+    path: String,
+    release: Option<String>,
+}
+
+impl Upload {
+    pub fn provider(&self) -> UploadProvider {
+        self.provider
+    }
+
+    /// `group/project` on GitLab, `owner/repository` on GitHub
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    /// Base URL of the API, e.g. of a self-hosted instance
+    pub fn url(&self) -> &str {
+        match (&self.url, self.provider) {
+            (Some(url), _) => url.trim_end_matches('/'),
+            (None, UploadProvider::GitLab) => "https://gitlab.com",
+            (None, UploadProvider::GitHub) => "https://api.github.com",
+        }
+    }
+
+    /// Environment variable holding the access token, preferred over the Secret Service
+    pub fn token_env(&self) -> &str {
+        &self.token_env
+    }
+
+    /// Service the token is stored for with `satty auth set`, the provider by default
+    pub fn token_service(&self) -> &str {
+        match (&self.token_service, self.provider) {
+            (Some(service), _) => service,
+            (None, UploadProvider::GitLab) => "gitlab",
+            (None, UploadProvider::GitHub) => "github",
+        }
+    }
+
+    /// File name template of the upload
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Tag of the GitHub release the image is attached to
+    pub fn release(&self) -> &str {
+        self.release.as_deref().unwrap_or("screenshots")
+    }
+}
+
+impl From<Upload> for UploadFile {
+    fn from(upload: Upload) -> Self {
+        Self {
+            provider: upload.provider,
+            project: upload.project,
+            url: upload.url,
+            token_env: Some(upload.token_env),
+            token_service: upload.token_service,
+            path: Some(upload.path),
+            release: upload.release,
+        }
+    }
+}
+
+impl From<UploadFile> for Upload {
+    fn from(file: UploadFile) -> Self {
+        Self {
+            provider: file.provider,
+            project: file.project,
+            url: file.url,
+            token_env: file
+                .token_env
+                .unwrap_or_else(|| "SATTY_UPLOAD_TOKEN".to_string()),
+            token_service: file.token_service,
+            path: file
+                .path
+                .unwrap_or_else(|| "satty-%Y%m%d-%H%M%S.png".to_string()),
+            release: file.release,
+        }
     }
 }
 
 pub struct ColorPalette {
-    palette: Vec<Color>,
+    /// the main `palette` first, then the `[[color-palette.palettes]]`
+    palettes: Vec<Palette>,
     custom: Vec<Color>,
 }
 
 impl ColorPalette {
+    /// Colors of the main palette
     pub fn palette(&self) -> &[Color] {
-        &self.palette
+        self.palettes[0].colors()
+    }
+
+    /// All palettes the toolbar can switch between, the main palette first
+    pub fn palettes(&self) -> &[Palette] {
+        &self.palettes
     }
 
     pub fn custom(&self) -> &[Color] {
         &self.custom
     }
 
+    fn to_file(&self) -> ColorPaletteFile {
+        ColorPaletteFile {
+            preset: None,
+            palette: Some(self.palettes[0].to_file().colors),
+            palettes: Some(self.palettes[1..].iter().map(Palette::to_file).collect()),
+            custom: Some(self.custom.iter().copied().map(HexColor::from).collect()),
+        }
+    }
+
     fn merge(&mut self, file_palette: ColorPaletteFile) {
+        if let Some(v) = file_palette.preset {
+            self.palettes[0] = Palette::unnamed(v.colors());
+        }
         if let Some(v) = file_palette.palette {
-            self.palette = v.into_iter().map(Color::from).collect();
+            self.palettes[0] = Palette::from(PaletteFile {
+                name: MAIN_PALETTE.to_string(),
+                colors: v,
+            });
+        }
+        if let Some(v) = file_palette.palettes {
+            self.palettes.truncate(1);
+            self.palettes.extend(v.into_iter().map(Palette::from));
         }
         if let Some(v) = file_palette.custom {
             self.custom = v.into_iter().map(Color::from).collect();
@@ -180,12 +1029,371 @@ impl ColorPalette {
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+/// Name of the palette given by `palette` or `preset`
+const MAIN_PALETTE: &str = "Default";
+
+/// Colors for quick selection in the toolbar, some named for their tooltip
+#[derive(Clone)]
+pub struct Palette {
+    name: String,
+    colors: Vec<Color>,
+    color_names: Vec<Option<String>>,
+}
+
+impl Palette {
+    fn unnamed(colors: Vec<Color>) -> Self {
+        Self {
+            name: MAIN_PALETTE.to_string(),
+            color_names: vec![None; colors.len()],
+            colors,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    /// Name of the color at `index`, if the config file gives one
+    pub fn color_name(&self, index: usize) -> Option<&str> {
+        self.color_names.get(index)?.as_deref()
+    }
+
+    fn to_file(&self) -> PaletteFile {
+        PaletteFile {
+            name: self.name.clone(),
+            colors: self
+                .colors
+                .iter()
+                .zip(&self.color_names)
+                .map(|(&color, name)| match name {
+                    Some(name) => PaletteColorFile::Named {
+                        name: name.clone(),
+                        value: color.into(),
+                    },
+                    None => PaletteColorFile::Color(color.into()),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<PaletteFile> for Palette {
+    fn from(file: PaletteFile) -> Self {
+        let (colors, color_names) = file
+            .colors
+            .into_iter()
+            .map(|entry| match entry {
+                PaletteColorFile::Color(value) => (Color::from(value), None),
+                PaletteColorFile::Named { name, value } => (Color::from(value), Some(name)),
+            })
+            .unzip();
+        Self {
+            name: file.name,
+            colors,
+            color_names,
+        }
+    }
+}
+
+/// Built-in palettes selectable with `[color-palette] preset`
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "kebab-case")]
+pub enum PalettePreset {
+    Default,
+    /// the Okabe-Ito palette, distinguishable with all common color vision deficiencies
+    ColorBlind,
+}
+
+impl PalettePreset {
+    fn colors(self) -> Vec<Color> {
+        match self {
+            Self::Default => ColorPalette::default().palette().to_vec(),
+            Self::ColorBlind => vec![
+                Color::new(230, 159, 0, 255),
+                Color::new(86, 180, 233, 255),
+                Color::new(0, 158, 115, 255),
+                Color::new(240, 228, 66, 255),
+                Color::new(0, 114, 178, 255),
+                Color::new(213, 94, 0, 255),
+                Color::new(204, 121, 167, 255),
+                Color::new(0, 0, 0, 255),
+            ],
+        }
+    }
+}
+
+/// Settings applied to images matching the given dimensions, see `[[size-rules]]`
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SizeRule {
+    min_width: Option<i32>,
+    max_width: Option<i32>,
+    min_height: Option<i32>,
+    max_height: Option<i32>,
+    output_scale: Option<f32>,
+    initial_zoom: Option<InitialZoom>,
+    export_split: Option<ExportSplit>,
+}
+
+/// A tool along with the parts of the style to use with it, see `[[presets]]`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Preset {
+    name: String,
+    tool: Tools,
+    color: Option<HexColor>,
+    size: Option<Size>,
+    fill: Option<bool>,
+    key: Option<char>,
+}
+
+impl Preset {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn tool(&self) -> Tools {
+        self.tool
+    }
+
+    /// Color, size and fill of the preset, the others are taken from `style`
+    pub fn apply_to(&self, mut style: ToolStyle) -> ToolStyle {
+        if let Some(v) = self.color {
+            style.color = v.into();
+        }
+        if let Some(v) = self.size {
+            style.size = v;
+        }
+        if let Some(v) = self.fill {
+            style.fill = v;
+        }
+        style
+    }
+
+    /// Key selecting the preset, it takes precedence over the tool keybinds
+    pub fn key(&self) -> Option<char> {
+        self.key
+    }
+}
+
+impl SizeRule {
+    /// All specified limits are inclusive, unspecified ones always match
+    fn matches(&self, width: i32, height: i32) -> bool {
+        self.min_width.is_none_or(|v| width >= v)
+            && self.max_width.is_none_or(|v| width <= v)
+            && self.min_height.is_none_or(|v| height >= v)
+            && self.max_height.is_none_or(|v| height <= v)
+    }
+
+    pub fn output_scale(&self) -> Option<f32> {
+        self.output_scale.filter(|v| *v > 0.0)
+    }
+
+    pub fn initial_zoom(&self) -> Option<InitialZoom> {
+        self.initial_zoom
+    }
+
+    /// How saved files get split into tiles, overrides the general `export-split`
+    pub fn export_split(&self) -> Option<ExportSplit> {
+        self.export_split
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(try_from = "InitialZoomFile", into = "InitialZoomFile")]
+pub enum InitialZoom {
+    Fit,
+    /// fit the width and start at the top, for tall images
+    FitWidth,
+    Factor(f32),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum InitialZoomFile {
+    Factor(f32),
+    Keyword(String),
+}
+
+impl From<InitialZoom> for InitialZoomFile {
+    fn from(value: InitialZoom) -> Self {
+        match value {
+            InitialZoom::Fit => Self::Keyword("fit".to_string()),
+            InitialZoom::FitWidth => Self::Keyword("fit-width".to_string()),
+            InitialZoom::Factor(v) => Self::Factor(v),
+        }
+    }
+}
+
+impl TryFrom<InitialZoomFile> for InitialZoom {
+    type Error = String;
+
+    fn try_from(value: InitialZoomFile) -> Result<Self, Self::Error> {
+        match value {
+            InitialZoomFile::Factor(v) if v > 0.0 => Ok(Self::Factor(v)),
+            InitialZoomFile::Factor(v) => Err(format!("zoom factor must be positive, got {v}")),
+            InitialZoomFile::Keyword(k) if k == "fit" => Ok(Self::Fit),
+            InitialZoomFile::Keyword(k) if k == "fit-width" => Ok(Self::FitWidth),
+            InitialZoomFile::Keyword(k) => Err(format!(
+                "unknown zoom '{k}', expected \"fit\", \"fit-width\" or a number"
+            )),
+        }
+    }
+}
+
+/// Fixed size of exported images, the rendered image is centered on a canvas of this size
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct ExportCanvas {
+    width: u32,
+    height: u32,
+}
+
+impl ExportCanvas {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl TryFrom<String> for ExportCanvas {
+    type Error = String;
+
+    /// Accepts `WIDTHxHEIGHT` or one of the presets `720p`, `1080p`, `1440p` and `4k`
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let (width, height) = match value.trim().to_lowercase().as_str() {
+            "720p" => (1280, 720),
+            "1080p" => (1920, 1080),
+            "1440p" => (2560, 1440),
+            "4k" | "2160p" => (3840, 2160),
+            size => size
+                .split_once('x')
+                .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+                .ok_or_else(|| {
+                    format!(
+                        "invalid canvas size '{value}', expected e.g. \"1920x1080\" or \"1080p\""
+                    )
+                })?,
+        };
+        if width == 0 || height == 0 {
+            return Err(format!("canvas size must not be empty, got '{value}'"));
+        }
+        Ok(Self { width, height })
+    }
+}
+
+impl Display for ExportCanvas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+impl From<ExportCanvas> for String {
+    fn from(value: ExportCanvas) -> Self {
+        value.to_string()
+    }
+}
+
+/// Split of saved images into several numbered files, e.g. for chat apps limiting the
+/// image size
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub enum ExportSplit {
+    /// a fixed grid, tiles are evenly sized
+    Grid { columns: u32, rows: u32 },
+    /// full width slices of at most this height, the last one takes the rest
+    SliceHeight(u32),
+}
+
+impl ExportSplit {
+    /// Tiles of an image of the given size as `(x, y, width, height)`, row by row
+    pub fn tiles(&self, width: u32, height: u32) -> Vec<(u32, u32, u32, u32)> {
+        // start offsets along one axis, followed by the end
+        let even = |size: u32, count: u32| {
+            let count = count.clamp(1, size.max(1));
+            (0..=count)
+                .map(|i| (size as u64 * i as u64 / count as u64) as u32)
+                .collect::<Vec<_>>()
+        };
+        let (xs, ys) = match *self {
+            Self::Grid { columns, rows } => (even(width, columns), even(height, rows)),
+            Self::SliceHeight(slice) => (
+                vec![0, width],
+                (0..height)
+                    .step_by(slice as usize)
+                    .chain([height])
+                    .collect(),
+            ),
+        };
+
+        ys.windows(2)
+            .flat_map(|y| {
+                xs.windows(2)
+                    .map(move |x| (x[0], y[0], x[1] - x[0], y[1] - y[0]))
+            })
+            .collect()
+    }
+}
+
+impl From<ExportSplit> for String {
+    fn from(value: ExportSplit) -> Self {
+        match value {
+            ExportSplit::Grid { columns, rows } => format!("{columns}x{rows}"),
+            ExportSplit::SliceHeight(height) => height.to_string(),
+        }
+    }
+}
+
+impl TryFrom<String> for ExportSplit {
+    type Error = String;
+
+    /// Accepts `COLUMNSxROWS` for a grid or a single number for slices of that height
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let trimmed = value.trim().to_lowercase();
+        let split = match trimmed.split_once('x') {
+            Some((columns, rows)) => columns
+                .trim()
+                .parse()
+                .ok()
+                .zip(rows.trim().parse().ok())
+                .map(|(columns, rows)| Self::Grid { columns, rows }),
+            None => trimmed.parse().ok().map(Self::SliceHeight),
+        };
+        match split {
+            Some(Self::Grid { columns, rows }) if columns > 0 && rows > 0 => {
+                Ok(Self::Grid { columns, rows })
+            }
+            Some(Self::SliceHeight(height)) if height > 0 => Ok(Self::SliceHeight(height)),
+            _ => Err(format!(
+                "invalid export split '{value}', expected e.g. \"2x3\" or a slice height like \"4000\""
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(from = "ActionFile", into = "ActionFile")]
 pub enum Action {
     SaveToClipboard,
-    SaveToFile,
+    /// save to the given output template, `output-filename` if `None`
+    SaveToFile(Option<String>),
     SaveToFileAs,
+    /// copy the text annotations as text
+    CopyText,
+    /// show the content of the QR codes and barcodes in the image
+    ScanCodes,
+    /// upload to the configured project and copy the Markdown link
+    Upload,
+    /// save the image without and with the annotations as `…-before` and `…-after` of the
+    /// `output-filename`
+    SaveBeforeAfter,
     Exit,
 }
 
@@ -193,12 +1401,102 @@ impl From<CommandLineAction> for Action {
     fn from(action: CommandLineAction) -> Self {
         match action {
             CommandLineAction::SaveToClipboard => Self::SaveToClipboard,
-            CommandLineAction::SaveToFile => Self::SaveToFile,
+            CommandLineAction::SaveToFile => Self::SaveToFile(None),
+            CommandLineAction::CopyText => Self::CopyText,
+            CommandLineAction::ScanCodes => Self::ScanCodes,
+            CommandLineAction::Upload => Self::Upload,
+            CommandLineAction::SaveBeforeAfter => Self::SaveBeforeAfter,
             CommandLineAction::Exit => Self::Exit,
         }
     }
 }
 
+/// An action as written in the configuration file, either its name or a table with the
+/// parameters, e.g. `{ save-to-file = "~/scratch/%s.png" }`
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum ActionFile {
+    Name(ActionName),
+    SaveToFile(SaveToFileFile),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum ActionName {
+    SaveToClipboard,
+    SaveToFile,
+    SaveToFileAs,
+    CopyText,
+    ScanCodes,
+    Upload,
+    SaveBeforeAfter,
+    Exit,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct SaveToFileFile {
+    save_to_file: String,
+}
+
+impl Action {
+    /// Name as in the configuration file, without the parameters
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SaveToClipboard => "save-to-clipboard",
+            Self::SaveToFile(_) => "save-to-file",
+            Self::SaveToFileAs => "save-to-file-as",
+            Self::CopyText => "copy-text",
+            Self::ScanCodes => "scan-codes",
+            Self::Upload => "upload",
+            Self::SaveBeforeAfter => "save-before-after",
+            Self::Exit => "exit",
+        }
+    }
+}
+
+impl From<Action> for ActionFile {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::SaveToClipboard => Self::Name(ActionName::SaveToClipboard),
+            Action::SaveToFile(None) => Self::Name(ActionName::SaveToFile),
+            Action::SaveToFile(Some(save_to_file)) => {
+                Self::SaveToFile(SaveToFileFile { save_to_file })
+            }
+            Action::SaveToFileAs => Self::Name(ActionName::SaveToFileAs),
+            Action::CopyText => Self::Name(ActionName::CopyText),
+            Action::ScanCodes => Self::Name(ActionName::ScanCodes),
+            Action::Upload => Self::Name(ActionName::Upload),
+            Action::SaveBeforeAfter => Self::Name(ActionName::SaveBeforeAfter),
+            Action::Exit => Self::Name(ActionName::Exit),
+        }
+    }
+}
+
+impl From<ActionFile> for Action {
+    fn from(action: ActionFile) -> Self {
+        match action {
+            ActionFile::Name(ActionName::SaveToClipboard) => Self::SaveToClipboard,
+            ActionFile::Name(ActionName::SaveToFile) => Self::SaveToFile(None),
+            ActionFile::Name(ActionName::SaveToFileAs) => Self::SaveToFileAs,
+            ActionFile::Name(ActionName::CopyText) => Self::CopyText,
+            ActionFile::Name(ActionName::ScanCodes) => Self::ScanCodes,
+            ActionFile::Name(ActionName::Upload) => Self::Upload,
+            ActionFile::Name(ActionName::SaveBeforeAfter) => Self::SaveBeforeAfter,
+            ActionFile::Name(ActionName::Exit) => Self::Exit,
+            ActionFile::SaveToFile(file) => Self::SaveToFile(Some(file.save_to_file)),
+        }
+    }
+}
+
+/// Where the image being annotated comes from, used to select a `[when.*]` section
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputSource {
+    Stdin,
+    File,
+    Daemon,
+}
+
 impl Configuration {
     pub fn load() {
         // parse commandline options and exit if error
@@ -209,6 +1507,14 @@ impl Configuration {
 
         // read configuration file and exit on error
         let file = match ConfigurationFile::try_read(&command_line.config) {
+            Ok(None) if command_line.config.is_none() => {
+                // first run, leave the documented options for the user to start from
+                match ConfigurationFile::write_template() {
+                    Ok(path) => eprintln!("Wrote example config file to {}", path.display()),
+                    Err(e) => eprintln!("config file not found, cannot write example: {e}"),
+                }
+                None
+            }
             Ok(c) => c,
             Err(ConfigurationFileError::ReadFile(e)) if e.kind() == io::ErrorKind::NotFound => {
                 eprintln!("config file not found");
@@ -226,12 +1532,132 @@ impl Configuration {
             }
         };
 
-        APP_CONFIG.write().merge(file, command_line);
+        let mut config = Configuration::default();
+        config.merge(file, command_line);
+        APP_CONFIG.replace(config);
+    }
+    /// The effective configuration as a config file, `[when.*]` sections already applied
+    pub fn dump(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(&self.to_file())
     }
+
+    fn to_file(&self) -> ConfigurationFile {
+        ConfigurationFile {
+            general: Some(ConfigurationFileGeneral {
+                fullscreen: Some(self.fullscreen),
+                layer_shell: Some(self.layer_shell),
+                layer_shell_output: self.layer_shell_output.clone(),
+                early_exit: Some(self.early_exit),
+                corner_roundness: Some(self.corner_roundness),
+                initial_tool: Some(self.initial_tool),
+                secondary_tool: self.secondary_tool,
+                copy_command: self.copy_command.clone(),
+                export_filter_command: self.export_filter_command.clone(),
+                recapture_command: self.recapture_command.clone(),
+                capture_delay: Some(self.capture_delay),
+                export_snippet: self.export_snippet.clone(),
+                code_scan_command: Some(self.code_scan_command.clone()),
+                annotation_size_factor: Some(self.annotation_size_factor),
+                save_after_copy: Some(self.save_after_copy),
+                output_filename: self.output_filename.clone(),
+                actions_on_enter: Some(self.actions_on_enter.clone()),
+                actions_on_escape: Some(self.actions_on_escape.clone()),
+                actions_on_right_click: Some(self.actions_on_right_click.clone()),
+                default_hide_toolbars: Some(self.default_hide_toolbars),
+                focus_toggles_toolbars: Some(self.focus_toggles_toolbars),
+                default_fill_shapes: Some(self.default_fill_shapes),
+                default_shadow: Some(self.default_shadow),
+                default_outline: Some(self.default_outline),
+                text_background_color: self.text_background_color.map(HexColor::from),
+                primary_highlighter: Some(self.primary_highlighter),
+                highlight_opacity: Some(self.highlight_opacity),
+                highlight_blend: Some(self.highlight_blend),
+                ruler_scale: self.ruler_scale,
+                ruler_unit: Some(self.ruler_unit.clone()),
+                table_rows: Some(self.table_rows),
+                table_columns: Some(self.table_columns),
+                table_cell_size: self.table_cell_size,
+                disable_notifications: Some(self.disable_notifications),
+                tray_icon: Some(self.tray_icon),
+                check_for_updates: Some(self.check_for_updates),
+                usage_statistics: Some(self.usage_statistics),
+                reduce_motion: self.reduce_motion,
+                theme: Some(self.theme.clone()),
+                toast_position: Some(self.toast_position),
+                toast_duration: Some(self.toast_duration),
+                no_window_decoration: Some(self.no_window_decoration),
+                window_opacity: Some(self.window_opacity),
+                brush_smooth_history_size: Some(self.brush_smooth_history_size),
+                zoom_factor: Some(self.zoom_factor),
+                pan_step_size: Some(self.pan_step_size),
+                scroll_action: Some(self.scroll_action),
+                tool_style_memory: Some(self.tool_style_memory),
+                latency_mode: Some(self.latency_mode),
+                copy_text_order: Some(self.copy_text_order),
+                copy_text_format: Some(self.copy_text_format),
+                invert_scroll: Some(self.invert_scroll),
+                canvas_padding: Some(self.canvas_padding),
+                canvas_padding_color: Some(self.canvas_padding_color.into()),
+                max_download_size: Some(self.max_download_size),
+                snap_to_guides: Some(self.snap_to_guides),
+                snap_threshold: Some(self.snap_threshold),
+                crosshair_guides: Some(self.crosshair_guides),
+                show_minimap: Some(self.show_minimap),
+                show_status_bar: Some(self.show_status_bar),
+                warn_low_contrast: Some(self.warn_low_contrast),
+                autosnapshot_interval: Some(self.autosnapshot_interval),
+                recovery_interval: Some(self.recovery_interval),
+                export_split: self.export_split,
+                export_canvas: self.export_canvas,
+                export_canvas_presets: Some(self.export_canvas_presets.clone()),
+                export_canvas_color: Some(self.export_canvas_color.into()),
+                right_click_copy: None,
+                action_on_enter: None,
+            }),
+            color_palette: Some(self.color_palette.to_file()),
+            font: Some(self.font.to_file()),
+            mouse: Some(self.mouse.to_file()),
+            auto_redact: Some(self.auto_redact.to_file()),
+            stitch: Some(self.stitch.to_file()),
+            global_shortcuts: Some(self.global_shortcuts.to_file()),
+            hooks: Some(self.hooks.to_file()),
+            toolbars: Some(self.toolbars.to_file()),
+            upload: self.upload.clone().map(UploadFile::from),
+            keybinds: Some(self.keybinds.to_file()),
+            variables: Some(self.variables.clone()),
+            when: None,
+            size_rules: Some(self.size_rules.clone()),
+            presets: Some(self.presets.clone()),
+        }
+    }
+
+    /// Apply `SATTY_*` environment variables, named after the `[general]` options in upper
+    /// case with underscores, e.g. `SATTY_EARLY_EXIT=true`
+    fn merge_environment(&mut self) {
+        for (name, value) in std::env::vars() {
+            let Some(option) = name.strip_prefix("SATTY_") else {
+                continue;
+            };
+            let key = option.to_lowercase().replace('_', "-");
+            match ConfigurationFileGeneral::from_env(&key, &value) {
+                Ok(general) => self.merge_general(general),
+                // other variables like the upload token share the prefix
+                Err(e) if e.message().starts_with("unknown field") => {}
+                Err(e) => eprintln!("Warning: Invalid value in {name}: {}", e.message()),
+            }
+        }
+    }
+
     fn merge_general(&mut self, general: ConfigurationFileGeneral) {
         if let Some(v) = general.fullscreen {
             self.fullscreen = v;
         }
+        if let Some(v) = general.layer_shell {
+            self.layer_shell = v;
+        }
+        if let Some(v) = general.layer_shell_output {
+            self.layer_shell_output = Some(v);
+        }
         if let Some(v) = general.early_exit {
             self.early_exit = v;
         }
@@ -241,9 +1667,27 @@ impl Configuration {
         if let Some(v) = general.initial_tool {
             self.initial_tool = v;
         }
+        if let Some(v) = general.secondary_tool {
+            self.secondary_tool = Some(v);
+        }
         if let Some(v) = general.copy_command {
             self.copy_command = Some(v);
         }
+        if let Some(v) = general.export_filter_command {
+            self.export_filter_command = Some(v);
+        }
+        if let Some(v) = general.recapture_command {
+            self.recapture_command = Some(v);
+        }
+        if let Some(v) = general.capture_delay {
+            self.capture_delay = v;
+        }
+        if let Some(v) = general.export_snippet {
+            self.export_snippet = Some(v);
+        }
+        if let Some(v) = general.code_scan_command {
+            self.code_scan_command = v;
+        }
         if let Some(v) = general.output_filename {
             self.output_filename = Some(v);
         }
@@ -268,26 +1712,143 @@ impl Configuration {
         if let Some(v) = general.focus_toggles_toolbars {
             self.focus_toggles_toolbars = v;
         }
-        if let Some(v) = general.default_fill_shapes {
-            self.default_fill_shapes = v;
+        if let Some(v) = general.default_fill_shapes {
+            self.default_fill_shapes = v;
+        }
+        if let Some(v) = general.default_shadow {
+            self.default_shadow = v;
+        }
+        if let Some(v) = general.default_outline {
+            self.default_outline = v;
+        }
+        if let Some(v) = general.text_background_color {
+            self.text_background_color = Some(v.into());
+        }
+        if let Some(v) = general.primary_highlighter {
+            self.primary_highlighter = v;
+        }
+        if let Some(v) = general.highlight_opacity {
+            self.highlight_opacity = v.clamp(0.0, 1.0);
+        }
+        if let Some(v) = general.highlight_blend {
+            self.highlight_blend = v;
+        }
+        if let Some(v) = general.ruler_scale.filter(|scale| *scale > 0.0) {
+            self.ruler_scale = Some(v);
+        }
+        if let Some(v) = general.ruler_unit {
+            self.ruler_unit = v;
+        }
+        if let Some(v) = general.table_rows {
+            self.table_rows = v.max(1);
+        }
+        if let Some(v) = general.table_columns {
+            self.table_columns = v.max(1);
+        }
+        if let Some(v) = general.table_cell_size.filter(|size| *size > 0.0) {
+            self.table_cell_size = Some(v);
+        }
+        if let Some(v) = general.disable_notifications {
+            self.disable_notifications = v;
+        }
+        if let Some(v) = general.tray_icon {
+            self.tray_icon = v;
+        }
+        if let Some(v) = general.check_for_updates {
+            self.check_for_updates = v;
+        }
+        if let Some(v) = general.usage_statistics {
+            self.usage_statistics = v;
+        }
+        if let Some(v) = general.reduce_motion {
+            self.reduce_motion = Some(v);
+        }
+        if let Some(v) = general.theme {
+            self.theme = v;
+        }
+        if let Some(v) = general.toast_position {
+            self.toast_position = v;
+        }
+        if let Some(v) = general.toast_duration.filter(|duration| *duration > 0.0) {
+            self.toast_duration = v;
+        }
+        if let Some(v) = general.no_window_decoration {
+            self.no_window_decoration = v;
+        }
+        if let Some(v) = general.window_opacity {
+            self.window_opacity = v.clamp(0.0, 1.0);
+        }
+        if let Some(v) = general.brush_smooth_history_size {
+            self.brush_smooth_history_size = v;
+        }
+        if let Some(v) = general.zoom_factor {
+            self.zoom_factor = v;
+        }
+        if let Some(v) = general.pan_step_size {
+            self.pan_step_size = v;
+        }
+        if let Some(v) = general.scroll_action {
+            self.scroll_action = v;
+        }
+        if let Some(v) = general.tool_style_memory {
+            self.tool_style_memory = v;
+        }
+        if let Some(v) = general.latency_mode {
+            self.latency_mode = v;
+        }
+        if let Some(v) = general.copy_text_order {
+            self.copy_text_order = v;
+        }
+        if let Some(v) = general.copy_text_format {
+            self.copy_text_format = v;
+        }
+        if let Some(v) = general.invert_scroll {
+            self.invert_scroll = v;
+        }
+        if let Some(v) = general.canvas_padding {
+            self.canvas_padding = v;
+        }
+        if let Some(v) = general.canvas_padding_color {
+            self.canvas_padding_color = v.into();
+        }
+        if let Some(v) = general.max_download_size {
+            self.max_download_size = v;
+        }
+        if let Some(v) = general.snap_to_guides {
+            self.snap_to_guides = v;
+        }
+        if let Some(v) = general.snap_threshold {
+            self.snap_threshold = v;
+        }
+        if let Some(v) = general.crosshair_guides {
+            self.crosshair_guides = v;
+        }
+        if let Some(v) = general.show_minimap {
+            self.show_minimap = v;
+        }
+        if let Some(v) = general.show_status_bar {
+            self.show_status_bar = v;
         }
-        if let Some(v) = general.primary_highlighter {
-            self.primary_highlighter = v;
+        if let Some(v) = general.warn_low_contrast {
+            self.warn_low_contrast = v;
         }
-        if let Some(v) = general.disable_notifications {
-            self.disable_notifications = v;
+        if let Some(v) = general.autosnapshot_interval {
+            self.autosnapshot_interval = v;
         }
-        if let Some(v) = general.no_window_decoration {
-            self.no_window_decoration = v;
+        if let Some(v) = general.recovery_interval {
+            self.recovery_interval = v;
         }
-        if let Some(v) = general.brush_smooth_history_size {
-            self.brush_smooth_history_size = v;
+        if let Some(v) = general.export_split {
+            self.export_split = Some(v);
         }
-        if let Some(v) = general.zoom_factor {
-            self.zoom_factor = v;
+        if let Some(v) = general.export_canvas {
+            self.export_canvas = Some(v);
         }
-        if let Some(v) = general.pan_step_size {
-            self.pan_step_size = v;
+        if let Some(v) = general.export_canvas_presets {
+            self.export_canvas_presets = v;
+        }
+        if let Some(v) = general.export_canvas_color {
+            self.export_canvas_color = v.into();
         }
 
         // --- deprecated options ---
@@ -305,13 +1866,68 @@ impl Configuration {
         }
         // ---
     }
+    fn merge_conditional(&mut self, conditional: ConditionalFile) {
+        if let Some(v) = conditional.initial_tool {
+            self.initial_tool = v;
+        }
+        if let Some(v) = conditional.output_filename {
+            self.output_filename = Some(v);
+        }
+        if let Some(v) = conditional.actions_on_enter {
+            self.actions_on_enter = v;
+        }
+        if let Some(v) = conditional.actions_on_escape {
+            self.actions_on_escape = v;
+        }
+        if let Some(v) = conditional.actions_on_right_click {
+            self.actions_on_right_click = v;
+        }
+    }
     fn merge(&mut self, file: Option<ConfigurationFile>, command_line: CommandLine) {
         // input_filename is not required anymore
-        self.input_filename = command_line.filename.unwrap_or_else(|| "-".to_string());
+        self.inputs = command_line
+            .filename
+            .into_iter()
+            .chain(command_line.inputs)
+            .flat_map(|input| expand_glob(&input).unwrap_or_else(|| vec![input]))
+            .collect();
+        self.input_filename = self
+            .inputs
+            .first()
+            .cloned()
+            .unwrap_or_else(|| "-".to_string());
 
         if command_line.daemon {
             self.daemon = true;
         }
+        if command_line.install_desktop_files {
+            self.install_desktop_files = true;
+        }
+        if command_line.dump_config {
+            self.dump_config = true;
+        }
+        if command_line.ping {
+            self.ping = true;
+        }
+        if command_line.check_update {
+            self.check_update = true;
+        }
+        if command_line.recover {
+            self.recover = true;
+        }
+        if command_line.stdin_stream {
+            self.stdin_stream = true;
+        }
+        if command_line.diff {
+            self.diff = true;
+        }
+        match command_line.command {
+            Some(Command::Auth { action }) => self.auth = Some(action),
+            Some(Command::Stats { reset }) => self.stats = Some(reset),
+            Some(Command::Render(render)) => self.render = Some(render),
+            Some(Command::Bench(bench)) => self.bench = Some(bench),
+            None => {}
+        }
 
         // overwrite with all specified values from config file
         if let Some(file) = file {
@@ -324,15 +1940,65 @@ impl Configuration {
             if let Some(v) = file.font {
                 self.font.merge(v);
             }
+            if let Some(v) = file.mouse {
+                self.mouse.merge(v);
+            }
+            if let Some(v) = file.auto_redact {
+                self.auto_redact.merge(v);
+            }
+            if let Some(v) = file.stitch {
+                self.stitch.merge(v);
+            }
+            if let Some(v) = file.global_shortcuts {
+                self.global_shortcuts.merge(v);
+            }
+            if let Some(v) = file.hooks {
+                self.hooks.merge(v);
+            }
+            if let Some(v) = file.toolbars {
+                self.toolbars.merge(v);
+            }
+            if let Some(v) = file.upload {
+                self.upload = Some(v.into());
+            }
             if let Some(v) = file.keybinds {
                 self.keybinds.merge(v);
             }
+            if let Some(v) = file.variables {
+                self.variables.extend(v);
+            }
+            if let Some(v) = file.size_rules {
+                self.size_rules = v;
+            }
+            if let Some(v) = file.presets {
+                self.presets = v;
+            }
+            // input source specific sections take precedence over [general]
+            if let Some(when) = file.when {
+                let conditional = match self.input_source() {
+                    InputSource::Stdin => when.stdin,
+                    InputSource::File => when.file,
+                    InputSource::Daemon => when.daemon,
+                };
+                if let Some(v) = conditional {
+                    self.merge_conditional(v);
+                }
+            }
         }
 
+        // environment variables are meant for wrappers, they take precedence over the file
+        self.merge_environment();
+
         // overwrite with all specified values from command line
         if command_line.fullscreen {
             self.fullscreen = command_line.fullscreen;
         }
+        if command_line.layer_shell {
+            self.layer_shell = command_line.layer_shell;
+        }
+        if let Some(v) = command_line.layer_shell_output {
+            self.layer_shell_output = Some(v);
+        }
         if command_line.early_exit {
             self.early_exit = command_line.early_exit;
         }
@@ -349,11 +2015,23 @@ impl Configuration {
             self.default_fill_shapes = command_line.default_fill_shapes;
         }
         if let Some(v) = command_line.initial_tool {
-            self.initial_tool = v.into();
+            self.initial_tool = tool_from(v);
+        }
+        if let Some(v) = command_line.secondary_tool {
+            self.secondary_tool = Some(tool_from(v));
         }
         if let Some(v) = command_line.copy_command {
             self.copy_command = Some(v);
         }
+        if let Some(v) = command_line.export_filter_command {
+            self.export_filter_command = Some(v);
+        }
+        if let Some(v) = command_line.recapture_command {
+            self.recapture_command = Some(v);
+        }
+        if let Some(v) = command_line.delay {
+            self.capture_delay = v;
+        }
         if let Some(v) = command_line.output_filename {
             self.output_filename = Some(v);
         }
@@ -378,12 +2056,30 @@ impl Configuration {
         if let Some(v) = command_line.font_style {
             self.font.style = Some(v);
         }
+        if let Some(v) = command_line.font_size.filter(|size| *size > 0.0) {
+            self.font.size = Some(v);
+        }
         if let Some(v) = command_line.primary_highlighter {
-            self.primary_highlighter = v.into();
+            self.primary_highlighter = highlighter_from(v);
+        }
+        if let Some(v) = command_line.highlight_opacity {
+            self.highlight_opacity = v.clamp(0.0, 1.0);
+        }
+        if let Some(v) = command_line.highlight_blend {
+            self.highlight_blend = highlight_blend_from(v);
+        }
+        if let Some(v) = command_line.ruler_scale.filter(|scale| *scale > 0.0) {
+            self.ruler_scale = Some(v);
+        }
+        if let Some(v) = command_line.ruler_unit {
+            self.ruler_unit = v;
         }
         if command_line.disable_notifications {
             self.disable_notifications = command_line.disable_notifications;
         }
+        if command_line.tray_icon {
+            self.tray_icon = true;
+        }
         if command_line.profile_startup {
             self.profile_startup = command_line.profile_startup;
         }
@@ -399,6 +2095,29 @@ impl Configuration {
         if let Some(v) = command_line.pan_step_size {
             self.pan_step_size = v;
         }
+        if let Some(v) = command_line.max_download_size {
+            self.max_download_size = v;
+        }
+        if let Some(v) = command_line.export_split {
+            match ExportSplit::try_from(v) {
+                Ok(v) => self.export_split = Some(v),
+                Err(e) => eprintln!("Warning: {e}, saving a single file."),
+            }
+        }
+        if let Some(v) = command_line.export_canvas {
+            match ExportCanvas::try_from(v) {
+                Ok(v) => self.export_canvas = Some(v),
+                Err(e) => eprintln!("Warning: {e}, exporting without a fixed canvas size."),
+            }
+        }
+        for v in command_line.vars {
+            match v.split_once('=') {
+                Some((name, value)) => {
+                    self.variables.insert(name.to_string(), value.to_string());
+                }
+                None => eprintln!("Warning: Invalid variable '{v}', expected NAME=VALUE."),
+            }
+        }
 
         // --- deprecated options ---
         if command_line.right_click_copy
@@ -415,26 +2134,95 @@ impl Configuration {
         // ---
     }
 
-    pub fn early_exit(&self) -> bool {
-        self.early_exit
+    /// The options drawing follows, for [`DRAWING_CONFIG`]
+    fn drawing(&self) -> DrawingConfiguration {
+        DrawingConfiguration {
+            corner_roundness: self.corner_roundness,
+            annotation_size_factor: self.annotation_size_factor,
+            color: self
+                .color_palette
+                .palette()
+                .first()
+                .copied()
+                .unwrap_or(Color::red()),
+            font_family: self.font.family.clone(),
+            font_style: self.font.style.clone(),
+            font_size: self.font.size,
+            fill_shapes: self.default_fill_shapes,
+            shadow: self.default_shadow,
+            outline: self.default_outline,
+            text_background_color: self.text_background_color,
+            primary_highlighter: self.primary_highlighter,
+            highlight_opacity: self.highlight_opacity,
+            highlight_blend: self.highlight_blend,
+            ruler_scale: self.ruler_scale,
+            ruler_unit: self.ruler_unit.clone(),
+            table_rows: self.table_rows,
+            table_columns: self.table_columns,
+            table_cell_size: self.table_cell_size,
+            brush_smooth_history_size: self.brush_smooth_history_size,
+            variables: self.variables.clone(),
+            input_filename: Some(self.input_filename.clone()).filter(|f| !f.is_empty()),
+        }
     }
 
-    pub fn corner_roundness(&self) -> f32 {
-        self.corner_roundness
+    pub fn early_exit(&self) -> bool {
+        self.early_exit
     }
 
     pub fn initial_tool(&self) -> Tools {
         self.initial_tool
     }
 
+    /// Tool bound to drags with the right mouse button
+    pub fn secondary_tool(&self) -> Option<Tools> {
+        self.secondary_tool
+    }
+
     pub fn copy_command(&self) -> Option<&String> {
         self.copy_command.as_ref()
     }
 
+    /// Shell command saved PNGs are piped through before they are written
+    pub fn export_filter_command(&self) -> Option<&String> {
+        self.export_filter_command.as_ref()
+    }
+
+    /// Shell command writing a new screenshot to stdout, it replaces the image when re-capturing
+    pub fn recapture_command(&self) -> Option<&String> {
+        self.recapture_command.as_ref()
+    }
+
+    /// Seconds counted down before the screenshot is taken again
+    pub fn capture_delay(&self) -> u32 {
+        self.capture_delay
+    }
+
+    /// Template of the text copied after saving, with `{path}` and `{texts}` placeholders
+    pub fn export_snippet(&self) -> Option<&String> {
+        self.export_snippet.as_ref()
+    }
+
+    /// Shell command reading a PNG and printing zbarimg's XML or the content of each QR code or
+    /// barcode found on its own line
+    pub fn code_scan_command(&self) -> &str {
+        &self.code_scan_command
+    }
+
     pub fn fullscreen(&self) -> bool {
         self.fullscreen
     }
 
+    /// Whether the window is an overlay above everything, on compositors with layer shell
+    pub fn layer_shell(&self) -> bool {
+        self.layer_shell
+    }
+
+    /// Connector name of the output the overlay covers, e.g. `DP-1`
+    pub fn layer_shell_output(&self) -> Option<&str> {
+        self.layer_shell_output.as_deref()
+    }
+
     pub fn output_filename(&self) -> Option<&String> {
         self.output_filename.as_ref()
     }
@@ -443,12 +2231,13 @@ impl Configuration {
         self.input_filename.as_ref()
     }
 
-    pub fn annotation_size_factor(&self) -> f32 {
-        self.annotation_size_factor
+    /// All inputs given on the command line, the first one is `input_filename`
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
     }
 
-    pub fn save_after_copy(&self) -> bool {
-        self.save_after_copy
+    pub fn annotation_size_factor(&self) -> f32 {
+        self.annotation_size_factor
     }
 
     pub fn actions_on_enter(&self) -> Vec<Action> {
@@ -479,14 +2268,55 @@ impl Configuration {
         self.default_fill_shapes
     }
 
-    pub fn primary_highlighter(&self) -> Highlighters {
-        self.primary_highlighter
+    /// Whether annotations get a drop shadow by default
+    pub fn default_shadow(&self) -> bool {
+        self.default_shadow
+    }
+
+    /// Whether annotations get a contrasting outline by default
+    pub fn default_outline(&self) -> bool {
+        self.default_outline
     }
 
     pub fn disable_notifications(&self) -> bool {
         self.disable_notifications
     }
 
+    /// The daemon shows a StatusNotifierItem
+    pub fn tray_icon(&self) -> bool {
+        self.tray_icon
+    }
+
+    /// Whether to look for a new release once per day and announce it in a toast
+    pub fn check_for_updates(&self) -> bool {
+        self.check_for_updates
+    }
+
+    /// Whether tool and action usage is counted in the state directory for `satty stats`
+    pub fn usage_statistics(&self) -> bool {
+        self.usage_statistics
+    }
+
+    /// Whether animations are turned off (or on), `None` follows the desktop preference
+    pub fn reduce_motion(&self) -> Option<bool> {
+        self.reduce_motion
+    }
+
+    /// "system", "dark", "light" or the name of a theme file in the config directory
+    pub fn theme(&self) -> &str {
+        &self.theme
+    }
+
+    /// Where status messages appear over the canvas
+    pub fn toast_position(&self) -> ToastPosition {
+        self.toast_position
+    }
+
+    /// Seconds a status message stays visible
+    pub fn toast_duration(&self) -> f32 {
+        self.toast_duration
+    }
+
     pub fn profile_startup(&self) -> bool {
         self.profile_startup
     }
@@ -495,12 +2325,42 @@ impl Configuration {
         self.no_window_decoration
     }
 
+    /// Opacity of the window background around the image, the image itself stays opaque
+    pub fn window_opacity(&self) -> f32 {
+        self.window_opacity
+    }
+
     pub fn font(&self) -> &FontConfiguration {
         &self.font
     }
 
-    pub fn brush_smooth_history_size(&self) -> usize {
-        self.brush_smooth_history_size
+    pub fn mouse(&self) -> &MouseBindings {
+        &self.mouse
+    }
+
+    pub fn auto_redact(&self) -> &AutoRedact {
+        &self.auto_redact
+    }
+
+    pub fn stitch(&self) -> &Stitch {
+        &self.stitch
+    }
+
+    pub fn global_shortcuts(&self) -> &GlobalShortcuts {
+        &self.global_shortcuts
+    }
+
+    pub fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    pub fn toolbars(&self) -> &Toolbars {
+        &self.toolbars
+    }
+
+    /// Target of the upload action, `None` if not configured
+    pub fn upload(&self) -> Option<&Upload> {
+        self.upload.as_ref()
     }
 
     pub fn keybinds(&self) -> &Keybinds {
@@ -515,21 +2375,206 @@ impl Configuration {
         self.pan_step_size
     }
 
+    /// What vertical scrolling does, Ctrl switches to the other one
+    pub fn scroll_action(&self) -> ScrollAction {
+        self.scroll_action
+    }
+
+    pub fn tool_style_memory(&self) -> ToolStyleMemory {
+        self.tool_style_memory
+    }
+
+    pub fn latency_mode(&self) -> LatencyMode {
+        self.latency_mode
+    }
+
+    pub fn copy_text_order(&self) -> TextOrder {
+        self.copy_text_order
+    }
+
+    pub fn copy_text_format(&self) -> TextFormat {
+        self.copy_text_format
+    }
+
+    /// Whether scrolling moves the image the other way round, e.g. for natural scrolling
+    pub fn invert_scroll(&self) -> bool {
+        self.invert_scroll
+    }
+
+    pub fn canvas_padding(&self) -> f32 {
+        self.canvas_padding
+    }
+
+    pub fn canvas_padding_color(&self) -> Color {
+        self.canvas_padding_color
+    }
+
+    /// Maximum size of images downloaded from http(s) URLs, in bytes
+    pub fn max_download_size(&self) -> u64 {
+        self.max_download_size.saturating_mul(1024 * 1024)
+    }
+
+    /// Whether dragged shapes snap to the edges and centers of the image and other annotations
+    pub fn snap_to_guides(&self) -> bool {
+        self.snap_to_guides
+    }
+
+    /// Distance in screen pixels within which dragged shapes snap to guides
+    pub fn snap_threshold(&self) -> f32 {
+        self.snap_threshold
+    }
+
+    /// Whether lines across the whole area follow the pointer
+    pub fn crosshair_guides(&self) -> bool {
+        self.crosshair_guides
+    }
+
+    /// Whether an overview of the image is shown in a corner while it doesn't fit
+    pub fn show_minimap(&self) -> bool {
+        self.show_minimap
+    }
+
+    /// Whether pointer position, color under it, zoom and image size are shown in a corner
+    pub fn show_status_bar(&self) -> bool {
+        self.show_status_bar
+    }
+
+    /// Whether committing hard to read text offers to put a background behind it
+    pub fn warn_low_contrast(&self) -> bool {
+        self.warn_low_contrast
+    }
+
+    /// Seconds between background snapshots of the annotated image, `None` if disabled
+    pub fn autosnapshot_interval(&self) -> Option<u32> {
+        Some(self.autosnapshot_interval).filter(|&v| v > 0)
+    }
+
+    /// Seconds between writes of the annotations for crash recovery, `None` if disabled
+    pub fn recovery_interval(&self) -> Option<u32> {
+        Some(self.recovery_interval).filter(|&v| v > 0)
+    }
+
+    /// How saved files get split into tiles, `None` saves a single file
+    pub fn export_split(&self) -> Option<ExportSplit> {
+        self.export_split
+    }
+
+    /// Fixed size of the exported image selected at startup, `None` keeps the rendered size
+    pub fn export_canvas(&self) -> Option<ExportCanvas> {
+        self.export_canvas
+    }
+
+    pub fn export_canvas_presets(&self) -> &[ExportCanvas] {
+        &self.export_canvas_presets
+    }
+
+    pub fn export_canvas_color(&self) -> Color {
+        self.export_canvas_color
+    }
+
+    /// Tools with a style selected together, see `[[presets]]`
+    pub fn presets(&self) -> &[Preset] {
+        &self.presets
+    }
+
+    /// The first size rule matching the given image dimensions
+    pub fn size_rule(&self, width: i32, height: i32) -> Option<SizeRule> {
+        self.size_rules
+            .iter()
+            .find(|r| r.matches(width, height))
+            .copied()
+    }
+
     pub fn daemon_mode(&self) -> bool {
         self.daemon
     }
+
+    pub fn install_desktop_files(&self) -> bool {
+        self.install_desktop_files
+    }
+
+    pub fn dump_config(&self) -> bool {
+        self.dump_config
+    }
+
+    pub fn ping(&self) -> bool {
+        self.ping
+    }
+
+    pub fn check_update(&self) -> bool {
+        self.check_update
+    }
+
+    /// Restore the annotations of the last session that did not end, onto any image
+    pub fn recover(&self) -> bool {
+        self.recover
+    }
+
+    /// stdin holds a stream of PNG images rather than a single image
+    pub fn stdin_stream(&self) -> bool {
+        self.stdin_stream
+    }
+
+    /// The two inputs are compared side by side
+    pub fn diff_mode(&self) -> bool {
+        self.diff
+    }
+
+    /// More than one image can be shown, switching between them is offered
+    pub fn has_several_inputs(&self) -> bool {
+        (self.inputs.len() > 1 && !self.diff) || self.stdin_stream
+    }
+
+    /// `satty auth` command to run instead of starting
+    pub fn auth(&self) -> Option<&AuthAction> {
+        self.auth.as_ref()
+    }
+
+    /// `satty stats` command to run instead of starting, `Some(true)` resets the counters
+    pub fn stats(&self) -> Option<bool> {
+        self.stats
+    }
+
+    /// `satty render` command to run instead of starting
+    pub fn render(&self) -> Option<&Render> {
+        self.render.as_ref()
+    }
+
+    /// `satty bench` command to run instead of starting
+    pub fn bench(&self) -> Option<&Bench> {
+        self.bench.as_ref()
+    }
+
+    pub fn input_source(&self) -> InputSource {
+        if self.daemon {
+            InputSource::Daemon
+        } else if self.input_filename == "-" {
+            InputSource::Stdin
+        } else {
+            InputSource::File
+        }
+    }
 }
 
 impl Default for Configuration {
     fn default() -> Self {
         Self {
             input_filename: String::new(),
+            inputs: vec![],
             output_filename: None,
             fullscreen: false,
+            layer_shell: false,
+            layer_shell_output: None,
             early_exit: false,
             corner_roundness: 12.0,
             initial_tool: Tools::Pointer,
+            secondary_tool: None,
             copy_command: None,
+            export_filter_command: None,
+            recapture_command: None,
+            capture_delay: 0,
+            export_snippet: None,
+            code_scan_command: "zbarimg --quiet --xml -".to_string(),
             annotation_size_factor: 1.0,
             save_after_copy: false,
             actions_on_enter: vec![],
@@ -539,16 +2584,89 @@ impl Default for Configuration {
             default_hide_toolbars: false,
             focus_toggles_toolbars: false,
             default_fill_shapes: false,
+            default_shadow: false,
+            default_outline: false,
+            text_background_color: None,
             font: FontConfiguration::default(),
+            mouse: MouseBindings::default(),
+            auto_redact: AutoRedact::default(),
+            stitch: Stitch::default(),
+            global_shortcuts: GlobalShortcuts::default(),
+            hooks: Hooks::default(),
+            toolbars: Toolbars::default(),
+            upload: None,
+            variables: HashMap::new(),
             primary_highlighter: Highlighters::Block,
+            highlight_opacity: 0.4,
+            highlight_blend: HighlightBlend::Normal,
+            ruler_scale: None,
+            ruler_unit: "mm".to_string(),
+            table_rows: 3,
+            table_columns: 3,
+            table_cell_size: None,
             disable_notifications: false,
+            tray_icon: false,
+            check_for_updates: false,
+            usage_statistics: true,
+            reduce_motion: None,
+            theme: "system".to_string(),
+            toast_position: ToastPosition::Top,
+            toast_duration: 1.2,
             profile_startup: false,
             no_window_decoration: false,
+            window_opacity: 1.0,
             brush_smooth_history_size: 0, // default to 0, no history
             keybinds: Keybinds::default(),
             zoom_factor: 1.1,
             pan_step_size: 50.,
+            scroll_action: ScrollAction::Zoom,
+            tool_style_memory: ToolStyleMemory::Session,
+            latency_mode: LatencyMode::Default,
+            copy_text_order: TextOrder::Reading,
+            copy_text_format: TextFormat::Plain,
+            invert_scroll: false,
+            canvas_padding: 50.,
+            canvas_padding_color: Color::new(0, 0, 0, 0),
+            max_download_size: 50,
+            snap_to_guides: true,
+            snap_threshold: 8.0,
+            crosshair_guides: false,
+            show_minimap: true,
+            show_status_bar: false,
+            warn_low_contrast: true,
+            autosnapshot_interval: 60,
+            recovery_interval: 10,
+            export_split: None,
+            export_canvas: None,
+            export_canvas_presets: vec![
+                ExportCanvas {
+                    width: 1280,
+                    height: 720,
+                },
+                ExportCanvas {
+                    width: 1920,
+                    height: 1080,
+                },
+                ExportCanvas {
+                    width: 2560,
+                    height: 1440,
+                },
+            ],
+            export_canvas_color: Color::new(255, 255, 255, 255),
+            size_rules: vec![],
+            presets: vec![],
             daemon: false,
+            install_desktop_files: false,
+            dump_config: false,
+            ping: false,
+            check_update: false,
+            recover: false,
+            stdin_stream: false,
+            diff: false,
+            auth: None,
+            stats: None,
+            render: None,
+            bench: None,
         }
     }
 }
@@ -556,28 +2674,57 @@ impl Default for Configuration {
 impl Default for ColorPalette {
     fn default() -> Self {
         Self {
-            palette: vec![
+            palettes: vec![Palette::unnamed(vec![
                 Color::orange(),
                 Color::red(),
                 Color::green(),
                 Color::blue(),
                 Color::cove(),
-            ],
+            ])],
             custom: vec![],
         }
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 struct ConfigurationFile {
     general: Option<ConfigurationFileGeneral>,
     color_palette: Option<ColorPaletteFile>,
     font: Option<FontFile>,
+    mouse: Option<MouseFile>,
+    auto_redact: Option<AutoRedactFile>,
+    stitch: Option<StitchFile>,
+    global_shortcuts: Option<GlobalShortcutsFile>,
+    hooks: Option<HooksFile>,
+    toolbars: Option<ToolbarsFile>,
+    upload: Option<UploadFile>,
     keybinds: Option<KeybindsFile>,
+    variables: Option<HashMap<String, String>>,
+    when: Option<WhenFile>,
+    size_rules: Option<Vec<SizeRule>>,
+    presets: Option<Vec<Preset>>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct WhenFile {
+    stdin: Option<ConditionalFile>,
+    file: Option<ConditionalFile>,
+    daemon: Option<ConditionalFile>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ConditionalFile {
+    initial_tool: Option<Tools>,
+    output_filename: Option<String>,
+    actions_on_enter: Option<Vec<Action>>,
+    actions_on_escape: Option<Vec<Action>>,
+    actions_on_right_click: Option<Vec<Action>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 struct KeybindsFile {
     pointer: Option<String>,
@@ -591,23 +2738,102 @@ struct KeybindsFile {
     marker: Option<String>,
     blur: Option<String>,
     highlight: Option<String>,
+    eraser: Option<String>,
+    select: Option<String>,
+    ruler: Option<String>,
+    table: Option<String>,
+    brace: Option<String>,
+    previous_tool: Option<String>,
+    cycle_next: Option<String>,
+    cycle_previous: Option<String>,
+    cycle_preset: Option<String>,
+    spring_loaded: Option<Vec<Tools>>,
+    toggle_minimap: Option<String>,
+    color_blindness_preview: Option<String>,
+    keyboard_placement: Option<String>,
+    auto_redact: Option<String>,
+    actions: Option<HashMap<String, Vec<Action>>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 struct FontFile {
     family: Option<String>,
     style: Option<String>,
+    size: Option<f32>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct MouseFile {
+    pan: Option<MouseButtonBinding>,
+    actions: Option<MouseButtonBinding>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct AutoRedactFile {
+    threshold: Option<u8>,
+    max_line_height: Option<u32>,
+    padding: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct StitchFile {
+    direction: Option<StitchDirection>,
+    separator_width: Option<u32>,
+    separator_color: Option<HexColor>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct GlobalShortcutsFile {
+    capture: Option<String>,
+    paste: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct HooksFile {
+    script: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ToolbarsFile {
+    top: Option<Vec<String>>,
+    bottom: Option<Vec<String>>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct UploadFile {
+    provider: UploadProvider,
+    project: String,
+    url: Option<String>,
+    token_env: Option<String>,
+    token_service: Option<String>,
+    path: Option<String>,
+    release: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 struct ConfigurationFileGeneral {
     fullscreen: Option<bool>,
+    layer_shell: Option<bool>,
+    layer_shell_output: Option<String>,
     early_exit: Option<bool>,
     corner_roundness: Option<f32>,
     initial_tool: Option<Tools>,
+    secondary_tool: Option<Tools>,
     copy_command: Option<String>,
+    export_filter_command: Option<String>,
+    recapture_command: Option<String>,
+    capture_delay: Option<u32>,
+    export_snippet: Option<String>,
+    code_scan_command: Option<String>,
     annotation_size_factor: Option<f32>,
     save_after_copy: Option<bool>,
     output_filename: Option<String>,
@@ -617,12 +2843,51 @@ struct ConfigurationFileGeneral {
     default_hide_toolbars: Option<bool>,
     focus_toggles_toolbars: Option<bool>,
     default_fill_shapes: Option<bool>,
+    default_shadow: Option<bool>,
+    default_outline: Option<bool>,
+    text_background_color: Option<HexColor>,
     primary_highlighter: Option<Highlighters>,
+    highlight_opacity: Option<f32>,
+    highlight_blend: Option<HighlightBlend>,
+    ruler_scale: Option<f32>,
+    ruler_unit: Option<String>,
+    table_rows: Option<u32>,
+    table_columns: Option<u32>,
+    table_cell_size: Option<f32>,
     disable_notifications: Option<bool>,
+    tray_icon: Option<bool>,
+    check_for_updates: Option<bool>,
+    usage_statistics: Option<bool>,
+    reduce_motion: Option<bool>,
+    theme: Option<String>,
+    toast_position: Option<ToastPosition>,
+    toast_duration: Option<f32>,
     no_window_decoration: Option<bool>,
+    window_opacity: Option<f32>,
     brush_smooth_history_size: Option<usize>,
     zoom_factor: Option<f32>,
     pan_step_size: Option<f32>,
+    scroll_action: Option<ScrollAction>,
+    tool_style_memory: Option<ToolStyleMemory>,
+    latency_mode: Option<LatencyMode>,
+    copy_text_order: Option<TextOrder>,
+    copy_text_format: Option<TextFormat>,
+    invert_scroll: Option<bool>,
+    canvas_padding: Option<f32>,
+    canvas_padding_color: Option<HexColor>,
+    max_download_size: Option<u64>,
+    snap_to_guides: Option<bool>,
+    snap_threshold: Option<f32>,
+    crosshair_guides: Option<bool>,
+    show_minimap: Option<bool>,
+    show_status_bar: Option<bool>,
+    warn_low_contrast: Option<bool>,
+    autosnapshot_interval: Option<u32>,
+    recovery_interval: Option<u32>,
+    export_split: Option<ExportSplit>,
+    export_canvas: Option<ExportCanvas>,
+    export_canvas_presets: Option<Vec<ExportCanvas>>,
+    export_canvas_color: Option<HexColor>,
 
     // --- deprecated options ---
     right_click_copy: Option<bool>,
@@ -630,13 +2895,44 @@ struct ConfigurationFileGeneral {
     // ---
 }
 
-#[derive(Deserialize)]
+impl ConfigurationFileGeneral {
+    /// Only the option `key` set to `value`, which is written as in the config file or as
+    /// plain string
+    fn from_env(key: &str, value: &str) -> Result<Self, toml::de::Error> {
+        let option = |value| toml::Value::Table(toml::Table::from_iter([(key.to_string(), value)]));
+        let typed = toml::from_str::<toml::Table>(&format!("value = {value}"))
+            .ok()
+            .and_then(|mut table| table.remove("value"));
+        if let Some(general) = typed.and_then(|typed| option(typed).try_into().ok()) {
+            return Ok(general);
+        }
+        option(toml::Value::String(value.to_string())).try_into()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 struct ColorPaletteFile {
-    palette: Option<Vec<HexColor>>,
+    preset: Option<PalettePreset>,
+    palette: Option<Vec<PaletteColorFile>>,
+    palettes: Option<Vec<PaletteFile>>,
     custom: Option<Vec<HexColor>>,
 }
 
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct PaletteFile {
+    name: String,
+    colors: Vec<PaletteColorFile>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum PaletteColorFile {
+    Color(HexColor),
+    Named { name: String, value: HexColor },
+}
+
 impl ConfigurationFile {
     fn try_read(
         specified_path: &Option<String>,
@@ -647,6 +2943,25 @@ impl ConfigurationFile {
         }
     }
 
+    /// Write the example configuration with every option commented out to the XDG config
+    /// directory, never replacing an existing file
+    fn write_template() -> io::Result<PathBuf> {
+        let dirs = BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
+        let path = dirs.place_config_file("config.toml")?;
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        for line in include_str!("../config.toml").lines() {
+            if line.is_empty() || line.starts_with('#') {
+                writeln!(file, "{line}")?;
+            } else {
+                writeln!(file, "# {line}")?;
+            }
+        }
+        Ok(path)
+    }
+
     fn try_read_xdg() -> Result<Option<ConfigurationFile>, ConfigurationFileError> {
         let dirs = BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
         match dirs.get_config_file("config.toml") {
@@ -662,3 +2977,75 @@ impl ConfigurationFile {
         Ok(Some(toml::from_str::<ConfigurationFile>(&content)?))
     }
 }
+
+fn tool_from(tool: command_line::Tools) -> Tools {
+    match tool {
+        command_line::Tools::Pointer => Tools::Pointer,
+        command_line::Tools::Crop => Tools::Crop,
+        command_line::Tools::Line => Tools::Line,
+        command_line::Tools::Arrow => Tools::Arrow,
+        command_line::Tools::Rectangle => Tools::Rectangle,
+        command_line::Tools::Ellipse => Tools::Ellipse,
+        command_line::Tools::Text => Tools::Text,
+        command_line::Tools::Marker => Tools::Marker,
+        command_line::Tools::Blur => Tools::Blur,
+        command_line::Tools::Highlight => Tools::Highlight,
+        command_line::Tools::Brush => Tools::Brush,
+        command_line::Tools::Eraser => Tools::Eraser,
+        command_line::Tools::Select => Tools::Select,
+        command_line::Tools::Ruler => Tools::Ruler,
+        command_line::Tools::Table => Tools::Table,
+        command_line::Tools::Brace => Tools::Brace,
+    }
+}
+
+fn highlighter_from(highlighter: command_line::Highlighters) -> Highlighters {
+    match highlighter {
+        command_line::Highlighters::Block => Highlighters::Block,
+        command_line::Highlighters::Freehand => Highlighters::Freehand,
+        command_line::Highlighters::Line => Highlighters::Line,
+    }
+}
+
+fn highlight_blend_from(blend: command_line::HighlightBlend) -> HighlightBlend {
+    match blend {
+        command_line::HighlightBlend::Normal => HighlightBlend::Normal,
+        command_line::HighlightBlend::Multiply => HighlightBlend::Multiply,
+    }
+}
+
+/// Paths matching `pattern` sorted by name, if it has `*` or `?` in its file name. Shells
+/// expand these themselves unless the pattern is quoted.
+fn expand_glob(pattern: &str) -> Option<Vec<String>> {
+    let path = Path::new(pattern);
+    let file_pattern = path.file_name()?.to_str()?;
+    if pattern.contains("://") || !file_pattern.contains(['*', '?']) {
+        return None;
+    }
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut matches: Vec<String> = fs::read_dir(directory)
+        .ok()?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            // like shells, `*` doesn't match hidden files
+            let hidden = name.starts_with('.') && !file_pattern.starts_with('.');
+            (!hidden && wildcard_match(file_pattern.as_bytes(), name.as_bytes()))
+                .then(|| path.with_file_name(name).to_string_lossy().into_owned())
+        })
+        .collect();
+    matches.sort();
+    (!matches.is_empty()).then_some(matches)
+}
+
+/// Whether `name` matches `pattern`, where `*` stands for any bytes and `?` for one
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| wildcard_match(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && wildcard_match(rest, &name[1..]),
+        Some((byte, rest)) => name.first() == Some(byte) && wildcard_match(rest, &name[1..]),
+    }
+}
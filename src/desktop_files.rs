@@ -0,0 +1,94 @@
+use std::{fs, path::Path, process::Command};
+
+use anyhow::{Context, Result};
+use xdg::BaseDirectories;
+
+use crate::APP_ID;
+
+const ICON: &[u8] = include_bytes!("../assets/satty.svg");
+
+const MIME_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/webp",
+    "image/gif",
+    "image/bmp",
+    "image/tiff",
+];
+
+/// Install the desktop entry, a file-manager service menu and the icon to the XDG data dir
+/// of the current user. Paths and app id are taken from the running binary so they can't
+/// get out of sync.
+pub fn install() -> Result<()> {
+    let exe = std::env::current_exe().context("couldn't determine path of satty binary")?;
+    let exe = exe.to_string_lossy();
+    let dirs = BaseDirectories::new();
+
+    let desktop_file = dirs.place_data_file(format!("applications/{APP_ID}.desktop"))?;
+    write_file(&desktop_file, desktop_entry(&exe).as_bytes())?;
+
+    let service_menu = dirs.place_data_file("kio/servicemenus/satty-annotate.desktop")?;
+    write_file(&service_menu, service_menu_entry(&exe).as_bytes())?;
+
+    let icon = dirs.place_data_file("icons/hicolor/scalable/apps/satty.svg")?;
+    write_file(&icon, ICON)?;
+
+    // refresh the mime cache, this is best effort as the tool might not be installed
+    if let Some(applications) = desktop_file.parent() {
+        let _ = Command::new("update-desktop-database")
+            .arg(applications)
+            .status();
+    }
+
+    Ok(())
+}
+
+fn write_file(path: &Path, content: &[u8]) -> Result<()> {
+    fs::write(path, content).with_context(|| format!("couldn't write '{}'", path.display()))?;
+    println!("Installed '{}'", path.display());
+    Ok(())
+}
+
+fn desktop_entry(exe: &str) -> String {
+    format!(
+        "[Desktop Entry]
+Name=Satty
+GenericName=Modern Screenshot Annotation.
+TryExec={exe}
+Exec=\"{exe}\" %U
+Terminal=false
+NoDisplay=true
+Type=Application
+Keywords=wayland;snapshot;annotation;editing;
+Icon=satty
+Categories=Utility;Graphics;
+StartupNotify=true
+MimeType={mime_types};
+StartupWMClass={APP_ID}
+Actions=annotate;
+
+[Desktop Action annotate]
+Name=Annotate with Satty
+Icon=satty
+Exec=\"{exe}\" %U
+",
+        mime_types = MIME_TYPES.join(";")
+    )
+}
+
+fn service_menu_entry(exe: &str) -> String {
+    format!(
+        "[Desktop Entry]
+Type=Service
+MimeType={mime_types};
+Actions=annotate;
+X-KDE-Priority=TopLevel
+
+[Desktop Action annotate]
+Name=Annotate with Satty
+Icon=satty
+Exec=\"{exe}\" %U
+",
+        mime_types = MIME_TYPES.join(";")
+    )
+}
@@ -0,0 +1,96 @@
+use gdk_pixbuf::{glib, Colorspace, Pixbuf};
+
+use crate::{configuration::APP_CONFIG, stitch};
+
+/// color of the pixels that differ in the heatmap
+const HEATMAP_COLOR: [u8; 3] = [255, 0, 0];
+
+/// tint of the slightest difference, it grows with the difference up to the full color
+const MIN_TINT: f32 = 0.35;
+
+/// The two images compared with `--diff` side by side, with and without the heatmap of their
+/// differences over the second one
+#[derive(Debug, Clone)]
+pub struct Diff {
+    plain: Pixbuf,
+    heatmap: Pixbuf,
+}
+
+impl Diff {
+    /// Returns `None` if the images are too large to be joined
+    pub fn new(first: &Pixbuf, second: &Pixbuf) -> Option<Self> {
+        let config = APP_CONFIG.read();
+        Some(Self {
+            plain: stitch::side_by_side(first, second, config.stitch())?,
+            heatmap: stitch::side_by_side(first, &heatmap(first, second), config.stitch())?,
+        })
+    }
+
+    pub fn image(&self, heatmap: bool) -> &Pixbuf {
+        if heatmap {
+            &self.heatmap
+        } else {
+            &self.plain
+        }
+    }
+}
+
+/// `second` with the pixels that differ from `first` tinted, the more they differ the
+/// stronger. Pixels outside of `first` differ entirely.
+fn heatmap(first: &Pixbuf, second: &Pixbuf) -> Pixbuf {
+    let (width, height) = (second.width(), second.height());
+    let first_pixels = first.read_pixel_bytes();
+    let second_pixels = second.read_pixel_bytes();
+    let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = pixel_at(second, &second_pixels, x, y);
+            let difference = if x < first.width() && y < first.height() {
+                let other = pixel_at(first, &first_pixels, x, y);
+                pixel
+                    .iter()
+                    .zip(other)
+                    .map(|(a, b)| a.abs_diff(b))
+                    .max()
+                    .unwrap_or(0)
+            } else {
+                u8::MAX
+            };
+            let tint = match difference {
+                0 => 0.0,
+                d => MIN_TINT + (1.0 - MIN_TINT) * d as f32 / u8::MAX as f32,
+            };
+            for (channel, color) in pixel[..3].iter().zip(HEATMAP_COLOR) {
+                let mixed = *channel as f32 + (color as f32 - *channel as f32) * tint;
+                pixels.push(mixed.round() as u8);
+            }
+            pixels.push(if difference == 0 { pixel[3] } else { u8::MAX });
+        }
+    }
+    Pixbuf::from_bytes(
+        &glib::Bytes::from_owned(pixels),
+        Colorspace::Rgb,
+        true,
+        8,
+        width,
+        height,
+        width * 4,
+    )
+}
+
+/// RGBA of the pixel at `x`, `y` of `image`, `pixels` are its bytes
+fn pixel_at(image: &Pixbuf, pixels: &[u8], x: i32, y: i32) -> [u8; 4] {
+    let channels = image.n_channels() as usize;
+    let offset = y as usize * image.rowstride() as usize + x as usize * channels;
+    let alpha = if channels == 4 {
+        pixels[offset + 3]
+    } else {
+        u8::MAX
+    };
+    [
+        pixels[offset],
+        pixels[offset + 1],
+        pixels[offset + 2],
+        alpha,
+    ]
+}
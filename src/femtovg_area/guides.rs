@@ -0,0 +1,79 @@
+use femtovg::{Color, Paint, Path};
+
+use crate::math::Vec2D;
+
+/// Lines dragged points and shapes snap to: the edges and centers of the image and of
+/// the other drawables, in image coordinates.
+pub struct Guides {
+    xs: Vec<f32>,
+    ys: Vec<f32>,
+    // guides aligned to by the last snap, they are shown while dragging
+    snapped_x: Option<f32>,
+    snapped_y: Option<f32>,
+}
+
+impl Guides {
+    pub fn new(image_size: Vec2D, bounds: impl Iterator<Item = (Vec2D, Vec2D)>) -> Self {
+        let mut xs = vec![0.0, image_size.x / 2.0, image_size.x];
+        let mut ys = vec![0.0, image_size.y / 2.0, image_size.y];
+        for (min, max) in bounds {
+            xs.extend([min.x, (min.x + max.x) / 2.0, max.x]);
+            ys.extend([min.y, (min.y + max.y) / 2.0, max.y]);
+        }
+
+        Self {
+            xs,
+            ys,
+            snapped_x: None,
+            snapped_y: None,
+        }
+    }
+
+    /// Offset moving the anchor closest to a guide onto it, separately on both axes. Guides
+    /// further away than `threshold` are ignored.
+    pub fn snap(&mut self, anchors: &[Vec2D], threshold: f32) -> Vec2D {
+        let x = Self::closest(&self.xs, anchors.iter().map(|a| a.x), threshold);
+        let y = Self::closest(&self.ys, anchors.iter().map(|a| a.y), threshold);
+        self.snapped_x = x.map(|(guide, _)| guide);
+        self.snapped_y = y.map(|(guide, _)| guide);
+
+        Vec2D::new(
+            x.map_or(0.0, |(_, distance)| distance),
+            y.map_or(0.0, |(_, distance)| distance),
+        )
+    }
+
+    // returns the guide and the signed distance to it
+    fn closest(
+        guides: &[f32],
+        values: impl Iterator<Item = f32>,
+        threshold: f32,
+    ) -> Option<(f32, f32)> {
+        values
+            .flat_map(|value| guides.iter().map(move |guide| (*guide, *guide - value)))
+            .filter(|(_, distance)| distance.abs() <= threshold)
+            .min_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+    }
+
+    pub fn draw(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        bounds: (Vec2D, Vec2D),
+    ) {
+        let (min, max) = bounds;
+        let pixel = 1.0 / canvas.transform().average_scale();
+
+        let mut path = Path::new();
+        if let Some(x) = self.snapped_x {
+            path.move_to(x, min.y);
+            path.line_to(x, max.y);
+        }
+        if let Some(y) = self.snapped_y {
+            path.move_to(min.x, y);
+            path.line_to(max.x, y);
+        }
+
+        let paint = Paint::color(Color::rgbf(0.9, 0.2, 0.6)).with_line_width(pixel);
+        canvas.stroke_path(&path, &paint);
+    }
+}
@@ -1,56 +1,155 @@
-use anyhow::{anyhow, Error, Result};
+use anyhow::Result;
 use glow::HasContext;
 use std::{
     cell::{RefCell, RefMut},
     num::NonZeroU32,
     rc::Rc,
+    time::{Duration, Instant},
 };
 
-use femtovg::{
-    imgref::{Img, ImgVec},
-    renderer,
-    rgb::{RGB, RGBA, RGBA8},
-    Canvas, FontId, ImageFlags, ImageId, ImageSource, Paint, Path, PixelFormat, Transform2D,
-};
-use fontconfig::Fontconfig;
+use femtovg::{imgref::ImgVec, renderer, rgb::RGBA8, Canvas, ImageId, Paint, Path, Transform2D};
 use gdk_pixbuf::Pixbuf;
 use gtk::{glib, prelude::*, subclass::prelude::*};
+use image::GrayImage;
 use relm4::{gtk, Sender};
-use resource::resource;
 
 use crate::{
-    configuration::Action,
-    math::{rect_ensure_in_bounds, rect_round, Vec2D},
+    canvas::{
+        max_texture_size, prepare_stamps, render_tiled, render_to_image, Fonts, ImageTiles,
+        GL_RENDERER, MAX_TEXTURE_SIZE,
+    },
+    configuration::{Action, TextOrder},
+    math::{
+        self, rect_contains, rect_extend_bounds, rect_round, Transformation, Vec2D, ViewTransform,
+    },
     sketch_board::SketchBoardInput,
-    tools::{CropTool, Drawable, Tool},
+    style::{Color, ColorBlindness, Style},
+    tools::{Annotation, CropTool, Drawable, SelectEvent, Selection, Tool},
     APP_CONFIG,
 };
 
+use super::{guides::Guides, minimap::Minimap};
+
 #[derive(Default)]
 pub struct FemtoVGArea {
     canvas: RefCell<Option<femtovg::Canvas<femtovg::renderer::OpenGl>>>,
-    font: RefCell<Option<FontId>>,
+    fonts: RefCell<Option<Fonts>>,
     inner: RefCell<Option<FemtoVgAreaMut>>,
     request_render: RefCell<Option<Vec<Action>>>,
+    request_snapshot: RefCell<bool>,
     sender: RefCell<Option<Sender<SketchBoardInput>>>,
 }
 
+/// A change to the list of drawables, as recorded on the undo stack
+enum UndoEntry {
+    /// a drawable got appended, it is the last one in the list
+    Commit,
+    /// a drawable got removed from the given index
+    Remove {
+        index: usize,
+        drawable: Box<dyn Drawable>,
+    },
+    /// the drawable at the given index got edited, this is how it looked before
+    Replace {
+        index: usize,
+        drawable: Box<dyn Drawable>,
+    },
+    /// several changes undone in one step, in the order they were made
+    Group(Vec<UndoEntry>),
+    /// the canvas got grown by this margin on each side
+    Grow(f32),
+}
+
+/// nudges of the selection less than this apart are undone in one step
+const NUDGE_BURST: Duration = Duration::from_secs(1);
+
+struct Nudge {
+    indices: Vec<usize>,
+    // revision after the nudge, anything else changing the drawables ends the burst
+    revision: u64,
+    time: Instant,
+}
+
+/// the baked layer is at most this many times the image resolution, closer zooms draw the
+/// drawables directly
+const MAX_LAYER_SCALE: f32 = 8.0;
+/// larger baked layers aren't worth the texture memory, the drawables are drawn directly
+const MAX_LAYER_SIZE: f32 = 8192.0;
+
+/// The background with the committed drawables, rendered once into an image and shown until
+/// any of them changes, so frames don't tessellate every drawable again
+struct BakedLayer {
+    image: ImageId,
+    key: LayerKey,
+}
+
+/// What a baked layer was rendered for, it is rendered again once this changes
+#[derive(PartialEq)]
+struct LayerKey {
+    revision: u64,
+    // drawables baked from the bottom, the ones from a dragged selection up are drawn on every
+    // frame
+    count: usize,
+    bounds: (Vec2D, Vec2D),
+    // resolution relative to the image, a power of two
+    scale: f32,
+    simulation: Option<ColorBlindness>,
+}
+
+/// An undone change, as recorded on the redo stack
+enum RedoEntry {
+    Commit(Box<dyn Drawable>),
+    Remove(usize),
+    Replace {
+        index: usize,
+        drawable: Box<dyn Drawable>,
+    },
+    Group(Vec<RedoEntry>),
+    Grow(f32),
+}
+
 pub struct FemtoVgAreaMut {
     background_image: Pixbuf,
-    background_image_id: Option<femtovg::ImageId>,
+    background_image_tiles: Option<ImageTiles>,
     active_tool: Rc<RefCell<dyn Tool>>,
     crop_tool: Rc<RefCell<CropTool>>,
     scale_factor: f32,
     offset: Vec2D,
     drawables: Vec<Box<dyn Drawable>>,
-    redo_stack: Vec<Box<dyn Drawable>>,
+    undo_stack: Vec<UndoEntry>,
+    redo_stack: Vec<RedoEntry>,
+    // counts changes to the drawables, tells whether a snapshot is outdated
+    revision: u64,
+    baked_layer: Option<BakedLayer>,
+    selection: Option<Selection>,
+    // last nudge of the selection, nudges following it quickly are undone together
+    nudge: Option<Nudge>,
+    // drawables changed by the recolor preview, as they were before
+    recolor_preview: Vec<(usize, Box<dyn Drawable>)>,
+    snapping: bool,
+    guides: Option<Guides>,
+    // text regions found by auto-redact as (min, max), outlined until blurred or dropped
+    redact_proposals: Vec<(Vec2D, Vec2D)>,
+    show_minimap: bool,
+    // recolors the annotations on screen as seen with the deficiency
+    color_simulation: Option<ColorBlindness>,
+    // minimap as laid out by the last frame
+    minimap: Option<Minimap>,
+    // crosshair of the keyboard placement mode, in device pixels
+    crosshair: Option<Vec2D>,
+    // lines through the pointer across the area are drawn while it is inside
+    crosshair_guides: bool,
+    pointer_inside: bool,
     zoom_scale: f32,
+    // fit to the width instead of the whole canvas while zoom_scale is 0
+    fit_width: bool,
     last_scale: f32,
     pointer_offset: Vec2D,
     last_offset: Vec2D,
     drag_offset: Vec2D,
     is_drag: bool,
     is_reset: bool,
+    padding: f32,
 }
 
 #[glib::object_subclass]
@@ -107,40 +206,68 @@ impl GLAreaImpl for FemtoVGArea {
 
         let mut bc = self.canvas.borrow_mut();
         let canvas = bc.as_mut().unwrap(); // this unwrap is safe as long as we call "ensure_canvas" before
-        let font = self.font.borrow().unwrap(); // this unwrap is safe as long as we call "ensure_canvas" before
+        let fonts = self.fonts.borrow();
+        let fonts = fonts.as_ref().unwrap(); // this unwrap is safe as long as we call "ensure_canvas" before
         let mut actions = self.request_render.borrow_mut();
 
         // if we got requested to render a frame
         if let Some(a) = actions.take() {
             // render image
-            let image = match self
-                .inner()
+            let mut inner = self.inner();
+            let inner = inner
                 .as_mut()
-                .expect("Did you call init before using FemtoVgArea?")
-                .render_native_resolution(canvas, font)
-            {
+                .expect("Did you call init before using FemtoVgArea?");
+            let image = match inner.render_native_resolution(canvas, fonts, true) {
                 Ok(t) => t,
                 Err(e) => {
                     println!("Error while rendering image: {e}");
                     return glib::Propagation::Stop;
                 }
             };
+            // the same area without annotations, from the same frame
+            let clean = if a.contains(&Action::SaveBeforeAfter) {
+                match inner.render_native_resolution(canvas, fonts, false) {
+                    Ok(t) => Some(t),
+                    Err(e) => {
+                        println!("Error while rendering image: {e}");
+                        return glib::Propagation::Stop;
+                    }
+                }
+            } else {
+                None
+            };
 
             // send result
             self.sender
                 .borrow()
                 .as_ref()
                 .expect("Did you call init before using FemtoVgArea?")
-                .emit(SketchBoardInput::RenderResult(image, a));
+                .emit(SketchBoardInput::RenderResult(image, clean, a));
 
             // reset request
             *actions = None;
         }
+        if self.request_snapshot.replace(false) {
+            match self
+                .inner()
+                .as_mut()
+                .expect("Did you call init before using FemtoVgArea?")
+                .render_native_resolution(canvas, fonts, true)
+            {
+                Ok(image) => self
+                    .sender
+                    .borrow()
+                    .as_ref()
+                    .expect("Did you call init before using FemtoVgArea?")
+                    .emit(SketchBoardInput::SnapshotResult(image)),
+                Err(e) => println!("Error while rendering snapshot: {e}"),
+            }
+        }
         if let Err(e) = self
             .inner()
             .as_mut()
             .expect("Did you call init before using FemtoVgArea?")
-            .render_framebuffer(canvas, font)
+            .render_framebuffer(canvas, fonts)
         {
             println!("Error rendering to framebuffer: {e}");
         }
@@ -157,65 +284,48 @@ impl FemtoVGArea {
     ) {
         self.inner().replace(FemtoVgAreaMut {
             background_image,
-            background_image_id: None,
+            background_image_tiles: None,
             active_tool,
             crop_tool,
             scale_factor: 1.0,
             offset: Vec2D::zero(),
             drawables: Vec::new(),
+            undo_stack: Vec::new(),
             redo_stack: Vec::new(),
+            revision: 0,
+            baked_layer: None,
+            selection: None,
+            nudge: None,
+            recolor_preview: Vec::new(),
+            snapping: false,
+            guides: None,
+            redact_proposals: Vec::new(),
+            show_minimap: APP_CONFIG.read().show_minimap(),
+            color_simulation: None,
+            minimap: None,
+            crosshair: None,
+            crosshair_guides: APP_CONFIG.read().crosshair_guides(),
+            pointer_inside: false,
             zoom_scale: 0.0,
+            fit_width: false,
             pointer_offset: Vec2D::zero(),
             last_offset: Vec2D::zero(),
             drag_offset: Vec2D::zero(),
             last_scale: 0.0,
             is_drag: false,
             is_reset: false,
+            padding: 0.0,
         });
         self.sender.borrow_mut().replace(sender);
     }
     fn ensure_canvas(&self) {
         if self.canvas.borrow().is_none() {
-            let c = self
+            let mut c = self
                 .setup_canvas()
                 .expect("Cannot setup renderer and canvas");
+            self.fonts.borrow_mut().replace(Fonts::new(&mut c));
             self.canvas.borrow_mut().replace(c);
         }
-
-        let app_config = APP_CONFIG.read();
-        let font = app_config
-            .font()
-            .family()
-            .map(|font| {
-                let font = Fontconfig::new()
-                    .ok_or_else(|| anyhow!("Error while initializing fontconfig"))?
-                    .find(font, app_config.font().style())
-                    .ok_or_else(|| anyhow!("Can not find font"))?;
-                let font_id = self
-                    .canvas
-                    .borrow_mut()
-                    .as_mut()
-                    .unwrap() // this unwrap is safe because it gets placed above
-                    .add_font(font.path)?;
-                Ok(font_id)
-            })
-            .transpose()
-            .unwrap_or_else(|e: Error| {
-                println!("Error while loading font. Using default font: {e}");
-                None
-            });
-        if let Some(font) = font {
-            self.font.borrow_mut().replace(font);
-        } else {
-            self.font.borrow_mut().replace(
-                self.canvas
-                    .borrow_mut()
-                    .as_mut()
-                    .unwrap() // this unwrap is safe because it gets placed above
-                    .add_font_mem(&resource!("src/assets/Roboto-Regular.ttf"))
-                    .expect("Cannot add font"),
-            );
-        }
     }
 
     fn setup_canvas(&self) -> Result<femtovg::Canvas<femtovg::renderer::OpenGl>> {
@@ -232,6 +342,8 @@ impl FemtoVGArea {
             let renderer =
                 renderer::OpenGl::new_from_function(LOAD_FN).expect("Cannot create renderer");
             let ctx = glow::Context::from_loader_function(LOAD_FN);
+            GL_RENDERER.get_or_init(|| ctx.get_parameter_string(glow::RENDERER));
+            MAX_TEXTURE_SIZE.get_or_init(|| ctx.get_parameter_i32(glow::MAX_TEXTURE_SIZE) as u32);
             let id = NonZeroU32::new(ctx.get_parameter_i32(glow::DRAW_FRAMEBUFFER_BINDING) as u32)
                 .expect("No GTK provided framebuffer binding");
             ctx.bind_framebuffer(glow::FRAMEBUFFER, None);
@@ -244,57 +356,713 @@ impl FemtoVGArea {
     pub fn inner(&self) -> RefMut<'_, Option<FemtoVgAreaMut>> {
         self.inner.borrow_mut()
     }
+
+    /// Show `image` instead of the background image, the annotations stay where they are
+    pub fn replace_background_image(&self, image: Pixbuf) {
+        let old_tiles = self
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .replace_background_image(image);
+        if let (Some(tiles), Some(canvas)) = (old_tiles, self.canvas.borrow_mut().as_mut()) {
+            self.obj().make_current();
+            tiles.delete(canvas);
+        }
+    }
     pub fn request_render(&self, actions: &[Action]) {
         self.request_render.borrow_mut().replace(actions.into());
         self.obj().queue_render();
     }
+    pub fn request_snapshot(&self) {
+        self.request_snapshot.replace(true);
+        self.obj().queue_render();
+    }
+    pub fn send(&self, input: SketchBoardInput) {
+        self.sender
+            .borrow()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .emit(input);
+    }
     pub fn set_parent_sender(&self, sender: Sender<SketchBoardInput>) {
         self.sender.borrow_mut().replace(sender);
     }
 }
 
 impl FemtoVgAreaMut {
-    pub fn commit(&mut self, drawable: Box<dyn Drawable>) {
+    /// Add `drawable` on top of the others, returns its index
+    pub fn commit(&mut self, drawable: Box<dyn Drawable>) -> usize {
         self.drawables.push(drawable);
+        self.undo_stack.push(UndoEntry::Commit);
+        self.redo_stack.clear();
+        self.revision += 1;
+        self.drawables.len() - 1
+    }
+
+    /// Remove the topmost drawable at `pos` (image coordinates), returns whether one was hit
+    pub fn erase_at(&mut self, pos: Vec2D, tolerance: f32) -> bool {
+        let Some(index) = self
+            .drawables
+            .iter()
+            .rposition(|d| d.hit_test(pos, tolerance))
+        else {
+            return false;
+        };
+
+        self.remove(index);
+        true
+    }
+
+    /// Remove the topmost drawable at `pos` matching `filter` and hand it out for editing
+    pub fn take_at(
+        &mut self,
+        pos: Vec2D,
+        tolerance: f32,
+        filter: impl Fn(&dyn Drawable) -> bool,
+    ) -> Option<Box<dyn Drawable>> {
+        let index = self
+            .drawables
+            .iter()
+            .rposition(|d| filter(d.as_ref()) && d.hit_test(pos, tolerance))?;
+
+        let drawable = self.drawables[index].clone_box();
+        self.remove(index);
+        Some(drawable)
+    }
+
+    fn remove(&mut self, index: usize) {
+        let drawable = self.drawables.remove(index);
+        self.undo_stack.push(UndoEntry::Remove { index, drawable });
         self.redo_stack.clear();
+        self.revision += 1;
+        self.selection = None;
+    }
+
+    /// Record several changes as a single undo step
+    fn push_undo_group(&mut self, mut entries: Vec<UndoEntry>) {
+        match entries.len() {
+            0 => return,
+            1 => self.undo_stack.push(entries.remove(0)),
+            _ => self.undo_stack.push(UndoEntry::Group(entries)),
+        }
+        self.redo_stack.clear();
+        self.revision += 1;
+    }
+
+    /// Handle input of the select tool. `pixel` is the size of a screen pixel in image
+    /// coordinates, handles and pick radius have a fixed size on screen.
+    pub fn select(&mut self, event: SelectEvent, pixel: f32) {
+        match event {
+            SelectEvent::Begin(pos) => {
+                // handles of the current selection take precedence over everything else
+                if let Some(selection) = &mut self.selection {
+                    if selection.grab_handle(&self.drawables, pos, pixel) {
+                        return;
+                    }
+                }
+
+                let tolerance = Selection::PICK_RADIUS * pixel;
+                let hit = self
+                    .drawables
+                    .iter()
+                    .rposition(|d| d.bounds().is_some() && d.hit_test(pos, tolerance));
+
+                let mut selection = match (hit, self.selection.take()) {
+                    // grabbing any drawable of the selection moves all of them
+                    (Some(index), Some(selection)) if selection.contains(index) => selection,
+                    (Some(index), _) => Selection::new(vec![index]),
+                    // outlines are only hit on their border, allow grabbing a selection
+                    // anywhere inside
+                    (None, Some(selection))
+                        if selection.bounds(&self.drawables).is_some_and(|(min, max)| {
+                            rect_contains((min, max - min), pos, tolerance)
+                        }) =>
+                    {
+                        selection
+                    }
+                    (None, _) => Selection::rubber_band(pos),
+                };
+                selection.grab(&self.drawables, pos);
+                self.selection = Some(selection);
+            }
+            SelectEvent::Update(pos) => {
+                let Some(selection) = &self.selection else {
+                    return;
+                };
+                let mut pos = pos;
+                if self.snapping {
+                    let anchors = selection.snap_anchors(pos);
+                    let mut guides = self.guides(selection.indices());
+                    pos += guides.snap(&anchors, APP_CONFIG.read().snap_threshold() * pixel);
+                    self.guides = Some(guides);
+                }
+                if let Some(selection) = &mut self.selection {
+                    for (index, drawable) in selection.drag_to(pos) {
+                        self.drawables[index] = drawable;
+                    }
+                }
+                // the drag counts as a change once it ends, the baked layer keeps the drawables
+                // below the selection meanwhile
+            }
+            SelectEvent::End => {
+                self.guides = None;
+                let Some(selection) = &mut self.selection else {
+                    return;
+                };
+                let entries = selection
+                    .release(&self.drawables)
+                    .into_iter()
+                    .map(|(index, drawable)| UndoEntry::Replace { index, drawable })
+                    .collect();
+                if selection.indices().is_empty() {
+                    self.selection = None;
+                }
+                self.push_undo_group(entries);
+            }
+            SelectEvent::Restyle { previous, current } => {
+                let Some(selection) = &self.selection else {
+                    return;
+                };
+                let mut entries = Vec::new();
+                for &index in selection.indices() {
+                    if let Some(mut style) = self.drawables[index].style() {
+                        style.apply_change(&previous, &current);
+                        let mut drawable = self.drawables[index].clone_box();
+                        drawable.set_style(style);
+                        let drawable = std::mem::replace(&mut self.drawables[index], drawable);
+                        entries.push(UndoEntry::Replace { index, drawable });
+                    }
+                }
+                self.push_undo_group(entries);
+            }
+            SelectEvent::Clear => self.selection = None,
+        }
+    }
+
+    /// Whether dragged points and shapes snap to guides
+    pub fn set_snapping(&mut self, snapping: bool) {
+        self.snapping = snapping;
+    }
+
+    /// Snap `pos` to the closest guides and show them until `clear_guides`. `pixel` is the
+    /// size of a screen pixel in image coordinates, the threshold is given in screen pixels.
+    pub fn snap_point(&mut self, pos: Vec2D, pixel: f32) -> Vec2D {
+        if !self.snapping {
+            self.guides = None;
+            return pos;
+        }
+        let mut guides = self.guides(&[]);
+        let offset = guides.snap(&[pos], APP_CONFIG.read().snap_threshold() * pixel);
+        self.guides = Some(guides);
+        pos + offset
+    }
+
+    pub fn clear_guides(&mut self) {
+        self.guides = None;
+    }
+
+    // guides for the image and all drawables except the ones at `exclude`
+    fn guides(&self, exclude: &[usize]) -> Guides {
+        let image_size = Vec2D::new(
+            self.background_image.width() as f32,
+            self.background_image.height() as f32,
+        );
+        Guides::new(
+            image_size,
+            self.drawables
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !exclude.contains(index))
+                .filter_map(|(_, d)| d.bounds()),
+        )
+    }
+
+    pub fn has_selection(&self) -> bool {
+        self.selection
+            .as_ref()
+            .is_some_and(|s| !s.indices().is_empty())
+    }
+
+    /// Remove the selected drawables, returns whether there were any
+    pub fn delete_selection(&mut self) -> bool {
+        let Some(selection) = self.selection.take() else {
+            return false;
+        };
+        let mut indices = selection.indices().to_vec();
+        // remove from the back, so the other indices stay valid
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+        let entries: Vec<_> = indices
+            .into_iter()
+            .map(|index| UndoEntry::Remove {
+                index,
+                drawable: self.drawables.remove(index),
+            })
+            .collect();
+        let removed = !entries.is_empty();
+        self.push_undo_group(entries);
+        removed
+    }
+
+    /// Commit copies of the selected drawables moved by `offset` and select the copies
+    pub fn duplicate_selection(&mut self, offset: Vec2D) -> bool {
+        let Some(selection) = &self.selection else {
+            return false;
+        };
+
+        let mut indices = selection.indices().to_vec();
+        indices.sort_unstable();
+        let first = self.drawables.len();
+        for index in &indices {
+            let mut drawable = self.drawables[*index].clone_box();
+            drawable.transform(Transformation::Translate(offset));
+            self.drawables.push(drawable);
+        }
+        self.push_undo_group(indices.iter().map(|_| UndoEntry::Commit).collect());
+        self.selection = Some(Selection::new((first..self.drawables.len()).collect()));
+        !indices.is_empty()
+    }
+
+    /// Commit all of `drawables` in a single undo step
+    pub fn commit_group(&mut self, drawables: Vec<Box<dyn Drawable>>) {
+        let entries = drawables.iter().map(|_| UndoEntry::Commit).collect();
+        self.drawables.extend(drawables);
+        self.push_undo_group(entries);
+    }
+
+    /// Replace all drawables with `drawables` in a single undo step
+    pub fn replace_all(&mut self, drawables: Vec<Box<dyn Drawable>>) {
+        self.selection = None;
+        let mut entries: Vec<_> = (0..self.drawables.len())
+            .rev()
+            .map(|index| UndoEntry::Remove {
+                index,
+                drawable: self.drawables.remove(index),
+            })
+            .collect();
+        entries.extend(drawables.iter().map(|_| UndoEntry::Commit));
+        self.drawables.extend(drawables);
+        self.push_undo_group(entries);
+    }
+
+    pub fn set_redact_proposals(&mut self, proposals: Vec<(Vec2D, Vec2D)>) {
+        self.redact_proposals = proposals;
+    }
+
+    pub fn has_redact_proposals(&self) -> bool {
+        !self.redact_proposals.is_empty()
+    }
+
+    /// Drop the proposed region at `pos` (image coordinates), returns whether there was one
+    pub fn drop_redact_proposal_at(&mut self, pos: Vec2D) -> bool {
+        let Some(index) = self
+            .redact_proposals
+            .iter()
+            .rposition(|&(min, max)| rect_contains((min, max - min), pos, 0.0))
+        else {
+            return false;
+        };
+        self.redact_proposals.remove(index);
+        true
+    }
+
+    pub fn take_redact_proposals(&mut self) -> Vec<(Vec2D, Vec2D)> {
+        std::mem::take(&mut self.redact_proposals)
+    }
+
+    /// Move the selected drawables by `offset`. A burst of nudges is a single undo step.
+    pub fn nudge_selection(&mut self, offset: Vec2D) -> bool {
+        let Some(selection) = &self.selection else {
+            return false;
+        };
+        let indices = selection.indices().to_vec();
+        if indices.is_empty() {
+            return false;
+        }
+
+        // the undo entry of the burst already holds the drawables as they were before it
+        let continues = self.nudge.as_ref().is_some_and(|nudge| {
+            nudge.indices == indices
+                && nudge.revision == self.revision
+                && nudge.time.elapsed() < NUDGE_BURST
+        });
+        let mut entries = Vec::new();
+        for &index in &indices {
+            let mut drawable = self.drawables[index].clone_box();
+            drawable.transform(Transformation::Translate(offset));
+            let drawable = std::mem::replace(&mut self.drawables[index], drawable);
+            entries.push(UndoEntry::Replace { index, drawable });
+        }
+        if continues {
+            self.redo_stack.clear();
+            self.revision += 1;
+        } else {
+            self.push_undo_group(entries);
+        }
+
+        self.nudge = Some(Nudge {
+            indices,
+            revision: self.revision,
+            time: Instant::now(),
+        });
+        true
+    }
+
+    /// Average color of the image pixel at `pos`, in image coordinates
+    pub fn background_color_at(&self, pos: Vec2D) -> Option<Color> {
+        let pixel = Vec2D::new(pos.x.floor(), pos.y.floor());
+        self.average_background_color((pixel, pixel + Vec2D::new(1.0, 1.0)))
+    }
+
+    /// The drawables as plain data, the ones that can't be stored are left out
+    pub fn annotations(&self) -> Vec<Annotation> {
+        self.drawables
+            .iter()
+            .filter_map(|d| d.annotation())
+            .collect()
+    }
+
+    /// Colors used by the drawables, in order of first use
+    pub fn used_colors(&self) -> Vec<Color> {
+        let mut colors = Vec::new();
+        for style in self.drawables.iter().filter_map(|d| d.style()) {
+            if !colors.contains(&style.color) {
+                colors.push(style.color);
+            }
+        }
+        colors
+    }
+
+    /// Show the drawables using color `from` with color `to` instead, without recording the
+    /// change. `None` only reverts a previous preview.
+    pub fn preview_recolor(&mut self, recolor: Option<(Color, Color)>) {
+        if !self.recolor_preview.is_empty() || recolor.is_some() {
+            self.revision += 1;
+        }
+        for (index, drawable) in self.recolor_preview.drain(..) {
+            self.drawables[index] = drawable;
+        }
+        let Some((from, to)) = recolor else {
+            return;
+        };
+        for index in 0..self.drawables.len() {
+            let Some(mut style) = self.drawables[index].style() else {
+                continue;
+            };
+            if style.color != from {
+                continue;
+            }
+            style.color = to;
+            let mut drawable = self.drawables[index].clone_box();
+            drawable.set_style(style);
+            let original = std::mem::replace(&mut self.drawables[index], drawable);
+            self.recolor_preview.push((index, original));
+        }
+    }
+
+    /// Keep the previewed recolor as a single undo step, returns whether anything changed
+    pub fn apply_recolor(&mut self) -> bool {
+        let entries: Vec<_> = self
+            .recolor_preview
+            .drain(..)
+            .map(|(index, drawable)| UndoEntry::Replace { index, drawable })
+            .collect();
+        let changed = !entries.is_empty();
+        self.push_undo_group(entries);
+        changed
+    }
+
+    /// Contrast ratio between the text at `index` and the image below it, `None` if it isn't
+    /// a text or already has a background
+    pub fn text_contrast(&self, index: usize) -> Option<f32> {
+        let drawable = self.drawables.get(index)?;
+        drawable.text()?;
+        let style = drawable.style().filter(|style| !style.fill)?;
+        let background = self.average_background_color(drawable.bounds()?)?;
+
+        // blend translucent text onto the background, that is how it ends up looking
+        let alpha = style.color.a as f32 / 255.0;
+        let blend = |fg: u8, bg: u8| (fg as f32 * alpha + bg as f32 * (1.0 - alpha)).round() as u8;
+        let text = Color::new(
+            blend(style.color.r, background.r),
+            blend(style.color.g, background.g),
+            blend(style.color.b, background.b),
+            255,
+        );
+        Some(text.contrast_ratio(background))
+    }
+
+    /// Brightness of the background image, as searched for text by auto-redact
+    pub fn background_grayscale(&self) -> GrayImage {
+        let image = &self.background_image;
+        let pixels = image.read_pixel_bytes();
+        let channels = image.n_channels() as usize;
+        let stride = image.rowstride() as usize;
+        GrayImage::from_fn(image.width() as u32, image.height() as u32, |x, y| {
+            let offset = y as usize * stride + x as usize * channels;
+            let rgb = &pixels[offset..offset + 3];
+            // ITU-R BT.601 luma
+            let luma = 0.299 * rgb[0] as f32 + 0.587 * rgb[1] as f32 + 0.114 * rgb[2] as f32;
+            image::Luma([luma.round() as u8])
+        })
+    }
+
+    /// Average color of the background image within `bounds` (image coordinates), sampled on
+    /// a grid so large areas stay cheap
+    fn average_background_color(&self, bounds: (Vec2D, Vec2D)) -> Option<Color> {
+        const SAMPLES: i32 = 64;
+
+        let image = &self.background_image;
+        let (min, max) = bounds;
+        let left = (min.x.floor() as i32).max(0);
+        let top = (min.y.floor() as i32).max(0);
+        let right = (max.x.ceil() as i32).min(image.width());
+        let bottom = (max.y.ceil() as i32).min(image.height());
+        if left >= right || top >= bottom {
+            return None;
+        }
+
+        let pixels = image.read_pixel_bytes();
+        let channels = image.n_channels() as usize;
+        let stride = image.rowstride() as usize;
+        let step_x = ((right - left) / SAMPLES).max(1) as usize;
+        let step_y = ((bottom - top) / SAMPLES).max(1) as usize;
+
+        let mut sum = [0u64; 3];
+        let mut count = 0u64;
+        for y in (top as usize..bottom as usize).step_by(step_y) {
+            for x in (left as usize..right as usize).step_by(step_x) {
+                let offset = y * stride + x * channels;
+                for (channel, sum) in sum.iter_mut().enumerate() {
+                    *sum += pixels[offset + channel] as u64;
+                }
+                count += 1;
+            }
+        }
+        let [r, g, b] = sum.map(|sum| (sum / count) as u8);
+        Some(Color::new(r, g, b, 255))
+    }
+
+    /// Put a background plate behind the text at `index` as an undoable step, returns
+    /// whether there was a text without one
+    pub fn add_text_background(&mut self, index: usize) -> bool {
+        let Some(drawable) = self.drawables.get(index).filter(|d| d.text().is_some()) else {
+            return false;
+        };
+        let Some(mut style) = drawable.style().filter(|style| !style.fill) else {
+            return false;
+        };
+        style.fill = true;
+        let mut replacement = drawable.clone_box();
+        replacement.set_style(style);
+        let original = std::mem::replace(&mut self.drawables[index], replacement);
+        self.push_undo_group(vec![UndoEntry::Replace {
+            index,
+            drawable: original,
+        }]);
+        true
+    }
+
+    /// Drawables showing text, with their index
+    pub fn texts(&self) -> Vec<(usize, String)> {
+        self.drawables
+            .iter()
+            .enumerate()
+            .filter_map(|(index, d)| d.text().map(|text| (index, text)))
+            .collect()
+    }
+
+    /// Contents of the text annotations in the given order
+    pub fn texts_in_order(&self, order: TextOrder) -> Vec<String> {
+        let mut texts: Vec<(String, Vec2D, Vec2D)> = self
+            .drawables
+            .iter()
+            .filter_map(|d| {
+                let (min, max) = d.bounds()?;
+                Some((d.text()?, min, max))
+            })
+            .collect();
+        if order == TextOrder::Reading {
+            texts.sort_by(|a, b| a.1.y.total_cmp(&b.1.y));
+            // texts starting above the vertical center of the first text of a line are on it
+            let mut lines: Vec<Vec<(String, Vec2D, Vec2D)>> = Vec::new();
+            for text in texts {
+                match lines.last_mut() {
+                    Some(line) if text.1.y < (line[0].1.y + line[0].2.y) / 2.0 => line.push(text),
+                    _ => lines.push(vec![text]),
+                }
+            }
+            texts = lines
+                .into_iter()
+                .flat_map(|mut line| {
+                    line.sort_by(|a, b| a.1.x.total_cmp(&b.1.x));
+                    line
+                })
+                .collect();
+        }
+        texts.into_iter().map(|(text, _, _)| text).collect()
+    }
+
+    /// Pan to the drawable at `index`, zooming out if it would not fit comfortably into the
+    /// `viewport` (in device pixels). Returns whether the drawable has bounds to pan to.
+    pub fn focus_drawable(&mut self, index: usize, viewport: Vec2D) -> bool {
+        let Some((min, max)) = self.drawables.get(index).and_then(|d| d.bounds()) else {
+            return false;
+        };
+        let size = max - min;
+        let center = (min + max) * 0.5;
+
+        // use up to half of the viewport, but don't zoom in further than 1:1 or the current zoom
+        let fit = (viewport.x / (size.x * 2.0)).min(viewport.y / (size.y * 2.0));
+        let scale = fit.min(self.scale_factor.max(1.0));
+        self.center_on(center, scale, viewport);
+        true
+    }
+
+    /// Zoom to `scale` and pan so `center` (image coordinates) is in the middle of the
+    /// `viewport` (device pixels)
+    fn center_on(&mut self, center: Vec2D, scale: f32, viewport: Vec2D) {
+        self.zoom_scale = scale;
+        self.last_scale = scale;
+
+        // same centering as in update_transformation, the drag offset moves from there
+        let (origin, extent) = self.canvas_bounds();
+        let centered = math::center_offset(viewport, origin, extent, scale);
+        self.drag_offset = math::center_drag_offset(viewport, center, scale, centered);
+        self.store_last_offset();
+        self.is_reset = false;
+    }
+
+    /// Switch to the next color blindness preview, returns the new one
+    pub fn cycle_color_simulation(&mut self) -> Option<ColorBlindness> {
+        self.color_simulation = ColorBlindness::cycle(self.color_simulation);
+        self.color_simulation
+    }
+
+    pub fn toggle_minimap(&mut self) {
+        self.show_minimap = !self.show_minimap;
+    }
+
+    pub fn set_crosshair(&mut self, crosshair: Option<Vec2D>) {
+        self.crosshair = crosshair;
+    }
+
+    /// Whether `pos` (device pixels) is on the minimap shown in the last frame
+    pub fn minimap_contains(&self, pos: Vec2D) -> bool {
+        self.minimap.as_ref().is_some_and(|m| m.contains(pos))
+    }
+
+    /// See [`Minimap::grab_offset`], zero without a minimap
+    pub fn minimap_grab_offset(&self, pos: Vec2D) -> Vec2D {
+        self.minimap
+            .as_ref()
+            .map_or(Vec2D::zero(), |m| m.grab_offset(pos))
+    }
+
+    /// Pan to the part of the image shown at `pos` (device pixels) on the minimap
+    pub fn minimap_jump(&mut self, pos: Vec2D, viewport: Vec2D) {
+        if let Some(center) = self.minimap.as_ref().map(|m| m.to_image(pos)) {
+            self.center_on(center, self.scale_factor, viewport);
+        }
     }
 
     pub fn undo(&mut self) -> bool {
-        match self.drawables.pop() {
-            Some(mut d) => {
+        // indices may change, don't keep a selection pointing to the wrong drawable
+        self.selection = None;
+        match self.undo_stack.pop() {
+            Some(entry) => {
+                let redo = self.undo_entry(entry);
+                self.redo_stack.push(redo);
+                self.revision += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn undo_entry(&mut self, entry: UndoEntry) -> RedoEntry {
+        match entry {
+            UndoEntry::Commit => {
+                let mut d = self
+                    .drawables
+                    .pop()
+                    .expect("committed drawable should be on top");
+
                 // notify of the undo action
                 d.handle_undo();
 
-                // push to redo stack
-                self.redo_stack.push(d);
-                true
+                RedoEntry::Commit(d)
+            }
+            UndoEntry::Remove { index, drawable } => {
+                self.drawables.insert(index, drawable);
+                RedoEntry::Remove(index)
+            }
+            UndoEntry::Replace { index, drawable } => {
+                let drawable = std::mem::replace(&mut self.drawables[index], drawable);
+                RedoEntry::Replace { index, drawable }
+            }
+            // undo in reverse order, redoing reverses it again
+            UndoEntry::Group(entries) => RedoEntry::Group(
+                entries
+                    .into_iter()
+                    .rev()
+                    .map(|entry| self.undo_entry(entry))
+                    .collect(),
+            ),
+            UndoEntry::Grow(margin) => {
+                self.padding -= margin;
+                RedoEntry::Grow(margin)
             }
-            None => false,
         }
     }
+
     pub fn redo(&mut self) -> bool {
+        self.selection = None;
         match self.redo_stack.pop() {
-            Some(mut d) => {
+            Some(entry) => {
+                let undo = self.redo_entry(entry);
+                self.undo_stack.push(undo);
+                self.revision += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn redo_entry(&mut self, entry: RedoEntry) -> UndoEntry {
+        match entry {
+            RedoEntry::Commit(mut d) => {
                 // notify of the redo action
                 d.handle_redo();
 
                 // push to drawable stack
                 self.drawables.push(d);
-
-                true
+                UndoEntry::Commit
+            }
+            RedoEntry::Remove(index) => {
+                let drawable = self.drawables.remove(index);
+                UndoEntry::Remove { index, drawable }
+            }
+            RedoEntry::Replace { index, drawable } => {
+                let drawable = std::mem::replace(&mut self.drawables[index], drawable);
+                UndoEntry::Replace { index, drawable }
+            }
+            RedoEntry::Group(entries) => UndoEntry::Group(
+                entries
+                    .into_iter()
+                    .rev()
+                    .map(|entry| self.redo_entry(entry))
+                    .collect(),
+            ),
+            RedoEntry::Grow(margin) => {
+                self.padding += margin;
+                UndoEntry::Grow(margin)
             }
-            None => false,
         }
     }
+
     pub fn reset(&mut self) -> bool {
         let mut any_undone = false;
-        while let Some(mut d) = self.drawables.pop() {
-            // notify of the undo action
-            d.handle_undo();
-
-            // push to redo stack
-            self.redo_stack.push(d);
-
+        while self.undo() {
             any_undone = true;
         }
         any_undone
@@ -304,62 +1072,98 @@ impl FemtoVgAreaMut {
         self.active_tool = active_tool;
     }
 
-    pub fn render_native_resolution(
-        &mut self,
-        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        font: FontId,
-    ) -> anyhow::Result<ImgVec<RGBA8>> {
-        let bounds = (
-            Vec2D::zero(),
+    /// Returns the textures of the old image, it is uploaded again on the next render
+    fn replace_background_image(&mut self, image: Pixbuf) -> Option<ImageTiles> {
+        self.background_image = image;
+        self.revision += 1;
+        // setting the style again drops what blurs cached of the old image
+        for drawable in &mut self.drawables {
+            if let Some(style) = drawable.style() {
+                drawable.set_style(style);
+            }
+        }
+        self.background_image_tiles.take()
+    }
+
+    pub fn grow_canvas(&mut self, margin: f32) {
+        if margin <= 0.0 {
+            return;
+        }
+        self.padding += margin;
+        self.undo_stack.push(UndoEntry::Grow(margin));
+        self.redo_stack.clear();
+        self.revision += 1;
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn background_image(&self) -> &Pixbuf {
+        &self.background_image
+    }
+
+    /// Bounds (min and max corner) of the canvas in image coordinates, i.e. the background
+    /// image plus the padding added by growing the canvas
+    fn canvas_bounds(&self) -> (Vec2D, Vec2D) {
+        let padding = Vec2D::new(self.padding, self.padding);
+        (
+            Vec2D::zero() - padding,
             Vec2D::new(
                 self.background_image.width() as f32,
                 self.background_image.height() as f32,
-            ),
-        );
-        // get offset and size of the area in question
-        let (pos, size) = self
-            .crop_tool
+            ) + padding,
+        )
+    }
+
+    /// The crop area if there is a non-empty one. It may extend beyond the canvas.
+    fn crop_rectangle(&self) -> Option<(Vec2D, Vec2D)> {
+        self.crop_tool
             .borrow()
             .get_crop()
             .map(|c| c.get_rectangle())
-            .map(|rect| rect_ensure_in_bounds(rect, bounds))
-            .map(rect_round)
             .filter(|(_, size)| !size.is_zero())
-            .unwrap_or(bounds);
-
-        // create render-target
-        let image_id = canvas.create_image_empty(
-            size.x as usize,
-            size.y as usize,
-            PixelFormat::Rgba8,
-            ImageFlags::empty(),
-        )?;
-        canvas.set_render_target(femtovg::RenderTarget::Image(image_id));
-
-        // apply offset
-        let mut transform = Transform2D::identity();
-        transform.translate(-pos.x, -pos.y);
-        canvas.reset_transform();
-        canvas.set_transform(&transform);
+    }
 
-        // render
-        self.render(canvas, font, false)?;
+    /// Canvas bounds extended to contain the crop area
+    fn render_bounds(&self) -> (Vec2D, Vec2D) {
+        let bounds = self.canvas_bounds();
+        match self.crop_rectangle() {
+            Some(rect) => rect_extend_bounds(rect, bounds),
+            None => bounds,
+        }
+    }
 
-        // return screenshot
-        let result = canvas.screenshot();
+    /// Render the exported area, only the image and its padding without `annotations`
+    pub fn render_native_resolution(
+        &mut self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        fonts: &Fonts,
+        annotations: bool,
+    ) -> anyhow::Result<ImgVec<RGBA8>> {
+        // get offset and size of the area in question. The crop area is not clamped to the
+        // image, anything outside of it gets filled with the padding color.
+        let (min, max) = self.canvas_bounds();
+        let (pos, size) = self
+            .crop_rectangle()
+            .map(rect_round)
+            .filter(|(_, size)| !size.is_zero())
+            .unwrap_or_else(|| rect_round((min, max - min)));
 
-        // clean up
-        canvas.set_render_target(femtovg::RenderTarget::Screen);
-        canvas.delete_image(image_id);
+        prepare_stamps(canvas);
 
-        Ok(result?)
+        // render targets are textures too, larger areas are rendered in tiles
+        render_tiled(canvas, pos, (size.x as u32, size.y as u32), |canvas| {
+            self.render(canvas, fonts, false, annotations)
+        })
     }
 
     pub fn render_framebuffer(
         &mut self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        font: FontId,
+        fonts: &Fonts,
     ) -> Result<()> {
+        prepare_stamps(canvas);
         canvas.set_render_target(femtovg::RenderTarget::Screen);
 
         // setup transform to image coordinates
@@ -370,50 +1174,182 @@ impl FemtoVgAreaMut {
         canvas.reset_transform();
         canvas.set_transform(&transform);
 
-        self.render(canvas, font, true)?;
+        self.render(canvas, fonts, true, true)?;
+        self.render_minimap(canvas);
+        self.render_crosshair(canvas);
+        self.render_crosshair_guides(canvas);
 
         Ok(())
     }
 
+    fn render_redact_proposals(&self, canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>) {
+        if self.redact_proposals.is_empty() {
+            return;
+        }
+        let pixel = 1.0 / canvas.transform().average_scale();
+
+        let mut path = Path::new();
+        for &(min, max) in &self.redact_proposals {
+            let size = max - min;
+            path.rect(min.x, min.y, size.x, size.y);
+        }
+        canvas.fill_path(
+            &path,
+            &Paint::color(femtovg::Color::rgbaf(0.9, 0.2, 0.6, 0.2)),
+        );
+        canvas.stroke_path(
+            &path,
+            &Paint::color(femtovg::Color::rgbf(0.9, 0.2, 0.6)).with_line_width(2.0 * pixel),
+        );
+    }
+
+    fn render_crosshair(&self, canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>) {
+        const ARM: f32 = 12.0;
+        const GAP: f32 = 3.0;
+        let Some(pos) = self.crosshair else {
+            return;
+        };
+
+        let mut path = Path::new();
+        for (dx, dy) in [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+            path.move_to(pos.x + dx * GAP, pos.y + dy * GAP);
+            path.line_to(pos.x + dx * ARM, pos.y + dy * ARM);
+        }
+
+        // a dark outline keeps it visible on bright images
+        canvas.reset_transform();
+        canvas.stroke_path(
+            &path,
+            &Paint::color(femtovg::Color::rgbaf(0.0, 0.0, 0.0, 0.7)).with_line_width(3.0),
+        );
+        canvas.stroke_path(
+            &path,
+            &Paint::color(femtovg::Color::white()).with_line_width(1.0),
+        );
+        canvas.flush();
+    }
+
+    fn render_crosshair_guides(&self, canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>) {
+        if !self.crosshair_guides || !self.pointer_inside {
+            return;
+        }
+        // centered on device pixels, so the lines stay sharp
+        let pos = Vec2D::new(
+            self.pointer_offset.x.floor() + 0.5,
+            self.pointer_offset.y.floor() + 0.5,
+        );
+        let mut path = Path::new();
+        path.move_to(0.0, pos.y);
+        path.line_to(canvas.width() as f32, pos.y);
+        path.move_to(pos.x, 0.0);
+        path.line_to(pos.x, canvas.height() as f32);
+
+        canvas.reset_transform();
+        canvas.stroke_path(
+            &path,
+            &Paint::color(femtovg::Color::rgbaf(0.0, 0.0, 0.0, 0.4)).with_line_width(3.0),
+        );
+        canvas.stroke_path(
+            &path,
+            &Paint::color(femtovg::Color::rgbaf(1.0, 1.0, 1.0, 0.8)).with_line_width(1.0),
+        );
+        canvas.flush();
+    }
+
+    fn render_minimap(&mut self, canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>) {
+        self.minimap = self
+            .show_minimap
+            .then(|| {
+                Minimap::new(
+                    self.canvas_bounds(),
+                    Vec2D::new(canvas.width() as f32, canvas.height() as f32),
+                    self.scale_factor,
+                )
+            })
+            .flatten();
+        let visible = (
+            self.abs_canvas_to_image_coordinates(Vec2D::zero(), 1.0),
+            self.abs_canvas_to_image_coordinates(
+                Vec2D::new(canvas.width() as f32, canvas.height() as f32),
+                1.0,
+            ),
+        );
+        let (Some(tiles), Some(minimap)) = (&self.background_image_tiles, &mut self.minimap) else {
+            return;
+        };
+        minimap.set_visible(visible);
+
+        canvas.reset_transform();
+        minimap.draw(canvas, tiles);
+        canvas.flush();
+    }
+
     fn render(
         &mut self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        font: FontId,
+        fonts: &Fonts,
         render_crop: bool,
+        annotations: bool,
     ) -> Result<()> {
-        // clear canvas
+        // clear canvas, keep exported images transparent outside of the padding
         canvas.clear_rect(
             0,
             0,
             canvas.width(),
             canvas.height(),
-            femtovg::Color::black(),
+            if render_crop {
+                femtovg::Color::rgbaf(0.0, 0.0, 0.0, APP_CONFIG.read().window_opacity())
+            } else {
+                femtovg::Color::rgbaf(0.0, 0.0, 0.0, 0.0)
+            },
         );
 
-        // render background
-        self.render_background_image(canvas)?;
+        let bounds = self.render_bounds();
+        // the color simulation is only a preview
+        let simulation = self.color_simulation.filter(|_| render_crop);
 
-        let bounds = (
-            Vec2D::zero(),
-            Vec2D::new(
-                self.background_image.width() as f32,
-                self.background_image.height() as f32,
-            ),
-        );
-        // render the whole stack
-        for d in &mut self.drawables {
-            d.draw(canvas, font, bounds)?;
+        // on screen the image and the whole stack come from the baked layer, exports are
+        // rendered directly in their own resolution
+        let baked = render_crop && annotations && self.render_baked_layer(canvas, fonts)?;
+        if !baked {
+            // render padding around the image
+            self.render_padding(canvas, bounds);
+
+            // render background
+            self.render_background_image(canvas)?;
+
+            if !annotations {
+                canvas.flush();
+                return Ok(());
+            }
+
+            // render the whole stack
+            for d in &self.drawables {
+                Self::draw_simulated(d.as_ref(), simulation, canvas, fonts, bounds)?;
+            }
         }
 
         // render active tool
         if let Some(d) = self.active_tool.borrow().get_drawable() {
-            d.draw(canvas, font, bounds)?;
+            Self::draw_simulated(d, simulation, canvas, fonts, bounds)?;
+        }
+
+        // render the selection of the select tool and snapping guides, never part of the
+        // exported image
+        if render_crop {
+            if let Some(guides) = &self.guides {
+                guides.draw(canvas, bounds);
+            }
+            self.render_redact_proposals(canvas);
+            if let Some(selection) = &self.selection {
+                selection.draw(canvas, &self.drawables)?;
+            }
         }
 
         // render crop tool
         if render_crop {
             if let Some(c) = self.crop_tool.borrow().get_crop() {
-                c.draw(canvas, font, bounds)?;
+                c.draw(canvas, fonts, bounds)?;
             }
         }
 
@@ -421,124 +1357,157 @@ impl FemtoVgAreaMut {
         Ok(())
     }
 
-    fn render_background_image(
+    /// Draw the image with the committed drawables from the baked layer, baking it first if
+    /// they changed. Returns `false` if they have to be drawn directly instead.
+    fn render_baked_layer(
         &mut self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-    ) -> Result<()> {
-        let background_image_id = match self.background_image_id {
-            Some(id) => id,
-            None => {
-                let id = Self::upload_background_image(canvas, &self.background_image)?;
-                self.background_image_id.replace(id);
-                id
+        fonts: &Fonts,
+    ) -> Result<bool> {
+        let bounds = self.canvas_bounds();
+        // a crop reaching past the canvas changes the bounds while it is dragged
+        if self.render_bounds() != bounds {
+            return Ok(false);
+        }
+        // sharp at the current zoom, baked again when zooming past the next power of two
+        let scale = canvas.transform().average_scale().log2().ceil().exp2();
+        let (min, max) = bounds;
+        let size = max - min;
+        let max_size = MAX_LAYER_SIZE.min(max_texture_size() as f32);
+        if scale > MAX_LAYER_SCALE || size.x.max(size.y) * scale > max_size {
+            return Ok(false);
+        }
+
+        let count = self
+            .selection
+            .as_ref()
+            .filter(|selection| selection.dragging())
+            .and_then(|selection| selection.indices().iter().min().copied())
+            .unwrap_or(self.drawables.len());
+        let key = LayerKey {
+            revision: self.revision,
+            count,
+            bounds,
+            scale,
+            simulation: self.color_simulation,
+        };
+        let image = match self.baked_layer.take() {
+            Some(layer) if layer.key == key => layer.image,
+            outdated => {
+                if let Some(layer) = outdated {
+                    canvas.delete_image(layer.image);
+                }
+                self.bake_layer(canvas, fonts, &key)?
             }
         };
+        self.baked_layer = Some(BakedLayer { image, key });
 
-        // render the image
         let mut path = Path::new();
-        path.rect(
-            0.0,
-            0.0,
-            self.background_image.width() as f32,
-            self.background_image.height() as f32,
-        );
-
+        path.rect(min.x, min.y, size.x, size.y);
         canvas.fill_path(
             &path,
-            &Paint::image(
-                background_image_id,
-                0f32,
-                0f32,
-                self.background_image.width() as f32,
-                self.background_image.height() as f32,
-                0f32,
-                1f32,
-            ),
+            &Paint::image(image, min.x, min.y, size.x, size.y, 0.0, 1.0),
         );
-
-        Ok(())
+        for d in &self.drawables[count..] {
+            Self::draw_simulated(d.as_ref(), self.color_simulation, canvas, fonts, bounds)?;
+        }
+        Ok(true)
     }
 
-    fn upload_background_image(
+    /// Render the padding, the image and the committed drawables into a new image
+    fn bake_layer(
+        &mut self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
-        image: &Pixbuf,
+        fonts: &Fonts,
+        key: &LayerKey,
     ) -> Result<ImageId> {
-        let format = if image.has_alpha() {
-            PixelFormat::Rgba8
-        } else {
-            PixelFormat::Rgb8
-        };
+        let (min, max) = key.bounds;
+        let size = (max - min) * key.scale;
+        let (width, height) = (size.x.ceil() as u32, size.y.ceil() as u32);
+        render_to_image(canvas, (width, height), |canvas| {
+            let mut transform = Transform2D::identity();
+            transform.translate(-min.x, -min.y);
+            transform.scale(key.scale, key.scale);
+            canvas.set_transform(&transform);
+
+            self.render_padding(canvas, key.bounds);
+            self.render_background_image(canvas)?;
+            self.drawables[..key.count].iter().try_for_each(|d| {
+                Self::draw_simulated(d.as_ref(), key.simulation, canvas, fonts, key.bounds)
+            })
+        })
+    }
 
-        let background_image_id = canvas.create_image_empty(
-            image.width() as usize,
-            image.height() as usize,
-            format,
-            ImageFlags::empty(),
-        )?;
-
-        // extract values
-        let width = image.width() as usize;
-        let stride = image.rowstride() as usize; // stride is in bytes per row
-        let height = image.height() as usize;
-        let bytes_per_pixel = if image.has_alpha() { 4 } else { 3 }; // pixbuf supports rgb or rgba
-
-        unsafe {
-            let src_buffer = image.pixels();
-
-            let row_length = width * bytes_per_pixel;
-            let mut dst_buffer = if row_length == stride {
-                // stride == row_length, there are no additional bytes after the end of each row
-                src_buffer.to_vec()
-            } else {
-                // stride != row_length, there are additional bytes after the end of each row that
-                // need to be truncated. We copy row by row..
-                let mut dst_buffer = Vec::<u8>::with_capacity(width * height * bytes_per_pixel);
+    fn draw_simulated(
+        drawable: &dyn Drawable,
+        simulation: Option<ColorBlindness>,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        fonts: &Fonts,
+        bounds: (Vec2D, Vec2D),
+    ) -> Result<()> {
+        match simulation.zip(drawable.style()) {
+            Some((deficiency, style)) => {
+                let mut simulated = drawable.clone_box();
+                simulated.set_style(Style {
+                    color: style.color.simulate(deficiency),
+                    ..style
+                });
+                simulated.draw(canvas, fonts, bounds)
+            }
+            None => drawable.draw(canvas, fonts, bounds),
+        }
+    }
 
-                for row in 0..height {
-                    let src_offset = row * stride;
-                    dst_buffer.extend_from_slice(&src_buffer[src_offset..src_offset + row_length]);
-                }
-                dst_buffer
-            };
+    fn render_padding(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        bounds: (Vec2D, Vec2D),
+    ) {
+        let (min, max) = bounds;
+        let image_size = Vec2D::new(
+            self.background_image.width() as f32,
+            self.background_image.height() as f32,
+        );
+        if min.x >= 0.0 && min.y >= 0.0 && max.x <= image_size.x && max.y <= image_size.y {
+            return;
+        }
 
-            // in almost all cases, that should be a no-op. Buf we might have additional elements after the
-            // end of the buffer, e.g. after width * height * bytes_per_pixel
-            dst_buffer.truncate(width * height * bytes_per_pixel);
+        let mut path = Path::new();
+        path.rect(min.x, min.y, max.x - min.x, max.y - min.y);
+        canvas.fill_path(
+            &path,
+            &Paint::color(APP_CONFIG.read().canvas_padding_color().into()),
+        );
+    }
 
-            if image.has_alpha() {
-                let img = Img::new_stride(
-                    dst_buffer.align_to::<RGBA<u8>>().1.to_vec(),
-                    width,
-                    height,
-                    width,
-                );
+    fn render_background_image(
+        &mut self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+    ) -> Result<()> {
+        let tiles = match &mut self.background_image_tiles {
+            Some(tiles) => tiles,
+            tiles => tiles.insert(ImageTiles::upload(canvas, &self.background_image)?),
+        };
 
-                canvas.update_image(background_image_id, ImageSource::Rgba(img.as_ref()), 0, 0)?;
-            } else {
-                let img = Img::new_stride(
-                    dst_buffer.align_to::<RGB<u8>>().1.to_owned(),
-                    width,
-                    height,
-                    width,
-                );
-
-                canvas.update_image(background_image_id, ImageSource::Rgb(img.as_ref()), 0, 0)?;
-            }
-        }
+        // render the image
+        tiles.draw(canvas, 1.0);
 
-        Ok(background_image_id)
+        Ok(())
     }
 
     pub fn update_transformation(
         &mut self,
         canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
     ) {
-        let image_width = self.background_image.width() as f32;
-        let image_height = self.background_image.height() as f32;
+        // fit the whole canvas including padding, not only the background image
+        let (origin, extent) = self.canvas_bounds();
+        let image_width = extent.x - origin.x;
+        let image_height = extent.y - origin.y;
         let aspect_ratio = image_width / image_height;
 
         let canvas_width = canvas.width() as f32;
         let canvas_height = canvas.height() as f32;
+        let viewport = Vec2D::new(canvas_width, canvas_height);
 
         let prev_scale = self.scale_factor;
         let mut center_offset = Vec2D::zero();
@@ -550,29 +1519,25 @@ impl FemtoVgAreaMut {
                 self.scale_factor = self.zoom_scale;
 
                 if !self.is_reset {
-                    // calculate offset from pointer
-                    let pointer_offset = self.pointer_offset;
-                    let zoom_offset = Vec2D::new(
-                        (pointer_offset.x - self.offset.x) / prev_scale,
-                        (pointer_offset.y - self.offset.y) / prev_scale,
-                    );
-
-                    let calculated_offset = pointer_offset - zoom_offset * self.scale_factor;
-
-                    // update drag_offset
-                    center_offset = Vec2D::new(
-                        (canvas_width - image_width * self.scale_factor) / 2.0,
-                        (canvas_height - image_height * self.scale_factor) / 2.0,
+                    // keep the image point under the pointer in place
+                    center_offset =
+                        math::center_offset(viewport, origin, extent, self.scale_factor);
+                    self.drag_offset = math::zoom_drag_offset(
+                        self.pointer_offset,
+                        self.offset,
+                        prev_scale,
+                        self.scale_factor,
+                        center_offset,
                     );
-
-                    self.drag_offset = calculated_offset - center_offset;
                     self.store_last_offset();
                 }
             } else {
                 self.scale_factor = self.zoom_scale;
             }
         } else {
-            self.scale_factor = if canvas_width / aspect_ratio <= canvas_height {
+            self.scale_factor = if self.fit_width {
+                canvas_width / image_width
+            } else if canvas_width / aspect_ratio <= canvas_height {
                 canvas_width / aspect_ratio / image_height
             } else {
                 canvas_height * aspect_ratio / image_width
@@ -581,10 +1546,12 @@ impl FemtoVgAreaMut {
 
         // final offset
         if center_offset.is_zero() {
-            center_offset = Vec2D::new(
-                (canvas_width - image_width * self.scale_factor) / 2.0,
-                (canvas_height - image_height * self.scale_factor) / 2.0,
-            );
+            center_offset = math::center_offset(viewport, origin, extent, self.scale_factor);
+
+            // tall images fitted to the width start at the top
+            if self.fit_width && self.zoom_scale == 0.0 {
+                center_offset.y = -origin.y * self.scale_factor;
+            }
         }
 
         if self.is_reset {
@@ -597,17 +1564,20 @@ impl FemtoVgAreaMut {
         }
     }
 
+    /// Current zoom and pan, for `dpi_scale_factor` device pixels per logical pixel
+    fn view(&self, dpi_scale_factor: f32) -> ViewTransform {
+        ViewTransform {
+            scale: self.scale_factor,
+            offset: self.offset,
+            dpi_scale: dpi_scale_factor,
+        }
+    }
+
     pub fn abs_canvas_to_image_coordinates(&self, input: Vec2D, dpi_scale_factor: f32) -> Vec2D {
-        Vec2D::new(
-            (input.x * dpi_scale_factor - self.offset.x) / self.scale_factor,
-            (input.y * dpi_scale_factor - self.offset.y) / self.scale_factor,
-        )
+        self.view(dpi_scale_factor).abs_to_image(input)
     }
     pub fn rel_canvas_to_image_coordinates(&self, input: Vec2D, dpi_scale_factor: f32) -> Vec2D {
-        Vec2D::new(
-            input.x * dpi_scale_factor / self.scale_factor,
-            input.y * dpi_scale_factor / self.scale_factor,
-        )
+        self.view(dpi_scale_factor).rel_to_image(input)
     }
 
     pub fn set_zoom_scale(&mut self, factor: f32, abs: bool) {
@@ -617,6 +1587,7 @@ impl FemtoVgAreaMut {
 
         if abs {
             self.zoom_scale = factor;
+            self.fit_width = false;
         } else {
             if self.zoom_scale == 0.0 {
                 self.zoom_scale = self.scale_factor;
@@ -627,8 +1598,52 @@ impl FemtoVgAreaMut {
         }
     }
 
+    /// Fit the width of the canvas into the area instead of all of it, until the zoom is set
+    pub fn set_fit_width(&mut self) {
+        self.zoom_scale = 0.0;
+        self.fit_width = true;
+    }
+
     pub fn set_pointer_offset(&mut self, offset: Vec2D) {
         self.pointer_offset = offset;
+        self.pointer_inside = true;
+    }
+
+    /// The pointer left the area, the crosshair guides are hidden until it is back
+    pub fn set_pointer_left(&mut self) {
+        self.pointer_inside = false;
+    }
+
+    /// Whether the pointer is on the minimap shown in the last frame
+    pub fn pointer_on_minimap(&self) -> bool {
+        self.pointer_inside && self.minimap_contains(self.pointer_offset)
+    }
+
+    /// Pointer position in image coordinates, `None` while it is outside of the image
+    pub fn pointer_in_image(&self) -> Option<Vec2D> {
+        // the pointer offset is in device pixels already
+        let pos = self.abs_canvas_to_image_coordinates(self.pointer_offset, 1.0);
+        let size = Vec2D::new(
+            self.background_image.width() as f32,
+            self.background_image.height() as f32,
+        );
+        (pos.x >= 0.0 && pos.y >= 0.0 && pos.x < size.x && pos.y < size.y).then_some(pos)
+    }
+
+    /// Zoom of the view, 1.0 shows an image pixel on a device pixel
+    pub fn zoom(&self) -> f32 {
+        if self.zoom_scale != 0.0 {
+            self.zoom_scale
+        } else {
+            self.scale_factor
+        }
+    }
+
+    pub fn image_size(&self) -> (i32, i32) {
+        (
+            self.background_image.width(),
+            self.background_image.height(),
+        )
     }
 
     pub fn set_drag_offset(&mut self, offset: Vec2D) {
@@ -648,4 +1663,8 @@ impl FemtoVgAreaMut {
     pub fn set_is_drag(&mut self, is_drag: bool) {
         self.is_drag = is_drag;
     }
+
+    pub fn is_drag(&self) -> bool {
+        self.is_drag
+    }
 }
@@ -0,0 +1,135 @@
+use femtovg::{Color, Paint, Path, Transform2D};
+
+use crate::{canvas::ImageTiles, math::Vec2D};
+
+/// Overview of the whole image in the bottom right corner of the area while it doesn't fit,
+/// with the visible part framed. Tall images get a strip along the right edge instead. It is
+/// laid out in device pixels.
+pub struct Minimap {
+    pos: Vec2D,
+    size: Vec2D,
+    // image coordinates of the top left corner and scale from image to minimap
+    origin: Vec2D,
+    scale: f32,
+    // frame of the visible part, in device pixels
+    frame: (Vec2D, Vec2D),
+}
+
+impl Minimap {
+    /// images at least this many times taller than wide get a strip along the right edge
+    const TALL_IMAGE_RATIO: f32 = 3.0;
+    const MAX_EXTENT: f32 = 200.0;
+    const MARGIN: f32 = 8.0;
+    const MIN_WIDTH: f32 = 24.0;
+
+    /// Minimap for the image `bounds` on an area of `canvas_size`, `None` if the image fits
+    /// into the area at the current `zoom`
+    pub fn new(bounds: (Vec2D, Vec2D), canvas_size: Vec2D, zoom: f32) -> Option<Self> {
+        let (min, max) = bounds;
+        let image_size = max - min;
+        // allow for rounding, the image fits exactly when zoomed to fit
+        if image_size.x * zoom <= canvas_size.x + 1.0 && image_size.y * zoom <= canvas_size.y + 1.0
+        {
+            return None;
+        }
+
+        let max_size = if image_size.y >= image_size.x * Self::TALL_IMAGE_RATIO {
+            Vec2D::new(Self::MAX_EXTENT, canvas_size.y - 2.0 * Self::MARGIN)
+        } else {
+            Vec2D::new(Self::MAX_EXTENT, Self::MAX_EXTENT)
+        };
+        let scale = (max_size.x / image_size.x).min(max_size.y / image_size.y);
+        let size = Vec2D::new(
+            (image_size.x * scale).max(Self::MIN_WIDTH),
+            image_size.y * scale,
+        );
+        if size.x + 2.0 * Self::MARGIN > canvas_size.x
+            || size.y + 2.0 * Self::MARGIN > canvas_size.y
+        {
+            return None;
+        }
+
+        let margin = Vec2D::new(Self::MARGIN, Self::MARGIN);
+        Some(Self {
+            pos: canvas_size - margin - size,
+            size,
+            origin: min,
+            scale,
+            frame: (Vec2D::zero(), Vec2D::zero()),
+        })
+    }
+
+    /// Whether `pos` (in device pixels) is on the minimap
+    pub fn contains(&self, pos: Vec2D) -> bool {
+        pos.x >= self.pos.x
+            && pos.y >= self.pos.y
+            && pos.x <= self.pos.x + self.size.x
+            && pos.y <= self.pos.y + self.size.y
+    }
+
+    /// The image coordinates shown at `pos` (in device pixels)
+    pub fn to_image(&self, pos: Vec2D) -> Vec2D {
+        self.origin + (pos - self.pos) * (1.0 / self.scale)
+    }
+
+    fn to_minimap(&self, pos: Vec2D) -> Vec2D {
+        self.pos + (pos - self.origin) * self.scale
+    }
+
+    /// Frame the `visible` part of the image, in image coordinates
+    pub fn set_visible(&mut self, visible: (Vec2D, Vec2D)) {
+        let end = self.pos + self.size;
+        let min = self.to_minimap(visible.0);
+        let max = self.to_minimap(visible.1);
+        let min = Vec2D::new(min.x.max(self.pos.x), min.y.max(self.pos.y));
+        let max = Vec2D::new(max.x.min(end.x), max.y.min(end.y));
+        self.frame = (min, max);
+    }
+
+    /// Distance from `pos` (in device pixels) to the middle of the frame if `pos` is on it,
+    /// dragging the frame keeps it under the pointer where it got grabbed
+    pub fn grab_offset(&self, pos: Vec2D) -> Vec2D {
+        let (min, max) = self.frame;
+        if pos.x >= min.x && pos.y >= min.y && pos.x <= max.x && pos.y <= max.y {
+            (min + max) * 0.5 - pos
+        } else {
+            Vec2D::zero()
+        }
+    }
+
+    /// Draw the background image and the frame set by [`Self::set_visible`]. Expects the
+    /// canvas transform to be reset to device pixels.
+    pub fn draw(
+        &self,
+        canvas: &mut femtovg::Canvas<femtovg::renderer::OpenGl>,
+        image: &ImageTiles,
+    ) {
+        let mut background = Path::new();
+        background.rect(self.pos.x, self.pos.y, self.size.x, self.size.y);
+        canvas.fill_path(&background, &Paint::color(Color::rgbaf(0.0, 0.0, 0.0, 0.6)));
+
+        // the image is drawn in minimap coordinates, so it gets scaled along
+        canvas.save();
+        let mut transform = Transform2D::identity();
+        transform.scale(self.scale, self.scale);
+        let offset = self.pos - self.origin * self.scale;
+        transform.translate(offset.x, offset.y);
+        canvas.set_transform(&transform);
+
+        image.draw(canvas, 0.8);
+        canvas.restore();
+
+        let (min, max) = self.frame;
+        let mut frame = Path::new();
+        frame.rect(
+            min.x,
+            min.y,
+            (max.x - min.x).max(1.0),
+            (max.y - min.y).max(1.0),
+        );
+        canvas.stroke_path(
+            &frame,
+            &Paint::color(Color::rgbf(0.2, 0.6, 1.0)).with_line_width(2.0),
+        );
+    }
+}
@@ -1,21 +1,35 @@
+mod guides;
 mod imp;
+mod minimap;
 
 use std::{cell::RefCell, rc::Rc};
 
 use gdk_pixbuf::{glib::subclass::types::ObjectSubclassIsExt, Pixbuf};
 use gtk::glib;
+use image::GrayImage;
 use relm4::{
     gtk::{self, prelude::WidgetExt, subclass::prelude::GLAreaImpl},
     Sender,
 };
 
 use crate::{
-    configuration::Action,
+    configuration::{Action, InitialZoom, TextOrder},
+    hooks::Hook,
     math::Vec2D,
     sketch_board::SketchBoardInput,
-    tools::{CropTool, Drawable, Tool},
+    style::{Color, ColorBlindness},
+    tools::{Annotation, CropTool, Drawable, SelectEvent, Selection, Tool},
 };
 
+/// An image with its annotations and undo history, kept while another image is shown
+pub struct ImageState(imp::FemtoVgAreaMut);
+
+impl ImageState {
+    pub fn background_image(&self) -> &Pixbuf {
+        self.0.background_image()
+    }
+}
+
 glib::wrapper! {
     pub struct FemtoVGArea(ObjectSubclass<imp::FemtoVGArea>)
         @extends gtk::Widget, gtk::GLArea,
@@ -37,12 +51,284 @@ impl FemtoVGArea {
             .set_active_tool(active_tool);
     }
 
-    pub fn commit(&mut self, drawable: Box<dyn Drawable>) {
+    pub fn commit(&mut self, drawable: Box<dyn Drawable>) -> usize {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .commit(drawable)
+    }
+    /// Commit all of `drawables` in a single undo step
+    pub fn commit_group(&mut self, drawables: Vec<Box<dyn Drawable>>) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .commit_group(drawables)
+    }
+    /// Replace all drawables with `drawables` in a single undo step
+    pub fn replace_all(&mut self, drawables: Vec<Box<dyn Drawable>>) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .replace_all(drawables)
+    }
+    /// Outline the text regions found by auto-redact, as (min, max) in image coordinates
+    pub fn set_redact_proposals(&self, proposals: Vec<(Vec2D, Vec2D)>) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .set_redact_proposals(proposals)
+    }
+    pub fn has_redact_proposals(&self) -> bool {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .has_redact_proposals()
+    }
+    pub fn drop_redact_proposal_at(&self, pos: Vec2D) -> bool {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .drop_redact_proposal_at(pos)
+    }
+    pub fn take_redact_proposals(&self) -> Vec<(Vec2D, Vec2D)> {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .take_redact_proposals()
+    }
+    pub fn background_image(&self) -> Pixbuf {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .background_image()
+            .clone()
+    }
+    pub fn background_grayscale(&self) -> GrayImage {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .background_grayscale()
+    }
+    pub fn erase_at(&mut self, pos: Vec2D, tolerance: f32) -> bool {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .erase_at(pos, tolerance)
+    }
+    pub fn take_at(
+        &mut self,
+        pos: Vec2D,
+        tolerance: f32,
+        filter: impl Fn(&dyn Drawable) -> bool,
+    ) -> Option<Box<dyn Drawable>> {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .take_at(pos, tolerance, filter)
+    }
+    pub fn select(&mut self, event: SelectEvent) {
+        let pixel = self.rel_canvas_to_image_coordinates(Vec2D::new(1.0, 0.0)).x;
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .select(event, pixel)
+    }
+    pub fn set_snapping(&mut self, snapping: bool) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .set_snapping(snapping)
+    }
+    pub fn snap_point(&mut self, pos: Vec2D) -> Vec2D {
+        let pixel = self.rel_canvas_to_image_coordinates(Vec2D::new(1.0, 0.0)).x;
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .snap_point(pos, pixel)
+    }
+    pub fn clear_guides(&mut self) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .clear_guides()
+    }
+    pub fn has_selection(&self) -> bool {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .has_selection()
+    }
+    /// Move the selected drawables by `offset` (image coordinates)
+    pub fn nudge_selection(&mut self, offset: Vec2D) -> bool {
         self.imp()
             .inner()
             .as_mut()
             .expect("Did you call init before using FemtoVgArea?")
-            .commit(drawable);
+            .nudge_selection(offset)
+    }
+    pub fn duplicate_selection(&mut self) -> bool {
+        let offset = self.rel_canvas_to_image_coordinates(Vec2D::new(
+            Selection::DUPLICATE_OFFSET,
+            Selection::DUPLICATE_OFFSET,
+        ));
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .duplicate_selection(offset)
+    }
+    pub fn delete_selection(&mut self) -> bool {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .delete_selection()
+    }
+    /// Color of the background image at `pos` (image coordinates)
+    pub fn background_color_at(&self, pos: Vec2D) -> Option<Color> {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .background_color_at(pos)
+    }
+    pub fn annotations(&self) -> Vec<Annotation> {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .annotations()
+    }
+    pub fn used_colors(&self) -> Vec<Color> {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .used_colors()
+    }
+    pub fn preview_recolor(&mut self, recolor: Option<(Color, Color)>) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .preview_recolor(recolor)
+    }
+    pub fn apply_recolor(&mut self) -> bool {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .apply_recolor()
+    }
+    pub fn text_contrast(&self, index: usize) -> Option<f32> {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .text_contrast(index)
+    }
+    pub fn add_text_background(&mut self, index: usize) -> bool {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .add_text_background(index)
+    }
+    pub fn texts(&self) -> Vec<(usize, String)> {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .texts()
+    }
+    pub fn texts_in_order(&self, order: TextOrder) -> Vec<String> {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .texts_in_order(order)
+    }
+    pub fn focus_drawable(&self, index: usize) -> bool {
+        let focused = self
+            .imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .focus_drawable(index, self.viewport());
+        //trigger resize to recalculate zoom and offset
+        self.imp().resize(0, 0);
+        focused
+    }
+    pub fn cycle_color_simulation(&self) -> Option<ColorBlindness> {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .cycle_color_simulation()
+    }
+    pub fn toggle_minimap(&self) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .toggle_minimap();
+    }
+    /// Show the crosshair of the keyboard placement mode at `pos` (widget coordinates)
+    pub fn set_crosshair(&self, pos: Option<Vec2D>) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .set_crosshair(pos.map(|pos| pos * self.scale_factor() as f32));
+    }
+    /// Whether `pos` (widget coordinates) is on the minimap
+    pub fn minimap_contains(&self, pos: Vec2D) -> bool {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .minimap_contains(pos * self.scale_factor() as f32)
+    }
+    /// Distance from `pos` (widget coordinates) to the middle of the minimap frame if the frame
+    /// is grabbed there, zero otherwise
+    pub fn minimap_grab_offset(&self, pos: Vec2D) -> Vec2D {
+        let scale_factor = self.scale_factor() as f32;
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .minimap_grab_offset(pos * scale_factor)
+            * (1.0 / scale_factor)
+    }
+    /// Pan to the part of the image shown at `pos` (widget coordinates) on the minimap
+    pub fn minimap_jump(&self, pos: Vec2D) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .minimap_jump(pos * self.scale_factor() as f32, self.viewport());
+        //trigger resize to recalculate the offset
+        self.imp().resize(0, 0);
+    }
+    // size of the area in device pixels
+    fn viewport(&self) -> Vec2D {
+        Vec2D::new(self.width() as f32, self.height() as f32) * self.scale_factor() as f32
     }
     pub fn undo(&mut self) -> bool {
         self.imp()
@@ -58,9 +344,34 @@ impl FemtoVGArea {
             .expect("Did you call init before using FemtoVgArea?")
             .redo()
     }
+    /// Run `actions` on the image as exported, after the configured hooks for them ran,
+    /// see [`SketchBoardInput::RunHooks`]
+    pub fn run_actions(&self, actions: &[Action]) {
+        let hooks = Hook::for_actions(actions);
+        if hooks.is_empty() {
+            self.request_render(actions);
+        } else {
+            self.imp()
+                .send(SketchBoardInput::RunHooks(hooks, actions.to_vec()));
+        }
+    }
+    /// Render the image as exported on the next frame for `actions`, without running hooks
     pub fn request_render(&self, actions: &[Action]) {
         self.imp().request_render(actions);
     }
+    /// Render the image as exported on the next frame and send it as
+    /// [`SketchBoardInput::SnapshotResult`]
+    pub fn request_snapshot(&self) {
+        self.imp().request_snapshot();
+    }
+    /// Changes whenever the drawables or the canvas change
+    pub fn revision(&self) -> u64 {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .revision()
+    }
     pub fn reset(&mut self) -> bool {
         self.imp()
             .inner()
@@ -84,6 +395,27 @@ impl FemtoVGArea {
             .expect("Did you call init before using FemtoVgArea?")
             .rel_canvas_to_image_coordinates(input, self.scale_factor() as f32)
     }
+    pub fn pointer_in_image(&self) -> Option<Vec2D> {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .pointer_in_image()
+    }
+    pub fn zoom(&self) -> f32 {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .zoom()
+    }
+    pub fn image_size(&self) -> (i32, i32) {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .image_size()
+    }
     pub fn init(
         &mut self,
         sender: Sender<SketchBoardInput>,
@@ -95,6 +427,20 @@ impl FemtoVGArea {
             .init(sender, crop_tool, active_tool, background_image);
     }
 
+    /// Take out the image along with its annotations, e.g. to show another one. Restore it
+    /// with [`Self::restore_state`].
+    pub fn take_state(&self) -> Option<ImageState> {
+        self.imp().inner().take().map(ImageState)
+    }
+
+    pub fn restore_state(&self, state: ImageState, active_tool: Rc<RefCell<dyn Tool>>) {
+        let mut inner = state.0;
+        inner.set_active_tool(active_tool);
+        self.imp().inner().replace(inner);
+        //trigger resize to fit the restored image
+        self.imp().resize(0, 0);
+    }
+
     pub fn set_zoom_scale(&self, factor: f32) {
         self.imp()
             .inner()
@@ -105,6 +451,13 @@ impl FemtoVGArea {
         self.imp().resize(0, 0);
     }
 
+    /// Zoom by `factor` keeping the middle of the area in place, see [`Self::set_zoom_scale`]
+    /// for keeping the point under the pointer
+    pub fn zoom_centered(&self, factor: f32) {
+        self.set_pointer_offset(Vec2D::new(self.width() as f32, self.height() as f32) * 0.5);
+        self.set_zoom_scale(factor);
+    }
+
     pub fn set_pointer_offset(&self, offset: Vec2D) {
         self.imp()
             .inner()
@@ -113,6 +466,31 @@ impl FemtoVGArea {
             .set_pointer_offset(offset * self.scale_factor() as f32);
     }
 
+    pub fn set_pointer_left(&self) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .set_pointer_left();
+    }
+
+    pub fn pointer_on_minimap(&self) -> bool {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .pointer_on_minimap()
+    }
+
+    /// Whether the view is being panned by dragging
+    pub fn is_drag(&self) -> bool {
+        self.imp()
+            .inner()
+            .as_ref()
+            .expect("Did you call init before using FemtoVgArea?")
+            .is_drag()
+    }
+
     pub fn set_drag_offset(&self, offset: Vec2D) {
         self.imp()
             .inner()
@@ -139,6 +517,36 @@ impl FemtoVGArea {
             .set_is_drag(is_drag);
     }
 
+    pub fn replace_background_image(&self, image: Pixbuf) {
+        self.imp().replace_background_image(image);
+        //trigger resize to fit the new image
+        self.imp().resize(0, 0);
+    }
+
+    pub fn grow_canvas(&self, margin: f32) {
+        self.imp()
+            .inner()
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?")
+            .grow_canvas(margin);
+        //trigger resize to fit the new canvas
+        self.imp().resize(0, 0);
+    }
+
+    /// Set the zoom to use once the area gets laid out, does not trigger a resize on its own
+    pub fn set_initial_zoom(&self, zoom: InitialZoom) {
+        let mut inner = self.imp().inner();
+        let inner = inner
+            .as_mut()
+            .expect("Did you call init before using FemtoVgArea?");
+        match zoom {
+            InitialZoom::Fit => inner.set_zoom_scale(0., true),
+            InitialZoom::FitWidth => inner.set_fit_width(),
+            InitialZoom::Factor(f) => inner.set_zoom_scale(f, true),
+        }
+        inner.reset_drag_offset();
+    }
+
     pub fn reset_size(&self, factor: f32) {
         self.imp()
             .inner()
@@ -0,0 +1,217 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use relm4::gtk::{
+    gio::{self, DBusConnection, DBusSignalFlags},
+    glib::{variant::ObjectPath, Variant},
+    prelude::*,
+};
+
+use crate::configuration::APP_CONFIG;
+
+const PORTAL: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const INTERFACE: &str = "org.freedesktop.portal.GlobalShortcuts";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+thread_local! {
+    /// makes the tokens of the requests unique
+    static REQUESTS: Cell<u32> = const { Cell::new(0) };
+}
+
+/// What the global shortcuts of the daemon do
+#[derive(Debug, Clone, Copy)]
+pub enum Shortcut {
+    Capture,
+    Paste,
+}
+
+impl Shortcut {
+    const ALL: [Shortcut; 2] = [Shortcut::Capture, Shortcut::Paste];
+
+    fn id(self) -> &'static str {
+        match self {
+            Shortcut::Capture => "capture",
+            Shortcut::Paste => "paste",
+        }
+    }
+
+    /// Shown by the desktop when it asks to confirm the shortcut
+    fn description(self) -> &'static str {
+        match self {
+            Shortcut::Capture => "Capture a screenshot and annotate it",
+            Shortcut::Paste => "Annotate the image in the clipboard",
+        }
+    }
+
+    fn trigger(self) -> Option<String> {
+        let config = APP_CONFIG.read();
+        match self {
+            Shortcut::Capture => config.global_shortcuts().capture().cloned(),
+            Shortcut::Paste => config.global_shortcuts().paste().cloned(),
+        }
+    }
+}
+
+/// Bind the configured shortcuts through the GlobalShortcuts portal, `on_activated` is called
+/// whenever one is pressed. The desktop may ask to confirm them first.
+pub fn register(on_activated: impl Fn(Shortcut) + 'static) {
+    let shortcuts: Vec<_> = Shortcut::ALL
+        .into_iter()
+        .filter_map(|shortcut| Some((shortcut, shortcut.trigger()?)))
+        .collect();
+    if shortcuts.is_empty() {
+        return;
+    }
+    let connection = match gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>) {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Warning: cannot register the global shortcuts, {e}");
+            return;
+        }
+    };
+
+    connection.signal_subscribe(
+        Some(PORTAL),
+        Some(INTERFACE),
+        Some("Activated"),
+        Some(PORTAL_PATH),
+        None,
+        DBusSignalFlags::NONE,
+        move |_, _, _, _, _, parameters| {
+            let id = parameters.child_value(1);
+            if let Some(shortcut) = Shortcut::ALL
+                .into_iter()
+                .find(|shortcut| id.str() == Some(shortcut.id()))
+            {
+                on_activated(shortcut);
+            }
+        },
+    );
+
+    request(&connection, "CreateSession", |token| {
+        let options = HashMap::from([
+            ("handle_token", token.to_variant()),
+            ("session_handle_token", next_token().to_variant()),
+        ]);
+        (options,).to_variant()
+    })
+    .then(move |connection, results| {
+        // older portals reply with an object path, newer ones with a string
+        let Some(session) = results
+            .get("session_handle")
+            .and_then(|handle| handle.str().map(str::to_string))
+            .and_then(|handle| ObjectPath::try_from(handle).ok())
+        else {
+            eprintln!("Warning: cannot register the global shortcuts, the portal sent no session");
+            return;
+        };
+        let shortcuts: Vec<_> = shortcuts
+            .iter()
+            .map(|(shortcut, trigger)| {
+                let properties = HashMap::from([
+                    ("description", shortcut.description().to_variant()),
+                    ("preferred_trigger", trigger.to_variant()),
+                ]);
+                (shortcut.id(), properties)
+            })
+            .collect();
+        request(connection, "BindShortcuts", |token| {
+            let options = HashMap::from([("handle_token", token.to_variant())]);
+            (session, shortcuts, "", options).to_variant()
+        })
+        .then(|_, _| {});
+    });
+}
+
+/// A call to the portal that answers with a Response signal on its request object
+struct Request<'a> {
+    connection: &'a DBusConnection,
+    method: &'static str,
+    parameters: Variant,
+    path: String,
+}
+
+/// Prepare a call of `method`, `parameters` get the handle token that names the request
+fn request<'a>(
+    connection: &'a DBusConnection,
+    method: &'static str,
+    parameters: impl FnOnce(&str) -> Variant,
+) -> Request<'a> {
+    let token = next_token();
+    let sender = connection
+        .unique_name()
+        .map(|name| name.trim_start_matches(':').replace('.', "_"))
+        .unwrap_or_default();
+    Request {
+        connection,
+        method,
+        parameters: parameters(&token),
+        path: format!("{PORTAL_PATH}/request/{sender}/{token}"),
+    }
+}
+
+impl Request<'_> {
+    /// Make the call, `on_response` gets the results once the request succeeded
+    fn then(self, on_response: impl FnOnce(&DBusConnection, HashMap<String, Variant>) + 'static) {
+        // subscribe before calling, the response may come before the reply to the call
+        let subscription = Rc::new(RefCell::new(None));
+        let on_response = RefCell::new(Some(on_response));
+        let method = self.method;
+        let id = self.connection.signal_subscribe(
+            Some(PORTAL),
+            Some(REQUEST_INTERFACE),
+            Some("Response"),
+            Some(&self.path),
+            None,
+            DBusSignalFlags::NONE,
+            {
+                let subscription = subscription.clone();
+                move |connection, _, _, _, _, parameters| {
+                    if let Some(id) = subscription.take() {
+                        connection.signal_unsubscribe(id);
+                    }
+                    let Some(on_response) = on_response.take() else {
+                        return;
+                    };
+                    match parameters.get::<(u32, HashMap<String, Variant>)>() {
+                        Some((0, results)) => on_response(connection, results),
+                        Some((1, _)) => {
+                            eprintln!("Warning: the global shortcuts were cancelled ({method})")
+                        }
+                        _ => eprintln!("Warning: cannot register the global shortcuts ({method})"),
+                    }
+                }
+            },
+        );
+        subscription.replace(Some(id));
+
+        let connection = self.connection.clone();
+        self.connection.call(
+            Some(PORTAL),
+            PORTAL_PATH,
+            INTERFACE,
+            method,
+            Some(&self.parameters),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+            None::<&gio::Cancellable>,
+            move |result| {
+                if let Err(e) = result {
+                    eprintln!("Warning: cannot register the global shortcuts, {e}");
+                    if let Some(id) = subscription.take() {
+                        connection.signal_unsubscribe(id);
+                    }
+                }
+            },
+        );
+    }
+}
+
+fn next_token() -> String {
+    let n = REQUESTS.get() + 1;
+    REQUESTS.set(n);
+    format!("satty{}_{n}", std::process::id())
+}
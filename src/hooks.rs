@@ -0,0 +1,187 @@
+use std::{env, fs, path::PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::configuration::{Action, APP_CONFIG};
+use crate::tools::Annotation;
+
+/// operations a hook may run, hooks block the editor and a script stuck in a loop is stopped
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+/// Events of the editor the functions of the `[hooks]` script run on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hook {
+    Load,
+    BeforeSave,
+    Action,
+}
+
+impl Hook {
+    /// Name of the event as passed to the script
+    fn name(self) -> &'static str {
+        match self {
+            Self::Load => "load",
+            Self::BeforeSave => "before-save",
+            Self::Action => "action",
+        }
+    }
+
+    /// Function of the script handling the event
+    fn function(self) -> &'static str {
+        match self {
+            Self::Load => "on_load",
+            Self::BeforeSave => "on_before_save",
+            Self::Action => "on_action",
+        }
+    }
+
+    /// The hooks that run when an image is shown, none without a script
+    pub fn for_load() -> Vec<Self> {
+        if APP_CONFIG.read().hooks().script().is_none() {
+            return Vec::new();
+        }
+        vec![Self::Load]
+    }
+
+    /// The hooks that run before `actions`, in order. Redraws without actions run none and
+    /// only saving to a file runs `BeforeSave`.
+    pub fn for_actions(actions: &[Action]) -> Vec<Self> {
+        if actions.is_empty() || APP_CONFIG.read().hooks().script().is_none() {
+            return Vec::new();
+        }
+        let saves = actions.iter().any(|a| matches!(a, Action::SaveToFile(_)));
+        [Self::Action, Self::BeforeSave]
+            .into_iter()
+            .filter(|hook| *hook != Self::BeforeSave || saves)
+            .collect()
+    }
+}
+
+/// What the functions get as a map
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Event {
+    event: &'static str,
+    width: i32,
+    height: i32,
+    annotations: Vec<Annotation>,
+    /// names of the actions about to run
+    actions: Vec<&'static str>,
+    /// file the image is saved to, with `before-save`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+}
+
+impl Event {
+    pub fn new(
+        (width, height): (i32, i32),
+        annotations: Vec<Annotation>,
+        actions: &[Action],
+        filename: Option<String>,
+    ) -> Self {
+        Self {
+            event: "",
+            width,
+            height,
+            annotations,
+            actions: actions.iter().map(Action::name).collect(),
+            filename,
+        }
+    }
+}
+
+/// What the functions may return as a map, fields left out stay as they are
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Changes {
+    /// replace all annotations
+    pub annotations: Option<Vec<Annotation>>,
+    /// save to this file instead, only read from `before-save`
+    pub filename: Option<String>,
+}
+
+/// The Rhai script configured in `[hooks]`, compiled once
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+impl Script {
+    /// Compile the configured script, `None` without one or if it doesn't compile
+    pub fn load() -> Option<Self> {
+        let path = APP_CONFIG.read().hooks().script()?.clone();
+        Self::compile(&path)
+            .inspect_err(|e| eprintln!("Error in hooks script: {e:#}"))
+            .ok()
+    }
+
+    fn compile(path: &str) -> Result<Self> {
+        let path = match (path.strip_prefix("~/"), env::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest),
+            _ => PathBuf::from(path),
+        };
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("couldn't read '{}'", path.display()))?;
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine
+            .compile(source)
+            .with_context(|| format!("couldn't compile '{}'", path.display()))?;
+        Ok(Self { engine, ast })
+    }
+
+    fn defines(&self, hook: Hook) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|f| f.name == hook.function() && f.params.len() == 1)
+    }
+
+    /// Run the functions of `hooks` one after another, each gets the event as changed by the
+    /// ones before. Returns the changes of all of them, a failing function changes nothing.
+    pub fn run_all(&self, hooks: &[Hook], mut event: Event) -> Changes {
+        let mut changes = Changes::default();
+        for hook in hooks.iter().filter(|hook| self.defines(**hook)) {
+            event.event = hook.name();
+            match self.run(*hook, &event) {
+                Ok(Changes {
+                    annotations,
+                    filename,
+                }) => {
+                    // handing the annotations back unchanged isn't a change worth an undo step
+                    if let Some(annotations) = annotations.filter(|a| !same(a, &event.annotations))
+                    {
+                        event.annotations = annotations.clone();
+                        changes.annotations = Some(annotations);
+                    }
+                    if let Some(filename) = filename.filter(|_| *hook == Hook::BeforeSave) {
+                        event.filename = Some(filename.clone());
+                        changes.filename = Some(filename);
+                    }
+                }
+                Err(e) => eprintln!("Error in {} hook: {e:#}", hook.function()),
+            }
+        }
+        changes
+    }
+
+    fn run(&self, hook: Hook, event: &Event) -> Result<Changes> {
+        let event = rhai::serde::to_dynamic(event).map_err(|e| anyhow!("{e}"))?;
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, hook.function(), (event,))
+            .map_err(|e| anyhow!("{e}"))?;
+        if result.is_unit() {
+            return Ok(Changes::default());
+        }
+        // through JSON, scripts may write whole numbers where the annotations have floats
+        let changes: serde_json::Value =
+            rhai::serde::from_dynamic(&result).map_err(|e| anyhow!("{e}"))?;
+        serde_json::from_value(changes).context("invalid changes")
+    }
+}
+
+fn same(a: &[Annotation], b: &[Annotation]) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
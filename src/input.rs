@@ -0,0 +1,165 @@
+use std::io::{self, Read};
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Context, Result};
+use gdk_pixbuf::{
+    gio::{
+        self,
+        prelude::{FileExt, FileInputStreamExt, InputStreamExt},
+        Cancellable,
+    },
+    prelude::PixbufLoaderExt,
+    Pixbuf, PixbufLoader,
+};
+
+use crate::configuration::APP_CONFIG;
+
+const DOWNLOAD_CHUNK_SIZE: usize = 64 * 1024;
+const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+const TRUNCATED_FRAME: &str = "stdin ended in the middle of an image";
+
+/// Load an image from stdin ('-'), a local path or a URI. URIs of local files are decoded
+/// to paths, anything else is streamed through GIO so remote locations supported by GVfs
+/// (sftp://, smb://, ...) work as well. http(s) URLs are limited to the configured maximum
+/// download size.
+pub fn load_image(input: &str) -> Result<Pixbuf> {
+    if input == "-" {
+        return load_image_from_stdin();
+    }
+    if is_web_url(input) {
+        let buf = download(input, APP_CONFIG.read().max_download_size(), |_, _| ())?;
+        return image_from_bytes(&buf);
+    }
+
+    let file = gio::File::for_commandline_arg(input);
+    match file.path() {
+        Some(path) => Pixbuf::from_file(&path)
+            .with_context(|| format!("couldn't load image '{}'", path.display())),
+        None => {
+            let stream = file
+                .read(None::<&Cancellable>)
+                .with_context(|| format!("couldn't open '{}'", file.uri()))?;
+            Pixbuf::from_stream(&stream, None::<&Cancellable>)
+                .with_context(|| format!("couldn't load image from '{}'", file.uri()))
+        }
+    }
+}
+
+/// Whether `input` is an http(s) URL, these are downloaded with a size limit
+pub fn is_web_url(input: &str) -> bool {
+    let input = input.to_ascii_lowercase();
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+/// Download a file through GIO, which needs GVfs for http(s) support. `progress` gets
+/// called with the number of bytes received so far and the total size, if the server sent
+/// one. Downloads larger than `max_size` bytes are aborted.
+pub fn download(url: &str, max_size: u64, progress: impl Fn(u64, Option<u64>)) -> Result<Vec<u8>> {
+    let stream = gio::File::for_uri(url)
+        .read(None::<&Cancellable>)
+        .with_context(|| format!("couldn't open '{url}'"))?;
+
+    // the size is only known if the server announced it
+    let total = stream
+        .query_info("standard::size", None::<&Cancellable>)
+        .ok()
+        .map(|info| info.size())
+        .filter(|size| *size > 0)
+        .map(|size| size as u64);
+    if total.is_some_and(|total| total > max_size) {
+        bail!("'{url}' is larger than the maximum download size of {max_size} bytes");
+    }
+
+    let mut buf = Vec::new();
+    loop {
+        let chunk = stream
+            .read_bytes(DOWNLOAD_CHUNK_SIZE, None::<&Cancellable>)
+            .with_context(|| format!("couldn't download '{url}'"))?;
+        if chunk.is_empty() {
+            return Ok(buf);
+        }
+
+        buf.extend_from_slice(&chunk);
+        if buf.len() as u64 > max_size {
+            bail!("'{url}' is larger than the maximum download size of {max_size} bytes");
+        }
+        progress(buf.len() as u64, total);
+    }
+}
+
+/// Decode an image from its encoded file content
+pub fn image_from_bytes(buf: &[u8]) -> Result<Pixbuf> {
+    let pb_loader = PixbufLoader::new();
+    pb_loader.write(buf)?;
+    pb_loader.close()?;
+    pb_loader.pixbuf().context("Conversion to Pixbuf failed")
+}
+
+/// Run the shell `command` and return the image it writes to stdout, still encoded
+pub fn capture(command: &str) -> Result<Vec<u8>> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .with_context(|| format!("couldn't run '{command}'"))?;
+    if !output.status.success() {
+        bail!(
+            "'{command}' failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    if output.stdout.is_empty() {
+        bail!("'{command}' wrote no image");
+    }
+    Ok(output.stdout)
+}
+
+/// Read the next image of the PNG stream on stdin given with `--stdin-stream`, `None` once it
+/// ends. A PNG ends with its IEND chunk, so the images need no delimiter or length prefix.
+pub fn read_stdin_frame() -> Result<Option<Vec<u8>>> {
+    let mut stdin = io::stdin().lock();
+    let mut buf = vec![0; PNG_SIGNATURE.len()];
+    // the stream may only end between images
+    if stdin.read(&mut buf[..1])? == 0 {
+        return Ok(None);
+    }
+    stdin.read_exact(&mut buf[1..]).context(TRUNCATED_FRAME)?;
+    if buf[..] != PNG_SIGNATURE[..] {
+        bail!("--stdin-stream expects PNG images on stdin");
+    }
+    loop {
+        // a chunk is its length, its type, the data and a CRC
+        let start = buf.len();
+        buf.resize(start + 8, 0);
+        stdin
+            .read_exact(&mut buf[start..])
+            .context(TRUNCATED_FRAME)?;
+        let length = u32::from_be_bytes(buf[start..start + 4].try_into()?) as usize;
+        let is_end = &buf[start + 4..start + 8] == b"IEND";
+        let data = buf.len();
+        buf.resize(data + length + 4, 0);
+        stdin
+            .read_exact(&mut buf[data..])
+            .context(TRUNCATED_FRAME)?;
+        if is_end {
+            return Ok(Some(buf));
+        }
+    }
+}
+
+fn load_image_from_stdin() -> Result<Pixbuf> {
+    if APP_CONFIG.read().stdin_stream() {
+        // only the first image, the others are read while it is shown
+        return match read_stdin_frame()? {
+            Some(buf) => image_from_bytes(&buf),
+            None => Err(anyhow!("No image provided on stdin.")),
+        };
+    }
+    let mut buf = Vec::<u8>::new();
+    match io::stdin().lock().read_to_end(&mut buf) {
+        Ok(_) if !buf.is_empty() => image_from_bytes(&buf),
+        _ => Err(anyhow!(
+            "No input data provided. Use --daemon or provide a file/stdin."
+        )),
+    }
+}
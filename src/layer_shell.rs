@@ -0,0 +1,119 @@
+use std::ffi::{c_char, c_int};
+use std::sync::OnceLock;
+
+use relm4::gtk::{self, gdk, glib::translate::ToGlibPtr, prelude::*};
+
+use crate::configuration::APP_CONFIG;
+
+const LIBRARY: &str = "libgtk4-layer-shell.so.0";
+
+// GTK calls into the library for as long as the overlay exists, it must never be unloaded
+static OVERLAY_LIBRARY: OnceLock<libloading::Library> = OnceLock::new();
+
+// values of the enums of gtk4-layer-shell
+const LAYER_OVERLAY: c_int = 3;
+const EDGES: [c_int; 4] = [0, 1, 2, 3];
+const KEYBOARD_MODE_EXCLUSIVE: c_int = 1;
+
+type IsSupported = unsafe extern "C" fn() -> c_int;
+type InitForWindow = unsafe extern "C" fn(*mut gtk::ffi::GtkWindow);
+type SetLayer = unsafe extern "C" fn(*mut gtk::ffi::GtkWindow, c_int);
+type SetAnchor = unsafe extern "C" fn(*mut gtk::ffi::GtkWindow, c_int, c_int);
+type SetInt = unsafe extern "C" fn(*mut gtk::ffi::GtkWindow, c_int);
+type SetMonitor = unsafe extern "C" fn(*mut gtk::ffi::GtkWindow, *mut gdk::ffi::GdkMonitor);
+type SetNamespace = unsafe extern "C" fn(*mut gtk::ffi::GtkWindow, *const c_char);
+
+/// gtk4-layer-shell has to be loaded before libwayland-client, which is linked already. Run
+/// satty again with the library preloaded if the layer shell is enabled, must happen before
+/// anything is read from stdin.
+#[cfg(unix)]
+pub fn preload() {
+    use std::os::unix::process::CommandExt;
+
+    if !APP_CONFIG.read().layer_shell() {
+        return;
+    }
+    let preload = std::env::var("LD_PRELOAD").unwrap_or_default();
+    if preload.split([' ', ':']).any(|lib| lib.ends_with(LIBRARY)) {
+        return;
+    }
+    // loading it now only tells whether it is installed
+    if let Err(e) = unsafe { libloading::Library::new(LIBRARY) } {
+        eprintln!("Warning: cannot use the layer shell, {e}");
+        return;
+    }
+    let preload = if preload.is_empty() {
+        LIBRARY.to_string()
+    } else {
+        format!("{LIBRARY}:{preload}")
+    };
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+    let e = std::process::Command::new(exe)
+        .args(std::env::args_os().skip(1))
+        .env("LD_PRELOAD", preload)
+        .exec();
+    eprintln!("Warning: cannot use the layer shell, failed to restart with it: {e}");
+}
+
+#[cfg(not(unix))]
+pub fn preload() {}
+
+/// Turn `window` into an overlay covering the configured output, before it is shown. Returns
+/// whether it is one, the compositor may not support the layer shell.
+pub fn init(window: &gtk::Window) -> bool {
+    if !APP_CONFIG.read().layer_shell() {
+        return false;
+    }
+    match unsafe { init_overlay(window) } {
+        Ok(supported) => supported,
+        Err(e) => {
+            eprintln!("Warning: cannot use the layer shell, {e}");
+            false
+        }
+    }
+}
+
+unsafe fn init_overlay(window: &gtk::Window) -> Result<bool, libloading::Error> {
+    let library = match OVERLAY_LIBRARY.get() {
+        Some(library) => library,
+        None => {
+            let library = libloading::Library::new(LIBRARY)?;
+            OVERLAY_LIBRARY.get_or_init(|| library)
+        }
+    };
+    if library.get::<IsSupported>(b"gtk_layer_is_supported")?() == 0 {
+        eprintln!("Warning: the compositor doesn't support the layer shell");
+        return Ok(false);
+    }
+    let window_ptr: *mut gtk::ffi::GtkWindow = window.to_glib_none().0;
+    library.get::<InitForWindow>(b"gtk_layer_init_for_window")?(window_ptr);
+    library.get::<SetLayer>(b"gtk_layer_set_layer")?(window_ptr, LAYER_OVERLAY);
+    let set_anchor = library.get::<SetAnchor>(b"gtk_layer_set_anchor")?;
+    for edge in EDGES {
+        set_anchor(window_ptr, edge, 1);
+    }
+    // cover panels too instead of being placed next to them
+    library.get::<SetInt>(b"gtk_layer_set_exclusive_zone")?(window_ptr, -1);
+    library.get::<SetInt>(b"gtk_layer_set_keyboard_mode")?(window_ptr, KEYBOARD_MODE_EXCLUSIVE);
+    library.get::<SetNamespace>(b"gtk_layer_set_namespace")?(window_ptr, c"satty".as_ptr());
+
+    if let Some(output) = APP_CONFIG.read().layer_shell_output() {
+        match find_monitor(output) {
+            Some(monitor) => library.get::<SetMonitor>(b"gtk_layer_set_monitor")?(
+                window_ptr,
+                monitor.to_glib_none().0,
+            ),
+            None => eprintln!("Warning: output {output} not found"),
+        }
+    }
+    Ok(true)
+}
+
+fn find_monitor(connector: &str) -> Option<gdk::Monitor> {
+    let monitors = gdk::Display::default()?.monitors();
+    (0..monitors.n_items())
+        .filter_map(|i| monitors.item(i)?.downcast::<gdk::Monitor>().ok())
+        .find(|monitor| monitor.connector().as_deref() == Some(connector))
+}
@@ -1,42 +1,73 @@
-use std::io::{self, Read, Write};
+use std::io::{Read, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
 use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::LazyLock;
-use std::{fs, ptr, thread};
+use std::{fs, thread};
 use std::time::Duration;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use configuration::{Configuration, APP_CONFIG};
-use gdk_pixbuf::gio::ApplicationFlags;
-use gdk_pixbuf::{Pixbuf, PixbufLoader, Colorspace};
+use configuration::{Configuration, LatencyMode, APP_CONFIG};
+use gdk_pixbuf::gio::{self, ApplicationFlags};
+use gdk_pixbuf::{Pixbuf, Colorspace};
 use gdk_pixbuf::glib::Bytes;
 use gtk::prelude::*;
 
 use relm4::gtk::gdk::Rectangle;
 
 use relm4::{
-    gtk::{self, gdk::DisplayManager, CssProvider, Window},
+    gtk::{self, gdk::DisplayManager, Window},
     Component, ComponentController, ComponentParts, ComponentSender, Controller, RelmApp,
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{bail, Context, Result};
 
 use sketch_board::{SketchBoardOutput, SketchBoardInput};
+use ui::status_bar::{Status, StatusBar, StatusBarInput};
+use ui::toast::{Toast, ToastInput, ToastOutput};
 use ui::toolbars::{StyleToolbar, StyleToolbarInput, ToolsToolbar, ToolsToolbarInput};
-use xdg::BaseDirectories;
 
+mod bench;
 mod configuration;
+mod desktop_files;
+mod diff;
 mod femtovg_area;
+mod global_shortcuts;
+mod hooks;
 mod icons;
-mod ime;
-mod math;
+mod input;
+mod layer_shell;
 mod notification;
+mod recovery;
+mod render;
+mod secrets;
 mod sketch_board;
-mod style;
-mod tools;
+mod snapshots;
+mod stats;
+mod stitch;
+#[cfg(feature = "terminal")]
+mod terminal;
+mod text_regions;
+mod theme;
+mod tool_styles;
+mod tray;
 mod ui;
+mod update;
+mod upload;
 
+// the drawing itself is the core crate, the modules above reach it through `crate::`
+use satty_core::{annotation_file, canvas, headless, ime, math, style, tools};
+
+use crate::diff::Diff;
+use crate::global_shortcuts::Shortcut;
+use crate::notification::log_result;
 use crate::sketch_board::SketchBoard;
+use crate::style::{Color, ToolStyle};
 use crate::tools::Tools;
+use crate::tray::TrayAction;
+use crate::ui::image_chooser;
+
+pub const APP_ID: &str = "com.gabm.satty";
 
 pub static START_TIME: LazyLock<chrono::DateTime<chrono::Local>> =
     LazyLock::new(chrono::Local::now);
@@ -48,6 +79,25 @@ struct RawImageData {
     n_channels: i32,
     rowstride: i32,
     data: Vec<u8>,
+    /// working directory of the client that sent the image, for `{cwd}` in output paths
+    working_directory: Option<PathBuf>,
+}
+
+/// Version of the messages exchanged over the daemon socket, raised on incompatible changes
+const PROTOCOL_VERSION: u32 = 1;
+
+/// Sent instead of the width of an image to ask the daemon for its info
+const PING: i32 = -1;
+
+/// Time the compositor gets to remove the hidden window before the screenshot is taken again
+const RECAPTURE_DELAY: Duration = Duration::from_millis(300);
+
+/// Images currently annotated by this process
+static OPEN_SESSIONS: AtomicUsize = AtomicUsize::new(0);
+
+enum DaemonMessage {
+    Image(RawImageData),
+    Ping,
 }
 
 fn get_socket_path() -> PathBuf {
@@ -77,15 +127,49 @@ fn try_send_to_daemon(image: &Pixbuf) -> bool {
     if stream.write_all(&(pixels.len() as u64).to_be_bytes()).is_err() { return false; }
     if stream.write_all(pixels).is_err() { return false; }
 
+    // trailing and optional, older daemons stop reading after the pixels
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let cwd = cwd.as_os_str().as_bytes();
+    if stream.write_all(&(cwd.len() as u32).to_be_bytes()).is_err() { return false; }
+    if stream.write_all(cwd).is_err() { return false; }
+
     true
 }
 
-fn read_raw_image_from_stream(mut stream: UnixStream) -> Option<RawImageData> {
-    let mut u32_buf = [0u8; 4];
-    let mut u64_buf = [0u8; 8];
+/// Ask the running daemon for its info, `None` if there is no daemon
+fn ping_daemon() -> Option<String> {
+    let mut stream = UnixStream::connect(get_socket_path()).ok()?;
+    // daemons predating the ping wait for the rest of an image instead of answering
+    stream.set_read_timeout(Some(Duration::from_secs(5))).ok()?;
+    stream.write_all(&PING.to_be_bytes()).ok()?;
+    let mut info = String::new();
+    stream.read_to_string(&mut info).ok()?;
+    Some(info)
+}
 
+/// Version, protocol version, GL renderer and open sessions as JSON
+fn daemon_info() -> String {
+    serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "protocol": PROTOCOL_VERSION,
+        "renderer": canvas::GL_RENDERER.get(),
+        "sessions": OPEN_SESSIONS.load(Ordering::Relaxed),
+    })
+    .to_string()
+}
+
+fn read_message_from_stream(stream: &mut UnixStream) -> Option<DaemonMessage> {
+    let mut u32_buf = [0u8; 4];
     stream.read_exact(&mut u32_buf).ok()?;
-    let width = i32::from_be_bytes(u32_buf);
+    match i32::from_be_bytes(u32_buf) {
+        PING => Some(DaemonMessage::Ping),
+        width => read_raw_image_from_stream(stream, width).map(DaemonMessage::Image),
+    }
+}
+
+fn read_raw_image_from_stream(stream: &mut UnixStream, width: i32) -> Option<RawImageData> {
+    let mut u32_buf = [0u8; 4];
+    let mut u64_buf = [0u8; 8];
 
     stream.read_exact(&mut u32_buf).ok()?;
     let height = i32::from_be_bytes(u32_buf);
@@ -108,17 +192,26 @@ fn read_raw_image_from_stream(mut stream: UnixStream) -> Option<RawImageData> {
         n_channels,
         rowstride,
         data: buffer,
+        working_directory: read_working_directory(stream),
     })
 }
 
+/// The working directory following the pixels, `None` from clients that don't send it
+fn read_working_directory(stream: &mut UnixStream) -> Option<PathBuf> {
+    let mut u32_buf = [0u8; 4];
+    stream.read_exact(&mut u32_buf).ok()?;
+    let mut buffer = vec![0u8; u32::from_be_bytes(u32_buf) as usize];
+    stream.read_exact(&mut buffer).ok()?;
+    let path = PathBuf::from(std::ffi::OsString::from_vec(buffer));
+    path.is_absolute().then_some(path)
+}
+
 macro_rules! generate_profile_output {
     ($e: expr) => {
+        let elapsed = (chrono::Local::now() - *START_TIME).num_milliseconds();
+        bench::record_startup_phase($e, elapsed);
         if (APP_CONFIG.read().profile_startup()) {
-            eprintln!(
-                "{:5} ms time elapsed: {}",
-                (chrono::Local::now() - *START_TIME).num_milliseconds(),
-                $e
-            );
+            eprintln!("{:5} ms time elapsed: {}", elapsed, $e);
         }
     };
 }
@@ -128,7 +221,29 @@ struct App {
     sketch_board: Controller<SketchBoard>,
     tools_toolbar: Controller<ToolsToolbar>,
     style_toolbar: Controller<StyleToolbar>,
+    toast: Controller<Toast>,
+    status_bar: Controller<StatusBar>,
     is_daemon: bool,
+    /// the window is a layer shell overlay, the compositor sizes it
+    layer_shell: bool,
+    /// index of the shown image among the inputs given on the command line
+    current_input: usize,
+    /// inputs shown so far, the sketch board keeps their annotations
+    shown_inputs: Vec<bool>,
+    /// images streamed to stdin after the first, taken once shown
+    stdin_frames: Vec<Vec<u8>>,
+    /// release notes to open when the action of the toast is clicked
+    release_url: Option<String>,
+}
+
+enum AppInit {
+    /// Wait for images sent by other instances
+    Daemon,
+    Image(Pixbuf),
+    /// Compare two images side by side
+    Diff(Diff),
+    /// Download the image from an http(s) URL while the window is already shown
+    Download(String),
 }
 
 #[derive(Debug)]
@@ -137,8 +252,29 @@ enum AppInput {
     SetToolbarsDisplay(bool),
     ToggleToolbarsDisplay,
     ToolSwitchShortcut(Tools),
+    ToolIndicator(Tools),
     ColorSwitchShortcut(u64),
+    ToolStyleChanged(ToolStyle),
+    PresetApplied(usize),
+    Flash(String),
+    ActionToast { text: String, label: String },
+    ToastAction,
+    ColorPicked(Option<Color>),
+    Status(Status),
+    ProgressStarted(String),
+    ProgressEnded,
+    Recapture,
+    CaptureCountdown(u32),
+    SwitchImage(isize),
+    StdinFrame(Vec<u8>),
+    Tray(TrayAction),
+    OpenImage(Result<Vec<u8>>),
+    GlobalShortcut(Shortcut),
+    RecaptureFinished(Result<Vec<u8>>),
     LoadImage(RawImageData),
+    DownloadProgress(u64, Option<u64>),
+    DownloadFinished(Result<Vec<u8>>),
+    UpdateAvailable(update::Release),
     Exit,
 }
 
@@ -158,6 +294,9 @@ impl App {
     }
 
     fn resize_window_initial(&self, root: &Window, sender: ComponentSender<Self>) {
+        if self.layer_shell {
+            return;
+        }
         let monitor_size = match Self::get_monitor_size(root) {
             Some(s) => s,
             None => {
@@ -203,40 +342,174 @@ impl App {
         });
     }
 
-    fn apply_style() {
-        let css_provider = CssProvider::new();
-        css_provider.load_from_data(
-            "
-            .root {
-                min-width: 50rem;
-                min-height: 10rem;
-            }
-            .toolbar {color: #f9f9f9 ; background: #00000099;}
-            .toast {
-                color: #f9f9f9;
-                background: #00000099;
-                border-radius: 6px;
-                margin-top: 50px;
+    fn start_download(url: String, sender: ComponentSender<Self>) {
+        let max_size = APP_CONFIG.read().max_download_size();
+        sender.input(AppInput::ProgressStarted("Downloading image…".to_string()));
+        thread::spawn(move || {
+            let progress_sender = sender.clone();
+            let result = input::download(&url, max_size, |received, total| {
+                progress_sender.input(AppInput::DownloadProgress(received, total));
+            });
+            sender.input(AppInput::DownloadFinished(result));
+        });
+    }
+
+    /// Run the `recapture-command` once the hidden window is gone from the screen and the
+    /// `capture-delay` is counted down, `finished` makes the message with its image
+    fn start_capture(sender: ComponentSender<Self>, finished: fn(Result<Vec<u8>>) -> AppInput) {
+        let Some(command) = APP_CONFIG.read().recapture_command().cloned() else {
+            log_result(
+                "Error: set recapture-command to capture a screenshot",
+                !APP_CONFIG.read().disable_notifications(),
+            );
+            return;
+        };
+        let delay = APP_CONFIG.read().capture_delay();
+        thread::spawn(move || {
+            if delay > 0 {
+                for seconds in (1..=delay).rev() {
+                    sender.input(AppInput::CaptureCountdown(seconds));
+                    thread::sleep(Duration::from_secs(1));
+                }
+                sender.input(AppInput::CaptureCountdown(0));
             }
-            .toolbar-bottom {border-radius: 6px 6px 0px 0px;}
-            .toolbar-top {border-radius: 0px 0px 6px 6px;}
-            ",
-        );
-        if let Some(overrides) = read_css_overrides() {
-            css_provider.load_from_data(&overrides);
+            thread::sleep(RECAPTURE_DELAY);
+            sender.input(finished(input::capture(&command)));
+        });
+    }
+
+    /// Look for a new release in the background if enabled and not done today
+    fn check_for_update(sender: &ComponentSender<Self>) {
+        if !APP_CONFIG.read().check_for_updates() || !update::due() {
+            return;
+        }
+        let sender = sender.clone();
+        thread::spawn(move || match update::check() {
+            Ok(Some(release)) => sender.input(AppInput::UpdateAvailable(release)),
+            Ok(None) => {}
+            Err(e) => eprintln!("Error: {e:#}"),
+        });
+    }
+
+    /// Show the input `step` places after the current one, skipping the ones that fail to
+    /// load. Returns whether there was one.
+    fn switch_input(&mut self, step: isize) -> bool {
+        let inputs = APP_CONFIG.read().inputs().to_vec();
+        let mut index = self.current_input;
+        loop {
+            index = match index.checked_add_signed(step) {
+                Some(index) if index < self.shown_inputs.len() => index,
+                _ => return false,
+            };
+            let image = if self.shown_inputs[index] {
+                None
+            } else {
+                let result = match inputs.get(index) {
+                    Some(input) => input::load_image(input),
+                    None => input::image_from_bytes(&std::mem::take(
+                        &mut self.stdin_frames[index - inputs.len()],
+                    )),
+                };
+                match result {
+                    Ok(image) => Some(image),
+                    Err(e) => {
+                        eprintln!("Error: {e:#}");
+                        continue;
+                    }
+                }
+            };
+            self.shown_inputs[index] = true;
+            self.current_input = index;
+            self.sketch_board
+                .emit(SketchBoardInput::SwitchImage { index, image });
+            let name = match inputs.get(index).filter(|input| *input != "-") {
+                Some(input) => Path::new(input)
+                    .file_name()
+                    .map_or(input.clone(), |name| name.to_string_lossy().into_owned()),
+                None => format!("Image {}", index + 1),
+            };
+            self.toast.emit(ToastInput::Flash(format!(
+                "{name} ({}/{})",
+                index + 1,
+                self.shown_inputs.len()
+            )));
+            return true;
         }
-        match DisplayManager::get().default_display() {
-            Some(display) => {
-                gtk::style_context_add_provider_for_display(&display, &css_provider, 1)
+    }
+
+    /// Ask for an image to annotate, for the tray icon of the daemon
+    fn open_file(sender: &ComponentSender<Self>) {
+        let sender = sender.clone();
+        image_chooser::show(None, "Open Image", "Open", move |path| {
+            let buf =
+                fs::read(&path).with_context(|| format!("couldn't read '{}'", path.display()));
+            sender.input(AppInput::OpenImage(buf));
+        });
+    }
+
+    /// Annotate the image in the clipboard, for the tray icon of the daemon
+    fn paste(sender: &ComponentSender<Self>) {
+        let Some(display) = DisplayManager::get().default_display() else {
+            return;
+        };
+        let sender = sender.clone();
+        display
+            .clipboard()
+            .read_texture_async(None::<&gio::Cancellable>, move |result| {
+                let buf = result
+                    .map_err(anyhow::Error::from)
+                    .and_then(|texture| texture.context("the clipboard holds no image"))
+                    .map(|texture| texture.save_to_png_bytes().to_vec());
+                sender.input(AppInput::OpenImage(buf));
+            });
+    }
+
+    /// Read the images streamed to stdin after the first while the window is shown
+    fn read_stdin_frames(sender: &ComponentSender<Self>) {
+        let sender = sender.clone();
+        thread::spawn(move || loop {
+            match input::read_stdin_frame() {
+                Ok(Some(frame)) => sender.input(AppInput::StdinFrame(frame)),
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("Error: {e:#}");
+                    break;
+                }
             }
-            None => println!("Cannot apply style"),
+        });
+    }
+
+    fn show_image(&mut self, image: Pixbuf, root: &Window, sender: ComponentSender<Self>) {
+        self.image_dimensions = (image.width(), image.height());
+        OPEN_SESSIONS.store(1, Ordering::Relaxed);
+        tray::set_session_open(true);
+        // the daemon runs for days, check when it is used rather than only at start
+        Self::check_for_update(&sender);
+        self.sketch_board
+            .sender()
+            .emit(SketchBoardInput::LoadImage(image));
+
+        root.set_visible(true);
+        root.present();
+        self.resize_window_initial(root, sender);
+    }
+
+    /// GTK follows the desktop's reduce animations preference by itself, only an explicit
+    /// setting needs to be applied. Satty's own animations check [`theme::reduce_motion`].
+    fn apply_motion_preference() {
+        let Some(reduce_motion) = APP_CONFIG.read().reduce_motion() else {
+            return;
+        };
+        match gtk::Settings::default() {
+            Some(settings) => settings.set_gtk_enable_animations(!reduce_motion),
+            None => println!("Cannot apply motion preference"),
         }
     }
 }
 
 #[relm4::component]
 impl Component for App {
-    type Init = Option<Pixbuf>; 
+    type Init = AppInit;
     type Input = AppInput;
     type Output = ();
     type CommandOutput = AppCommandOutput;
@@ -245,15 +518,18 @@ impl Component for App {
         main_window = gtk::Window {
             set_decorated: !APP_CONFIG.read().no_window_decoration(),
             set_default_size: (500, 500),
+            set_icon_name: Some("satty"),
             add_css_class: "root",
             
             // ИСПРАВЛЕНИЕ 1: используем set_visible вместо visible
             set_visible: false,
 
-            // ИСПРАВЛЕНИЕ 2: захватываем [sender], так как он существует в области видимости.
-            // Внутри замыкания он не используется, но это стандартный способ захвата в макросах Relm4.
-            connect_close_request[sender] => move |window| {
+            connect_close_request => move |window| {
+                // the session ended normally, nothing to recover
+                recovery::discard();
                 if model.is_daemon {
+                    OPEN_SESSIONS.store(0, Ordering::Relaxed);
+                    tray::set_session_open(false);
                     window.set_visible(false);
                     glib::Propagation::Stop
                 } else {
@@ -269,6 +545,8 @@ impl Component for App {
             gtk::Overlay {
                 add_overlay = model.tools_toolbar.widget(),
                 add_overlay = model.style_toolbar.widget(),
+                add_overlay = model.toast.widget(),
+                add_overlay = model.status_bar.widget(),
                 model.sketch_board.widget(),
             }
         }
@@ -277,12 +555,13 @@ impl Component for App {
     fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>, root: &Self::Root) {
         match message {
             AppInput::Exit => {
-                // Закрываем окно. Поведение определится в connect_close_request
-                root.close();
+                // more images were given on the command line, continue with the next one
+                if !self.switch_input(1) {
+                    // Закрываем окно. Поведение определится в connect_close_request
+                    root.close();
+                }
             }
             AppInput::LoadImage(raw_img) => {
-                self.image_dimensions = (raw_img.width, raw_img.height);
-                
                 let bytes = Bytes::from(&raw_img.data);
                 let pixbuf = Pixbuf::from_bytes(
                     &bytes,
@@ -294,11 +573,28 @@ impl Component for App {
                     raw_img.rowstride
                 );
 
-                self.sketch_board.sender().emit(SketchBoardInput::LoadImage(pixbuf));
-                
-                root.set_visible(true); 
-                root.present();
-                self.resize_window_initial(root, sender);
+                self.sketch_board.emit(SketchBoardInput::WorkingDirectory(
+                    raw_img.working_directory,
+                ));
+                self.show_image(pixbuf, root, sender);
+            }
+            AppInput::DownloadProgress(received, total) => {
+                let text = match total {
+                    Some(total) => format!("Downloading image… {}%", received * 100 / total),
+                    None => format!("Downloading image… {} KiB", received / 1024),
+                };
+                let fraction = total.map(|total| received as f64 / total as f64);
+                self.toast.emit(ToastInput::Progress { text, fraction });
+            }
+            AppInput::DownloadFinished(result) => {
+                self.toast.emit(ToastInput::ProgressEnded);
+                match result.and_then(|buf| input::image_from_bytes(&buf)) {
+                    Ok(image) => self.show_image(image, root, sender),
+                    Err(e) => {
+                        eprintln!("Error: {e:#}");
+                        sender.input(AppInput::Exit);
+                    }
+                }
             }
             AppInput::Realized => self.resize_window_initial(root, sender),
             AppInput::SetToolbarsDisplay(visible) => {
@@ -322,6 +618,86 @@ impl Component for App {
                     .sender()
                     .emit(ToolsToolbarInput::SwitchSelectedTool(tool));
             }
+            AppInput::ToolIndicator(tool) => {
+                self.toast
+                    .emit(ToastInput::Flash(tool.display_name().to_string()));
+            }
+            AppInput::Flash(message) => self.toast.emit(ToastInput::Flash(message)),
+            AppInput::ActionToast { text, label } => {
+                self.release_url = None;
+                self.toast.emit(ToastInput::Action { text, label })
+            }
+            AppInput::ToastAction => match self.release_url.take() {
+                Some(url) => gtk::show_uri(Some(root), &url, gtk::gdk::CURRENT_TIME),
+                None => self.sketch_board.emit(SketchBoardInput::ToastAction),
+            },
+            AppInput::ColorPicked(color) => self
+                .style_toolbar
+                .emit(StyleToolbarInput::ColorPicked(color)),
+            AppInput::Status(status) => self.status_bar.emit(StatusBarInput::Update(status)),
+            AppInput::ProgressStarted(text) => self.toast.emit(ToastInput::ProgressStarted(text)),
+            AppInput::ProgressEnded => self.toast.emit(ToastInput::ProgressEnded),
+            AppInput::Recapture => {
+                root.set_visible(false);
+                Self::start_capture(sender, AppInput::RecaptureFinished);
+            }
+            AppInput::CaptureCountdown(seconds) => notification::show_countdown(seconds),
+            AppInput::SwitchImage(step) => {
+                if !self.switch_input(step) {
+                    let end = if step > 0 { "last" } else { "first" };
+                    self.toast
+                        .emit(ToastInput::Flash(format!("This is the {end} image")));
+                }
+            }
+            AppInput::StdinFrame(frame) => {
+                self.stdin_frames.push(frame);
+                self.shown_inputs.push(false);
+                self.toast.emit(ToastInput::Flash(format!(
+                    "Received image {}",
+                    self.shown_inputs.len()
+                )));
+            }
+            AppInput::Tray(TrayAction::OpenFile) => Self::open_file(&sender),
+            AppInput::Tray(TrayAction::Paste) => Self::paste(&sender),
+            AppInput::Tray(TrayAction::Quit) => relm4::main_application().quit(),
+            AppInput::GlobalShortcut(Shortcut::Capture) => {
+                Self::start_capture(sender, AppInput::OpenImage);
+            }
+            AppInput::GlobalShortcut(Shortcut::Paste) => Self::paste(&sender),
+            AppInput::OpenImage(buf) => match buf.and_then(|buf| input::image_from_bytes(&buf)) {
+                Ok(image) => {
+                    self.sketch_board
+                        .emit(SketchBoardInput::WorkingDirectory(None));
+                    self.show_image(image, root, sender);
+                }
+                Err(e) => log_result(
+                    &format!("Error: couldn't open the image: {e:#}"),
+                    !APP_CONFIG.read().disable_notifications(),
+                ),
+            },
+            AppInput::RecaptureFinished(result) => {
+                root.set_visible(true);
+                root.present();
+                match result.and_then(|buf| input::image_from_bytes(&buf)) {
+                    Ok(image) => {
+                        self.image_dimensions = (image.width(), image.height());
+                        self.sketch_board
+                            .emit(SketchBoardInput::ReplaceImage(image));
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e:#}");
+                        self.toast
+                            .emit(ToastInput::Flash(format!("Capture failed: {e}")));
+                    }
+                }
+            }
+            AppInput::UpdateAvailable(release) => {
+                self.toast.emit(ToastInput::Action {
+                    text: format!("Satty {} is available", release.version),
+                    label: "Release notes".to_string(),
+                });
+                self.release_url = Some(release.url);
+            }
             AppInput::ColorSwitchShortcut(index) => {
                 self.style_toolbar
                     .sender()
@@ -329,6 +705,16 @@ impl Component for App {
                         ui::toolbars::ColorButtons::Palette(index),
                     ));
             }
+            AppInput::ToolStyleChanged(tool_style) => {
+                self.style_toolbar
+                    .sender()
+                    .emit(StyleToolbarInput::ShowToolStyle(tool_style));
+            }
+            AppInput::PresetApplied(index) => {
+                self.style_toolbar
+                    .sender()
+                    .emit(StyleToolbarInput::ShowPreset(index));
+            }
         }
     }
 
@@ -344,13 +730,41 @@ impl Component for App {
     }
 
     fn init(
-        image_opt: Self::Init,
+        init: Self::Init,
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        Self::apply_style();
+        theme::apply();
+        notification::register_actions();
+        Self::apply_motion_preference();
+
+        let is_daemon = matches!(init, AppInit::Daemon);
+        let mut diff = None;
+        let image_opt = match init {
+            AppInit::Image(image) => Some(image),
+            AppInit::Diff(images) => {
+                let image = images.image(false).clone();
+                diff = Some(images);
+                Some(image)
+            }
+            AppInit::Download(url) => {
+                Self::start_download(url, sender.clone());
+                None
+            }
+            AppInit::Daemon => None,
+        };
 
-        let is_daemon = image_opt.is_none();
+        if is_daemon && APP_CONFIG.read().tray_icon() {
+            let sender = sender.clone();
+            tray::start(move |action| sender.input(AppInput::Tray(action)));
+        }
+
+        if is_daemon {
+            let sender = sender.clone();
+            global_shortcuts::register(move |shortcut| {
+                sender.input(AppInput::GlobalShortcut(shortcut))
+            });
+        }
 
         if is_daemon {
             let sender = sender.clone();
@@ -361,11 +775,15 @@ impl Component for App {
                 }
                 
                 if let Ok(listener) = UnixListener::bind(&socket_path) {
-                    for stream in listener.incoming() {
-                        if let Ok(stream) = stream {
-                            if let Some(raw_img) = read_raw_image_from_stream(stream) {
+                    for mut stream in listener.incoming().flatten() {
+                        match read_message_from_stream(&mut stream) {
+                            Some(DaemonMessage::Image(raw_img)) => {
                                 sender.input(AppInput::LoadImage(raw_img));
                             }
+                            Some(DaemonMessage::Ping) => {
+                                let _ = stream.write_all(daemon_info().as_bytes());
+                            }
+                            None => {}
                         }
                     }
                 } else {
@@ -374,6 +792,10 @@ impl Component for App {
             });
         }
 
+        if !is_daemon {
+            Self::check_for_update(&sender);
+        }
+
         let image_dimensions = if let Some(ref img) = image_opt {
              (img.width(), img.height())
         } else {
@@ -389,12 +811,31 @@ impl Component for App {
                     SketchBoardOutput::ToolSwitchShortcut(tool) => {
                         AppInput::ToolSwitchShortcut(tool)
                     }
+                    SketchBoardOutput::ToolIndicator(tool) => AppInput::ToolIndicator(tool),
                     SketchBoardOutput::ColorSwitchShortcut(index) => {
                         AppInput::ColorSwitchShortcut(index)
                     }
+                    SketchBoardOutput::Flash(message) => AppInput::Flash(message),
+                    SketchBoardOutput::ActionToast { text, label } => {
+                        AppInput::ActionToast { text, label }
+                    }
+                    SketchBoardOutput::ColorPicked(color) => AppInput::ColorPicked(color),
+                    SketchBoardOutput::Status(status) => AppInput::Status(status),
+                    SketchBoardOutput::ProgressStarted(text) => AppInput::ProgressStarted(text),
+                    SketchBoardOutput::ProgressEnded => AppInput::ProgressEnded,
+                    SketchBoardOutput::Recapture => AppInput::Recapture,
+                    SketchBoardOutput::SwitchImage(step) => AppInput::SwitchImage(step),
+                    SketchBoardOutput::ToolStyleChanged(tool_style) => {
+                        AppInput::ToolStyleChanged(tool_style)
+                    }
+                    SketchBoardOutput::PresetApplied(index) => AppInput::PresetApplied(index),
                     SketchBoardOutput::Exit => AppInput::Exit,
                 });
 
+        if let Some(diff) = diff {
+            sketch_board.emit(SketchBoardInput::SetDiff(diff));
+        }
+
         // Toolbars
         let tools_toolbar = ToolsToolbar::builder()
             .launch(())
@@ -404,17 +845,50 @@ impl Component for App {
             .launch(())
             .forward(sketch_board.sender(), SketchBoardInput::ToolbarEvent);
 
+        let toast = Toast::builder()
+            .launch(())
+            .forward(sender.input_sender(), |t| match t {
+                ToastOutput::ActionClicked => AppInput::ToastAction,
+            });
+
+        let status_bar = StatusBar::builder().launch(()).detach();
+
+        let layer_shell = layer_shell::init(&root);
+        if layer_shell {
+            sketch_board.emit(SketchBoardInput::Overlay);
+        }
+        // the images compared are shown together
+        let input_count = match APP_CONFIG.read().diff_mode() {
+            true => 1,
+            false => APP_CONFIG.read().inputs().len(),
+        };
+
         // Model
         let model = App {
             sketch_board,
             tools_toolbar,
             style_toolbar,
+            toast,
+            status_bar,
             image_dimensions,
             is_daemon,
+            layer_shell,
+            current_input: 0,
+            shown_inputs: (0..input_count).map(|index| index == 0).collect(),
+            stdin_frames: Vec::new(),
+            release_url: None,
         };
 
+        if APP_CONFIG.read().stdin_stream() && APP_CONFIG.read().input_filename() == "-" {
+            Self::read_stdin_frames(&sender);
+        }
+
         let widgets = view_output!();
 
+        if APP_CONFIG.read().window_opacity() < 1.0 || layer_shell {
+            root.add_css_class("translucent");
+        }
+
         if APP_CONFIG.read().focus_toggles_toolbars() {
             let motion_controller = gtk::EventControllerMotion::builder().build();
             let sender_clone = sender.clone();
@@ -449,43 +923,54 @@ impl Component for App {
     }
 }
 
-fn read_css_overrides() -> Option<String> {
-    let dirs = BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
-    let path = dirs.get_config_file("overrides.css")?;
+fn run_app(init: AppInit) {
+    let app = relm4::main_application();
+    app.set_application_id(Some(APP_ID));
+    app.set_flags(ApplicationFlags::NON_UNIQUE);
 
-    if !path.exists() {
-        return None;
-    }
+    let app = RelmApp::from_app(app).with_args(vec![]);
+    relm4_icons::initialize_icons(
+        icons::icon_names::GRESOURCE_BYTES,
+        icons::icon_names::RESOURCE_PREFIX,
+    );
 
-    match fs::read_to_string(&path) {
-        Ok(content) => Some(content),
-        Err(e) => {
-            eprintln!("failed to read CSS overrides: {}", e);
-            None
-        }
-    }
+    app.run::<App>(init);
 }
 
-fn load_gl() -> Result<()> {
-    #[cfg(target_os = "macos")]
-    let library = unsafe { libloading::os::unix::Library::new("libepoxy.0.dylib") }?;
-    #[cfg(all(unix, not(target_os = "macos")))]
-    let library = unsafe { libloading::os::unix::Library::new("libepoxy.so.0") }?;
-    #[cfg(windows)]
-    let library = libloading::os::windows::Library::open_already_loaded("libepoxy-0.dll")
-        .or_else(|_| libloading::os::windows::Library::open_already_loaded("epoxy-0.dll"))?;
-
-    epoxy::load_with(|name| {
-        unsafe { library.get::<_>(name.as_bytes()) }
-            .map(|symbol| *symbol)
-            .unwrap_or(ptr::null())
-    });
-
-    Ok(())
+/// Ask GTK and the GL drivers not to wait for vsync, must happen before GTK is initialized.
+/// Variables already set in the environment are left alone.
+fn apply_latency_mode() {
+    if APP_CONFIG.read().latency_mode() != LatencyMode::Low {
+        return;
+    }
+    let gdk_debug = match std::env::var("GDK_DEBUG") {
+        Ok(flags) if flags.split([',', ':']).any(|f| f == "no-vsync") => flags,
+        Ok(flags) if !flags.is_empty() => format!("{flags},no-vsync"),
+        _ => "no-vsync".to_string(),
+    };
+    std::env::set_var("GDK_DEBUG", gdk_debug);
+    // Mesa, NVIDIA and NVIDIA's limit of queued frames
+    for (name, value) in [
+        ("vblank_mode", "0"),
+        ("__GL_SYNC_TO_VBLANK", "0"),
+        ("__GL_MaxFramesAllowed", "1"),
+    ] {
+        if std::env::var_os(name).is_none() {
+            std::env::set_var(name, value);
+        }
+    }
 }
 
 fn run_satty() -> Result<()> {
-    load_gl()?;
+    // without a display, e.g. over SSH, fall back to a preview in the terminal
+    #[cfg(feature = "terminal")]
+    if terminal::no_display() {
+        return terminal::run(APP_CONFIG.read().input_filename());
+    }
+
+    layer_shell::preload();
+    apply_latency_mode();
+    canvas::load_gl()?;
     generate_profile_output!("loaded gl");
 
     let config = APP_CONFIG.read();
@@ -503,18 +988,8 @@ fn run_satty() -> Result<()> {
         }
 
         generate_profile_output!("starting in DAEMON mode");
-        
-        let app = relm4::main_application();
-        app.set_application_id(Some("com.gabm.satty"));
-        app.set_flags(ApplicationFlags::NON_UNIQUE);
-        
-        let app = RelmApp::from_app(app).with_args(vec![]);
-        relm4_icons::initialize_icons(
-            icons::icon_names::GRESOURCE_BYTES,
-            icons::icon_names::RESOURCE_PREFIX,
-        );
-        
-        app.run::<App>(None);
+
+        run_app(AppInit::Daemon);
 
         if socket_path.exists() {
             let _ = fs::remove_file(socket_path);
@@ -522,43 +997,40 @@ fn run_satty() -> Result<()> {
         return Ok(());
     }
 
+    if input::is_web_url(config.input_filename()) {
+        // show the window right away and report the download progress there, the daemon
+        // is not used as it can't show the progress
+        generate_profile_output!("starting gui (download)");
+        run_app(AppInit::Download(config.input_filename().to_string()));
+        return Ok(());
+    }
+
+    if config.diff_mode() {
+        let [first, second] = config.inputs() else {
+            bail!("--diff needs two images");
+        };
+        let diff = Diff::new(&input::load_image(first)?, &input::load_image(second)?)
+            .context("the images are too large to compare")?;
+        generate_profile_output!("starting gui (diff)");
+        run_app(AppInit::Diff(diff));
+        return Ok(());
+    }
+
     generate_profile_output!("loading image");
     
-    let image_result = if config.input_filename() == "-" {
-        let mut buf = Vec::<u8>::new();
-        match io::stdin().lock().read_to_end(&mut buf) {
-            Ok(_) if !buf.is_empty() => {
-                 let pb_loader = PixbufLoader::new();
-                 pb_loader.write(&buf)?;
-                 pb_loader.close()?;
-                 pb_loader.pixbuf().context("Conversion to Pixbuf failed")
-            }
-            _ => Err(anyhow!("No input data provided. Use --daemon or provide a file/stdin.")),
-        }
-    } else {
-        Pixbuf::from_file(config.input_filename()).context("couldn't load image")
-    };
+    let image_result = input::load_image(config.input_filename());
 
     match image_result {
         Ok(image) => {
-            if try_send_to_daemon(&image) {
+            // the daemon shows a single image at a time, open multiple inputs in a new window
+            if !config.has_several_inputs() && try_send_to_daemon(&image) {
                 generate_profile_output!("Sent to daemon, exiting");
                 return Ok(());
             }
 
             generate_profile_output!("starting gui (standalone)");
-            
-            let app = relm4::main_application();
-            app.set_application_id(Some("com.gabm.satty"));
-            app.set_flags(ApplicationFlags::NON_UNIQUE);
-            
-            let app = RelmApp::from_app(app).with_args(vec![]);
-            relm4_icons::initialize_icons(
-                icons::icon_names::GRESOURCE_BYTES,
-                icons::icon_names::RESOURCE_PREFIX,
-            );
-            
-            app.run::<App>(Some(image));
+
+            run_app(AppInit::Image(image));
             
             Ok(())
         },
@@ -580,6 +1052,83 @@ fn main() -> Result<()> {
     }
     generate_profile_output!("configuration loaded");
 
+    if APP_CONFIG.read().install_desktop_files() {
+        if let Err(e) = desktop_files::install() {
+            eprintln!("Error installing desktop files: {e:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if APP_CONFIG.read().dump_config() {
+        match APP_CONFIG.read().dump() {
+            Ok(dump) => print!("{dump}"),
+            Err(e) => {
+                eprintln!("Error dumping configuration: {e}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    if APP_CONFIG.read().ping() {
+        match ping_daemon() {
+            Some(info) => println!("{info}"),
+            None => {
+                eprintln!("No Satty daemon is running");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    if APP_CONFIG.read().check_update() {
+        match update::check() {
+            Ok(Some(release)) => {
+                println!("Satty {} is available: {}", release.version, release.url);
+                std::process::exit(2);
+            }
+            Ok(None) => println!("Satty {} is up to date", env!("CARGO_PKG_VERSION")),
+            Err(e) => {
+                eprintln!("Error: {e:#}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(action) = APP_CONFIG.read().auth() {
+        if let Err(e) = secrets::run(action) {
+            eprintln!("Error: {e:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(reset) = APP_CONFIG.read().stats() {
+        if let Err(e) = stats::run(reset) {
+            eprintln!("Error: {e:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(render) = APP_CONFIG.read().render().cloned() {
+        if let Err(e) = render::run(&render) {
+            eprintln!("Error: {e:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(bench) = APP_CONFIG.read().bench().cloned() {
+        if let Err(e) = bench::run(&bench) {
+            eprintln!("Error: {e:#}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     match run_satty() {
         Err(_e) => {
             std::process::exit(1);
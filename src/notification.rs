@@ -1,19 +1,104 @@
+use std::path::Path;
+
 use gdk_pixbuf::gio::FileIcon;
-use relm4::gtk::gio::{prelude::ApplicationExt, Notification};
+use relm4::gtk::gio::{self, prelude::ApplicationExt, Notification, SimpleAction};
+
+use relm4::gtk::{
+    accessible::Property, gdk::DisplayManager, glib::VariantTy, prelude::*, IconLookupFlags,
+    IconTheme, TextDirection,
+};
 
-use relm4::gtk::{IconLookupFlags, IconTheme, TextDirection};
+/// Buttons of the notification about a saved file and the app actions they activate with
+/// the path of the file
+const FILE_ACTIONS: [(&str, &str); 3] = [
+    ("Open", "open-file"),
+    ("Open folder", "open-folder"),
+    ("Copy path", "copy-path"),
+];
+
+const COUNTDOWN_ID: &str = "capture-countdown";
 
 pub fn log_result(msg: &str, notify: bool) {
     println!("{msg}");
+    announce(msg);
     if notify {
-        show_notification(msg);
+        show_notification(msg, None);
     }
 }
 
-fn show_notification(msg: &str) {
+/// Like [`log_result`], the notification gets buttons to open the saved file at `path`, its
+/// folder or to copy the path
+pub fn log_saved(msg: &str, path: &str, notify: bool) {
+    println!("{msg}");
+    announce(msg);
+    if notify {
+        show_notification(msg, Some(path));
+    }
+}
+
+/// Count down the seconds until the screenshot is taken, the notification is replaced each
+/// second and withdrawn at 0 so it isn't captured
+pub fn show_countdown(seconds: u32) {
+    let app = relm4::main_application();
+    if seconds == 0 {
+        app.withdraw_notification(COUNTDOWN_ID);
+        return;
+    }
+    let notification = Notification::new("Satty");
+    notification.set_body(Some(&format!("Capturing in {seconds}…")));
+    app.send_notification(Some(COUNTDOWN_ID), &notification);
+}
+
+/// Add the app actions the buttons of notifications activate. They only work as long as the
+/// app runs, as it does in daemon mode.
+pub fn register_actions() {
+    let app = relm4::main_application();
+    for (_, name) in FILE_ACTIONS {
+        let action = SimpleAction::new(name, Some(VariantTy::STRING));
+        action.connect_activate(move |_, parameter| {
+            if let Some(path) = parameter.and_then(|p| p.get::<String>()) {
+                activate_file_action(name, Path::new(&path));
+            }
+        });
+        app.add_action(&action);
+    }
+}
+
+fn activate_file_action(name: &str, path: &Path) {
+    let result = match name {
+        "open-file" => launch_default(path),
+        "open-folder" => launch_default(path.parent().unwrap_or(path)),
+        _ => {
+            match DisplayManager::get().default_display() {
+                Some(display) => display.clipboard().set_text(&path.to_string_lossy()),
+                None => eprintln!("Cannot open default display for clipboard."),
+            }
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("Error while opening {}: {e}", path.display());
+    }
+}
+
+fn launch_default(path: &Path) -> Result<(), gio::glib::Error> {
+    gio::AppInfo::launch_default_for_uri(
+        &gio::File::for_path(path).uri(),
+        None::<&gio::AppLaunchContext>,
+    )
+}
+
+fn show_notification(msg: &str, file: Option<&str>) {
     // construct
     let notification = Notification::new("Satty");
     notification.set_body(Some(msg));
+    for (label, name) in file.map_or(&[][..], |_| &FILE_ACTIONS[..]) {
+        notification.add_button_with_target_value(
+            label,
+            &format!("app.{name}"),
+            file.map(|file| file.to_variant()).as_ref(),
+        );
+    }
 
     // lookup sattys icon
     let theme = IconTheme::default();
@@ -36,3 +121,11 @@ fn show_notification(msg: &str) {
     // send notification
     relm4::main_application().send_notification(None, &notification);
 }
+
+/// Expose `msg` to assistive technologies as the description of the active window, so screen
+/// reader users learn about the outcome of actions they can't see
+pub fn announce(msg: &str) {
+    if let Some(window) = relm4::main_application().active_window() {
+        window.update_property(&[Property::Description(msg)]);
+    }
+}
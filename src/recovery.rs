@@ -0,0 +1,131 @@
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use anyhow::Result;
+use gdk_pixbuf::Pixbuf;
+use serde_derive::{Deserialize, Serialize};
+use xdg::BaseDirectories;
+
+use crate::{configuration::APP_CONFIG, tools::Annotation};
+
+const DIRECTORY: &str = "recovery";
+
+/// Key of the image annotated right now, its annotations are written to the file of the key
+static CURRENT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Annotations of an image, written while it is annotated so a crash, e.g. of the GL
+/// driver, doesn't lose them. The file is deleted when the session ends normally.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    /// when the annotations were written, as `YYYY-MM-DD HH:MM:SS`
+    pub saved: String,
+    pub annotations: Vec<Annotation>,
+}
+
+/// Start recovering the annotations of `image`, ends the previous session. Returns the
+/// annotations left by an earlier session on the same image that did not end.
+pub fn start(image: &Pixbuf) -> Option<Session> {
+    discard();
+    APP_CONFIG.read().recovery_interval()?;
+    let key = image_key(image);
+    let earlier = read(&key);
+    *CURRENT.lock().unwrap() = Some(key);
+    earlier
+}
+
+/// Write the annotations of the current image, nothing is kept for an image without any
+pub fn save(annotations: Vec<Annotation>) {
+    let Some(key) = CURRENT.lock().unwrap().clone() else {
+        return;
+    };
+    let result = if annotations.is_empty() {
+        remove(&key)
+    } else {
+        write(
+            &key,
+            &Session {
+                saved: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                annotations,
+            },
+        )
+    };
+    if let Err(e) = result {
+        println!("Error while saving annotations for recovery: {e}");
+    }
+}
+
+/// End the current session normally, its annotations are not offered again
+pub fn discard() {
+    if let Some(key) = CURRENT.lock().unwrap().take() {
+        if let Err(e) = remove(&key) {
+            println!("Error while removing recovered annotations: {e}");
+        }
+    }
+}
+
+/// The annotations of the session that was written last, for any image
+pub fn latest() -> Option<Session> {
+    let directory =
+        BaseDirectories::with_prefix(env!("CARGO_PKG_NAME")).get_state_file(DIRECTORY)?;
+    let newest = fs::read_dir(directory)
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let path = entry.path();
+            let finished = path.extension().is_some_and(|e| e == "json");
+            finished.then_some((entry.metadata().ok()?.modified().ok()?, path))
+        })
+        .max_by_key(|(modified, _)| *modified)?;
+    let key = newest.1.file_stem()?.to_string_lossy().into_owned();
+    read(&key)
+}
+
+/// FNV-1a hash of the size and the pixels, the same image piped in again gets the same key
+fn image_key(image: &Pixbuf) -> String {
+    let bytes = image.read_pixel_bytes();
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let size = [image.width(), image.height()].map(i32::to_be_bytes);
+    for byte in size.iter().flatten().chain(bytes.iter()) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{hash:016x}")
+}
+
+fn read(key: &str) -> Option<Session> {
+    let path = path(key, false).ok()??;
+    let data = fs::read(path).ok()?;
+    match serde_json::from_slice(&data) {
+        Ok(session) => Some(session),
+        Err(e) => {
+            println!("Error while reading recovered annotations: {e}");
+            None
+        }
+    }
+}
+
+fn write(key: &str, session: &Session) -> Result<()> {
+    if let Some(path) = path(key, true)? {
+        // a crash while writing must not destroy the previous state
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_vec(session)?)?;
+        fs::rename(partial, path)?;
+    }
+    Ok(())
+}
+
+fn remove(key: &str) -> Result<()> {
+    if let Some(path) = path(key, false)?.filter(|path| path.exists()) {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn path(key: &str, create: bool) -> Result<Option<PathBuf>> {
+    let dirs = BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
+    let file = format!("{DIRECTORY}/{key}.json");
+    Ok(if create {
+        Some(dirs.place_state_file(file)?)
+    } else {
+        dirs.get_state_file(file)
+    })
+}
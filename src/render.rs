@@ -0,0 +1,28 @@
+use std::io::{self, Cursor, Write};
+
+use anyhow::{Context, Result};
+use image::{ImageFormat, RgbaImage};
+use satty_core::Annotator;
+
+use crate::{annotation_file, input};
+use satty_cli::command_line::Render;
+
+/// `satty render`, draw the annotations of the script onto the input and save the result
+pub fn run(render: &Render) -> Result<()> {
+    let image = input::load_image(&render.input)?;
+    let annotations = annotation_file::read(&render.script, (image.width(), image.height()))?;
+    let result = Annotator::new(image).extend(annotations).render()?;
+    save(&result, &render.output)
+}
+
+fn save(image: &RgbaImage, output: &str) -> Result<()> {
+    if output == "-" {
+        let mut png = Cursor::new(Vec::new());
+        image.write_to(&mut png, ImageFormat::Png)?;
+        io::stdout().write_all(png.get_ref())?;
+        return Ok(());
+    }
+    image
+        .save(output)
+        .with_context(|| format!("couldn't save '{output}'"))
+}
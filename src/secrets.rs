@@ -0,0 +1,95 @@
+use std::{
+    io::{self, BufRead, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use satty_cli::command_line::AuthAction;
+
+/// Token stored for `service` in the Secret Service, `None` if there is none. Uses
+/// `secret-tool` from libsecret, which talks to whatever keyring implements the service.
+pub fn lookup(service: &str) -> Result<Option<String>> {
+    let output = Command::new("secret-tool")
+        .args(["lookup", "application", "satty", "service", service])
+        .stderr(Stdio::null())
+        .output()
+        .context("Cannot run secret-tool")?;
+    // fails without output if there is no such secret
+    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((output.status.success() && !token.is_empty()).then_some(token))
+}
+
+fn store(service: &str, token: &str) -> Result<()> {
+    let mut child = Command::new("secret-tool")
+        .args(["store", "--label", &format!("Satty {service} token")])
+        .args(["application", "satty", "service", service])
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("Cannot run secret-tool")?;
+    child.stdin.take().unwrap().write_all(token.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("secret-tool failed with {status}"));
+    }
+    Ok(())
+}
+
+fn clear(service: &str) -> Result<()> {
+    let status = Command::new("secret-tool")
+        .args(["clear", "application", "satty", "service", service])
+        .status()
+        .context("Cannot run secret-tool")?;
+    if !status.success() {
+        return Err(anyhow!("secret-tool failed with {status}"));
+    }
+    Ok(())
+}
+
+/// Run `satty auth`, the token to set is read from stdin without echoing it
+pub fn run(action: &AuthAction) -> Result<()> {
+    match action {
+        AuthAction::Set { service } => {
+            let token = read_token(service)?;
+            if token.is_empty() {
+                return Err(anyhow!("No token given"));
+            }
+            store(service, &token)?;
+            println!("Stored token for {service}.");
+        }
+        AuthAction::Clear { service } => {
+            clear(service)?;
+            println!("Removed token for {service}.");
+        }
+    }
+    Ok(())
+}
+
+fn read_token(service: &str) -> Result<String> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        let mut token = String::new();
+        stdin.lock().read_line(&mut token)?;
+        return Ok(token.trim().to_string());
+    }
+
+    eprint!("Token for {service}: ");
+    io::stderr().flush()?;
+    // hide the token while it is typed
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    let echo = unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } == 0;
+    if echo {
+        termios.c_lflag &= !libc::ECHO;
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) };
+    }
+
+    let mut token = String::new();
+    let result = stdin.lock().read_line(&mut token);
+    if echo {
+        termios.c_lflag |= libc::ECHO;
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &termios) };
+        eprintln!();
+    }
+    result?;
+    Ok(token.trim().to_string())
+}
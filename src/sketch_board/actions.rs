@@ -0,0 +1,633 @@
+use gdk_pixbuf::Pixbuf;
+use gtk::prelude::*;
+
+use relm4::{gtk, Component, ComponentController, ComponentSender, RelmWidgetExt};
+
+use crate::annotation_file;
+use crate::configuration::{Action, APP_CONFIG};
+use crate::math::Vec2D;
+use crate::notification::{announce, log_result};
+use crate::stats;
+use crate::text_regions::find_text_regions;
+use crate::tools::{SelectEvent, Selection, TextTool, ToolEvent, ToolUpdateResult, Tools};
+use crate::ui::annotation_search::AnnotationSearchInput;
+use crate::ui::image_chooser;
+use crate::ui::recolor_dialog::{RecolorDialog, RecolorDialogInput, RecolorDialogOutput};
+use crate::ui::scanned_codes::{ScannedCodesDialog, ScannedCodesDialogInput};
+use crate::ui::snapshot_history::SnapshotHistoryInput;
+use crate::ui::toolbars::ToolbarEvent;
+
+use super::input::KeyCommand;
+use super::{
+    SketchBoard, SketchBoardInput, SketchBoardOutput, ERASER_RADIUS, PAGE_SCROLL_FRACTION,
+};
+
+impl SketchBoard {
+    /// Carry out a key press routed by [`key_command`](super::input::key_command), `active_tool_result` is what the
+    /// active tool made of the key
+    pub fn run_key_command(
+        &mut self,
+        command: KeyCommand,
+        active_tool_result: ToolUpdateResult,
+        sender: ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        match command {
+            KeyCommand::Actions(actions) => self.handle_action(&actions),
+            KeyCommand::Render(actions) => {
+                self.renderer.run_actions(&actions);
+                ToolUpdateResult::Unmodified
+            }
+            KeyCommand::Undo => self.handle_undo(),
+            KeyCommand::Redo => self.handle_redo(),
+            KeyCommand::ToggleToolbars => self.handle_toggle_toolbars_display(sender),
+            KeyCommand::CopyText => {
+                self.handle_copy_text();
+                ToolUpdateResult::Unmodified
+            }
+            KeyCommand::SwitchImage(step) => self.handle_switch_image(step, &sender),
+            KeyCommand::Recapture => self.handle_recapture(&sender),
+            KeyCommand::ZoomToFit => self.handle_resize(&sender),
+            KeyCommand::ZoomOriginal => self.handle_original_scale(&sender),
+            KeyCommand::Zoom(factor) => self.handle_zoom(factor, &sender),
+            KeyCommand::Pan(offset) => {
+                self.renderer.set_drag_offset(offset);
+                self.renderer.store_last_offset();
+                self.renderer
+                    .request_render(&APP_CONFIG.read().actions_on_right_click());
+                ToolUpdateResult::Unmodified
+            }
+            KeyCommand::PreviousTool => self.switch_to_previous_tool(sender),
+            KeyCommand::DuplicateSelection => {
+                self.renderer.duplicate_selection();
+                ToolUpdateResult::Redraw
+            }
+            KeyCommand::ScrollPage { down } => self.handle_scroll_page(down),
+            KeyCommand::Search => self.show_annotation_search(),
+            KeyCommand::SnapshotHistory => self.show_snapshot_history(),
+            KeyCommand::Nudge(offset) => {
+                if self.renderer.nudge_selection(offset) {
+                    ToolUpdateResult::Redraw
+                } else {
+                    ToolUpdateResult::Unmodified
+                }
+            }
+            KeyCommand::DeleteSelection => {
+                self.renderer.delete_selection();
+                ToolUpdateResult::Redraw
+            }
+            KeyCommand::ClearSelection => {
+                self.renderer.select(SelectEvent::Clear);
+                ToolUpdateResult::Redraw
+            }
+            KeyCommand::Reset => self.handle_reset(),
+            KeyCommand::Finish(actions) => {
+                if let ToolUpdateResult::Unmodified = active_tool_result {
+                    self.renderer.run_actions(&actions);
+                }
+                active_tool_result
+            }
+        }
+    }
+
+    pub fn handle_action(&mut self, actions: &[Action]) -> ToolUpdateResult {
+        let rv = if self.deactivate_active_tool() {
+            ToolUpdateResult::Redraw
+        } else {
+            ToolUpdateResult::Unmodified
+        };
+        self.renderer.run_actions(actions);
+        stats::count_actions(actions);
+        rv
+    }
+
+    pub fn handle_undo(&mut self) -> ToolUpdateResult {
+        if self.active_tool.borrow().active() {
+            self.active_tool.borrow_mut().handle_undo()
+        } else if self.renderer.undo() {
+            announce("Undone.");
+            ToolUpdateResult::Redraw
+        } else {
+            announce("Nothing to undo.");
+            ToolUpdateResult::Unmodified
+        }
+    }
+
+    pub fn handle_redo(&mut self) -> ToolUpdateResult {
+        if self.active_tool.borrow().active() {
+            self.active_tool.borrow_mut().handle_redo()
+        } else if self.renderer.redo() {
+            announce("Redone.");
+            ToolUpdateResult::Redraw
+        } else {
+            announce("Nothing to redo.");
+            ToolUpdateResult::Unmodified
+        }
+    }
+
+    pub fn handle_reset(&mut self) -> ToolUpdateResult {
+        if self.deactivate_active_tool() | self.renderer.reset() {
+            ToolUpdateResult::Redraw
+        } else {
+            ToolUpdateResult::Unmodified
+        }
+    }
+
+    pub fn handle_resize(&mut self, sender: &ComponentSender<Self>) -> ToolUpdateResult {
+        self.renderer.reset_size(0.);
+        self.renderer
+            .request_render(&APP_CONFIG.read().actions_on_right_click());
+        self.flash_zoom(sender);
+        ToolUpdateResult::Unmodified
+    }
+
+    pub fn handle_original_scale(&mut self, sender: &ComponentSender<Self>) -> ToolUpdateResult {
+        self.renderer.reset_size(1.);
+        self.renderer
+            .request_render(&APP_CONFIG.read().actions_on_right_click());
+        self.flash_zoom(sender);
+        ToolUpdateResult::Unmodified
+    }
+
+    /// Zoom by `factor` around the middle of the area, the pointer is elsewhere when a
+    /// button or key zooms
+    pub fn handle_zoom(&mut self, factor: f32, sender: &ComponentSender<Self>) -> ToolUpdateResult {
+        self.renderer.zoom_centered(factor);
+        self.renderer
+            .request_render(&APP_CONFIG.read().actions_on_right_click());
+        self.flash_zoom(sender);
+        ToolUpdateResult::Unmodified
+    }
+
+    /// Briefly show the zoom after it changed
+    pub fn flash_zoom(&self, sender: &ComponentSender<Self>) {
+        sender
+            .output_sender()
+            .emit(SketchBoardOutput::Flash(format!(
+                "{:.0}%",
+                self.renderer.zoom() * 100.0
+            )));
+    }
+
+    pub fn show_annotation_search(&self) -> ToolUpdateResult {
+        let search = self.annotation_search.widget();
+        search.set_pointing_to(Some(&gtk::gdk::Rectangle::new(
+            self.renderer.width() / 2,
+            0,
+            1,
+            1,
+        )));
+        self.annotation_search
+            .emit(AnnotationSearchInput::Show(self.renderer.texts()));
+        ToolUpdateResult::Unmodified
+    }
+
+    pub fn show_snapshot_history(&self) -> ToolUpdateResult {
+        let history = self.snapshot_history.widget();
+        history.set_pointing_to(Some(&gtk::gdk::Rectangle::new(
+            self.renderer.width() / 2,
+            0,
+            1,
+            1,
+        )));
+        self.snapshot_history
+            .emit(SnapshotHistoryInput::Show(self.snapshots.list().to_vec()));
+        ToolUpdateResult::Unmodified
+    }
+
+    fn handle_recolor(&mut self, sender: ComponentSender<Self>) -> ToolUpdateResult {
+        let used = self.renderer.used_colors();
+        if used.is_empty() {
+            return ToolUpdateResult::Unmodified;
+        }
+
+        let dialog = self.recolor_dialog.get_or_insert_with(|| {
+            let mut builder = RecolorDialog::builder();
+            if let Some(w) = self.renderer.toplevel_window() {
+                builder = builder.transient_for(&w);
+            }
+            let mut controller =
+                builder
+                    .launch(())
+                    .forward(sender.input_sender(), |output| match output {
+                        RecolorDialogOutput::Preview(recolor) => {
+                            SketchBoardInput::RecolorPreview(recolor)
+                        }
+                        RecolorDialogOutput::Apply => SketchBoardInput::ApplyRecolor,
+                    });
+            controller.detach_runtime();
+            controller
+        });
+        dialog.emit(RecolorDialogInput::Show {
+            used,
+            to: self.style.color,
+        });
+        ToolUpdateResult::Unmodified
+    }
+
+    pub fn show_scanned_codes(&mut self, codes: Vec<String>) -> ToolUpdateResult {
+        if codes.is_empty() {
+            log_result(
+                "No QR code or barcode found.",
+                !APP_CONFIG.read().disable_notifications(),
+            );
+            return ToolUpdateResult::Unmodified;
+        }
+
+        let dialog = self.scanned_codes_dialog.get_or_insert_with(|| {
+            let mut builder = ScannedCodesDialog::builder();
+            if let Some(w) = self.renderer.toplevel_window() {
+                builder = builder.transient_for(&w);
+            }
+            let mut controller = builder.launch(()).detach();
+            controller.detach_runtime();
+            controller
+        });
+        dialog.emit(ScannedCodesDialogInput::Show(codes));
+        ToolUpdateResult::Unmodified
+    }
+
+    /// Pan by the height of the area, keeping a bit of the previous page visible
+    pub fn handle_scroll_page(&mut self, down: bool) -> ToolUpdateResult {
+        let step = self.renderer.height() as f32 * PAGE_SCROLL_FRACTION;
+        self.renderer
+            .set_drag_offset(Vec2D::new(0., if down { -step } else { step }));
+        self.renderer.store_last_offset();
+        ToolUpdateResult::Redraw
+    }
+
+    /// Ask the app to take the screenshot again, if there is a command for it
+    pub fn handle_recapture(&self, sender: &ComponentSender<Self>) -> ToolUpdateResult {
+        let message = if APP_CONFIG.read().recapture_command().is_some() {
+            SketchBoardOutput::Recapture
+        } else {
+            SketchBoardOutput::Flash("No recapture-command configured".to_string())
+        };
+        sender.output_sender().emit(message);
+        ToolUpdateResult::Unmodified
+    }
+
+    /// Ask the app to show the input `step` places after the current one, if there are
+    /// several
+    pub fn handle_switch_image(
+        &self,
+        step: isize,
+        sender: &ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        if APP_CONFIG.read().has_several_inputs() {
+            sender
+                .output_sender()
+                .emit(SketchBoardOutput::SwitchImage(step));
+        }
+        ToolUpdateResult::Unmodified
+    }
+
+    /// Ask for an image file to join to the canvas, it comes back as
+    /// [`SketchBoardInput::AppendImage`]
+    fn handle_append_image(&self, sender: &ComponentSender<Self>) -> ToolUpdateResult {
+        let input = sender.input_sender().clone();
+        let output = sender.output_sender().clone();
+        image_chooser::show(
+            self.renderer.toplevel_window(),
+            "Append Image",
+            "Append",
+            move |path| match Pixbuf::from_file(&path) {
+                Ok(image) => input.emit(SketchBoardInput::AppendImage(image)),
+                Err(e) => {
+                    eprintln!("Error: couldn't load image '{}': {e}", path.display());
+                    output.emit(SketchBoardOutput::Flash(format!(
+                        "Couldn't load '{}'",
+                        path.display()
+                    )));
+                }
+            },
+        );
+        ToolUpdateResult::Unmodified
+    }
+
+    fn handle_export_annotations(&self, sender: &ComponentSender<Self>) -> ToolUpdateResult {
+        let annotations = self.renderer.annotations();
+        let output = sender.output_sender().clone();
+        if annotations.is_empty() {
+            output.emit(SketchBoardOutput::Flash(
+                "No annotations to export".to_string(),
+            ));
+            return ToolUpdateResult::Unmodified;
+        }
+        let size = self.renderer.image_size();
+        image_chooser::show_annotations(
+            self.renderer.toplevel_window(),
+            "Export Annotations",
+            true,
+            move |path| {
+                let count = annotations.len();
+                let text = match annotation_file::write(&path, size, annotations.clone()) {
+                    Ok(()) => format!("Exported {count} annotations"),
+                    Err(e) => {
+                        eprintln!("Error: {e:#}");
+                        e.to_string()
+                    }
+                };
+                output.emit(SketchBoardOutput::Flash(text));
+            },
+        );
+        ToolUpdateResult::Unmodified
+    }
+
+    /// [`SketchBoardInput::ImportAnnotations`]
+    fn handle_import_annotations(&self, sender: &ComponentSender<Self>) -> ToolUpdateResult {
+        let input = sender.input_sender().clone();
+        image_chooser::show_annotations(
+            self.renderer.toplevel_window(),
+            "Import Annotations",
+            false,
+            move |path| input.emit(SketchBoardInput::ImportAnnotations(path)),
+        );
+        ToolUpdateResult::Unmodified
+    }
+
+    fn handle_diff_heatmap(&mut self, show: bool) -> ToolUpdateResult {
+        match &self.diff {
+            Some(diff) => {
+                // a view of the same images, the annotations and their recovery stay
+                self.renderer
+                    .replace_background_image(diff.image(show).clone());
+                ToolUpdateResult::Redraw
+            }
+            None => ToolUpdateResult::Unmodified,
+        }
+    }
+
+    fn handle_grow_canvas(&mut self) -> ToolUpdateResult {
+        self.renderer
+            .grow_canvas(APP_CONFIG.read().canvas_padding());
+        ToolUpdateResult::Redraw
+    }
+
+    /// Search the image for text in the background, the regions found come back as
+    /// [`SketchBoardInput::RedactProposals`]
+    pub fn find_redact_proposals(&self, sender: &ComponentSender<Self>) {
+        let image = self.renderer.background_grayscale();
+        let input = sender.input_sender().clone();
+        relm4::spawn_local(async move {
+            let regions = relm4::spawn_blocking(move || {
+                let config = APP_CONFIG.read();
+                let auto_redact = config.auto_redact();
+                find_text_regions(
+                    &image,
+                    auto_redact.threshold(),
+                    auto_redact.max_line_height(),
+                    auto_redact.padding(),
+                )
+            })
+            .await;
+            if let Ok(regions) = regions {
+                input.emit(SketchBoardInput::RedactProposals(regions));
+            }
+        });
+    }
+
+    pub fn handle_erase(&mut self, pos: Vec2D) -> ToolUpdateResult {
+        let tolerance = self
+            .renderer
+            .rel_canvas_to_image_coordinates(Vec2D::new(ERASER_RADIUS, 0.0))
+            .x;
+
+        if self.renderer.erase_at(pos, tolerance) {
+            ToolUpdateResult::Redraw
+        } else {
+            ToolUpdateResult::Unmodified
+        }
+    }
+
+    pub fn handle_toggle_toolbars_display(
+        &mut self,
+        sender: ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        sender
+            .output_sender()
+            .emit(SketchBoardOutput::ToggleToolbarsDisplay);
+        ToolUpdateResult::Unmodified
+    }
+
+    pub fn switch_to_previous_tool(&mut self, sender: ComponentSender<Self>) -> ToolUpdateResult {
+        let Some(tool) = self.previous_tool else {
+            return ToolUpdateResult::Unmodified;
+        };
+        let output = sender.output_sender();
+        output.emit(SketchBoardOutput::ToolSwitchShortcut(tool));
+        output.emit(SketchBoardOutput::ToolIndicator(tool));
+        self.handle_toolbar_event(ToolbarEvent::ToolSelected(tool), sender)
+    }
+
+    pub fn handle_edit_text(
+        &mut self,
+        pos: Vec2D,
+        sender: ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        let tolerance = self
+            .renderer
+            .rel_canvas_to_image_coordinates(Vec2D::new(Selection::PICK_RADIUS, 0.0))
+            .x;
+
+        let Some(drawable) = self
+            .renderer
+            .take_at(pos, tolerance, |d| TextTool::can_edit(d))
+        else {
+            return ToolUpdateResult::Unmodified;
+        };
+
+        if self.active_tool_type() != Tools::Text {
+            self.handle_toolbar_event(ToolbarEvent::ToolSelected(Tools::Text), sender.clone());
+            sender
+                .output_sender()
+                .emit(SketchBoardOutput::ToolSwitchShortcut(Tools::Text));
+        }
+
+        self.tools
+            .get_text_tool()
+            .borrow_mut()
+            .edit(drawable.as_ref(), pos)
+    }
+
+    pub fn handle_toolbar_event(
+        &mut self,
+        toolbar_event: ToolbarEvent,
+        sender: ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        match toolbar_event {
+            ToolbarEvent::ToolSelected(tool) => {
+                let old_tool = self.active_tool.clone();
+                let old_tool_type = old_tool.borrow().get_tool_type();
+                if old_tool_type != tool {
+                    self.previous_tool = Some(old_tool_type);
+                    stats::count_tool(tool);
+                }
+                let mut deactivate_result =
+                    old_tool.borrow_mut().handle_event(ToolEvent::Deactivated);
+
+                old_tool.borrow_mut().set_im_context(None);
+
+                if let ToolUpdateResult::Commit(d) = deactivate_result {
+                    self.commit(d, sender.output_sender());
+                    deactivate_result = ToolUpdateResult::Redraw;
+                }
+
+                self.active_tool = self.tools.get(&tool);
+                self.renderer.set_active_tool(self.active_tool.clone());
+                let widget_ref: gtk::Widget = self.renderer.clone().upcast();
+                self.active_tool
+                    .borrow_mut()
+                    .set_im_context(Some(crate::tools::InputContext {
+                        im_context: self.im_context.clone(),
+                        widget: widget_ref,
+                    }));
+
+                self.active_tool
+                    .borrow_mut()
+                    .set_sender(Self::tool_sender(&sender));
+
+                if let Some(tool_style) = self
+                    .tool_styles
+                    .get(tool)
+                    .filter(|&s| s != self.style.tool_style())
+                {
+                    self.style.apply_tool_style(tool_style);
+                    sender
+                        .output_sender()
+                        .emit(SketchBoardOutput::ToolStyleChanged(tool_style));
+                }
+                self.active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::StyleChanged(self.style));
+
+                let activate_result = self
+                    .active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::Activated);
+
+                match activate_result {
+                    ToolUpdateResult::Unmodified => deactivate_result,
+                    _ => activate_result,
+                }
+            }
+            ToolbarEvent::ColorSelected(color) => {
+                self.style.color = color;
+                self.remember_tool_style();
+                self.active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::StyleChanged(self.style))
+            }
+            ToolbarEvent::SizeSelected(size) => {
+                self.style.size = size;
+                self.remember_tool_style();
+                self.active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::StyleChanged(self.style))
+            }
+            ToolbarEvent::SaveFile => self.handle_action(&[Action::SaveToFile(None)]),
+            ToolbarEvent::CopyClipboard => self.handle_action(&[Action::SaveToClipboard]),
+            ToolbarEvent::Undo => self.handle_undo(),
+            ToolbarEvent::Redo => self.handle_redo(),
+            ToolbarEvent::Reset => self.handle_reset(),
+            ToolbarEvent::ToggleFill => {
+                self.style.fill = !self.style.fill;
+                self.remember_tool_style();
+                self.active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::StyleChanged(self.style))
+            }
+            ToolbarEvent::ToggleShadow => {
+                self.style.shadow = !self.style.shadow;
+                self.active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::StyleChanged(self.style))
+            }
+            ToolbarEvent::ToggleOutline => {
+                self.style.outline = !self.style.outline;
+                self.active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::StyleChanged(self.style))
+            }
+            ToolbarEvent::FontSelected(font) => {
+                self.style.font = font;
+                self.active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::StyleChanged(self.style))
+            }
+            ToolbarEvent::TextAlignSelected(align) => {
+                self.style.text_align = align;
+                self.active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::StyleChanged(self.style))
+            }
+            ToolbarEvent::AnnotationSizeChanged(value) => {
+                self.style.annotation_size_factor = value;
+                self.active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::StyleChanged(self.style))
+            }
+            ToolbarEvent::SaveFileAs => self.handle_action(&[Action::SaveToFileAs]),
+            ToolbarEvent::Resize => self.handle_resize(&sender),
+            ToolbarEvent::OriginalScale => self.handle_original_scale(&sender),
+            ToolbarEvent::ZoomIn => self.handle_zoom(APP_CONFIG.read().zoom_factor(), &sender),
+            ToolbarEvent::ZoomOut => {
+                self.handle_zoom(1.0 / APP_CONFIG.read().zoom_factor(), &sender)
+            }
+            ToolbarEvent::Zoom(zoom) => self.handle_zoom(zoom / self.renderer.zoom(), &sender),
+            ToolbarEvent::GrowCanvas => self.handle_grow_canvas(),
+            ToolbarEvent::Recapture => self.handle_recapture(&sender),
+            ToolbarEvent::PreviousImage => self.handle_switch_image(-1, &sender),
+            ToolbarEvent::NextImage => self.handle_switch_image(1, &sender),
+            ToolbarEvent::AppendImage => self.handle_append_image(&sender),
+            ToolbarEvent::DiffHeatmap(show) => self.handle_diff_heatmap(show),
+            ToolbarEvent::ExportAnnotations => self.handle_export_annotations(&sender),
+            ToolbarEvent::ImportAnnotations => self.handle_import_annotations(&sender),
+            ToolbarEvent::ExportCanvasSelected(canvas) => {
+                self.export_canvas = canvas;
+                ToolUpdateResult::Unmodified
+            }
+            ToolbarEvent::Recolor => self.handle_recolor(sender),
+            ToolbarEvent::AutoRedact => {
+                self.find_redact_proposals(&sender);
+                ToolUpdateResult::Unmodified
+            }
+            ToolbarEvent::PresetSelected(index) => self.apply_preset(index, sender),
+            ToolbarEvent::PickColor => {
+                self.picking_color = true;
+                sender.output_sender().emit(SketchBoardOutput::Flash(
+                    "Click the image to pick a color".to_string(),
+                ));
+                ToolUpdateResult::Unmodified
+            }
+        }
+    }
+
+    /// Select the tool of the preset and apply its style
+    fn apply_preset(&mut self, index: usize, sender: ComponentSender<Self>) -> ToolUpdateResult {
+        let Some(preset) = APP_CONFIG.read().presets().get(index).cloned() else {
+            return ToolUpdateResult::Unmodified;
+        };
+        self.current_preset = Some(index);
+        let output = sender.output_sender().clone();
+
+        let tool = preset.tool();
+        let result = if tool != self.active_tool_type() {
+            output.emit(SketchBoardOutput::ToolSwitchShortcut(tool));
+            self.handle_toolbar_event(ToolbarEvent::ToolSelected(tool), sender)
+        } else {
+            ToolUpdateResult::Unmodified
+        };
+
+        let tool_style = preset.apply_to(self.style.tool_style());
+        self.style.apply_tool_style(tool_style);
+        self.remember_tool_style();
+        self.active_tool
+            .borrow_mut()
+            .handle_event(ToolEvent::StyleChanged(self.style));
+
+        output.emit(SketchBoardOutput::ToolStyleChanged(tool_style));
+        output.emit(SketchBoardOutput::PresetApplied(index));
+        output.emit(SketchBoardOutput::Flash(preset.name().to_string()));
+        result
+    }
+}
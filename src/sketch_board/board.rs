@@ -0,0 +1,930 @@
+use gdk_pixbuf::{Colorspace, Pixbuf};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use gtk::prelude::*;
+
+use relm4::actions::{RelmAction, RelmActionGroup};
+use relm4::{gtk, Component, ComponentController, ComponentParts, ComponentSender, Sender};
+
+use crate::annotation_file;
+use crate::configuration::{Action, InitialZoom, APP_CONFIG};
+use crate::femtovg_area::FemtoVGArea;
+use crate::hooks::{self, Changes, Event, Hook};
+use crate::ime::pango_adapter::spans_from_pango_attrs;
+use crate::math::Vec2D;
+use crate::recovery;
+use crate::snapshots::Snapshots;
+use crate::stitch;
+use crate::style::Style;
+use crate::tool_styles::ToolStyles;
+use crate::tools::{
+    Annotation, Drawable, ToolEvent, ToolSender, ToolUpdateResult, Tools, ToolsManager,
+};
+use crate::ui::annotation_search::{AnnotationSearch, AnnotationSearchOutput};
+use crate::ui::snapshot_history::{SnapshotHistory, SnapshotHistoryOutput};
+use crate::ui::status_bar::Status;
+
+use super::input::is_stylus_event;
+use super::{
+    InputEvent, KeyEventMsg, MouseEventType, PinchEvent, SketchBoard, SketchBoardInput,
+    SketchBoardOutput, StylusEventType, TextEventMsg, MIN_TEXT_CONTRAST,
+};
+
+impl SketchBoard {
+    fn refresh_screen(&mut self) {
+        self.renderer.queue_render();
+    }
+
+    /// Forwards the requests of the tools to the board, they are handled as board input
+    pub fn tool_sender(sender: &ComponentSender<Self>) -> ToolSender {
+        let input = sender.input_sender().clone();
+        ToolSender::new(move |request| input.emit(request.into()))
+    }
+
+    /// Apply the first matching `[[size-rules]]` entry for a freshly loaded image
+    fn apply_size_rule(&mut self, width: i32, height: i32) {
+        let rule = APP_CONFIG.read().size_rule(width, height);
+        self.output_scale = rule.and_then(|r| r.output_scale()).unwrap_or(1.0);
+        self.export_split = rule
+            .and_then(|r| r.export_split())
+            .or(APP_CONFIG.read().export_split());
+        if self.overlay {
+            // the screenshot lies exactly over the output it shows
+            self.renderer.set_initial_zoom(InitialZoom::Factor(1.0));
+        } else if let Some(zoom) = rule.and_then(|r| r.initial_zoom()) {
+            self.renderer.set_initial_zoom(zoom);
+        }
+    }
+
+    fn load_image(&mut self, image: Pixbuf, sender: &ComponentSender<Self>) {
+        let (width, height) = (image.width(), image.height());
+        let earlier = recovery::start(&image);
+        self.renderer.init(
+            sender.input_sender().clone(),
+            self.tools.get_crop_tool(),
+            self.active_tool.clone(),
+            image,
+        );
+        self.apply_size_rule(width, height);
+        self.snapshots.clear();
+        self.crosshair = None;
+        self.offer_recovery(earlier, sender.output_sender());
+        self.run_hooks(&Hook::for_load(), Vec::new());
+    }
+
+    /// Show the input with `index`, the current one keeps its annotations and undo history
+    /// until it is shown again. `image` is only needed for inputs not shown before.
+    fn switch_image(
+        &mut self,
+        index: usize,
+        image: Option<Pixbuf>,
+        sender: &ComponentSender<Self>,
+    ) {
+        self.deactivate_active_tool();
+        if let Some(state) = self.renderer.take_state() {
+            self.parked_images.insert(self.input_index, state);
+        }
+        self.input_index = index;
+        match (self.parked_images.remove(&index), image) {
+            (Some(state), _) => {
+                recovery::start(state.background_image());
+                let image = state.background_image();
+                let (width, height) = (image.width(), image.height());
+                self.renderer.restore_state(state, self.active_tool.clone());
+                self.apply_size_rule(width, height);
+                self.snapshots.clear();
+                self.crosshair = None;
+                self.recovery_offer = None;
+                self.recovery_revision = self.renderer.revision();
+            }
+            (None, Some(image)) => self.load_image(image, sender),
+            (None, None) => {}
+        }
+    }
+
+    /// Show `image` instead of the current one, keeping the annotations
+    fn replace_image(&mut self, image: Pixbuf) {
+        // the annotations are recovered with the new image from now on
+        recovery::start(&image);
+        self.renderer.replace_background_image(image);
+        self.snapshots.clear();
+    }
+
+    /// Name of the shown input without extension, for `{name}` in output paths. Images
+    /// from stdin or sent to the daemon are called `image`, the ones streamed to stdin after
+    /// the first `image-2` and so on.
+    pub fn input_name(&self) -> String {
+        let stem = APP_CONFIG
+            .read()
+            .inputs()
+            .get(self.input_index)
+            .filter(|input| *input != "-")
+            .and_then(|input| Path::new(input).file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned());
+        match stem {
+            Some(stem) => stem,
+            None if self.input_index > 0 => format!("image-{}", self.input_index + 1),
+            None => "image".to_string(),
+        }
+    }
+
+    /// Commit `drawable` and warn if it is text that is hard to read on the image
+    pub fn commit(&mut self, drawable: Box<dyn Drawable>, output: &Sender<SketchBoardOutput>) {
+        let index = self.renderer.commit(drawable);
+        self.contrast_fix = None;
+        if !self.warn_low_contrast {
+            return;
+        }
+        let Some(ratio) = self.renderer.text_contrast(index) else {
+            return;
+        };
+        if ratio < MIN_TEXT_CONTRAST {
+            self.contrast_fix = Some(index);
+            self.recovery_offer = None;
+            output.emit(SketchBoardOutput::ActionToast {
+                text: format!("Text is hard to read (contrast {ratio:.1}:1)"),
+                label: "Add background".to_string(),
+            });
+        }
+    }
+
+    /// Restore the annotations of a crashed session on the same image, right away with
+    /// `--recover`, otherwise if the toast is clicked
+    fn offer_recovery(
+        &mut self,
+        earlier: Option<recovery::Session>,
+        output: &Sender<SketchBoardOutput>,
+    ) {
+        self.recovery_offer = None;
+        if APP_CONFIG.read().recover() {
+            if let Some(session) = earlier.or_else(recovery::latest) {
+                self.restore_annotations(session.annotations);
+            }
+        } else if let Some(session) = earlier {
+            output.emit(SketchBoardOutput::ActionToast {
+                text: format!("Unsaved annotations from {} were found", session.saved),
+                label: "Restore".to_string(),
+            });
+            self.recovery_offer = Some(session);
+        }
+        self.recovery_revision = self.renderer.revision();
+    }
+
+    /// Commit `annotations` as one undo step
+    fn restore_annotations(&mut self, annotations: Vec<Annotation>) {
+        let marker_numbers = self.tools.marker_numbers();
+        let drawables = annotations
+            .into_iter()
+            .map(|annotation| annotation.into_drawable(&marker_numbers))
+            .collect();
+        self.renderer.commit_group(drawables);
+    }
+
+    /// Run the functions of the hooks script for `hooks` on the current annotations, then
+    /// `actions` with their changes
+    fn run_hooks(&mut self, hooks: &[Hook], mut actions: Vec<Action>) -> ToolUpdateResult {
+        if hooks.is_empty() || self.hooks.is_none() {
+            if !actions.is_empty() {
+                self.renderer.request_render(&actions);
+            }
+            return ToolUpdateResult::Unmodified;
+        }
+        // a text still being typed is part of the annotations the script gets
+        self.deactivate_active_tool();
+        let filename = actions
+            .iter()
+            .find_map(|action| match action {
+                Action::SaveToFile(template) => Some(template.as_deref()),
+                _ => None,
+            })
+            .and_then(|template| self.output_filename(template));
+        let event = Event::new(
+            self.renderer.image_size(),
+            self.renderer.annotations(),
+            &actions,
+            filename,
+        );
+        let changes = self
+            .hooks
+            .as_ref()
+            .map_or_else(Changes::default, |script| script.run_all(hooks, event));
+        let result = match changes.annotations {
+            Some(annotations) => {
+                let marker_numbers = self.tools.marker_numbers();
+                let drawables = annotations
+                    .into_iter()
+                    .map(|annotation| annotation.into_drawable(&marker_numbers))
+                    .collect();
+                self.renderer.replace_all(drawables);
+                ToolUpdateResult::Redraw
+            }
+            None => ToolUpdateResult::Unmodified,
+        };
+        if let Some(filename) = changes.filename {
+            let save = actions
+                .iter_mut()
+                .find(|action| matches!(action, Action::SaveToFile(_)));
+            if let Some(save) = save {
+                // a file name, not to be taken for format specifiers
+                *save = Action::SaveToFile(Some(filename.replace('%', "%%")));
+            }
+        }
+        if !actions.is_empty() {
+            self.renderer.request_render(&actions);
+        }
+        result
+    }
+
+    fn send_status(&self, sender: &ComponentSender<Self>) {
+        let pos = self.renderer.pointer_in_image();
+        sender
+            .output_sender()
+            .emit(SketchBoardOutput::Status(Status {
+                pos,
+                color: pos.and_then(|pos| self.renderer.background_color_at(pos)),
+                zoom: self.renderer.zoom(),
+                image_size: self.renderer.image_size(),
+            }));
+    }
+
+    /// Cursor of the active tool, a hand while panning or on the minimap
+    fn refresh_cursor(&self) {
+        let name = if self.renderer.is_drag() || self.minimap_drag.is_some() {
+            "grabbing"
+        } else if self.renderer.pointer_on_minimap() {
+            "grab"
+        } else {
+            self.active_tool_type().cursor_name()
+        };
+        if self.renderer.cursor().and_then(|c| c.name()).as_deref() != Some(name) {
+            self.renderer.set_cursor_from_name(Some(name));
+        }
+    }
+
+    pub fn deactivate_active_tool(&mut self) -> bool {
+        if self.active_tool.borrow().active() {
+            if let ToolUpdateResult::Commit(result) =
+                self.active_tool.borrow_mut().handle_deactivated()
+            {
+                self.renderer.commit(result);
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn remember_tool_style(&mut self) {
+        self.tool_styles
+            .remember(self.active_tool_type(), self.style.tool_style());
+    }
+
+    fn create_selection_menu() -> gtk::PopoverMenu {
+        let menu = gtk::gio::Menu::new();
+        menu.append(Some("Duplicate"), Some("selection.duplicate"));
+        menu.append(Some("Delete"), Some("selection.delete"));
+
+        let popover = gtk::PopoverMenu::from_model(Some(&menu));
+        popover.set_has_arrow(false);
+        popover.set_halign(gtk::Align::Start);
+        popover
+    }
+
+    pub fn active_tool_type(&self) -> Tools {
+        self.active_tool.borrow().get_tool_type()
+    }
+}
+
+relm4::new_action_group!(SelectionActionGroup, "selection");
+relm4::new_stateless_action!(DuplicateAction, SelectionActionGroup, "duplicate");
+relm4::new_stateless_action!(DeleteAction, SelectionActionGroup, "delete");
+
+#[relm4::component(pub)]
+impl Component for SketchBoard {
+    type CommandOutput = ();
+    type Input = SketchBoardInput;
+    type Output = SketchBoardOutput;
+    type Init = Option<Pixbuf>;
+
+    view! {
+        gtk::Box {
+            #[local_ref]
+            area -> FemtoVGArea {
+                set_vexpand: true,
+                set_hexpand: true,
+                set_can_focus: true,
+                set_focusable: true,
+                grab_focus: (),
+
+                add_controller = gtk::GestureDrag {
+                        set_button: 0,
+                        connect_drag_begin[sender] => move |controller, x, y| {
+                            // stylus input is handled by the GestureStylus below
+                            if is_stylus_event(controller) {
+                                return;
+                            }
+                            sender.input(SketchBoardInput::new_mouse_event(
+                                MouseEventType::BeginDrag,
+                                controller.current_button(),
+                                1,
+                                controller.current_event_state(),
+                                Vec2D::new(x as f32, y as f32),
+                                false,
+                            ));
+
+                        },
+                        connect_drag_update[sender] => move |controller, x, y| {
+                            // stylus input is handled by the GestureStylus below
+                            if is_stylus_event(controller) {
+                                return;
+                            }
+                            sender.input(SketchBoardInput::new_mouse_event(
+                                MouseEventType::UpdateDrag,
+                                controller.current_button(),
+                                1,
+                                controller.current_event_state(),
+                                Vec2D::new(x as f32, y as f32),
+                                false,
+                            ));
+                        },
+                        connect_drag_end[sender] => move |controller, x, y| {
+                            // stylus input is handled by the GestureStylus below
+                            if is_stylus_event(controller) {
+                                return;
+                            }
+                            sender.input(SketchBoardInput::new_mouse_event(
+                                MouseEventType::EndDrag,
+                                controller.current_button(),
+                                1,
+                                controller.current_event_state(),
+                                Vec2D::new(x as f32, y as f32),
+                                false
+                            ));
+                        },
+                        connect_cancel[sender] => move |controller, _| {
+                            if is_stylus_event(controller) {
+                                return;
+                            }
+                            sender.input(SketchBoardInput::DragCanceled);
+                        },
+                },
+
+                add_controller = gtk::GestureZoom {
+                    connect_begin[sender] => move |gesture, _| {
+                        // a drag started by the first finger stops being one
+                        gesture.set_state(gtk::EventSequenceState::Claimed);
+                        if let Some((x, y)) = gesture.bounding_box_center() {
+                            sender.input(SketchBoardInput::Pinch(PinchEvent::Begin(
+                                Vec2D::new(x as f32, y as f32),
+                            )));
+                        }
+                    },
+                    connect_scale_changed[sender] => move |gesture, scale| {
+                        if let Some((x, y)) = gesture.bounding_box_center() {
+                            sender.input(SketchBoardInput::Pinch(PinchEvent::Update {
+                                scale: scale as f32,
+                                center: Vec2D::new(x as f32, y as f32),
+                            }));
+                        }
+                    },
+                    connect_end[sender] => move |_, _| {
+                        sender.input(SketchBoardInput::Pinch(PinchEvent::End));
+                    },
+                },
+
+                add_controller = gtk::GestureClick {
+                    set_button: 0,
+                    connect_pressed[sender] => move |controller, n_pressed, x, y| {
+                        if is_stylus_event(controller) {
+                            return;
+                        }
+                        sender.input(SketchBoardInput::new_mouse_event(
+                            MouseEventType::Click,
+                            controller.current_button(),
+                            n_pressed,
+                            controller.current_event_state(),
+                            Vec2D::new(x as f32, y as f32),
+                            false,
+                        ));
+                    },
+                    connect_released[sender] => move |controller, n_released, x, y| {
+                        if is_stylus_event(controller) {
+                            return;
+                        }
+                        sender.input(SketchBoardInput::new_mouse_event(
+                            MouseEventType::Release,
+                            controller.current_button(),
+                            n_released,
+                            controller.current_event_state(),
+                            Vec2D::new(x as f32, y as f32),
+                            true,
+                        ));
+                    }
+                },
+
+                add_controller = gtk::EventControllerScroll{
+                    set_flags: gtk::EventControllerScrollFlags::BOTH_AXES
+                        | gtk::EventControllerScrollFlags::KINETIC,
+                    connect_scroll[sender] => move |controller, dx, dy| {
+                        sender.input(SketchBoardInput::new_scroll_event(
+                            dx,
+                            dy,
+                            controller.current_event_state(),
+                        ));
+                        glib::Propagation::Stop
+                    },
+                    connect_decelerate[sender] => move |_, velocity_x, velocity_y| {
+                        sender.input(SketchBoardInput::KineticPan(Vec2D::new(
+                            velocity_x as f32,
+                            velocity_y as f32,
+                        )));
+                    },
+                },
+
+                add_controller = gtk::GestureStylus {
+                    connect_down[sender] => move |controller, x, y| {
+                        sender.input(SketchBoardInput::new_stylus_event(
+                            StylusEventType::Down, controller, x, y,
+                        ));
+                    },
+                    connect_motion[sender] => move |controller, x, y| {
+                        sender.input(SketchBoardInput::new_stylus_event(
+                            StylusEventType::Motion, controller, x, y,
+                        ));
+                    },
+                    connect_up[sender] => move |controller, x, y| {
+                        sender.input(SketchBoardInput::new_stylus_event(
+                            StylusEventType::Up, controller, x, y,
+                        ));
+                    },
+                },
+
+                add_controller = gtk::EventControllerKey {
+                    connect_key_pressed[sender] => move |controller, key, code, modifier | {
+                        if let Some(im_context) = controller.im_context() {
+                            im_context.focus_in();
+                            if !im_context.filter_keypress(controller.current_event().unwrap()) {
+                                sender.input(SketchBoardInput::new_key_event(KeyEventMsg::new(key, code, modifier)));
+                            }
+                        } else {
+                            sender.input(SketchBoardInput::new_key_event(KeyEventMsg::new(key, code, modifier)));
+                        }
+                        glib::Propagation::Stop
+                    },
+
+                    connect_key_released[sender] => move |controller, key, code, modifier | {
+                        if let Some(im_context) = controller.im_context() {
+                            im_context.focus_in();
+                            if !im_context.filter_keypress(controller.current_event().unwrap()) {
+                                sender.input(SketchBoardInput::new_key_release_event(KeyEventMsg::new(key, code, modifier)));
+                            }
+                        } else {
+                            sender.input(SketchBoardInput::new_key_release_event(KeyEventMsg::new(key, code, modifier)));
+                        }
+                    },
+                    set_im_context: Some(&model.im_context),
+                },
+
+                add_controller = gtk::EventControllerMotion {
+                    connect_motion[sender] => move |controller, x, y| {
+                        sender.input(SketchBoardInput::new_mouse_event(
+                            MouseEventType::PointerPos,
+                            0,
+                            0,
+                            controller.current_event_state(),
+                            Vec2D::new(x as f32, y as f32),
+                            false
+                        ));
+                    },
+                    connect_leave[sender] => move |_| {
+                        sender.input(SketchBoardInput::PointerLeft);
+                    },
+                }
+            }
+        },
+    }
+
+    fn update(&mut self, msg: SketchBoardInput, sender: ComponentSender<Self>, _root: &Self::Root) {
+        // commits may need to show a warning, after `sender` got handed on
+        let output = sender.output_sender().clone();
+        let result = match msg {
+            SketchBoardInput::LoadImage(image) => {
+                // a new image sent to the daemon starts over
+                self.parked_images.clear();
+                self.input_index = 0;
+                self.load_image(image, &sender);
+                ToolUpdateResult::Redraw
+            }
+            SketchBoardInput::SwitchImage { index, image } => {
+                self.switch_image(index, image, &sender);
+                ToolUpdateResult::Redraw
+            }
+            SketchBoardInput::ReplaceImage(image) => {
+                self.replace_image(image);
+                ToolUpdateResult::Redraw
+            }
+            SketchBoardInput::SetDiff(diff) => {
+                self.diff = Some(diff);
+                ToolUpdateResult::Unmodified
+            }
+            SketchBoardInput::AppendImage(image) => {
+                let current = self.renderer.background_image();
+                match stitch::stitch(&current, &image, APP_CONFIG.read().stitch()) {
+                    Some(image) => {
+                        self.replace_image(image);
+                        ToolUpdateResult::Redraw
+                    }
+                    None => {
+                        sender.output_sender().emit(SketchBoardOutput::Flash(
+                            "The images are too large to join".to_string(),
+                        ));
+                        ToolUpdateResult::Unmodified
+                    }
+                }
+            }
+            SketchBoardInput::ImportAnnotations(path) => {
+                match annotation_file::read(&path, self.renderer.image_size()) {
+                    Ok(annotations) => {
+                        sender
+                            .output_sender()
+                            .emit(SketchBoardOutput::Flash(format!(
+                                "Imported {} annotations",
+                                annotations.len()
+                            )));
+                        self.restore_annotations(annotations);
+                        ToolUpdateResult::Redraw
+                    }
+                    Err(e) => {
+                        eprintln!("Error: {e:#}");
+                        sender
+                            .output_sender()
+                            .emit(SketchBoardOutput::Flash(e.to_string()));
+                        ToolUpdateResult::Unmodified
+                    }
+                }
+            }
+            SketchBoardInput::RunHooks(hooks, actions) => self.run_hooks(&hooks, actions),
+            SketchBoardInput::WorkingDirectory(dir) => {
+                self.working_directory =
+                    dir.unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+                ToolUpdateResult::Unmodified
+            }
+            SketchBoardInput::Overlay => {
+                self.overlay = true;
+                let (width, height) = self.renderer.image_size();
+                self.apply_size_rule(width, height);
+                ToolUpdateResult::Redraw
+            }
+            SketchBoardInput::InputEvent(ie) => {
+                let moved =
+                    matches!(&ie, InputEvent::Mouse(me) if me.type_ == MouseEventType::PointerPos);
+                let result = self.handle_input_event(ie, sender.clone());
+                if APP_CONFIG.read().show_status_bar() {
+                    self.send_status(&sender);
+                }
+                if moved && APP_CONFIG.read().crosshair_guides() {
+                    self.renderer.queue_render();
+                }
+                self.refresh_cursor();
+                result
+            }
+            SketchBoardInput::PointerLeft => {
+                self.renderer.set_pointer_left();
+                if APP_CONFIG.read().crosshair_guides() {
+                    ToolUpdateResult::Redraw
+                } else {
+                    ToolUpdateResult::Unmodified
+                }
+            }
+            SketchBoardInput::DragCanceled => self.handle_drag_canceled(),
+            SketchBoardInput::Pinch(event) => self.handle_pinch(event, &sender),
+            SketchBoardInput::KineticPan(velocity) => self.start_kinetic_pan(velocity, &sender),
+            SketchBoardInput::KineticStep => self.handle_kinetic_step(&sender),
+            SketchBoardInput::ToolbarEvent(toolbar_event) => {
+                let result = self.handle_toolbar_event(toolbar_event, sender);
+                self.refresh_cursor();
+                result
+            }
+            SketchBoardInput::RenderResult(img, clean, action) => {
+                // Передаем sender для выхода
+                self.handle_render_result(img, clean, action, sender);
+                ToolUpdateResult::Unmodified
+            }
+            SketchBoardInput::CommitEvent(txt) => {
+                self.handle_text_commit(txt, sender);
+                ToolUpdateResult::Unmodified
+            }
+            SketchBoardInput::StylusEvent(event) => self.handle_stylus_event(event, sender),
+            SketchBoardInput::EraseAt(pos) => self.handle_erase(pos),
+            SketchBoardInput::Select(event) => {
+                self.renderer.select(event);
+                ToolUpdateResult::Redraw
+            }
+            SketchBoardInput::EditTextAt(pos) => self.handle_edit_text(pos, sender),
+            SketchBoardInput::DuplicateSelection => {
+                if self.renderer.duplicate_selection() {
+                    ToolUpdateResult::Redraw
+                } else {
+                    ToolUpdateResult::Unmodified
+                }
+            }
+            SketchBoardInput::DeleteSelection => {
+                if self.renderer.delete_selection() {
+                    ToolUpdateResult::Redraw
+                } else {
+                    ToolUpdateResult::Unmodified
+                }
+            }
+            SketchBoardInput::RecolorPreview(recolor) => {
+                self.renderer.preview_recolor(recolor);
+                ToolUpdateResult::Redraw
+            }
+            SketchBoardInput::ApplyRecolor => {
+                self.renderer.apply_recolor();
+                ToolUpdateResult::Redraw
+            }
+            SketchBoardInput::JumpToDrawable(index) => {
+                if self.renderer.focus_drawable(index) {
+                    ToolUpdateResult::Redraw
+                } else {
+                    ToolUpdateResult::Unmodified
+                }
+            }
+            SketchBoardInput::ToastAction => {
+                if let Some(session) = self.recovery_offer.take() {
+                    self.restore_annotations(session.annotations);
+                    ToolUpdateResult::Redraw
+                } else {
+                    match self.contrast_fix.take() {
+                        Some(index) if self.renderer.add_text_background(index) => {
+                            ToolUpdateResult::Redraw
+                        }
+                        _ => ToolUpdateResult::Unmodified,
+                    }
+                }
+            }
+            SketchBoardInput::Autosnapshot => {
+                if self.snapshots.due(self.renderer.revision()) {
+                    self.renderer.request_snapshot();
+                }
+                ToolUpdateResult::Unmodified
+            }
+            SketchBoardInput::AutosaveRecovery => {
+                let revision = self.renderer.revision();
+                if revision != self.recovery_revision {
+                    self.recovery_revision = revision;
+                    recovery::save(self.renderer.annotations());
+                }
+                ToolUpdateResult::Unmodified
+            }
+            SketchBoardInput::SnapshotResult(image) => {
+                let image = self.export_image(image);
+                self.snapshots.save(
+                    image.width,
+                    image.height,
+                    image.data,
+                    sender.input_sender().clone(),
+                );
+                ToolUpdateResult::Unmodified
+            }
+            SketchBoardInput::SnapshotSaved(snapshot) => {
+                self.snapshots.add(snapshot);
+                ToolUpdateResult::Unmodified
+            }
+            SketchBoardInput::ExportSnapshot(path) => self.handle_export_snapshot(&path),
+            SketchBoardInput::RedactProposals(regions) => {
+                let message = match regions.len() {
+                    0 => "No text found".to_string(),
+                    count => format!(
+                        "{count} text regions found: click one to leave it out, Enter blurs the rest"
+                    ),
+                };
+                output.emit(SketchBoardOutput::Flash(message));
+                self.renderer.set_redact_proposals(regions);
+                ToolUpdateResult::Redraw
+            }
+            SketchBoardInput::ScannedCodes(codes) => self.show_scanned_codes(codes),
+            SketchBoardInput::Refresh => ToolUpdateResult::Redraw,
+        };
+
+        match result {
+            ToolUpdateResult::Commit(drawable) => {
+                self.commit(drawable, &output);
+                self.refresh_screen();
+            }
+            ToolUpdateResult::Unmodified | ToolUpdateResult::StopPropagation => (),
+            ToolUpdateResult::Redraw | ToolUpdateResult::RedrawAndStopPropagation => {
+                self.refresh_screen()
+            }
+        };
+    }
+
+    fn init(
+        image_opt: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let config = APP_CONFIG.read();
+        let tools = ToolsManager::new();
+
+        let im_context = gtk::IMMulticontext::new();
+
+        let mut model = Self {
+            renderer: FemtoVGArea::default(),
+            active_tool: tools.get(&config.initial_tool()),
+            style: Style::default(),
+            tools,
+            im_context,
+            output_scale: 1.0,
+            export_split: APP_CONFIG.read().export_split(),
+            export_canvas: config.export_canvas(),
+            working_directory: std::env::current_dir().unwrap_or_default(),
+            overlay: false,
+            stylus_origin: None,
+            selection_menu: Self::create_selection_menu(),
+            previous_tool: None,
+            snap_drag: None,
+            secondary_drag: None,
+            secondary_moved: false,
+            spring_tool: None,
+            recolor_dialog: None,
+            scanned_codes_dialog: None,
+            annotation_search: AnnotationSearch::builder().launch(()).forward(
+                sender.input_sender(),
+                |output| match output {
+                    AnnotationSearchOutput::Jump(index) => SketchBoardInput::JumpToDrawable(index),
+                },
+            ),
+            minimap_drag: None,
+            pinch: None,
+            drag_canceled: false,
+            kinetic_pan: None,
+            picking_color: false,
+            warn_low_contrast: config.warn_low_contrast(),
+            contrast_fix: None,
+            recovery_offer: None,
+            recovery_revision: 0,
+            snapshots: Snapshots::default(),
+            tool_styles: ToolStyles::load(),
+            current_preset: None,
+            crosshair: None,
+            input_index: 0,
+            parked_images: HashMap::new(),
+            diff: None,
+            hooks: hooks::Script::load(),
+            snapshot_history: SnapshotHistory::builder().launch(()).forward(
+                sender.input_sender(),
+                |output| match output {
+                    SnapshotHistoryOutput::Export(path) => SketchBoardInput::ExportSnapshot(path),
+                },
+            ),
+        };
+
+        let image_dimensions = image_opt.as_ref().map(|i| (i.width(), i.height()));
+        // the daemon starts without an image
+        let earlier = image_opt.as_ref().and_then(recovery::start);
+        let image = image_opt.unwrap_or_else(|| {
+            Pixbuf::new(Colorspace::Rgb, true, 8, 1, 1).expect("Failed to create dummy pixbuf")
+        });
+
+        if let Some(tool_style) = model.tool_styles.get(config.initial_tool()) {
+            model.style.apply_tool_style(tool_style);
+            model
+                .active_tool
+                .borrow_mut()
+                .handle_event(ToolEvent::StyleChanged(model.style));
+            sender
+                .output_sender()
+                .emit(SketchBoardOutput::ToolStyleChanged(tool_style));
+        }
+
+        let area = &mut model.renderer;
+        area.init(
+            sender.input_sender().clone(),
+            model.tools.get_crop_tool(),
+            model.active_tool.clone(),
+            image,
+        );
+
+        let widgets = view_output!();
+
+        model.selection_menu.set_parent(&model.renderer);
+        model.annotation_search.widget().set_parent(&model.renderer);
+        model.snapshot_history.widget().set_parent(&model.renderer);
+        model.refresh_cursor();
+        model.run_hooks(&Hook::for_load(), Vec::new());
+
+        // the board lives as long as the app, in daemon mode across images
+        if let Some(interval) = config.autosnapshot_interval() {
+            let sender = sender.input_sender().clone();
+            glib::timeout_add_local_full(
+                Duration::from_secs(interval.into()),
+                glib::Priority::LOW,
+                move || {
+                    sender.emit(SketchBoardInput::Autosnapshot);
+                    glib::ControlFlow::Continue
+                },
+            );
+        }
+        let mut group = RelmActionGroup::<SelectionActionGroup>::new();
+        let duplicate_sender = sender.input_sender().clone();
+        group.add_action(RelmAction::<DuplicateAction>::new_stateless(move |_| {
+            duplicate_sender.emit(SketchBoardInput::DuplicateSelection);
+        }));
+        let delete_sender = sender.input_sender().clone();
+        group.add_action(RelmAction::<DeleteAction>::new_stateless(move |_| {
+            delete_sender.emit(SketchBoardInput::DeleteSelection);
+        }));
+        group.register_for_widget(&model.renderer);
+
+        if let Some((width, height)) = image_dimensions {
+            model.apply_size_rule(width, height);
+            model.offer_recovery(earlier, sender.output_sender());
+        }
+        if let Some(interval) = config.recovery_interval() {
+            let sender = sender.input_sender().clone();
+            glib::timeout_add_local_full(
+                Duration::from_secs(interval.into()),
+                glib::Priority::LOW,
+                move || {
+                    sender.emit(SketchBoardInput::AutosaveRecovery);
+                    glib::ControlFlow::Continue
+                },
+            );
+        }
+
+        model.im_context.set_client_widget(Some(&model.renderer));
+        model.im_context.set_use_preedit(true);
+
+        if let Ok(module) = std::env::var("GTK_IM_MODULE") {
+            if module.eq_ignore_ascii_case("fcitx") || module.eq_ignore_ascii_case("fcitx5") {
+                model.im_context.set_context_id(Some("fcitx"));
+            }
+        }
+
+        {
+            let sender = sender.input_sender().clone();
+            model.im_context.connect_commit(move |_cx, txt| {
+                sender.emit(SketchBoardInput::new_commit_event(TextEventMsg::Commit(
+                    txt.to_string(),
+                )));
+            });
+        }
+
+        {
+            let sender = sender.input_sender().clone();
+            model.im_context.connect_preedit_changed(move |cx| {
+                let (text, attrs, cursor) = cx.preedit_string();
+                let cursor_chars = if cursor >= 0 {
+                    Some(cursor as usize)
+                } else {
+                    None
+                };
+                let spans = spans_from_pango_attrs(text.as_str(), Some(attrs));
+                sender.emit(SketchBoardInput::new_commit_event(TextEventMsg::Preedit {
+                    text: text.to_string(),
+                    cursor_chars,
+                    spans,
+                }));
+            });
+        }
+
+        {
+            let sender = sender.input_sender().clone();
+            model.im_context.connect_preedit_end(move |_cx| {
+                sender.emit(SketchBoardInput::new_commit_event(TextEventMsg::PreeditEnd));
+            });
+        }
+
+        let focus_controller = gtk::EventControllerFocus::new();
+        {
+            let im_context = model.im_context.clone();
+            focus_controller.connect_enter(move |_| {
+                im_context.focus_in();
+            });
+        }
+        {
+            let im_context = model.im_context.clone();
+            focus_controller.connect_leave(move |_| {
+                im_context.focus_out();
+            });
+        }
+        model.renderer.add_controller(focus_controller);
+
+        let widget_ref: gtk::Widget = model.renderer.clone().upcast();
+        model
+            .active_tool
+            .borrow_mut()
+            .set_im_context(Some(crate::tools::InputContext {
+                im_context: model.im_context.clone(),
+                widget: widget_ref,
+            }));
+        model
+            .active_tool
+            .borrow_mut()
+            .set_sender(Self::tool_sender(&sender));
+
+        ComponentParts { model, widgets }
+    }
+}
@@ -0,0 +1,824 @@
+use anyhow::anyhow;
+
+use femtovg::rgb::ComponentBytes;
+use gdk_pixbuf::glib::Bytes;
+use gdk_pixbuf::{Colorspace, Pixbuf};
+use std::io::Write;
+use std::panic;
+use std::process::{Command, Stdio};
+use std::{fs, io, thread};
+
+use gtk::prelude::*;
+
+use relm4::gtk::gdk::{DisplayManager, Texture};
+use relm4::{gtk, ComponentSender, RelmWidgetExt};
+
+use crate::configuration::{Action, ExportCanvas, TextFormat, APP_CONFIG};
+use crate::notification::{log_result, log_saved};
+use crate::tools::ToolUpdateResult;
+use crate::upload;
+
+use super::{ImageDataSendable, RenderedImage, SketchBoard, SketchBoardInput, SketchBoardOutput};
+
+use image::imageops::FilterType;
+use image::{ImageBuffer, Rgba};
+use serde_derive::Serialize;
+
+/// Written next to the tiles of a split export
+#[derive(Serialize)]
+struct SplitSummary {
+    width: u32,
+    height: u32,
+    tiles: Vec<TileSummary>,
+}
+
+#[derive(Serialize)]
+struct TileSummary {
+    path: String,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Shows a progress toast from its creation until it is dropped, however the operation ends
+struct ProgressToast(relm4::Sender<SketchBoardOutput>);
+
+impl ProgressToast {
+    fn start(text: &str, sender: &ComponentSender<SketchBoard>) -> Self {
+        let sender = sender.output_sender().clone();
+        sender.emit(SketchBoardOutput::ProgressStarted(text.to_string()));
+        Self(sender)
+    }
+}
+
+impl Drop for ProgressToast {
+    fn drop(&mut self) {
+        self.0.emit(SketchBoardOutput::ProgressEnded);
+    }
+}
+
+impl SketchBoard {
+    fn scale_output(width: u32, height: u32, data: Vec<u8>, scale: f32) -> (u32, u32, Vec<u8>) {
+        if scale == 1.0 {
+            return (width, height, data);
+        }
+
+        let new_width = ((width as f32 * scale).round() as u32).max(1);
+        let new_height = ((height as f32 * scale).round() as u32).max(1);
+        let buffer: ImageBuffer<Rgba<u8>, Vec<u8>> =
+            ImageBuffer::from_raw(width, height, data).unwrap();
+        let scaled = image::imageops::resize(&buffer, new_width, new_height, FilterType::Triangle);
+
+        (new_width, new_height, scaled.into_raw())
+    }
+
+    /// Center the image on a canvas of fixed size filled with the export canvas color,
+    /// images larger than the canvas are scaled down to fit
+    fn place_on_canvas(
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+        canvas: ExportCanvas,
+    ) -> (u32, u32, Vec<u8>) {
+        let mut image: ImageBuffer<Rgba<u8>, Vec<u8>> =
+            ImageBuffer::from_raw(width, height, data).unwrap();
+
+        let scale = (canvas.width() as f32 / width as f32)
+            .min(canvas.height() as f32 / height as f32)
+            .min(1.0);
+        if scale < 1.0 {
+            let new_width = ((width as f32 * scale).round() as u32).clamp(1, canvas.width());
+            let new_height = ((height as f32 * scale).round() as u32).clamp(1, canvas.height());
+            image = image::imageops::resize(&image, new_width, new_height, FilterType::Triangle);
+        }
+
+        let color = APP_CONFIG.read().export_canvas_color();
+        let mut result = ImageBuffer::from_pixel(
+            canvas.width(),
+            canvas.height(),
+            Rgba([color.r, color.g, color.b, color.a]),
+        );
+        image::imageops::overlay(
+            &mut result,
+            &image,
+            ((canvas.width() - image.width()) / 2) as i64,
+            ((canvas.height() - image.height()) / 2) as i64,
+        );
+
+        (canvas.width(), canvas.height(), result.into_raw())
+    }
+
+    pub fn handle_render_result(
+        &self,
+        image: RenderedImage,
+        clean: Option<RenderedImage>,
+        actions: Vec<Action>,
+        sender: ComponentSender<Self>,
+    ) {
+        let image_data = self.export_image(image);
+        let clean_data = clean.map(|clean| self.export_image(clean));
+
+        for action in &actions {
+            match action {
+                Action::SaveToClipboard => {
+                    self.handle_copy_clipboard(
+                        image_data.width,
+                        image_data.height,
+                        image_data.data.clone(),
+                    );
+                }
+                Action::SaveToFile(template) => {
+                    self.handle_save(
+                        image_data.width,
+                        image_data.height,
+                        image_data.data.clone(),
+                        template.as_deref(),
+                        &sender,
+                    );
+                }
+                Action::SaveToFileAs => {
+                    let bytes = Bytes::from(&image_data.data);
+                    let pixbuf = Pixbuf::from_bytes(
+                        &bytes,
+                        Colorspace::Rgb,
+                        true,
+                        8,
+                        image_data.width as i32,
+                        image_data.height as i32,
+                        (image_data.width * 4) as i32,
+                    );
+                    self.handle_save_as(&pixbuf);
+                }
+                Action::CopyText => self.handle_copy_text(),
+                Action::ScanCodes => Self::scan_codes(&self.renderer.background_image(), &sender),
+                Action::Upload => Self::handle_upload(&image_data, &sender),
+                Action::SaveBeforeAfter => {
+                    if let Some(clean_data) = &clean_data {
+                        self.handle_save_before_after(clean_data, &image_data, &sender);
+                    }
+                }
+                _ => (),
+            }
+
+            if APP_CONFIG.read().early_exit() || *action == Action::Exit {
+                sender.output_sender().emit(SketchBoardOutput::Exit);
+                return;
+            }
+        }
+    }
+
+    /// Scale the rendered image and place it on the export canvas, as it gets exported
+    pub fn export_image(&self, image: RenderedImage) -> ImageDataSendable {
+        let (buf, w, h) = image.into_contiguous_buf();
+        let raw_data = buf.as_bytes().to_vec();
+        let (width, height, raw_data) =
+            Self::scale_output(w as u32, h as u32, raw_data, self.output_scale);
+        let (width, height, data) = match self.export_canvas {
+            Some(canvas) => Self::place_on_canvas(width, height, raw_data, canvas),
+            None => (width, height, raw_data),
+        };
+        ImageDataSendable {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Save to `template`, the `output-filename` if `None`
+    fn handle_save(
+        &self,
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+        template: Option<&str>,
+        sender: &ComponentSender<Self>,
+    ) {
+        if let Some(output_filename) = self.output_filename(template) {
+            self.save(width, height, data, output_filename, sender);
+        }
+    }
+
+    /// Save the image without and with annotations as `…-before` and `…-after` of the
+    /// `output-filename`, both get the same time in their names
+    fn handle_save_before_after(
+        &self,
+        clean: &ImageDataSendable,
+        annotated: &ImageDataSendable,
+        sender: &ComponentSender<Self>,
+    ) {
+        let Some(output_filename) = self.output_filename(None) else {
+            return;
+        };
+        if output_filename == "-" {
+            println!("Cannot write a before/after pair to stdout");
+            return;
+        }
+        let path = std::path::Path::new(&output_filename);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = path
+            .extension()
+            .map_or(String::new(), |e| format!(".{}", e.to_string_lossy()));
+        for (image, suffix) in [(clean, "before"), (annotated, "after")] {
+            let name = path.with_file_name(format!("{stem}-{suffix}{extension}"));
+            self.save(
+                image.width,
+                image.height,
+                image.data.clone(),
+                name.to_string_lossy().into_owned(),
+                sender,
+            );
+        }
+    }
+
+    /// `template` with `{cwd}`, the date and a leading `~` expanded, the `output-filename`
+    /// if `None`
+    pub fn output_filename(&self, template: Option<&str>) -> Option<String> {
+        let output_filename = template
+            .map(str::to_string)
+            .or_else(|| APP_CONFIG.read().output_filename().cloned());
+        let mut output_filename = match output_filename {
+            None => {
+                println!("No Output filename specified!");
+                return None;
+            }
+            Some(o) => o,
+        };
+        if output_filename.contains("{cwd}") {
+            // the directory must not be taken for format specifiers
+            let cwd = self.working_directory.to_string_lossy().replace('%', "%%");
+            output_filename = output_filename.replace("{cwd}", &cwd);
+        }
+        if output_filename.contains("{name}") {
+            let name = self.input_name().replace('%', "%%");
+            output_filename = output_filename.replace("{name}", &name);
+        }
+
+        let delayed_format = chrono::Local::now().format(&output_filename);
+        let result = panic::catch_unwind(|| {
+            delayed_format.to_string();
+        });
+
+        if result.is_err() {
+            println!("Warning: chrono format error");
+        } else {
+            output_filename = format!("{delayed_format}");
+        }
+
+        if let Some(tilde_stripped) =
+            output_filename.strip_prefix(&format!("~{}", std::path::MAIN_SEPARATOR_STR))
+        {
+            if let Some(mut p) = std::env::home_dir() {
+                p.push(tilde_stripped);
+                output_filename = p.to_string_lossy().into_owned();
+            }
+        }
+        Some(output_filename)
+    }
+
+    /// Save to `output_filename` in the background, split into tiles if configured
+    fn save(
+        &self,
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+        output_filename: String,
+        sender: &ComponentSender<Self>,
+    ) {
+        let tiles = self
+            .export_split
+            .map(|split| split.tiles(width, height))
+            .filter(|tiles| tiles.len() > 1 && output_filename != "-");
+        let snippet = self.export_snippet();
+        let progress = ProgressToast::start(
+            if tiles.is_some() {
+                "Saving tiles…"
+            } else {
+                "Saving image…"
+            },
+            sender,
+        );
+
+        thread::spawn(move || {
+            let _progress = progress;
+            if let Some(tiles) = tiles {
+                let buffer = ImageBuffer::from_raw(width, height, data).unwrap();
+                let message = match Self::save_tiles(&buffer, &tiles, &output_filename) {
+                    Ok((paths, summary)) => format!(
+                        "Saved {} tiles:\n{}\nSummary in '{summary}'.",
+                        paths.len(),
+                        paths.join("\n")
+                    ),
+                    Err(e) => format!("Error while saving file: {e}"),
+                };
+                glib::idle_add_once(move || {
+                    log_result(&message, !APP_CONFIG.read().disable_notifications());
+                });
+                return;
+            }
+
+            let buffer: ImageBuffer<Rgba<u8>, Vec<u8>> =
+                ImageBuffer::from_raw(width, height, data).unwrap();
+
+            let mut png_data = Vec::new();
+            let mut cursor = std::io::Cursor::new(&mut png_data);
+
+            if let Err(e) = buffer.write_to(&mut cursor, image::ImageFormat::Png) {
+                // ИСПРАВЛЕНИЕ: используем idle_add_once (глобальный), а не local
+                glib::idle_add_once(move || {
+                    log_result(
+                        &format!("Error encoding PNG: {e}"),
+                        !APP_CONFIG.read().disable_notifications(),
+                    );
+                });
+                return;
+            }
+            let png_data = Self::apply_export_filter(png_data);
+
+            if output_filename == "-" {
+                let stdout = io::stdout();
+                let mut handle = stdout.lock();
+                if let Err(e) = handle.write_all(&png_data) {
+                    eprintln!("Error writing image to stdout: {e}");
+                }
+            } else {
+                match fs::write(&output_filename, png_data) {
+                    Ok(_) => {
+                        let path = fs::canonicalize(&output_filename)
+                            .map_or(output_filename.clone(), |p| {
+                                p.to_string_lossy().into_owned()
+                            });
+                        // ИСПРАВЛЕНИЕ: используем idle_add_once
+                        glib::idle_add_once(move || {
+                            log_saved(
+                                &format!("File saved to '{}'.", &output_filename),
+                                &path,
+                                !APP_CONFIG.read().disable_notifications(),
+                            );
+                            if let Some(snippet) = snippet {
+                                Self::copy_export_snippet(snippet, &path);
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        // ИСПРАВЛЕНИЕ: используем idle_add_once
+                        glib::idle_add_once(move || {
+                            log_result(
+                                &format!("Error while saving file: {e}"),
+                                !APP_CONFIG.read().disable_notifications(),
+                            );
+                        });
+                    }
+                }
+            }
+        });
+    }
+
+    /// Pipe `png_data` through the `export-filter-command`, if any. Returns the unfiltered
+    /// image if the command fails or has no output.
+    fn apply_export_filter(png_data: Vec<u8>) -> Vec<u8> {
+        let Some(command) = APP_CONFIG.read().export_filter_command().cloned() else {
+            return png_data;
+        };
+
+        let result = (|| -> anyhow::Result<Vec<u8>> {
+            let mut child = Command::new("sh")
+                .arg("-c")
+                .arg(&command)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()?;
+
+            // written from another thread, the filter may start writing before it read all
+            let mut child_stdin = child.stdin.take().unwrap();
+            let input = png_data.clone();
+            let writer = thread::spawn(move || child_stdin.write_all(&input));
+
+            let output = child.wait_with_output()?;
+            writer
+                .join()
+                .map_err(|_| anyhow!("Writing to process '{command}' failed."))??;
+            if !output.status.success() {
+                return Err(anyhow!(
+                    "Process '{command}' failed with {}.",
+                    output.status
+                ));
+            }
+            if output.stdout.is_empty() {
+                return Err(anyhow!("Process '{command}' returned no image."));
+            }
+            Ok(output.stdout)
+        })();
+
+        result.unwrap_or_else(|e| {
+            eprintln!("Error in export filter, saving the unfiltered image: {e}");
+            png_data
+        })
+    }
+
+    /// Scan the loaded image for codes in the background, annotations drawn over a code must
+    /// not break it
+    fn scan_codes(image: &Pixbuf, sender: &ComponentSender<Self>) {
+        let png_data = match image.save_to_bufferv("png", &Vec::new()) {
+            Ok(d) => d,
+            Err(e) => {
+                println!("Error serializing image: {e}");
+                return;
+            }
+        };
+        let input = sender.input_sender().clone();
+        relm4::spawn_local(async move {
+            let codes = relm4::spawn_blocking(move || -> anyhow::Result<Vec<String>> {
+                let command = APP_CONFIG.read().code_scan_command().to_string();
+                let mut child = Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()?;
+                let mut child_stdin = child.stdin.take().unwrap();
+                let writer = thread::spawn(move || child_stdin.write_all(&png_data));
+
+                let output = child.wait_with_output()?;
+                // the scanner may exit before reading all of the image if it cannot handle it
+                let _ = writer.join();
+                let codes = parse_codes(&String::from_utf8_lossy(&output.stdout));
+                // scanners like zbarimg fail quietly if there is no code
+                if codes.is_empty() && !output.status.success() && !output.stderr.is_empty() {
+                    return Err(anyhow!(
+                        "Process '{command}' failed: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ));
+                }
+                Ok(codes)
+            })
+            .await;
+            match codes {
+                Ok(Ok(codes)) => input.emit(SketchBoardInput::ScannedCodes(codes)),
+                Ok(Err(e)) => log_result(
+                    &format!("Error while scanning for codes: {e}"),
+                    !APP_CONFIG.read().disable_notifications(),
+                ),
+                Err(_) => (),
+            }
+        });
+    }
+
+    /// Upload the image in the background as configured in the `[upload]` section and copy
+    /// the Markdown link to it
+    fn handle_upload(image_data: &ImageDataSendable, sender: &ComponentSender<Self>) {
+        let Some(upload) = APP_CONFIG.read().upload().cloned() else {
+            log_result(
+                "No upload target configured.",
+                !APP_CONFIG.read().disable_notifications(),
+            );
+            return;
+        };
+        let (width, height) = (image_data.width, image_data.height);
+        let data = image_data.data.clone();
+        let progress = ProgressToast::start("Uploading image…", sender);
+        relm4::spawn_local(async move {
+            let _progress = progress;
+            let link = relm4::spawn_blocking(move || -> anyhow::Result<String> {
+                let buffer: ImageBuffer<Rgba<u8>, Vec<u8>> =
+                    ImageBuffer::from_raw(width, height, data)
+                        .ok_or_else(|| anyhow!("Invalid image size."))?;
+                let mut png_data = Vec::new();
+                let mut cursor = std::io::Cursor::new(&mut png_data);
+                buffer.write_to(&mut cursor, image::ImageFormat::Png)?;
+                let png_data = Self::apply_export_filter(png_data);
+                upload::upload(&upload, &png_data)
+            })
+            .await;
+            let notify = !APP_CONFIG.read().disable_notifications();
+            match link {
+                Ok(Ok(link)) => {
+                    if Self::set_clipboard_text(&link) {
+                        log_result("Uploaded, copied link to clipboard.", notify);
+                    }
+                }
+                Ok(Err(e)) => log_result(&format!("Error while uploading: {e}"), notify),
+                Err(_) => (),
+            }
+        });
+    }
+
+    /// Save the `tiles` of `buffer` as numbered PNG files next to `output_filename`, along
+    /// with a JSON summary listing them. Returns the paths of the tiles and the summary.
+    fn save_tiles(
+        buffer: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+        tiles: &[(u32, u32, u32, u32)],
+        output_filename: &str,
+    ) -> anyhow::Result<(Vec<String>, String)> {
+        let path = std::path::Path::new(output_filename);
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let extension = path
+            .extension()
+            .map_or("png".into(), |e| e.to_string_lossy());
+        let digits = tiles.len().to_string().len();
+
+        let summary_tiles = tiles
+            .iter()
+            .enumerate()
+            .map(|(i, &(x, y, width, height))| {
+                let tile = image::imageops::crop_imm(buffer, x, y, width, height).to_image();
+                let name = format!("{stem}-{:0digits$}.{extension}", i + 1);
+                let tile_path = path.with_file_name(name).to_string_lossy().into_owned();
+                tile.save_with_format(&tile_path, image::ImageFormat::Png)?;
+                Ok(TileSummary {
+                    path: tile_path,
+                    x,
+                    y,
+                    width,
+                    height,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let summary = SplitSummary {
+            width: buffer.width(),
+            height: buffer.height(),
+            tiles: summary_tiles,
+        };
+        let summary_path = path
+            .with_file_name(format!("{stem}.json"))
+            .to_string_lossy()
+            .into_owned();
+        fs::write(&summary_path, serde_json::to_string_pretty(&summary)?)?;
+
+        Ok((
+            summary.tiles.into_iter().map(|tile| tile.path).collect(),
+            summary_path,
+        ))
+    }
+
+    fn handle_save_as(&self, image: &Pixbuf) {
+        let data = match image.save_to_bufferv("png", &Vec::new()) {
+            Ok(d) => d,
+            Err(e) => {
+                println!("Error serializing image: {e}");
+                return;
+            }
+        };
+
+        let root = self.renderer.toplevel_window();
+        let snippet = self.export_snippet();
+
+        relm4::spawn_local(async move {
+            let Ok(data) = relm4::spawn_blocking(move || Self::apply_export_filter(data)).await
+            else {
+                return;
+            };
+            let builder = gtk::FileChooserDialog::builder()
+                .modal(false)
+                .title("Save Image As")
+                .action(gtk::FileChooserAction::Save);
+
+            let dialog = match root {
+                Some(w) => builder.transient_for(&w),
+                None => builder,
+            }
+            .build();
+
+            dialog.add_buttons(&[
+                ("Cancel", gtk::ResponseType::Cancel),
+                ("Save", gtk::ResponseType::Accept),
+            ]);
+
+            dialog.connect_response(move |dialog, response| {
+                if response == gtk::ResponseType::Accept {
+                    if let Some(file) = dialog.file() {
+                        let output_filename = match file.path() {
+                            Some(path) => path.to_string_lossy().into_owned(),
+                            None => return,
+                        };
+
+                        match fs::write(&output_filename, &data) {
+                            Err(e) => log_result(
+                                &format!("Error while saving file: {e}"),
+                                !APP_CONFIG.read().disable_notifications(),
+                            ),
+                            Ok(_) => {
+                                log_saved(
+                                    &format!("File saved to '{}'.", &output_filename),
+                                    &output_filename,
+                                    !APP_CONFIG.read().disable_notifications(),
+                                );
+                                if let Some(snippet) = snippet.clone() {
+                                    Self::copy_export_snippet(snippet, &output_filename);
+                                }
+                            }
+                        };
+                    }
+                }
+                dialog.close();
+            });
+
+            dialog.show();
+        });
+    }
+
+    fn handle_copy_clipboard(&self, width: u32, height: u32, data: Vec<u8>) {
+        let copy_command = APP_CONFIG.read().copy_command().cloned();
+
+        if let Some(command) = copy_command {
+            thread::spawn(move || {
+                let buffer: ImageBuffer<Rgba<u8>, Vec<u8>> =
+                    ImageBuffer::from_raw(width, height, data.clone()).unwrap();
+
+                let mut png_data = Vec::new();
+                let mut cursor = std::io::Cursor::new(&mut png_data);
+
+                if let Err(e) = buffer.write_to(&mut cursor, image::ImageFormat::Png) {
+                    eprintln!("Error encoding png for clipboard: {}", e);
+                    return;
+                }
+
+                let result = (|| -> anyhow::Result<()> {
+                    let mut child = Command::new("sh")
+                        .arg("-c")
+                        .arg(&command)
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::null())
+                        .spawn()?;
+
+                    let child_stdin = child.stdin.as_mut().unwrap();
+                    child_stdin.write_all(&png_data)?;
+
+                    if !child.wait()?.success() {
+                        return Err(anyhow!("Writing to process '{command}' failed."));
+                    }
+                    Ok(())
+                })();
+
+                // ИСПРАВЛЕНИЕ: используем idle_add_once
+                glib::idle_add_once(move || match result {
+                    Err(e) => println!("Error saving {e}"),
+                    Ok(()) => {
+                        log_result(
+                            "Copied to clipboard.",
+                            !APP_CONFIG.read().disable_notifications(),
+                        );
+                    }
+                });
+            });
+        } else {
+            let bytes = Bytes::from(&data);
+            let pixbuf = Pixbuf::from_bytes(
+                &bytes,
+                Colorspace::Rgb,
+                true,
+                8,
+                width as i32,
+                height as i32,
+                (width * 4) as i32,
+            );
+            let texture = Texture::for_pixbuf(&pixbuf);
+
+            let display = DisplayManager::get().default_display();
+            if let Some(display) = display {
+                display.clipboard().set_texture(&texture);
+                log_result(
+                    "Copied to clipboard (GTK).",
+                    !APP_CONFIG.read().disable_notifications(),
+                );
+            }
+        }
+    }
+
+    /// Copy the text annotations to the clipboard, ordered and joined as configured
+    pub fn handle_copy_text(&self) {
+        let (order, format, notify) = {
+            let config = APP_CONFIG.read();
+            (
+                config.copy_text_order(),
+                config.copy_text_format(),
+                !config.disable_notifications(),
+            )
+        };
+        let texts = self.renderer.texts_in_order(order);
+        if texts.is_empty() {
+            log_result("No text annotations to copy.", notify);
+            return;
+        }
+        let text = match format {
+            TextFormat::Plain => texts.join("\n\n"),
+            TextFormat::Markdown => Self::markdown_list(&texts),
+        };
+        if Self::set_clipboard_text(&text) {
+            log_result("Copied text to clipboard.", notify);
+        }
+    }
+
+    fn markdown_list(texts: &[String]) -> String {
+        texts
+            .iter()
+            // continued lines are indented to stay in their list item
+            .map(|text| format!("- {}", text.trim_end().replace('\n', "\n  ")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn set_clipboard_text(text: &str) -> bool {
+        match DisplayManager::get().default_display() {
+            Some(display) => {
+                display.clipboard().set_text(text);
+                true
+            }
+            None => {
+                eprintln!("Cannot open default display for clipboard.");
+                false
+            }
+        }
+    }
+
+    /// The `export-snippet` template along with the text annotations to fill in, the path is
+    /// only known once the image is saved
+    fn export_snippet(&self) -> Option<(String, String)> {
+        let config = APP_CONFIG.read();
+        let template = config.export_snippet()?.clone();
+        let texts = self.renderer.texts_in_order(config.copy_text_order());
+        Some((template, Self::markdown_list(&texts)))
+    }
+
+    /// Copy the snippet for the image saved at `path` to the clipboard
+    fn copy_export_snippet((template, texts): (String, String), path: &str) {
+        let snippet = template.replace("{path}", path).replace("{texts}", &texts);
+        if Self::set_clipboard_text(snippet.trim_end()) {
+            log_result(
+                "Copied snippet to clipboard.",
+                !APP_CONFIG.read().disable_notifications(),
+            );
+        }
+    }
+
+    pub fn handle_export_snapshot(&self, path: &std::path::Path) -> ToolUpdateResult {
+        match Pixbuf::from_file(path) {
+            Ok(pixbuf) => self.handle_save_as(&pixbuf),
+            Err(e) => log_result(
+                &format!("Error while loading snapshot: {e}"),
+                !APP_CONFIG.read().disable_notifications(),
+            ),
+        }
+        ToolUpdateResult::Unmodified
+    }
+}
+
+/// The codes printed by the scan command. zbarimg's XML output keeps codes spanning several
+/// lines whole, other commands print one code per line.
+fn parse_codes(output: &str) -> Vec<String> {
+    if !output.contains("<barcodes") {
+        return output
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+
+    let mut codes = Vec::new();
+    let mut rest = output;
+    while let Some(start) = rest.find("<data") {
+        let Some(length) = rest[start..].find("</data>") else {
+            break;
+        };
+        // zbarimg splits the CDATA section around any "]]>" in the code, binary codes stay
+        // base64 encoded
+        let mut code = String::new();
+        let mut element = &rest[start..start + length];
+        while let Some(open) = element.find("<![CDATA[") {
+            element = &element[open + "<![CDATA[".len()..];
+            let close = element.find("]]>").unwrap_or(element.len());
+            code.push_str(&element[..close]);
+            element = &element[close..];
+        }
+        codes.push(code);
+        rest = &rest[start + length + "</data>".len()..];
+    }
+    codes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_codes;
+
+    #[test]
+    fn codes_keep_their_lines() {
+        let output = "<barcodes xmlns='http://zbar.sourceforge.net/2008/barcode'>
+<source href='-'>
+<index num='0'>
+<symbol type='QR-Code' quality='1'><data><![CDATA[first
+second]]></data></symbol>
+<symbol type='EAN-13' quality='1'><data><![CDATA[a]]]]><![CDATA[>b]]></data></symbol>
+</index>
+</source>
+</barcodes>
+";
+        assert_eq!(parse_codes(output), ["first\nsecond", "a]]>b"]);
+    }
+
+    #[test]
+    fn plain_output_has_a_code_per_line() {
+        assert_eq!(parse_codes("one\n\ntwo\n"), ["one", "two"]);
+    }
+}
@@ -0,0 +1,1159 @@
+use keycode::{KeyMap, KeyMappingId};
+use std::time::Instant;
+
+use gtk::prelude::*;
+
+use relm4::gtk::gdk::{InputSource, Key, ModifierType};
+use relm4::{gtk, gtk::glib, ComponentSender};
+
+use crate::configuration::{Action, Configuration, MouseButtonBinding, ScrollAction, APP_CONFIG};
+use crate::femtovg_area::FemtoVGArea;
+use crate::math::Vec2D;
+use crate::theme;
+use crate::tools::{Blur, Drawable, ToolEvent, ToolUpdateResult, Tools};
+use crate::ui::toolbars::ToolbarEvent;
+
+use super::{
+    Crosshair, InputEvent, KeyEventMsg, MouseButton, MouseEventMsg, MouseEventType, Pinch,
+    PinchEvent, SketchBoard, SketchBoardInput, SketchBoardOutput, SpringTool, StylusEventMsg,
+    StylusEventType, TextEventMsg, CROSSHAIR_STEP, KINETIC_FRAME, KINETIC_FRICTION,
+    KINETIC_MIN_VELOCITY, SPRING_TOOL_HOLD_DURATION,
+};
+
+impl SketchBoard {
+    /// Route an input event: modes like keyboard placement see it first, then the active
+    /// tool, shortcuts and finally the board itself, e.g. for panning and zooming
+    pub fn handle_input_event(
+        &mut self,
+        mut ie: InputEvent,
+        sender: ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        if !matches!(&ie, InputEvent::Mouse(me) if me.type_ == MouseEventType::PointerPos) {
+            self.kinetic_pan = None;
+        }
+        if !apply_mouse_bindings(&mut ie) || self.skip_canceled_drag(&ie) {
+            ToolUpdateResult::Unmodified
+        } else if let Some(result) = self.handle_color_pick(&ie, &sender) {
+            result
+        } else if let Some(result) = self.handle_redact_proposals(&ie, &sender) {
+            result
+        } else if let Some(result) = self.handle_keyboard_placement(&ie, &sender) {
+            result
+        } else if let InputEvent::Key(ke) = ie {
+            self.handle_key_event(&ie, ke, sender)
+        } else if let Some(result) = self.handle_spring_tool_release(&ie, &sender) {
+            result
+        } else if let Some(result) = self.handle_minimap_drag(&ie) {
+            result
+        } else if let Some(pos) = self.selection_menu_position(&ie) {
+            // positions are still in widget coordinates here
+            self.selection_menu
+                .set_pointing_to(Some(&gtk::gdk::Rectangle::new(
+                    pos.x as i32,
+                    pos.y as i32,
+                    1,
+                    1,
+                )));
+            self.selection_menu.popup();
+            ToolUpdateResult::Unmodified
+        } else {
+            handle_event_mouse_input(&mut ie, &self.renderer);
+            if let Some(result) = self.handle_secondary_tool(&ie, &sender) {
+                result
+            } else {
+                self.snap_mouse_event(&mut ie);
+                let active_tool_result = self
+                    .active_tool
+                    .borrow_mut()
+                    .handle_event(ToolEvent::Input(ie.clone()));
+
+                match active_tool_result {
+                    ToolUpdateResult::StopPropagation
+                    | ToolUpdateResult::RedrawAndStopPropagation => active_tool_result,
+                    _ => {
+                        let zoom = self.renderer.zoom();
+                        let result = handle_mouse_event(&mut ie, &self.renderer);
+                        if self.renderer.zoom() != zoom {
+                            self.flash_zoom(&sender);
+                        }
+                        result.unwrap_or(active_tool_result)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Key presses go to the active tool first, shortcuts only see the keys it passes on
+    fn handle_key_event(
+        &mut self,
+        ie: &InputEvent,
+        ke: KeyEventMsg,
+        sender: ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        let active_tool_result = self
+            .active_tool
+            .borrow_mut()
+            .handle_event(ToolEvent::Input(ie.clone()));
+
+        match active_tool_result {
+            ToolUpdateResult::StopPropagation | ToolUpdateResult::RedrawAndStopPropagation => {
+                active_tool_result
+            }
+            _ => match key_command(&ke, &APP_CONFIG.read(), self.renderer.has_selection()) {
+                Some(command) => self.run_key_command(command, active_tool_result, sender),
+                None => active_tool_result,
+            },
+        }
+    }
+
+    /// Translate stylus input into the mouse events the tools understand, with the pen
+    /// pressure attached. The eraser end of the pen removes drawables instead.
+    pub fn handle_stylus_event(
+        &mut self,
+        event: StylusEventMsg,
+        sender: ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        if event.eraser {
+            if event.type_ == StylusEventType::Up {
+                self.stylus_origin = None;
+                return ToolUpdateResult::Unmodified;
+            }
+            let pos = self.renderer.abs_canvas_to_image_coordinates(event.pos);
+            return self.handle_erase(pos);
+        }
+
+        let mouse_event = |type_, pos| {
+            SketchBoardInput::InputEvent(InputEvent::Mouse(MouseEventMsg {
+                type_,
+                button: MouseButton::Primary,
+                modifier: event.modifier,
+                pos,
+                n_pressed: 1,
+                release: type_ == MouseEventType::Release,
+                pressure: event.pressure,
+            }))
+        };
+
+        match event.type_ {
+            StylusEventType::Down => {
+                self.stylus_origin = Some(event.pos);
+                sender.input(mouse_event(MouseEventType::Click, event.pos));
+                sender.input(mouse_event(MouseEventType::BeginDrag, event.pos));
+            }
+            StylusEventType::Motion => {
+                if let Some(origin) = self.stylus_origin {
+                    sender.input(mouse_event(MouseEventType::UpdateDrag, event.pos - origin));
+                }
+            }
+            StylusEventType::Up => {
+                if let Some(origin) = self.stylus_origin.take() {
+                    sender.input(mouse_event(MouseEventType::EndDrag, event.pos - origin));
+                    sender.input(mouse_event(MouseEventType::Release, event.pos));
+                }
+            }
+        }
+        ToolUpdateResult::Unmodified
+    }
+
+    /// While a color is picked for the color dialog, a click takes the color of the image
+    /// there and Escape cancels. Zooming and panning still work meanwhile.
+    pub fn handle_color_pick(
+        &mut self,
+        ie: &InputEvent,
+        sender: &ComponentSender<Self>,
+    ) -> Option<ToolUpdateResult> {
+        if !self.picking_color {
+            return None;
+        }
+        let picked = match ie {
+            InputEvent::Key(ke) if ke.key == Key::Escape => None,
+            InputEvent::Mouse(me)
+                if me.button == MouseButton::Primary && me.type_ == MouseEventType::Click =>
+            {
+                let pos = self.renderer.abs_canvas_to_image_coordinates(me.pos);
+                self.renderer.background_color_at(pos)
+            }
+            InputEvent::Mouse(me)
+                if me.button != MouseButton::Primary
+                    || matches!(
+                        me.type_,
+                        MouseEventType::Scroll | MouseEventType::PointerPos
+                    ) =>
+            {
+                return None;
+            }
+            _ => return Some(ToolUpdateResult::Unmodified),
+        };
+        self.picking_color = false;
+        sender
+            .output_sender()
+            .emit(SketchBoardOutput::ColorPicked(picked));
+        Some(ToolUpdateResult::Unmodified)
+    }
+
+    /// While text regions are proposed, clicking one drops it, Enter blurs the remaining ones
+    /// in a single undo step and Escape drops all of them
+    pub fn handle_redact_proposals(
+        &mut self,
+        ie: &InputEvent,
+        sender: &ComponentSender<Self>,
+    ) -> Option<ToolUpdateResult> {
+        if !self.renderer.has_redact_proposals() {
+            return None;
+        }
+        match ie {
+            InputEvent::Key(ke) if ke.modifier.is_empty() => match ke.key {
+                Key::Return | Key::KP_Enter => {
+                    let mut style = self.style;
+                    if let Some(tool_style) = self.tool_styles.get(Tools::Blur) {
+                        style.apply_tool_style(tool_style);
+                    }
+                    let blurs = self
+                        .renderer
+                        .take_redact_proposals()
+                        .into_iter()
+                        .map(|(min, max)| {
+                            Box::new(Blur::new(min, max - min, style)) as Box<dyn Drawable>
+                        })
+                        .collect();
+                    self.renderer.commit_group(blurs);
+                    Some(ToolUpdateResult::Redraw)
+                }
+                Key::Escape => {
+                    self.renderer.take_redact_proposals();
+                    sender
+                        .output_sender()
+                        .emit(SketchBoardOutput::Flash("Redaction canceled".to_string()));
+                    Some(ToolUpdateResult::Redraw)
+                }
+                _ => None,
+            },
+            // the mouse only picks proposals, it draws nothing meanwhile
+            InputEvent::Mouse(me)
+                if me.button == MouseButton::Primary
+                    && !matches!(
+                        me.type_,
+                        MouseEventType::Scroll | MouseEventType::PointerPos
+                    ) =>
+            {
+                if me.type_ == MouseEventType::Click {
+                    let pos = self.renderer.abs_canvas_to_image_coordinates(me.pos);
+                    self.renderer.drop_redact_proposal_at(pos);
+                }
+                Some(ToolUpdateResult::Redraw)
+            }
+            _ => None,
+        }
+    }
+
+    // tools get the same events as from a mouse at the crosshair
+    fn crosshair_event(type_: MouseEventType, pos: Vec2D) -> SketchBoardInput {
+        SketchBoardInput::InputEvent(InputEvent::Mouse(MouseEventMsg {
+            type_,
+            button: MouseButton::Primary,
+            modifier: ModifierType::empty(),
+            pos,
+            n_pressed: 1,
+            release: type_ == MouseEventType::Release,
+            pressure: None,
+        }))
+    }
+
+    fn toggle_keyboard_placement(&mut self, sender: &ComponentSender<Self>) {
+        let message = match self.crosshair.take() {
+            Some(crosshair) => {
+                // finish a shape still being placed
+                if let Some(anchor) = crosshair.anchor {
+                    sender.input(Self::crosshair_event(
+                        MouseEventType::EndDrag,
+                        crosshair.pos - anchor,
+                    ));
+                    sender.input(Self::crosshair_event(
+                        MouseEventType::Release,
+                        crosshair.pos,
+                    ));
+                }
+                "Keyboard placement off"
+            }
+            None => {
+                let center =
+                    Vec2D::new(self.renderer.width() as f32, self.renderer.height() as f32) * 0.5;
+                self.crosshair = Some(Crosshair {
+                    pos: center,
+                    anchor: None,
+                });
+                "Keyboard placement: arrow keys move, Enter places"
+            }
+        };
+        self.renderer
+            .set_crosshair(self.crosshair.map(|crosshair| crosshair.pos));
+        self.renderer.queue_render();
+        sender
+            .output_sender()
+            .emit(SketchBoardOutput::Flash(message.to_string()));
+    }
+
+    /// Move the crosshair with the arrow keys and place annotations at it with Enter. The
+    /// first Enter starts a shape, the second one finishes it, markers and texts only need one.
+    pub fn handle_keyboard_placement(
+        &mut self,
+        ie: &InputEvent,
+        sender: &ComponentSender<Self>,
+    ) -> Option<ToolUpdateResult> {
+        let InputEvent::Key(ke) = ie else {
+            return None;
+        };
+        // a text being typed keeps the arrow keys and Enter
+        if self.active_tool.borrow().input_enabled() {
+            return None;
+        }
+        let Crosshair { pos, anchor } = self.crosshair?;
+        let step = if ke.modifier.is_empty() {
+            CROSSHAIR_STEP
+        } else if ke.modifier == ModifierType::SHIFT_MASK {
+            1.0
+        } else {
+            return None;
+        };
+
+        let direction = match ke.key {
+            Key::Return | Key::KP_Enter if ke.modifier.is_empty() => {
+                let anchor = match anchor {
+                    None => {
+                        sender.input(Self::crosshair_event(MouseEventType::Click, pos));
+                        // markers and texts are placed by a click, there is nothing to drag
+                        if matches!(self.active_tool_type(), Tools::Marker | Tools::Text) {
+                            sender.input(Self::crosshair_event(MouseEventType::Release, pos));
+                            None
+                        } else {
+                            sender.input(Self::crosshair_event(MouseEventType::BeginDrag, pos));
+                            Some(pos)
+                        }
+                    }
+                    Some(anchor) => {
+                        sender.input(Self::crosshair_event(MouseEventType::EndDrag, pos - anchor));
+                        sender.input(Self::crosshair_event(MouseEventType::Release, pos));
+                        None
+                    }
+                };
+                self.crosshair = Some(Crosshair { pos, anchor });
+                return Some(ToolUpdateResult::Unmodified);
+            }
+            Key::Escape if ke.modifier.is_empty() => {
+                if anchor.is_some() {
+                    // the tool drops the unfinished shape on Escape
+                    self.crosshair = Some(Crosshair { pos, anchor: None });
+                    return None;
+                }
+                self.toggle_keyboard_placement(sender);
+                return Some(ToolUpdateResult::Unmodified);
+            }
+            key => arrow_direction(key)?,
+        };
+
+        let pos = pos + direction * step;
+        let pos = Vec2D::new(
+            pos.x.clamp(0.0, self.renderer.width() as f32),
+            pos.y.clamp(0.0, self.renderer.height() as f32),
+        );
+        if let Some(anchor) = anchor {
+            sender.input(Self::crosshair_event(
+                MouseEventType::UpdateDrag,
+                pos - anchor,
+            ));
+        }
+        self.crosshair = Some(Crosshair { pos, anchor });
+        self.renderer.set_crosshair(Some(pos));
+        Some(ToolUpdateResult::Redraw)
+    }
+
+    /// Whether `ie` belongs to a drag that got taken over by a pinch
+    fn skip_canceled_drag(&mut self, ie: &InputEvent) -> bool {
+        let InputEvent::Mouse(me) = ie else {
+            return false;
+        };
+        match me.type_ {
+            MouseEventType::BeginDrag => self.drag_canceled = false,
+            MouseEventType::UpdateDrag => return self.drag_canceled,
+            MouseEventType::EndDrag => return std::mem::take(&mut self.drag_canceled),
+            _ => {}
+        }
+        false
+    }
+
+    /// Drop what the active tool is drawing, like Escape does, as the drag turned into a pinch
+    pub fn handle_drag_canceled(&mut self) -> ToolUpdateResult {
+        self.drag_canceled = true;
+        // key events carry X11 keycodes, the evdev ones offset by 8
+        let escape_code = KeyMap::from(KeyMappingId::Escape).evdev as u32 + 8;
+        self.active_tool
+            .borrow_mut()
+            .handle_event(ToolEvent::Input(InputEvent::Key(KeyEventMsg::new(
+                Key::Escape,
+                escape_code,
+                ModifierType::empty(),
+            ))))
+    }
+
+    /// Zoom around the point between the fingers as they move apart or together, and pan
+    /// along with them
+    pub fn handle_pinch(
+        &mut self,
+        event: PinchEvent,
+        sender: &ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        match event {
+            PinchEvent::Begin(center) => {
+                self.kinetic_pan = None;
+                self.pinch = Some(Pinch {
+                    zoom: self.renderer.zoom(),
+                    scale: 1.0,
+                    center,
+                });
+                ToolUpdateResult::Unmodified
+            }
+            PinchEvent::Update { scale, center } => {
+                let Some(pinch) = self.pinch.as_mut() else {
+                    return ToolUpdateResult::Unmodified;
+                };
+                self.renderer.set_pointer_offset(center);
+                if scale > 0.0 && scale != pinch.scale {
+                    self.renderer.set_zoom_scale(scale / pinch.scale);
+                }
+                self.renderer.set_drag_offset(center - pinch.center);
+                self.renderer.store_last_offset();
+                pinch.scale = scale;
+                pinch.center = center;
+                ToolUpdateResult::Redraw
+            }
+            PinchEvent::End => {
+                if self
+                    .pinch
+                    .take()
+                    .is_some_and(|pinch| pinch.zoom != self.renderer.zoom())
+                {
+                    self.flash_zoom(sender);
+                }
+                ToolUpdateResult::Unmodified
+            }
+        }
+    }
+
+    /// Keep panning after touchpad scrolling ended, slowing down until it stops. Vertical
+    /// scrolling only pans if it doesn't zoom, nothing keeps moving when motion is reduced.
+    pub fn start_kinetic_pan(
+        &mut self,
+        velocity: Vec2D,
+        sender: &ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        self.kinetic_pan = None;
+        if theme::reduce_motion() {
+            return ToolUpdateResult::Unmodified;
+        }
+        let app_config = APP_CONFIG.read();
+        let mut velocity = velocity * -app_config.pan_step_size();
+        if app_config.invert_scroll() {
+            velocity = velocity * -1.0;
+        }
+        if app_config.scroll_action() == ScrollAction::Zoom {
+            velocity.y = 0.0;
+        }
+        if velocity.norm() >= KINETIC_MIN_VELOCITY {
+            self.kinetic_pan = Some(velocity);
+            schedule_kinetic_step(sender);
+        }
+        ToolUpdateResult::Unmodified
+    }
+
+    pub fn handle_kinetic_step(&mut self, sender: &ComponentSender<Self>) -> ToolUpdateResult {
+        let Some(velocity) = self.kinetic_pan else {
+            return ToolUpdateResult::Unmodified;
+        };
+        self.renderer
+            .set_drag_offset(velocity * KINETIC_FRAME.as_secs_f32());
+        self.renderer.store_last_offset();
+
+        let velocity = velocity * KINETIC_FRICTION;
+        self.kinetic_pan = (velocity.norm() >= KINETIC_MIN_VELOCITY).then_some(velocity);
+        if self.kinetic_pan.is_some() {
+            schedule_kinetic_step(sender);
+        }
+        ToolUpdateResult::Redraw
+    }
+
+    /// Ctrl with 0 fits the image, 1 shows it at its size, plus and minus zoom in and out.
+    /// Shift is allowed as many layouts need it for plus.
+    /// Return to the previous tool when the key of a spring-loaded tool is released after
+    /// holding it, a short tap keeps the tool selected
+    pub fn handle_spring_tool_release(
+        &mut self,
+        event: &InputEvent,
+        sender: &ComponentSender<Self>,
+    ) -> Option<ToolUpdateResult> {
+        let InputEvent::KeyRelease(ke) = event else {
+            return None;
+        };
+        let key = ke.key.to_unicode()?;
+        if !self.spring_tool.as_ref()?.key.eq_ignore_ascii_case(&key) {
+            return None;
+        }
+
+        let spring_tool = self.spring_tool.take()?;
+        if spring_tool.pressed.elapsed() < SPRING_TOOL_HOLD_DURATION {
+            return Some(ToolUpdateResult::Unmodified);
+        }
+        sender
+            .output_sender()
+            .emit(SketchBoardOutput::ToolSwitchShortcut(spring_tool.return_to));
+        Some(self.handle_toolbar_event(
+            ToolbarEvent::ToolSelected(spring_tool.return_to),
+            sender.clone(),
+        ))
+    }
+
+    /// Route right button drags to the configured secondary tool. Right click actions are
+    /// delayed until the button is released, as any press may start a drag.
+    pub fn handle_secondary_tool(
+        &mut self,
+        event: &InputEvent,
+        sender: &ComponentSender<Self>,
+    ) -> Option<ToolUpdateResult> {
+        let InputEvent::Mouse(me) = event else {
+            return None;
+        };
+        if me.button != MouseButton::Secondary {
+            return None;
+        }
+        let secondary_tool = APP_CONFIG.read().secondary_tool()?;
+
+        match me.type_ {
+            MouseEventType::Click => return Some(ToolUpdateResult::Unmodified),
+            MouseEventType::Release => {
+                if !self.secondary_moved {
+                    self.renderer
+                        .run_actions(&APP_CONFIG.read().actions_on_right_click());
+                }
+                return Some(ToolUpdateResult::Unmodified);
+            }
+            MouseEventType::BeginDrag => {
+                let tool = self.tools.get(&secondary_tool);
+                tool.borrow_mut().set_sender(Self::tool_sender(sender));
+                tool.borrow_mut()
+                    .handle_event(ToolEvent::StyleChanged(self.style));
+                self.renderer.set_active_tool(tool.clone());
+                self.secondary_drag = Some(tool);
+                self.secondary_moved = false;
+            }
+            MouseEventType::UpdateDrag => self.secondary_moved = true,
+            _ => (),
+        }
+
+        let tool = self.secondary_drag.clone()?;
+        // tools only draw with the primary button
+        let primary = MouseEventMsg {
+            button: MouseButton::Primary,
+            ..*me
+        };
+        let result = tool
+            .borrow_mut()
+            .handle_event(ToolEvent::Input(InputEvent::Mouse(primary)));
+
+        if me.type_ == MouseEventType::EndDrag {
+            self.secondary_drag = None;
+            self.renderer.set_active_tool(self.active_tool.clone());
+        }
+        Some(result)
+    }
+
+    /// Snap drags of shape tools to guides, holding Alt disables snapping
+    pub fn snap_mouse_event(&mut self, event: &mut InputEvent) {
+        let InputEvent::Mouse(me) = event else {
+            return;
+        };
+        let snapping =
+            APP_CONFIG.read().snap_to_guides() && !me.modifier.contains(ModifierType::ALT_MASK);
+        self.renderer.set_snapping(snapping);
+
+        if me.button != MouseButton::Primary || !self.active_tool.borrow().snaps_to_guides() {
+            return;
+        }
+
+        match me.type_ {
+            MouseEventType::BeginDrag => {
+                let snapped = self.renderer.snap_point(me.pos);
+                self.snap_drag = Some((me.pos, snapped));
+                me.pos = snapped;
+            }
+            MouseEventType::UpdateDrag | MouseEventType::EndDrag => {
+                if let Some((start, snapped_start)) = self.snap_drag {
+                    me.pos = self.renderer.snap_point(start + me.pos) - snapped_start;
+                }
+                if me.type_ == MouseEventType::EndDrag {
+                    self.snap_drag = None;
+                    self.renderer.clear_guides();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Pan to where the minimap is clicked or dragged, the tools don't see these events. The
+    /// frame of the visible part moves along without jumping when it is grabbed. Positions
+    /// are still in widget coordinates here.
+    pub fn handle_minimap_drag(&mut self, event: &InputEvent) -> Option<ToolUpdateResult> {
+        let InputEvent::Mouse(me) = event else {
+            return None;
+        };
+        if me.button != MouseButton::Primary {
+            return None;
+        }
+
+        match me.type_ {
+            MouseEventType::BeginDrag if self.renderer.minimap_contains(me.pos) => {
+                let start = me.pos + self.renderer.minimap_grab_offset(me.pos);
+                self.minimap_drag = Some(start);
+                self.renderer.minimap_jump(start);
+            }
+            MouseEventType::UpdateDrag | MouseEventType::EndDrag => {
+                // drag positions are relative to the start
+                let start = self.minimap_drag?;
+                self.renderer.minimap_jump(start + me.pos);
+                if me.type_ == MouseEventType::EndDrag {
+                    self.minimap_drag = None;
+                }
+            }
+            MouseEventType::Click | MouseEventType::Release
+                if self.minimap_drag.is_some() || self.renderer.minimap_contains(me.pos) => {}
+            _ => return None,
+        }
+        Some(ToolUpdateResult::Redraw)
+    }
+
+    /// Position of a right click that opens the menu for the selected drawable
+    pub fn selection_menu_position(&self, event: &InputEvent) -> Option<Vec2D> {
+        match event {
+            InputEvent::Mouse(me)
+                if me.type_ == MouseEventType::Click
+                    && me.button == MouseButton::Secondary
+                    && self.active_tool_type() == Tools::Select
+                    && self.renderer.has_selection() =>
+            {
+                Some(me.pos)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn handle_text_commit(
+        &mut self,
+        event: TextEventMsg,
+        sender: ComponentSender<Self>,
+    ) -> ToolUpdateResult {
+        match event {
+            TextEventMsg::Commit(txt) => {
+                if self.active_tool_type() == Tools::Text
+                    && self.active_tool.borrow().input_enabled()
+                {
+                    sender.input(SketchBoardInput::new_text_event(TextEventMsg::Commit(
+                        txt.to_string(),
+                    )));
+                } else if let Some(forward) = txt.chars().next().and_then(|char| {
+                    let config = APP_CONFIG.read();
+                    let keybinds = config.keybinds();
+                    (char == keybinds.cycle_next() || char == keybinds.cycle_previous())
+                        .then_some(char == keybinds.cycle_next())
+                }) {
+                    let tool = self.active_tool_type().cycle(forward);
+                    sender.input(SketchBoardInput::ToolbarEvent(ToolbarEvent::ToolSelected(
+                        tool,
+                    )));
+                    let output = sender.output_sender();
+                    output.emit(SketchBoardOutput::ToolSwitchShortcut(tool));
+                    output.emit(SketchBoardOutput::ToolIndicator(tool));
+                } else if txt.starts_with(APP_CONFIG.read().keybinds().toggle_minimap()) {
+                    self.renderer.toggle_minimap();
+                    self.renderer.queue_render();
+                } else if txt.starts_with(APP_CONFIG.read().keybinds().auto_redact()) {
+                    self.find_redact_proposals(&sender);
+                } else if txt.starts_with(APP_CONFIG.read().keybinds().keyboard_placement()) {
+                    self.toggle_keyboard_placement(&sender);
+                } else if txt.starts_with(APP_CONFIG.read().keybinds().color_blindness_preview()) {
+                    let message = match self.renderer.cycle_color_simulation() {
+                        Some(deficiency) => format!("{} preview", deficiency.display_name()),
+                        None => "Original colors".to_string(),
+                    };
+                    sender
+                        .output_sender()
+                        .emit(SketchBoardOutput::Flash(message));
+                    self.renderer.queue_render();
+                } else if let Some(index) = txt.chars().next().and_then(|char| {
+                    let config = APP_CONFIG.read();
+                    let presets = config.presets();
+                    if char == config.keybinds().cycle_preset() && !presets.is_empty() {
+                        Some(self.current_preset.map_or(0, |i| (i + 1) % presets.len()))
+                    } else {
+                        presets.iter().position(|p| p.key() == Some(char))
+                    }
+                }) {
+                    sender.input(SketchBoardInput::ToolbarEvent(
+                        ToolbarEvent::PresetSelected(index),
+                    ));
+                } else if let Some((char, tool)) = txt.chars().next().and_then(|char| {
+                    APP_CONFIG
+                        .read()
+                        .keybinds()
+                        .get_tool(char)
+                        .map(|tool| (char, tool))
+                }) {
+                    // a held key repeats, it must not switch again
+                    if self.spring_tool.as_ref().is_some_and(|s| s.key == char) {
+                        return ToolUpdateResult::Unmodified;
+                    }
+                    self.spring_tool = (tool != self.active_tool_type()
+                        && APP_CONFIG.read().keybinds().is_spring_loaded(tool))
+                    .then(|| SpringTool {
+                        key: char,
+                        return_to: self.active_tool_type(),
+                        pressed: Instant::now(),
+                    });
+                    sender.input(SketchBoardInput::ToolbarEvent(ToolbarEvent::ToolSelected(
+                        tool,
+                    )));
+                    sender
+                        .output_sender()
+                        .emit(SketchBoardOutput::ToolSwitchShortcut(tool));
+                } else if let Some(hotkey_digit) =
+                    txt.chars().next().and_then(|char| char.to_digit(10))
+                {
+                    let index_digit = if hotkey_digit == 0 {
+                        9
+                    } else {
+                        hotkey_digit - 1
+                    };
+                    // the toolbar ignores keys beyond the palette it shows
+                    if APP_CONFIG
+                        .read()
+                        .color_palette()
+                        .palettes()
+                        .iter()
+                        .any(|palette| palette.colors().len() > index_digit as usize)
+                    {
+                        sender
+                            .output_sender()
+                            .emit(SketchBoardOutput::ColorSwitchShortcut(index_digit as u64));
+                    }
+                }
+            }
+            TextEventMsg::Preedit {
+                text,
+                cursor_chars,
+                spans,
+            } => {
+                if self.active_tool_type() == Tools::Text
+                    && self.active_tool.borrow().input_enabled()
+                {
+                    sender.input(SketchBoardInput::new_text_event(TextEventMsg::Preedit {
+                        text,
+                        cursor_chars,
+                        spans,
+                    }));
+                }
+            }
+            TextEventMsg::PreeditEnd => {
+                if self.active_tool_type() == Tools::Text
+                    && self.active_tool.borrow().input_enabled()
+                {
+                    sender.input(SketchBoardInput::new_text_event(TextEventMsg::PreeditEnd));
+                }
+            }
+        }
+        ToolUpdateResult::Unmodified
+    }
+}
+
+fn schedule_kinetic_step(sender: &ComponentSender<SketchBoard>) {
+    let sender = sender.input_sender().clone();
+    glib::timeout_add_local_once(KINETIC_FRAME, move || {
+        sender.emit(SketchBoardInput::KineticStep)
+    });
+}
+
+/// Whether the event currently handled by `controller` comes from a pen or tablet
+/// Give the middle and right button the roles configured in `[mouse]`: afterwards `Middle`
+/// pans and `Secondary` triggers the right click actions. Returns `false` for presses of
+/// a button without a role.
+fn apply_mouse_bindings(event: &mut InputEvent) -> bool {
+    let InputEvent::Mouse(me) = event else {
+        return true;
+    };
+    // scroll events carry a made up button
+    if matches!(
+        me.type_,
+        MouseEventType::Scroll | MouseEventType::PointerPos
+    ) {
+        return true;
+    }
+    let physical = match me.button {
+        MouseButton::Primary => return true,
+        MouseButton::Middle => MouseButtonBinding::Middle,
+        MouseButton::Secondary => MouseButtonBinding::Right,
+    };
+
+    let app_config = APP_CONFIG.read();
+    me.button = if app_config.mouse().pan() == physical {
+        MouseButton::Middle
+    } else if app_config.mouse().actions() == physical {
+        MouseButton::Secondary
+    } else {
+        return false;
+    };
+    true
+}
+
+fn handle_event_mouse_input(
+    event: &mut InputEvent,
+    renderer: &FemtoVGArea,
+) -> Option<ToolUpdateResult> {
+    if let InputEvent::Mouse(me) = event {
+        match me.type_ {
+            MouseEventType::Click => {
+                me.pos = renderer.abs_canvas_to_image_coordinates(me.pos);
+                None
+            }
+            MouseEventType::Release => {
+                me.pos = renderer.abs_canvas_to_image_coordinates(me.pos);
+                None
+            }
+            MouseEventType::BeginDrag => {
+                me.pos = renderer.abs_canvas_to_image_coordinates(me.pos);
+                None
+            }
+            MouseEventType::EndDrag | MouseEventType::UpdateDrag => {
+                me.pos = renderer.rel_canvas_to_image_coordinates(me.pos);
+                None
+            }
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+fn handle_mouse_event(event: &mut InputEvent, renderer: &FemtoVGArea) -> Option<ToolUpdateResult> {
+    if let InputEvent::Mouse(me) = event {
+        match me.type_ {
+            MouseEventType::Click => {
+                if me.button == MouseButton::Secondary {
+                    renderer.run_actions(&APP_CONFIG.read().actions_on_right_click());
+                    None
+                } else {
+                    None
+                }
+            }
+            MouseEventType::EndDrag | MouseEventType::UpdateDrag => {
+                if me.button == MouseButton::Middle {
+                    renderer.set_drag_offset(me.pos);
+                    renderer.set_is_drag(true);
+
+                    if me.type_ == MouseEventType::EndDrag {
+                        renderer.store_last_offset();
+                        renderer.set_is_drag(false);
+                    }
+                    renderer.request_render(&APP_CONFIG.read().actions_on_right_click());
+                }
+                None
+            }
+
+            MouseEventType::Scroll => {
+                let app_config = APP_CONFIG.read();
+                let mut delta = me.pos;
+                if app_config.invert_scroll() {
+                    delta = delta * -1.0;
+                }
+                if me.modifier.contains(ModifierType::SHIFT_MASK) {
+                    delta = Vec2D::new(delta.x + delta.y, 0.0);
+                }
+                let zoom = (app_config.scroll_action() == ScrollAction::Zoom)
+                    != me.modifier.contains(ModifierType::CONTROL_MASK);
+
+                if zoom && delta.y != 0.0 {
+                    // fractional deltas zoom smoothly, a wheel step zooms by the factor
+                    renderer.set_zoom_scale(app_config.zoom_factor().powf(-delta.y));
+                    delta.y = 0.0;
+                }
+                if delta != Vec2D::zero() {
+                    renderer.set_drag_offset(delta * -app_config.pan_step_size());
+                    renderer.store_last_offset();
+                }
+                renderer.request_render(&app_config.actions_on_right_click());
+                None
+            }
+            MouseEventType::PointerPos => {
+                renderer.set_pointer_offset(me.pos);
+                None
+            }
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+pub fn is_stylus_event(controller: &impl IsA<gtk::EventController>) -> bool {
+    controller
+        .current_event_device()
+        .is_some_and(|device| device.source() == InputSource::Pen)
+}
+
+fn arrow_direction(key: Key) -> Option<Vec2D> {
+    match key {
+        Key::Left => Some(Vec2D::new(-1.0, 0.0)),
+        Key::Right => Some(Vec2D::new(1.0, 0.0)),
+        Key::Up => Some(Vec2D::new(0.0, -1.0)),
+        Key::Down => Some(Vec2D::new(0.0, 1.0)),
+        _ => None,
+    }
+}
+
+/// What a key press stands for once the active tool passed it on, decided by [`key_command`]
+/// and carried out by the board
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyCommand {
+    /// actions bound in `[keybinds.actions]`, run like actions from the toolbar
+    Actions(Vec<Action>),
+    /// actions of a built in shortcut, rendered right away
+    Render(Vec<Action>),
+    Undo,
+    Redo,
+    ToggleToolbars,
+    CopyText,
+    /// show the input this many places after the current one
+    SwitchImage(isize),
+    Recapture,
+    ZoomToFit,
+    ZoomOriginal,
+    /// zoom by this factor
+    Zoom(f32),
+    /// pan by this offset, in screen pixels
+    Pan(Vec2D),
+    PreviousTool,
+    DuplicateSelection,
+    ScrollPage {
+        down: bool,
+    },
+    Search,
+    SnapshotHistory,
+    /// move the selection by this offset, in image pixels
+    Nudge(Vec2D),
+    DeleteSelection,
+    ClearSelection,
+    Reset,
+    /// Escape or Enter, runs these actions unless the tool used the key
+    Finish(Vec<Action>),
+}
+
+/// The command for `ke`, from the key, the configuration and whether annotations are selected
+/// alone, so the routing doesn't depend on the board or a display
+pub fn key_command(
+    ke: &KeyEventMsg,
+    config: &Configuration,
+    has_selection: bool,
+) -> Option<KeyCommand> {
+    let ctrl = ModifierType::CONTROL_MASK;
+    let ctrl_shift = ModifierType::CONTROL_MASK | ModifierType::SHIFT_MASK;
+    let no_modifier = ke.modifier.is_empty();
+
+    let command = if let Some(actions) = config.keybinds().actions(ke.modifier, ke.key) {
+        KeyCommand::Actions(actions.to_vec())
+    } else if ke.is_one_of(Key::z, KeyMappingId::UsZ) && ke.modifier == ctrl {
+        KeyCommand::Undo
+    } else if ke.is_one_of(Key::y, KeyMappingId::UsY) && ke.modifier == ctrl {
+        KeyCommand::Redo
+    } else if ke.is_one_of(Key::t, KeyMappingId::UsT) && ke.modifier == ctrl {
+        KeyCommand::ToggleToolbars
+    } else if ke.is_one_of(Key::s, KeyMappingId::UsS) && ke.modifier == ctrl {
+        KeyCommand::Render(vec![Action::SaveToFile(None)])
+    } else if ke.is_one_of(Key::s, KeyMappingId::UsS) && ke.modifier == ctrl_shift {
+        KeyCommand::Render(vec![Action::SaveToFileAs])
+    } else if ke.is_one_of(Key::c, KeyMappingId::UsC) && ke.modifier == ctrl {
+        KeyCommand::Render(vec![Action::SaveToClipboard])
+    } else if ke.is_one_of(Key::c, KeyMappingId::UsC) && ke.modifier == ctrl_shift {
+        KeyCommand::CopyText
+    } else if ke.is_one_of(Key::q, KeyMappingId::UsQ) && ke.modifier == ctrl_shift {
+        KeyCommand::Render(vec![Action::ScanCodes])
+    } else if ke.modifier == ctrl && (ke.key == Key::Page_Up || ke.key == Key::Page_Down) {
+        KeyCommand::SwitchImage(if ke.key == Key::Page_Down { 1 } else { -1 })
+    } else if ke.is_one_of(Key::r, KeyMappingId::UsR) && ke.modifier == ctrl {
+        KeyCommand::Recapture
+    } else if let Some(command) = zoom_command(ke, config.zoom_factor()) {
+        command
+    } else if (ke.is_one_of(Key::leftarrow, KeyMappingId::ArrowLeft)
+        || ke.is_one_of(Key::rightarrow, KeyMappingId::ArrowRight)
+        || ke.is_one_of(Key::uparrow, KeyMappingId::ArrowUp)
+        || ke.is_one_of(Key::downarrow, KeyMappingId::ArrowDown))
+        && ke.modifier == ModifierType::ALT_MASK
+    {
+        KeyCommand::Pan(arrow_direction(ke.key).unwrap_or_default() * config.pan_step_size())
+    } else if no_modifier && ke.key == config.keybinds().previous_tool() {
+        KeyCommand::PreviousTool
+    } else if ke.is_one_of(Key::d, KeyMappingId::UsD) && ke.modifier == ctrl && has_selection {
+        KeyCommand::DuplicateSelection
+    } else if no_modifier && (ke.key == Key::Page_Up || ke.key == Key::Page_Down) {
+        KeyCommand::ScrollPage {
+            down: ke.key == Key::Page_Down,
+        }
+    } else if ke.is_one_of(Key::f, KeyMappingId::UsF) && ke.modifier == ctrl {
+        KeyCommand::Search
+    } else if ke.is_one_of(Key::h, KeyMappingId::UsH) && ke.modifier == ctrl {
+        KeyCommand::SnapshotHistory
+    } else if let Some(command) = nudge_command(ke, has_selection) {
+        command
+    } else if no_modifier && (ke.key == Key::Delete || ke.key == Key::BackSpace) && has_selection {
+        KeyCommand::DeleteSelection
+    } else if no_modifier && ke.key == Key::Escape && has_selection {
+        KeyCommand::ClearSelection
+    } else if no_modifier && ke.key == Key::Delete {
+        KeyCommand::Reset
+    } else if no_modifier && ke.key == Key::Escape {
+        KeyCommand::Finish(config.actions_on_escape())
+    } else if no_modifier && (ke.key == Key::Return || ke.key == Key::KP_Enter) {
+        KeyCommand::Finish(config.actions_on_enter())
+    } else {
+        return None;
+    };
+    Some(command)
+}
+
+/// Ctrl with 0 fits the image, 1 shows it at its original size, + and - zoom in and out
+fn zoom_command(ke: &KeyEventMsg, zoom_factor: f32) -> Option<KeyCommand> {
+    if !ke.modifier.contains(ModifierType::CONTROL_MASK)
+        || !(ke.modifier - ModifierType::SHIFT_MASK - ModifierType::CONTROL_MASK).is_empty()
+    {
+        return None;
+    }
+    if ke.is_one_of(Key::_0, KeyMappingId::Digit0) || ke.key == Key::KP_0 {
+        Some(KeyCommand::ZoomToFit)
+    } else if ke.is_one_of(Key::_1, KeyMappingId::Digit1) || ke.key == Key::KP_1 {
+        Some(KeyCommand::ZoomOriginal)
+    } else if ke.is_one_of(Key::plus, KeyMappingId::Equal)
+        || ke.key == Key::equal
+        || ke.key == Key::KP_Add
+    {
+        Some(KeyCommand::Zoom(zoom_factor))
+    } else if ke.is_one_of(Key::minus, KeyMappingId::Minus) || ke.key == Key::KP_Subtract {
+        Some(KeyCommand::Zoom(1.0 / zoom_factor))
+    } else {
+        None
+    }
+}
+
+/// The arrow keys move the selected annotations by a pixel, or ten with Shift
+fn nudge_command(ke: &KeyEventMsg, has_selection: bool) -> Option<KeyCommand> {
+    let step = if ke.modifier.is_empty() {
+        1.0
+    } else if ke.modifier == ModifierType::SHIFT_MASK {
+        10.0
+    } else {
+        return None;
+    };
+    let direction = arrow_direction(ke.key)?;
+    has_selection.then(|| KeyCommand::Nudge(direction * step))
+}
+
+#[cfg(test)]
+mod tests {
+    use keycode::{KeyMap, KeyMappingId};
+    use relm4::gtk::gdk::{Key, ModifierType};
+
+    use super::{key_command, KeyCommand};
+    use crate::configuration::{Action, Configuration};
+    use crate::math::Vec2D;
+    use crate::sketch_board::KeyEventMsg;
+
+    fn key(key: Key, id: KeyMappingId, modifier: ModifierType) -> KeyEventMsg {
+        KeyEventMsg {
+            key,
+            // hardware key codes are evdev codes offset by 8
+            code: KeyMap::from(id).evdev as u32 + 8,
+            modifier,
+        }
+    }
+
+    fn command(ke: KeyEventMsg, has_selection: bool) -> Option<KeyCommand> {
+        key_command(&ke, &Configuration::default(), has_selection)
+    }
+
+    #[test]
+    fn shortcuts_follow_the_physical_key() {
+        let ctrl = ModifierType::CONTROL_MASK;
+        assert_eq!(
+            command(key(Key::z, KeyMappingId::UsZ, ctrl), false),
+            Some(KeyCommand::Undo)
+        );
+        // Ctrl+Z on a Cyrillic layout
+        assert_eq!(
+            command(key(Key::Cyrillic_ya, KeyMappingId::UsZ, ctrl), false),
+            Some(KeyCommand::Undo)
+        );
+        assert_eq!(
+            command(key(Key::s, KeyMappingId::UsS, ctrl), false),
+            Some(KeyCommand::Render(vec![Action::SaveToFile(None)]))
+        );
+        assert_eq!(
+            command(key(Key::z, KeyMappingId::UsZ, ModifierType::empty()), false),
+            None
+        );
+    }
+
+    #[test]
+    fn selection_takes_delete_escape_and_arrows() {
+        let none = ModifierType::empty();
+        let config = Configuration::default();
+        assert_eq!(
+            command(key(Key::Delete, KeyMappingId::Del, none), true),
+            Some(KeyCommand::DeleteSelection)
+        );
+        assert_eq!(
+            command(key(Key::Delete, KeyMappingId::Del, none), false),
+            Some(KeyCommand::Reset)
+        );
+        assert_eq!(
+            command(key(Key::Escape, KeyMappingId::Escape, none), true),
+            Some(KeyCommand::ClearSelection)
+        );
+        assert_eq!(
+            command(key(Key::Escape, KeyMappingId::Escape, none), false),
+            Some(KeyCommand::Finish(config.actions_on_escape()))
+        );
+        assert_eq!(
+            command(
+                key(Key::Left, KeyMappingId::ArrowLeft, ModifierType::SHIFT_MASK),
+                true
+            ),
+            Some(KeyCommand::Nudge(Vec2D::new(-10.0, 0.0)))
+        );
+        assert_eq!(
+            command(key(Key::Left, KeyMappingId::ArrowLeft, none), false),
+            None
+        );
+    }
+
+    #[test]
+    fn view_keys_zoom_and_pan() {
+        let config = Configuration::default();
+        assert_eq!(
+            command(
+                key(Key::plus, KeyMappingId::Equal, ModifierType::CONTROL_MASK),
+                false
+            ),
+            Some(KeyCommand::Zoom(config.zoom_factor()))
+        );
+        assert_eq!(
+            command(
+                key(Key::Up, KeyMappingId::ArrowUp, ModifierType::ALT_MASK),
+                false
+            ),
+            Some(KeyCommand::Pan(Vec2D::new(0.0, -config.pan_step_size())))
+        );
+    }
+}
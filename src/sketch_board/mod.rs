@@ -0,0 +1,380 @@
+use femtovg::imgref::Img;
+use femtovg::rgb::RGBA;
+use gdk_pixbuf::Pixbuf;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use gtk::prelude::*;
+
+use relm4::gtk::gdk::{AxisUse, DeviceToolType, ModifierType};
+use relm4::{gtk, Controller};
+
+use crate::configuration::{Action, ExportCanvas, ExportSplit};
+use crate::diff::Diff;
+use crate::femtovg_area::{FemtoVGArea, ImageState};
+use crate::hooks::{self, Hook};
+use crate::math::Vec2D;
+use crate::recovery;
+use crate::snapshots::{Snapshot, Snapshots};
+use crate::style::{Color, Style, ToolStyle};
+use crate::tool_styles::ToolStyles;
+use crate::tools::{SelectEvent, Tool, ToolRequest, Tools, ToolsManager};
+use crate::ui::annotation_search::AnnotationSearch;
+use crate::ui::recolor_dialog::RecolorDialog;
+use crate::ui::scanned_codes::ScannedCodesDialog;
+use crate::ui::snapshot_history::SnapshotHistory;
+use crate::ui::status_bar::Status;
+use crate::ui::toolbars::ToolbarEvent;
+
+pub use satty_core::events::{
+    InputEvent, KeyEventMsg, MouseButton, MouseEventMsg, MouseEventType, TextEventMsg,
+};
+
+// `input` routes events to the tools or turns key presses into `KeyCommand`s, `actions`
+// carries those out, `export` handles rendered images and `board` is the component itself
+mod actions;
+mod board;
+mod export;
+mod input;
+
+type RenderedImage = Img<Vec<RGBA<u8>>>;
+
+// radius around the eraser position that removes drawables, in screen pixels
+const ERASER_RADIUS: f32 = 6.0;
+
+#[derive(Debug, Clone)]
+pub enum SketchBoardInput {
+    InputEvent(InputEvent),
+    /// the pointer left the area
+    PointerLeft,
+    /// the current drag got taken over by another gesture, e.g. a pinch
+    DragCanceled,
+    Pinch(PinchEvent),
+    /// touchpad scrolling ended with this velocity, in scroll units per second
+    KineticPan(Vec2D),
+    KineticStep,
+    ToolbarEvent(ToolbarEvent),
+    /// the rendered image, the same area without annotations if an action asked for it
+    RenderResult(RenderedImage, Option<RenderedImage>, Vec<Action>),
+    CommitEvent(TextEventMsg),
+    StylusEvent(StylusEventMsg),
+    /// remove the topmost drawable at the position, in image coordinates
+    EraseAt(Vec2D),
+    Select(SelectEvent),
+    /// continue editing the committed text at the position, in image coordinates
+    EditTextAt(Vec2D),
+    DuplicateSelection,
+    DeleteSelection,
+    /// show annotations of one color with another one, `None` reverts the preview
+    RecolorPreview(Option<(Color, Color)>),
+    ApplyRecolor,
+    /// pan to the drawable with the given index
+    JumpToDrawable(usize),
+    /// the button of a toast shown with [`SketchBoardOutput::ActionToast`] got clicked
+    ToastAction,
+    /// take a snapshot if the annotations changed since the last one
+    Autosnapshot,
+    /// write the annotations for crash recovery if they changed
+    AutosaveRecovery,
+    SnapshotResult(RenderedImage),
+    SnapshotSaved(Snapshot),
+    /// export a snapshot listed in the history, asks for the file name
+    ExportSnapshot(std::path::PathBuf),
+    /// text regions found by auto-redact, as (min, max) in image coordinates
+    RedactProposals(Vec<(Vec2D, Vec2D)>),
+    /// content of the codes found by the scan-codes action
+    ScannedCodes(Vec<String>),
+    Refresh,
+    LoadImage(Pixbuf),
+    /// new screenshot taken by the `recapture-command`, the annotations are kept
+    ReplaceImage(Pixbuf),
+    /// join the image to the canvas as configured in `[stitch]`
+    AppendImage(Pixbuf),
+    /// the images compared with `--diff`, shown already
+    SetDiff(Diff),
+    /// add the annotations of an exported file to the ones of the image
+    ImportAnnotations(std::path::PathBuf),
+    /// run the hooks before the actions, see [`FemtoVGArea::run_actions`]
+    RunHooks(Vec<Hook>, Vec<Action>),
+    /// show the input with the given index, `image` is `None` if it was shown before and
+    /// still has its annotations
+    SwitchImage {
+        index: usize,
+        image: Option<Pixbuf>,
+    },
+    /// working directory of the client sending the next image, this process' own if `None`
+    WorkingDirectory(Option<std::path::PathBuf>),
+    /// the window covers an output as a layer shell overlay, images are shown 1:1 on it
+    Overlay,
+}
+
+#[derive(Debug, Clone)]
+pub enum SketchBoardOutput {
+    ToggleToolbarsDisplay,
+    ToolSwitchShortcut(Tools),
+    /// briefly show which tool got selected by a shortcut
+    ToolIndicator(Tools),
+    ColorSwitchShortcut(u64),
+    /// the selected tool got the color, size and fill last used with it
+    ToolStyleChanged(ToolStyle),
+    /// the preset with the given index got selected by a key
+    PresetApplied(usize),
+    /// briefly show a status message
+    Flash(String),
+    /// show a message with a button that sends [`SketchBoardInput::ToastAction`]
+    ActionToast {
+        text: String,
+        label: String,
+    },
+    /// color of the image clicked after [`ToolbarEvent::PickColor`], `None` if canceled
+    ColorPicked(Option<Color>),
+    /// pointer position, color under it, zoom and image size for the status bar
+    Status(Status),
+    /// a long-running export started, its progress is shown until [`SketchBoardOutput::ProgressEnded`]
+    ProgressStarted(String),
+    ProgressEnded,
+    /// take the screenshot again with the `recapture-command`, the window has to be hidden
+    Recapture,
+    /// show the input this many places after the current one, negative for earlier ones
+    SwitchImage(isize),
+    Exit,
+}
+
+/// Two fingers on a touchscreen or touchpad, positions in widget coordinates
+#[derive(Debug, Clone, Copy)]
+pub enum PinchEvent {
+    Begin(Vec2D),
+    /// `scale` is the distance between the fingers relative to the one at the begin
+    Update {
+        scale: f32,
+        center: Vec2D,
+    },
+    End,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StylusEventType {
+    Down,
+    Motion,
+    Up,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StylusEventMsg {
+    pub type_: StylusEventType,
+    pub modifier: ModifierType,
+    pub pos: Vec2D,
+    pub pressure: Option<f32>,
+    pub eraser: bool,
+}
+
+impl SketchBoardInput {
+    pub fn new_mouse_event(
+        event_type: MouseEventType,
+        button: u32,
+        n_pressed: i32,
+        modifier: ModifierType,
+        pos: Vec2D,
+        release: bool,
+    ) -> SketchBoardInput {
+        SketchBoardInput::InputEvent(InputEvent::Mouse(MouseEventMsg {
+            type_: event_type,
+            button: button.into(),
+            n_pressed,
+            modifier,
+            pos,
+            release,
+            pressure: None,
+        }))
+    }
+    pub fn new_key_event(event: KeyEventMsg) -> SketchBoardInput {
+        SketchBoardInput::InputEvent(InputEvent::Key(event))
+    }
+
+    pub fn new_key_release_event(event: KeyEventMsg) -> SketchBoardInput {
+        SketchBoardInput::InputEvent(InputEvent::KeyRelease(event))
+    }
+
+    pub fn new_text_event(event: TextEventMsg) -> SketchBoardInput {
+        SketchBoardInput::InputEvent(InputEvent::Text(event))
+    }
+
+    pub fn new_commit_event(event: TextEventMsg) -> SketchBoardInput {
+        SketchBoardInput::CommitEvent(event)
+    }
+
+    /// Scroll by `delta_x` and `delta_y`, touchpads and high resolution wheels send fractions
+    /// of a wheel step
+    pub fn new_scroll_event(
+        delta_x: f64,
+        delta_y: f64,
+        modifier: ModifierType,
+    ) -> SketchBoardInput {
+        SketchBoardInput::InputEvent(InputEvent::Mouse(MouseEventMsg {
+            type_: MouseEventType::Scroll,
+            button: MouseButton::Middle,
+            n_pressed: 0,
+            modifier,
+            pos: Vec2D::new(delta_x as f32, delta_y as f32),
+            release: false,
+            pressure: None,
+        }))
+    }
+
+    pub fn new_stylus_event(
+        event_type: StylusEventType,
+        controller: &gtk::GestureStylus,
+        x: f64,
+        y: f64,
+    ) -> SketchBoardInput {
+        SketchBoardInput::StylusEvent(StylusEventMsg {
+            type_: event_type,
+            modifier: controller.current_event_state(),
+            pos: Vec2D::new(x as f32, y as f32),
+            pressure: controller.axis(AxisUse::Pressure).map(|p| p as f32),
+            eraser: controller
+                .device_tool()
+                .is_some_and(|tool| tool.tool_type() == DeviceToolType::Eraser),
+        })
+    }
+}
+
+impl From<ToolRequest> for SketchBoardInput {
+    fn from(request: ToolRequest) -> Self {
+        match request {
+            ToolRequest::Refresh => SketchBoardInput::Refresh,
+            ToolRequest::EraseAt(pos) => SketchBoardInput::EraseAt(pos),
+            ToolRequest::Select(event) => SketchBoardInput::Select(event),
+            ToolRequest::EditTextAt(pos) => SketchBoardInput::EditTextAt(pos),
+        }
+    }
+}
+
+/// The drawing area along with the tools and dialogs working on it. Everything arrives as a
+/// [`SketchBoardInput`] handled in `board`:
+///
+/// - widget events become [`InputEvent`]s routed by `input`, to modes like keyboard placement
+///   first, then to the active tool, the shortcuts and finally to panning and zooming
+/// - shortcuts, toolbar and menu entries run the handlers in `actions`
+/// - save, copy and upload actions request a render, its
+///   [`SketchBoardInput::RenderResult`] is exported by `export`
+///
+/// Handlers return a [`ToolUpdateResult`](crate::tools::ToolUpdateResult), `board` commits or redraws accordingly.
+pub struct SketchBoard {
+    renderer: FemtoVGArea,
+    active_tool: Rc<RefCell<dyn Tool>>,
+    tools: ToolsManager,
+    style: Style,
+    im_context: gtk::IMMulticontext,
+    output_scale: f32,
+    // saved files get split into tiles
+    export_split: Option<ExportSplit>,
+    export_canvas: Option<ExportCanvas>,
+    // replaces `{cwd}` in output paths
+    working_directory: std::path::PathBuf,
+    // the window is a layer shell overlay, an image pixel is shown on an output pixel
+    overlay: bool,
+    // where the stylus touched down, drag positions are reported relative to it
+    stylus_origin: Option<Vec2D>,
+    // opened by a right click on a selected drawable
+    selection_menu: gtk::PopoverMenu,
+    // tool that was active before the current one
+    previous_tool: Option<Tools>,
+    // raw and snapped start of the current drag, drag updates are relative to the start
+    snap_drag: Option<(Vec2D, Vec2D)>,
+    // tool handling the current right button drag
+    secondary_drag: Option<Rc<RefCell<dyn Tool>>>,
+    // whether the last right button press moved, it is no right click then
+    secondary_moved: bool,
+    // tool selected by a key that is still held down
+    spring_tool: Option<SpringTool>,
+    recolor_dialog: Option<Controller<RecolorDialog>>,
+    scanned_codes_dialog: Option<Controller<ScannedCodesDialog>>,
+    // lists text annotations, opened with Ctrl+F
+    annotation_search: Controller<AnnotationSearch>,
+    // widget position centered by a drag on the minimap when it started
+    minimap_drag: Option<Vec2D>,
+    pinch: Option<Pinch>,
+    // the current drag was taken over by a pinch, the rest of it is ignored
+    drag_canceled: bool,
+    // velocity of the panning that continues after touchpad scrolling, in widget pixels
+    // per second
+    kinetic_pan: Option<Vec2D>,
+    // the next click picks a color of the image for the color dialog
+    picking_color: bool,
+    warn_low_contrast: bool,
+    // hard to read text that the warning toast offers a background for
+    contrast_fix: Option<usize>,
+    // annotations of a crashed session that the toast offers to restore
+    recovery_offer: Option<recovery::Session>,
+    // revision of the drawables last written for recovery
+    recovery_revision: u64,
+    snapshots: Snapshots,
+    // lists the snapshots, opened with Ctrl+H
+    snapshot_history: Controller<SnapshotHistory>,
+    tool_styles: ToolStyles,
+    // index of the last applied preset, the cycle key continues after it
+    current_preset: Option<usize>,
+    // set while annotations are placed with the keyboard
+    crosshair: Option<Crosshair>,
+    // index of the shown image among the inputs given on the command line
+    input_index: usize,
+    // the other inputs shown so far, with their annotations and undo history
+    parked_images: HashMap<usize, ImageState>,
+    // the images compared with `--diff`, to switch the heatmap
+    diff: Option<Diff>,
+    // the `[hooks]` script, compiled at start
+    hooks: Option<hooks::Script>,
+}
+
+/// Crosshair of the keyboard placement mode, in widget coordinates
+#[derive(Clone, Copy)]
+struct Crosshair {
+    pos: Vec2D,
+    // where Enter started the current shape, moving the crosshair drags from there
+    anchor: Option<Vec2D>,
+}
+
+/// Pinch in progress, zooming and panning follow the fingers
+#[derive(Clone, Copy)]
+struct Pinch {
+    // zoom when the pinch began, it is shown at the end if it changed
+    zoom: f32,
+    scale: f32,
+    center: Vec2D,
+}
+
+struct SpringTool {
+    key: char,
+    return_to: Tools,
+    pressed: Instant,
+}
+
+/// keys of spring-loaded tools held longer than this switch back on release
+const SPRING_TOOL_HOLD_DURATION: Duration = Duration::from_millis(300);
+
+/// distance the arrow keys move the crosshair, in widget pixels, Shift moves by one
+const CROSSHAIR_STEP: f32 = 10.0;
+
+/// part of the area height scrolled by PageUp and PageDown
+const PAGE_SCROLL_FRACTION: f32 = 0.9;
+
+/// time between the steps of kinetic panning
+const KINETIC_FRAME: Duration = Duration::from_millis(16);
+
+/// part of the kinetic panning velocity kept by each step
+const KINETIC_FRICTION: f32 = 0.92;
+
+/// kinetic panning stops below this velocity, in widget pixels per second
+const KINETIC_MIN_VELOCITY: f32 = 20.0;
+
+/// texts below this contrast ratio with the image get a warning, WCAG asks 3:1 for large text
+const MIN_TEXT_CONTRAST: f32 = 3.0;
+
+pub struct ImageDataSendable {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
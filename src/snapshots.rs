@@ -0,0 +1,102 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use image::{ImageBuffer, Rgba};
+use relm4::Sender;
+use xdg::BaseDirectories;
+
+use crate::sketch_board::SketchBoardInput;
+
+/// the oldest snapshots get deleted beyond this, each one is a full size render
+const MAX_SNAPSHOTS: usize = 30;
+
+/// Renders of the annotated image taken in the background during long sessions, so an
+/// earlier state can be exported without undoing everything done since. They are kept in
+/// the cache directory until the next image gets loaded.
+#[derive(Default)]
+pub struct Snapshots {
+    taken: Vec<Snapshot>,
+    // revision of the drawables the last snapshot got requested for
+    revision: u64,
+    // bumped by clear, snapshots still being written for the previous image are dropped
+    session: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub time: DateTime<Local>,
+    pub path: PathBuf,
+    session: u64,
+}
+
+impl Snapshots {
+    /// Whether there is no snapshot of `revision` yet, it counts as taken afterwards
+    pub fn due(&mut self, revision: u64) -> bool {
+        if revision == self.revision {
+            return false;
+        }
+        self.revision = revision;
+        true
+    }
+
+    /// Write the RGBA image in the background, `sender` gets
+    /// [`SketchBoardInput::SnapshotSaved`] once it is done
+    pub fn save(&self, width: u32, height: u32, data: Vec<u8>, sender: Sender<SketchBoardInput>) {
+        let session = self.session;
+        let time = Local::now();
+        relm4::spawn_local(async move {
+            let result = relm4::spawn_blocking(move || Self::write(width, height, data, &time))
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|r| r);
+            match result {
+                Ok(path) => sender.emit(SketchBoardInput::SnapshotSaved(Snapshot {
+                    time,
+                    path,
+                    session,
+                })),
+                Err(e) => println!("Error while saving snapshot: {e}"),
+            }
+        });
+    }
+
+    fn write(width: u32, height: u32, data: Vec<u8>, time: &DateTime<Local>) -> Result<PathBuf> {
+        let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, data)
+            .ok_or_else(|| anyhow!("Image data does not match its size"))?;
+        let path = Self::directory()?.join(format!("{}.png", time.format("%Y%m%d-%H%M%S")));
+        buffer.save_with_format(&path, image::ImageFormat::Png)?;
+        Ok(path)
+    }
+
+    // one directory per process, several daemons don't delete each other's snapshots
+    fn directory() -> Result<PathBuf> {
+        Ok(BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"))
+            .create_cache_directory(format!("snapshots-{}", std::process::id()))?)
+    }
+
+    pub fn add(&mut self, snapshot: Snapshot) {
+        if snapshot.session != self.session {
+            let _ = fs::remove_file(&snapshot.path);
+            return;
+        }
+        self.taken.push(snapshot);
+        if self.taken.len() > MAX_SNAPSHOTS {
+            let _ = fs::remove_file(self.taken.remove(0).path);
+        }
+    }
+
+    /// Snapshots taken so far, the oldest first
+    pub fn list(&self) -> &[Snapshot] {
+        &self.taken
+    }
+
+    /// Delete all snapshots, the next image starts a new session
+    pub fn clear(&mut self) {
+        for snapshot in self.taken.drain(..) {
+            let _ = fs::remove_file(snapshot.path);
+        }
+        self.revision = 0;
+        self.session += 1;
+    }
+}
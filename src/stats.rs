@@ -0,0 +1,118 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anyhow::Result;
+use serde_derive::{Deserialize, Serialize};
+use xdg::BaseDirectories;
+
+use crate::{
+    configuration::{Action, APP_CONFIG},
+    tools::Tools,
+};
+
+const STATE_FILE: &str = "usage.json";
+
+/// How often each tool got selected and each action got used, only ever stored locally
+/// and printed by `satty stats`
+#[derive(Default, Serialize, Deserialize)]
+struct Usage {
+    /// when counting started, as `YYYY-MM-DD`
+    since: Option<String>,
+    tools: BTreeMap<Tools, u64>,
+    actions: BTreeMap<String, u64>,
+}
+
+/// Count a switch to `tool`, if `usage-statistics` is enabled
+pub fn count_tool(tool: Tools) {
+    update(|usage| *usage.tools.entry(tool).or_default() += 1);
+}
+
+/// Count each of `actions`, if `usage-statistics` is enabled
+pub fn count_actions(actions: &[Action]) {
+    update(|usage| {
+        for action in actions {
+            *usage.actions.entry(action.name().to_string()).or_default() += 1;
+        }
+    });
+}
+
+/// Print the counters, most used first, or delete them with `reset`
+pub fn run(reset: bool) -> Result<()> {
+    if reset {
+        if let Some(path) = path(false)?.filter(|path| path.exists()) {
+            fs::remove_file(path)?;
+        }
+        println!("Usage statistics cleared");
+        return Ok(());
+    }
+
+    let usage = read()?;
+    match &usage.since {
+        Some(since) => println!("Usage since {since}"),
+        None if APP_CONFIG.read().usage_statistics() => println!("Nothing counted yet"),
+        None => println!("Nothing counted, usage-statistics is disabled"),
+    }
+    print_counts(
+        "Tools",
+        usage
+            .tools
+            .iter()
+            .map(|(tool, n)| (tool.display_name(), *n)),
+    );
+    print_counts(
+        "Actions",
+        usage.actions.iter().map(|(name, n)| (name.as_str(), *n)),
+    );
+    Ok(())
+}
+
+fn print_counts<'a>(title: &str, counts: impl Iterator<Item = (&'a str, u64)>) {
+    let mut counts: Vec<_> = counts.collect();
+    if counts.is_empty() {
+        return;
+    }
+    counts.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+    let width = counts.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    println!("\n{title}");
+    for (name, n) in counts {
+        println!("  {name:<width$}  {n}");
+    }
+}
+
+fn update(count: impl FnOnce(&mut Usage)) {
+    if !APP_CONFIG.read().usage_statistics() {
+        return;
+    }
+    let result = read().and_then(|mut usage| {
+        usage
+            .since
+            .get_or_insert_with(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+        count(&mut usage);
+        write(&usage)
+    });
+    if let Err(e) = result {
+        println!("Error while saving usage statistics: {e}");
+    }
+}
+
+fn read() -> Result<Usage> {
+    match path(false)? {
+        Some(path) if path.exists() => Ok(serde_json::from_slice(&fs::read(path)?)?),
+        _ => Ok(Usage::default()),
+    }
+}
+
+fn write(usage: &Usage) -> Result<()> {
+    if let Some(path) = path(true)? {
+        fs::write(path, serde_json::to_vec_pretty(usage)?)?;
+    }
+    Ok(())
+}
+
+fn path(create: bool) -> Result<Option<PathBuf>> {
+    let dirs = BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
+    Ok(if create {
+        Some(dirs.place_state_file(STATE_FILE)?)
+    } else {
+        dirs.get_state_file(STATE_FILE)
+    })
+}
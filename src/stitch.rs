@@ -0,0 +1,70 @@
+use gdk_pixbuf::{Colorspace, Pixbuf};
+
+use crate::configuration::{Stitch, StitchDirection};
+
+/// `first` with `second` appended to the right of or below it, separated by a line. `first`
+/// stays at the top left, so the annotations on it keep their place. Where the images differ in
+/// size the result is transparent. Returns `None` if the result can't be allocated.
+pub fn stitch(first: &Pixbuf, second: &Pixbuf, config: &Stitch) -> Option<Pixbuf> {
+    let right = match config.direction() {
+        StitchDirection::Right => true,
+        StitchDirection::Below => false,
+        StitchDirection::Auto => {
+            let gap = config.separator_width() as i32;
+            let side_by_side = squareness(
+                first.width() + gap + second.width(),
+                first.height().max(second.height()),
+            );
+            let stacked = squareness(
+                first.width().max(second.width()),
+                first.height() + gap + second.height(),
+            );
+            side_by_side >= stacked
+        }
+    };
+    join(first, second, right, config)
+}
+
+/// `second` to the right of `first` whatever the configured direction
+pub fn side_by_side(first: &Pixbuf, second: &Pixbuf, config: &Stitch) -> Option<Pixbuf> {
+    join(first, second, true, config)
+}
+
+fn join(first: &Pixbuf, second: &Pixbuf, right: bool, config: &Stitch) -> Option<Pixbuf> {
+    let gap = config.separator_width() as i32;
+    let (width, height) = if right {
+        (
+            first.width() + gap + second.width(),
+            first.height().max(second.height()),
+        )
+    } else {
+        (
+            first.width().max(second.width()),
+            first.height() + gap + second.height(),
+        )
+    };
+    let result = Pixbuf::new(Colorspace::Rgb, true, 8, width, height)?;
+    result.fill(0);
+    if gap > 0 {
+        let separator = if right {
+            result.new_subpixbuf(first.width(), 0, gap, height)
+        } else {
+            result.new_subpixbuf(0, first.height(), width, gap)
+        };
+        let color = config.separator_color();
+        separator.fill(u32::from_be_bytes([color.r, color.g, color.b, color.a]));
+    }
+    first.copy_area(0, 0, first.width(), first.height(), &result, 0, 0);
+    let (x, y) = if right {
+        (first.width() + gap, 0)
+    } else {
+        (0, first.height() + gap)
+    };
+    second.copy_area(0, 0, second.width(), second.height(), &result, x, y);
+    Some(result)
+}
+
+/// 1.0 for a square, the longer a rectangle the smaller
+fn squareness(width: i32, height: i32) -> f64 {
+    width.min(height) as f64 / width.max(height) as f64
+}
@@ -0,0 +1,298 @@
+use std::{
+    env,
+    fmt::Write as _,
+    fs,
+    io::{self, BufRead, BufReader, Write},
+};
+
+use anyhow::{anyhow, Context, Result};
+use gdk_pixbuf::Pixbuf;
+use image::{imageops, RgbaImage};
+
+use crate::{configuration::APP_CONFIG, input};
+
+/// Widest preview shown when the terminal doesn't tell its size in pixels
+const FALLBACK_PREVIEW_WIDTH: u32 = 800;
+
+// the terminal graphics protocols take base64
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Whether there is no display to open a window on, e.g. when satty runs over SSH
+pub fn no_display() -> bool {
+    env::var_os("WAYLAND_DISPLAY").is_none() && env::var_os("DISPLAY").is_none()
+}
+
+/// Show the image in the terminal and apply the commands typed there until it is saved or
+/// the user quits. Commands are read from the terminal as stdin may hold the image.
+pub fn run(input_filename: &str) -> Result<()> {
+    let mut image = to_rgba(&input::load_image(input_filename)?);
+    let mut saved: Option<String> = None;
+
+    let tty = fs::File::open("/dev/tty").context("No terminal to read commands from")?;
+    let mut commands = BufReader::new(tty).lines();
+    let mut out = io::stderr();
+
+    preview(&image, &mut out)?;
+    loop {
+        write!(out, "satty> ")?;
+        out.flush()?;
+        let Some(line) = commands.next().transpose()? else {
+            break;
+        };
+        let mut words = line.split_whitespace();
+        match words.next() {
+            None => {}
+            Some("crop") => {
+                let numbers: Vec<u32> = words.filter_map(|w| w.parse().ok()).collect();
+                let [x, y, width, height] = numbers[..] else {
+                    writeln!(out, "usage: crop X Y WIDTH HEIGHT")?;
+                    continue;
+                };
+                if width == 0
+                    || height == 0
+                    || x + width > image.width()
+                    || y + height > image.height()
+                {
+                    writeln!(
+                        out,
+                        "the area must be inside the image of {}x{}",
+                        image.width(),
+                        image.height()
+                    )?;
+                    continue;
+                }
+                image = imageops::crop_imm(&image, x, y, width, height).to_image();
+                preview(&image, &mut out)?;
+            }
+            Some("save") => {
+                let template = words
+                    .next()
+                    .map(str::to_string)
+                    .or_else(|| APP_CONFIG.read().output_filename().cloned());
+                let Some(template) = template else {
+                    writeln!(out, "usage: save PATH, no output filename is configured")?;
+                    continue;
+                };
+                let path = output_path(&template);
+                match image.save_with_format(&path, image::ImageFormat::Png) {
+                    Ok(()) => {
+                        writeln!(out, "Saved to {path}")?;
+                        saved = Some(path);
+                    }
+                    Err(e) => writeln!(out, "Error saving {path}: {e}")?,
+                }
+            }
+            Some("copy") => match &saved {
+                // OSC 52 sets the clipboard of the local terminal, also over SSH
+                Some(path) => {
+                    write!(out, "\x1b]52;c;{}\x07", base64(path.as_bytes()))?;
+                    writeln!(out, "Copied {path}")?;
+                }
+                None => writeln!(out, "Nothing saved yet")?,
+            },
+            Some("show") => preview(&image, &mut out)?,
+            Some("quit" | "q" | "exit") => break,
+            Some(_) => writeln!(
+                out,
+                "commands: crop X Y WIDTH HEIGHT, save [PATH], copy (the saved path), show, quit"
+            )?,
+        }
+    }
+    Ok(())
+}
+
+fn to_rgba(pixbuf: &Pixbuf) -> RgbaImage {
+    let pixbuf = if pixbuf.has_alpha() {
+        pixbuf.clone()
+    } else {
+        pixbuf
+            .add_alpha(false, 0, 0, 0)
+            .unwrap_or_else(|_| pixbuf.clone())
+    };
+    let (width, height) = (pixbuf.width() as u32, pixbuf.height() as u32);
+    let stride = pixbuf.rowstride() as usize;
+    let bytes = pixbuf.read_pixel_bytes();
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for row in 0..height as usize {
+        data.extend_from_slice(&bytes[row * stride..row * stride + width as usize * 4]);
+    }
+    RgbaImage::from_raw(width, height, data).expect("buffer matches the dimensions")
+}
+
+/// Expand `{cwd}`, the date format and a leading `~/` of an output filename
+fn output_path(template: &str) -> String {
+    let cwd = env::current_dir().unwrap_or_default();
+    let template = template.replace("{cwd}", &cwd.to_string_lossy().replace('%', "%%"));
+    let mut path = String::new();
+    if write!(path, "{}", chrono::Local::now().format(&template)).is_err() {
+        path = template;
+    }
+    match (path.strip_prefix("~/"), env::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+        _ => path,
+    }
+}
+
+/// Width of the terminal window in pixels, if the terminal reports it
+fn terminal_width() -> Option<u32> {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let ok = unsafe { libc::ioctl(libc::STDERR_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    (ok && size.ws_xpixel > 0).then_some(size.ws_xpixel as u32)
+}
+
+fn preview(image: &RgbaImage, out: &mut impl Write) -> Result<()> {
+    let max_width = terminal_width().unwrap_or(FALLBACK_PREVIEW_WIDTH);
+    let scaled;
+    let image = if image.width() > max_width {
+        let height = (image.height() as u64 * max_width as u64 / image.width() as u64) as u32;
+        scaled = imageops::resize(image, max_width, height.max(1), imageops::Triangle);
+        &scaled
+    } else {
+        image
+    };
+
+    if supports_kitty_graphics() {
+        write_kitty(image, out)?;
+    } else {
+        write_sixel(image, out)?;
+    }
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Terminals known to implement the kitty graphics protocol, others get sixel
+fn supports_kitty_graphics() -> bool {
+    env::var_os("KITTY_WINDOW_ID").is_some()
+        || env::var("TERM").is_ok_and(|term| term.contains("kitty") || term.contains("ghostty"))
+        || env::var("TERM_PROGRAM").is_ok_and(|program| program == "WezTerm")
+}
+
+fn write_kitty(image: &RgbaImage, out: &mut impl Write) -> Result<()> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| anyhow!("Error encoding PNG: {e}"))?;
+    let encoded = base64(&png);
+
+    // the payload is sent in chunks of at most 4096 bytes
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            write!(out, "\x1b_Gf=100,a=T,m={more};")?;
+        } else {
+            write!(out, "\x1b_Gm={more};")?;
+        }
+        out.write_all(chunk)?;
+        write!(out, "\x1b\\")?;
+    }
+    Ok(())
+}
+
+/// Sixel image with the colors reduced to a 6x6x6 cube, transparent pixels are left out
+fn write_sixel(image: &RgbaImage, out: &mut impl Write) -> Result<()> {
+    let level = |value: u8| (value as u16 * 5 + 127) / 255;
+    let index = |pixel: &image::Rgba<u8>| {
+        (pixel[3] >= 128).then(|| level(pixel[0]) * 36 + level(pixel[1]) * 6 + level(pixel[2]))
+    };
+
+    let mut sixel = String::from("\x1bPq");
+    write!(sixel, "\"1;1;{};{}", image.width(), image.height())?;
+    for i in 0..216u16 {
+        // sixel colors are given in percent
+        let percent = |l: u16| l * 20;
+        write!(
+            sixel,
+            "#{i};2;{};{};{}",
+            percent(i / 36),
+            percent(i / 6 % 6),
+            percent(i % 6)
+        )?;
+    }
+
+    for band in (0..image.height()).step_by(6) {
+        let rows = (image.height() - band).min(6);
+        let mut colors: Vec<u16> = (0..image.width())
+            .flat_map(|x| (0..rows).filter_map(move |y| index(image.get_pixel(x, band + y))))
+            .collect();
+        colors.sort_unstable();
+        colors.dedup();
+
+        for color in colors {
+            write!(sixel, "#{color}")?;
+            let mut run: Option<(char, u32)> = None;
+            for x in 0..image.width() {
+                let bits = (0..rows)
+                    .filter(|&y| index(image.get_pixel(x, band + y)) == Some(color))
+                    .fold(0u8, |bits, y| bits | 1 << y);
+                let c = (b'?' + bits) as char;
+                run = match run {
+                    Some((previous, n)) if previous == c => Some((c, n + 1)),
+                    Some((previous, n)) => {
+                        push_run(&mut sixel, previous, n);
+                        Some((c, 1))
+                    }
+                    None => Some((c, 1)),
+                };
+            }
+            if let Some((c, n)) = run {
+                push_run(&mut sixel, c, n);
+            }
+            // back to the start of the band for the next color
+            sixel.push('$');
+        }
+        sixel.push('-');
+    }
+    sixel.push_str("\x1b\\");
+    out.write_all(sixel.as_bytes())?;
+    Ok(())
+}
+
+fn push_run(sixel: &mut String, c: char, n: u32) {
+    if n > 3 {
+        let _ = write!(sixel, "!{n}{c}");
+    } else {
+        sixel.extend(std::iter::repeat_n(c, n as usize));
+    }
+}
+
+fn base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| {
+            bits | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_rfc_4648() {
+        // the test vectors of RFC 4648
+        for (data, encoded) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64(data.as_bytes()), encoded);
+        }
+        assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+    }
+}
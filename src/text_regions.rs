@@ -0,0 +1,142 @@
+use image::GrayImage;
+
+use crate::math::Vec2D;
+
+/// runs with fewer edges are lines or noise, not letters
+const MIN_EDGES: u32 = 3;
+
+/// gap in pixels bridged between the letters and words of a line
+const MAX_GAP: u32 = 8;
+
+/// regions lower than this are dots, underlines and the like
+const MIN_LINE_HEIGHT: u32 = 5;
+
+/// Find lines of text in `image` by their dense edges. Every row is split into runs of edges
+/// close to each other, runs overlapping in consecutive rows form a region. `threshold` is the
+/// brightness difference between neighboring pixels counting as an edge. Returns the bounds of
+/// the regions, grown by `padding` and merged where they overlap.
+pub fn find_text_regions(
+    image: &GrayImage,
+    threshold: u8,
+    max_line_height: u32,
+    padding: u32,
+) -> Vec<(Vec2D, Vec2D)> {
+    let (width, height) = image.dimensions();
+    if width < 2 || height < 2 {
+        return Vec::new();
+    }
+
+    // runs of each row as (row, start, end), ordered by row and start
+    let mut runs: Vec<(u32, u32, u32)> = Vec::new();
+    let mut rows = Vec::with_capacity(height as usize);
+    for (y, row) in image.as_raw().chunks_exact(width as usize).enumerate() {
+        let first = runs.len();
+        // start, end and number of edges of the current run
+        let mut current: Option<(u32, u32, u32)> = None;
+        for (x, pair) in row.windows(2).enumerate() {
+            if pair[0].abs_diff(pair[1]) < threshold {
+                continue;
+            }
+            let x = x as u32;
+            current = match current {
+                Some((start, end, edges)) if x <= end + MAX_GAP => Some((start, x + 2, edges + 1)),
+                previous => {
+                    if let Some((start, end, _)) = previous.filter(|run| run.2 >= MIN_EDGES) {
+                        runs.push((y as u32, start, end));
+                    }
+                    Some((x, x + 2, 1))
+                }
+            };
+        }
+        if let Some((start, end, _)) = current.filter(|run| run.2 >= MIN_EDGES) {
+            runs.push((y as u32, start, end));
+        }
+        rows.push(first..runs.len());
+    }
+
+    // join overlapping runs of consecutive rows
+    let mut parents: Vec<usize> = (0..runs.len()).collect();
+    for pair in rows.windows(2) {
+        let (mut above, mut below) = (pair[0].start, pair[1].start);
+        while above < pair[0].end && below < pair[1].end {
+            let (_, above_start, above_end) = runs[above];
+            let (_, below_start, below_end) = runs[below];
+            if above_start < below_end && below_start < above_end {
+                let (a, b) = (root(&mut parents, above), root(&mut parents, below));
+                parents[a] = b;
+            }
+            if above_end < below_end {
+                above += 1;
+            } else {
+                below += 1;
+            }
+        }
+    }
+
+    // bounds of each region as (left, top, right, bottom)
+    let mut bounds: Vec<Option<(u32, u32, u32, u32)>> = vec![None; runs.len()];
+    for (index, &(y, start, end)) in runs.iter().enumerate() {
+        let region = &mut bounds[root(&mut parents, index)];
+        *region = Some(match *region {
+            Some((left, top, right, bottom)) => (
+                left.min(start),
+                top.min(y),
+                right.max(end),
+                bottom.max(y + 1),
+            ),
+            None => (start, y, end, y + 1),
+        });
+    }
+
+    // lines of text are wider than high, squares are rather icons
+    let mut regions: Vec<(u32, u32, u32, u32)> = bounds
+        .into_iter()
+        .flatten()
+        .filter(|&(left, top, right, bottom)| {
+            let line_height = bottom - top;
+            (MIN_LINE_HEIGHT..=max_line_height).contains(&line_height) && right - left > line_height
+        })
+        .map(|(left, top, right, bottom)| {
+            (
+                left.saturating_sub(padding),
+                top.saturating_sub(padding),
+                (right + padding).min(width),
+                (bottom + padding).min(height),
+            )
+        })
+        .collect();
+
+    // parts of a line, like the accents above it, overlap it once padded
+    let mut merged = true;
+    while merged {
+        merged = false;
+        for i in 0..regions.len() {
+            for j in (i + 1..regions.len()).rev() {
+                let (a, b) = (regions[i], regions[j]);
+                if a.0 < b.2 && b.0 < a.2 && a.1 < b.3 && b.1 < a.3 {
+                    regions[i] = (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3));
+                    regions.remove(j);
+                    merged = true;
+                }
+            }
+        }
+    }
+
+    regions
+        .into_iter()
+        .map(|(left, top, right, bottom)| {
+            (
+                Vec2D::new(left as f32, top as f32),
+                Vec2D::new(right as f32, bottom as f32),
+            )
+        })
+        .collect()
+}
+
+fn root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
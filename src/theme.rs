@@ -0,0 +1,128 @@
+use std::fs;
+
+use relm4::{
+    adw,
+    gtk::{self, gdk::DisplayManager, CssProvider},
+};
+use xdg::BaseDirectories;
+
+use crate::configuration::APP_CONFIG;
+
+/// Colors of the overlays for a dark and a light desktop. Theme files and `overrides.css`
+/// redefine them with `@define-color`, the last definition wins.
+const DARK_COLORS: &str = "
+    @define-color satty_toolbar_fg #f9f9f9;
+    @define-color satty_toolbar_bg #00000099;
+    @define-color satty_toast_fg #f9f9f9;
+    @define-color satty_toast_bg #00000099;
+    @define-color satty_accent #78aeed;
+";
+const LIGHT_COLORS: &str = "
+    @define-color satty_toolbar_fg #241f31;
+    @define-color satty_toolbar_bg #fafafacc;
+    @define-color satty_toast_fg #241f31;
+    @define-color satty_toast_bg #fafafacc;
+    @define-color satty_accent #1c71d8;
+";
+
+const STYLE: &str = "
+    .root {
+        min-width: 50rem;
+        min-height: 10rem;
+    }
+    .toolbar {color: @satty_toolbar_fg; background: @satty_toolbar_bg;}
+    .toolbar button:checked {color: @satty_accent;}
+    .toast {
+        color: @satty_toast_fg;
+        background: @satty_toast_bg;
+        border-radius: 6px;
+    }
+    .toast-top {margin-top: 50px;}
+    .toast-bottom {margin-bottom: 50px;}
+    .toast progressbar trough, .toast progressbar progress {min-height: 4px;}
+    .status-bar {
+        color: @satty_toast_fg;
+        background: @satty_toast_bg;
+        border-radius: 0px 6px 0px 0px;
+        padding: 2px 6px;
+        font-size: smaller;
+        font-feature-settings: 'tnum';
+    }
+    .toolbar-bottom {border-radius: 6px 6px 0px 0px;}
+    .toolbar-top {border-radius: 0px 0px 6px 6px;}
+    .toolbar-floating {border-radius: 6px;}
+    .translucent {background: transparent;}
+";
+
+thread_local! {
+    static PROVIDER: CssProvider = CssProvider::new();
+}
+
+/// Style the app with the configured theme. With `theme = "system"` it switches between the
+/// dark and light one whenever the desktop does.
+pub fn apply() {
+    let Some(display) = DisplayManager::get().default_display() else {
+        println!("Cannot apply style");
+        return;
+    };
+    PROVIDER.with(|provider| gtk::style_context_add_provider_for_display(&display, provider, 1));
+    load();
+    if APP_CONFIG.read().theme() == "system" {
+        adw::StyleManager::default().connect_dark_notify(|_| load());
+    }
+}
+
+/// Whether satty's own animations are left out: the `reduce-motion` setting if given,
+/// otherwise the desktop's preference as GTK follows it
+pub fn reduce_motion() -> bool {
+    APP_CONFIG.read().reduce_motion().unwrap_or_else(|| {
+        gtk::Settings::default().is_some_and(|settings| !settings.is_gtk_enable_animations())
+    })
+}
+
+fn load() {
+    let theme = match APP_CONFIG.read().theme() {
+        "system" if adw::StyleManager::default().is_dark() => "dark".to_string(),
+        "system" => "light".to_string(),
+        theme => theme.to_string(),
+    };
+    // themes of other names build on the dark colors, they suit any image
+    let mut css = if theme == "light" {
+        LIGHT_COLORS
+    } else {
+        DARK_COLORS
+    }
+    .to_string();
+    css.push_str(STYLE);
+    let file = format!("themes/{theme}.css");
+    match read_config_css(&file) {
+        Some(content) => css.push_str(&content),
+        None if theme != "dark" && theme != "light" => {
+            eprintln!(
+                "Warning: theme `{theme}` not found, expected {file} in the config directory"
+            );
+        }
+        None => {}
+    }
+    if let Some(content) = read_config_css("overrides.css") {
+        css.push_str(&content);
+    }
+    PROVIDER.with(|provider| provider.load_from_data(&css));
+}
+
+fn read_config_css(file: &str) -> Option<String> {
+    let dirs = BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
+    let path = dirs.get_config_file(file)?;
+
+    if !path.exists() {
+        return None;
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(content) => Some(content),
+        Err(e) => {
+            eprintln!("failed to read CSS from {}: {}", path.display(), e);
+            None
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use anyhow::Result;
+use xdg::BaseDirectories;
+
+use crate::{
+    configuration::{ToolStyleMemory, APP_CONFIG},
+    style::ToolStyle,
+    tools::Tools,
+};
+
+const STATE_FILE: &str = "tool-styles.json";
+
+/// Color, size and fill last used with each tool, depending on `tool-style-memory`
+pub struct ToolStyles {
+    memory: ToolStyleMemory,
+    styles: HashMap<Tools, ToolStyle>,
+}
+
+impl ToolStyles {
+    /// Start with the styles of the last session if they are persistent
+    pub fn load() -> Self {
+        let memory = APP_CONFIG.read().tool_style_memory();
+        let styles = match memory {
+            ToolStyleMemory::Persistent => Self::read().unwrap_or_else(|e| {
+                println!("Error while reading tool styles: {e}");
+                HashMap::new()
+            }),
+            _ => HashMap::new(),
+        };
+        Self { memory, styles }
+    }
+
+    /// Style last used with `tool`, always `None` if all tools share one style
+    pub fn get(&self, tool: Tools) -> Option<ToolStyle> {
+        self.styles.get(&tool).copied()
+    }
+
+    pub fn remember(&mut self, tool: Tools, style: ToolStyle) {
+        if self.memory == ToolStyleMemory::Shared {
+            return;
+        }
+        let changed = self.styles.insert(tool, style) != Some(style);
+        if changed && self.memory == ToolStyleMemory::Persistent {
+            if let Err(e) = self.write() {
+                println!("Error while saving tool styles: {e}");
+            }
+        }
+    }
+
+    fn read() -> Result<HashMap<Tools, ToolStyle>> {
+        match Self::path(false)? {
+            Some(path) if path.exists() => Ok(serde_json::from_slice(&fs::read(path)?)?),
+            _ => Ok(HashMap::new()),
+        }
+    }
+
+    fn write(&self) -> Result<()> {
+        if let Some(path) = Self::path(true)? {
+            fs::write(path, serde_json::to_vec_pretty(&self.styles)?)?;
+        }
+        Ok(())
+    }
+
+    fn path(create: bool) -> Result<Option<PathBuf>> {
+        let dirs = BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
+        Ok(if create {
+            Some(dirs.place_state_file(STATE_FILE)?)
+        } else {
+            dirs.get_state_file(STATE_FILE)
+        })
+    }
+}
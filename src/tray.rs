@@ -0,0 +1,316 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use relm4::gtk::{
+    gio::{self, BusNameOwnerFlags, BusNameWatcherFlags, DBusConnection},
+    glib::{self, variant::ObjectPath, Variant},
+    prelude::*,
+};
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+const MENU_INTERFACE: &str = "com.canonical.dbusmenu";
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+
+/// shown over the icon while an image is annotated
+const BADGE_ICON: &str = "media-record";
+
+const INTROSPECTION: &str = r#"<node>
+  <interface name="org.kde.StatusNotifierItem">
+    <property name="Category" type="s" access="read"/>
+    <property name="Id" type="s" access="read"/>
+    <property name="Title" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="IconName" type="s" access="read"/>
+    <property name="OverlayIconName" type="s" access="read"/>
+    <property name="ToolTip" type="(sa(iiay)ss)" access="read"/>
+    <property name="ItemIsMenu" type="b" access="read"/>
+    <property name="Menu" type="o" access="read"/>
+    <method name="Activate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="SecondaryActivate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="ContextMenu">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="Scroll">
+      <arg name="delta" type="i" direction="in"/>
+      <arg name="orientation" type="s" direction="in"/>
+    </method>
+    <signal name="NewOverlayIcon"/>
+    <signal name="NewToolTip"/>
+  </interface>
+  <interface name="com.canonical.dbusmenu">
+    <property name="Version" type="u" access="read"/>
+    <property name="TextDirection" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="IconThemePath" type="as" access="read"/>
+    <method name="GetLayout">
+      <arg name="parentId" type="i" direction="in"/>
+      <arg name="recursionDepth" type="i" direction="in"/>
+      <arg name="propertyNames" type="as" direction="in"/>
+      <arg name="revision" type="u" direction="out"/>
+      <arg name="layout" type="(ia{sv}av)" direction="out"/>
+    </method>
+    <method name="GetGroupProperties">
+      <arg name="ids" type="ai" direction="in"/>
+      <arg name="propertyNames" type="as" direction="in"/>
+      <arg name="properties" type="a(ia{sv})" direction="out"/>
+    </method>
+    <method name="GetProperty">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="name" type="s" direction="in"/>
+      <arg name="value" type="v" direction="out"/>
+    </method>
+    <method name="Event">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="eventId" type="s" direction="in"/>
+      <arg name="data" type="v" direction="in"/>
+      <arg name="timestamp" type="u" direction="in"/>
+    </method>
+    <method name="EventGroup">
+      <arg name="events" type="a(isvu)" direction="in"/>
+      <arg name="idErrors" type="ai" direction="out"/>
+    </method>
+    <method name="AboutToShow">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="needUpdate" type="b" direction="out"/>
+    </method>
+    <method name="AboutToShowGroup">
+      <arg name="ids" type="ai" direction="in"/>
+      <arg name="updatesNeeded" type="ai" direction="out"/>
+      <arg name="idErrors" type="ai" direction="out"/>
+    </method>
+    <signal name="LayoutUpdated">
+      <arg name="revision" type="u"/>
+      <arg name="parent" type="i"/>
+    </signal>
+  </interface>
+</node>"#;
+
+/// What the entries of the tray menu do
+#[derive(Debug, Clone, Copy)]
+pub enum TrayAction {
+    OpenFile,
+    Paste,
+    Quit,
+}
+
+/// Entries of the menu by their id, `None` is a separator. The menu never changes, so its
+/// layout has a single revision.
+const MENU: [(i32, Option<(&str, TrayAction)>); 4] = [
+    (1, Some(("Open File…", TrayAction::OpenFile))),
+    (2, Some(("Paste from Clipboard", TrayAction::Paste))),
+    (3, None),
+    (4, Some(("Quit Daemon", TrayAction::Quit))),
+];
+
+thread_local! {
+    static CONNECTION: RefCell<Option<DBusConnection>> = const { RefCell::new(None) };
+    static SESSION_OPEN: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Show the StatusNotifierItem of the daemon, `on_action` is called with the chosen menu
+/// entry. Clicking the icon opens a file.
+pub fn start(on_action: impl Fn(TrayAction) + 'static) {
+    if let Err(e) = register(Rc::new(on_action)) {
+        eprintln!("Warning: cannot show the tray icon, {e}");
+    }
+}
+
+/// Mark the icon while an image is annotated
+pub fn set_session_open(open: bool) {
+    if SESSION_OPEN.replace(open) == open {
+        return;
+    }
+    CONNECTION.with_borrow(|connection| {
+        let Some(connection) = connection else {
+            return;
+        };
+        for signal in ["NewOverlayIcon", "NewToolTip"] {
+            if let Err(e) = connection.emit_signal(None, ITEM_PATH, ITEM_INTERFACE, signal, None) {
+                eprintln!("Warning: cannot update the tray icon, {e}");
+            }
+        }
+    });
+}
+
+fn register(on_action: Rc<dyn Fn(TrayAction)>) -> Result<(), glib::Error> {
+    let connection = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>)?;
+    let node = gio::DBusNodeInfo::for_xml(INTROSPECTION)?;
+    let interface = |name| {
+        node.lookup_interface(name)
+            .expect("interface is in the XML")
+    };
+
+    let on_click = on_action.clone();
+    connection
+        .register_object(ITEM_PATH, &interface(ITEM_INTERFACE))
+        .method_call(move |_, _, _, _, method, _, invocation| {
+            if method == "Activate" {
+                on_click(TrayAction::OpenFile);
+            }
+            invocation.return_value(None);
+        })
+        .property(|_, _, _, _, property| item_property(property))
+        .build()?;
+    connection
+        .register_object(MENU_PATH, &interface(MENU_INTERFACE))
+        .method_call(move |_, _, _, _, method, parameters, invocation| {
+            invocation.return_value(menu_call(method, &parameters, &*on_action).as_ref());
+        })
+        .property(|_, _, _, _, property| menu_property(property))
+        .build()?;
+
+    let name = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+    gio::bus_own_name_on_connection(
+        &connection,
+        &name,
+        BusNameOwnerFlags::NONE,
+        |connection, name| {
+            // the watcher belongs to the panel, register again whenever it restarts
+            let name = name.to_string();
+            gio::bus_watch_name_on_connection(
+                &connection,
+                WATCHER,
+                BusNameWatcherFlags::NONE,
+                move |connection, _, _| register_with_watcher(&connection, &name),
+                |_, _| {},
+            );
+        },
+        |_, name| eprintln!("Warning: lost the bus name {name} of the tray icon"),
+    );
+    CONNECTION.set(Some(connection));
+    Ok(())
+}
+
+fn register_with_watcher(connection: &DBusConnection, name: &str) {
+    connection.call(
+        Some(WATCHER),
+        "/StatusNotifierWatcher",
+        WATCHER,
+        "RegisterStatusNotifierItem",
+        Some(&(name,).to_variant()),
+        None,
+        gio::DBusCallFlags::NONE,
+        -1,
+        None::<&gio::Cancellable>,
+        |result| {
+            if let Err(e) = result {
+                eprintln!("Warning: cannot show the tray icon, {e}");
+            }
+        },
+    );
+}
+
+fn item_property(property: &str) -> Variant {
+    let tooltip = if SESSION_OPEN.get() {
+        "An image is annotated"
+    } else {
+        "Waiting for images"
+    };
+    match property {
+        "Category" => "ApplicationStatus".to_variant(),
+        "Id" | "IconName" => "satty".to_variant(),
+        "Title" => "Satty".to_variant(),
+        "Status" => "Active".to_variant(),
+        "OverlayIconName" if SESSION_OPEN.get() => BADGE_ICON.to_variant(),
+        "ToolTip" => ("satty", Vec::<(i32, i32, Vec<u8>)>::new(), "Satty", tooltip).to_variant(),
+        "ItemIsMenu" => false.to_variant(),
+        "Menu" => ObjectPath::try_from(MENU_PATH).unwrap().to_variant(),
+        _ => "".to_variant(),
+    }
+}
+
+fn menu_property(property: &str) -> Variant {
+    match property {
+        "Version" => 3u32.to_variant(),
+        "TextDirection" => "ltr".to_variant(),
+        "Status" => "normal".to_variant(),
+        _ => Vec::<String>::new().to_variant(),
+    }
+}
+
+/// The reply to a call of a dbusmenu method, `None` for methods without one
+fn menu_call(
+    method: &str,
+    parameters: &Variant,
+    on_action: &dyn Fn(TrayAction),
+) -> Option<Variant> {
+    match method {
+        "GetLayout" => {
+            let (parent, _, _) = parameters.get::<(i32, i32, Vec<String>)>()?;
+            let layout = match parent {
+                0 => (
+                    0,
+                    HashMap::from([("children-display".to_string(), "submenu".to_variant())]),
+                    MENU.iter()
+                        .filter_map(|(id, _)| entry_layout(*id))
+                        .map(|entry| Variant::from_variant(&entry.to_variant()))
+                        .collect(),
+                ),
+                id => entry_layout(id)?,
+            };
+            Some((1u32, layout).to_variant())
+        }
+        "GetGroupProperties" => {
+            let (ids, _) = parameters.get::<(Vec<i32>, Vec<String>)>()?;
+            let properties: Vec<_> = ids
+                .into_iter()
+                .filter_map(|id| Some((id, entry_properties(id)?)))
+                .collect();
+            Some((properties,).to_variant())
+        }
+        "GetProperty" => {
+            let (id, name) = parameters.get::<(i32, String)>()?;
+            let value = entry_properties(id)?.remove(&name)?;
+            Some((value,).to_variant())
+        }
+        "Event" => {
+            let (id, event, _, _) = parameters.get::<(i32, String, Variant, u32)>()?;
+            handle_event(id, &event, on_action);
+            None
+        }
+        "EventGroup" => {
+            let (events,) = parameters.get::<(Vec<(i32, String, Variant, u32)>,)>()?;
+            for (id, event, _, _) in events {
+                handle_event(id, &event, on_action);
+            }
+            Some((Vec::<i32>::new(),).to_variant())
+        }
+        "AboutToShow" => Some((false,).to_variant()),
+        "AboutToShowGroup" => Some((Vec::<i32>::new(), Vec::<i32>::new()).to_variant()),
+        _ => None,
+    }
+}
+
+/// An entry of the menu as dbusmenu lays it out, its id, its properties and its children
+type Layout = (i32, HashMap<String, Variant>, Vec<Variant>);
+
+fn entry_layout(id: i32) -> Option<Layout> {
+    Some((id, entry_properties(id)?, Vec::new()))
+}
+
+fn entry_properties(id: i32) -> Option<HashMap<String, Variant>> {
+    let (_, entry) = MENU.iter().find(|(entry_id, _)| *entry_id == id)?;
+    Some(match entry {
+        Some((label, _)) => HashMap::from([("label".to_string(), label.to_variant())]),
+        None => HashMap::from([("type".to_string(), "separator".to_variant())]),
+    })
+}
+
+fn handle_event(id: i32, event: &str, on_action: &dyn Fn(TrayAction)) {
+    if event != "clicked" {
+        return;
+    }
+    if let Some((_, Some((_, action)))) = MENU.iter().find(|(entry_id, _)| *entry_id == id) {
+        on_action(*action);
+    }
+}
@@ -0,0 +1,134 @@
+use relm4::{
+    gtk::{pango::EllipsizeMode, prelude::*},
+    prelude::*,
+    RelmRemoveAllExt,
+};
+
+/// Lists the text annotations matching a query, activating one jumps to it
+pub struct AnnotationSearch {
+    texts: Vec<(usize, String)>,
+    // drawable index of each listed row
+    shown: Vec<usize>,
+    list: gtk::ListBox,
+}
+
+#[derive(Debug, Clone)]
+pub enum AnnotationSearchInput {
+    /// open the search for these texts and their drawable indices
+    Show(Vec<(usize, String)>),
+    QueryChanged(String),
+    /// jump to the listed row, Enter in the search field jumps to the first one
+    Activate(usize),
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum AnnotationSearchOutput {
+    Jump(usize),
+}
+
+impl AnnotationSearch {
+    fn filter(&mut self, query: &str) {
+        self.list.remove_all();
+        self.shown.clear();
+
+        let query = query.to_lowercase();
+        for (index, text) in &self.texts {
+            if !text.to_lowercase().contains(&query) {
+                continue;
+            }
+            let label = gtk::Label::builder()
+                .label(text.lines().next().unwrap_or_default())
+                .xalign(0.0)
+                .ellipsize(EllipsizeMode::End)
+                .max_width_chars(40)
+                .build();
+            self.list.append(&label);
+            self.shown.push(*index);
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for AnnotationSearch {
+    type Init = ();
+    type Input = AnnotationSearchInput;
+    type Output = AnnotationSearchOutput;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Popover {
+            set_has_arrow: false,
+            set_position: gtk::PositionType::Bottom,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 6,
+
+                #[name = "entry"]
+                gtk::SearchEntry {
+                    set_placeholder_text: Some("Search annotations"),
+                    connect_search_changed[sender] => move |entry| {
+                        sender.input(AnnotationSearchInput::QueryChanged(entry.text().into()));
+                    },
+                    connect_activate[sender] => move |_| {
+                        sender.input(AnnotationSearchInput::Activate(0));
+                    },
+                },
+                gtk::ScrolledWindow {
+                    set_hscrollbar_policy: gtk::PolicyType::Never,
+                    set_propagate_natural_height: true,
+                    set_max_content_height: 300,
+
+                    #[local_ref]
+                    list -> gtk::ListBox {
+                        set_activate_on_single_click: true,
+                        connect_row_activated[sender] => move |_, row| {
+                            sender.input(AnnotationSearchInput::Activate(row.index() as usize));
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(_: (), root: Self::Root, sender: ComponentSender<Self>) -> ComponentParts<Self> {
+        let model = AnnotationSearch {
+            texts: Vec::new(),
+            shown: Vec::new(),
+            list: gtk::ListBox::new(),
+        };
+        model
+            .list
+            .set_placeholder(Some(&gtk::Label::new(Some("No matching annotations"))));
+
+        let list = &model.list;
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: AnnotationSearchInput,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            AnnotationSearchInput::Show(texts) => {
+                self.texts = texts;
+                self.filter(&widgets.entry.text());
+                root.popup();
+                widgets.entry.grab_focus();
+            }
+            AnnotationSearchInput::QueryChanged(query) => self.filter(&query),
+            AnnotationSearchInput::Activate(row) => {
+                if let Some(&index) = self.shown.get(row) {
+                    sender
+                        .output_sender()
+                        .emit(AnnotationSearchOutput::Jump(index));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,423 @@
+use std::{cell::Cell, fs, path::PathBuf, rc::Rc};
+
+use anyhow::Result;
+use hex_color::HexColor;
+use relm4::{
+    gtk::{glib, prelude::*},
+    prelude::*,
+};
+use xdg::BaseDirectories;
+
+use crate::{
+    configuration::APP_CONFIG,
+    style::Color,
+    ui::toolbars::{create_icon, create_icon_pixbuf},
+};
+
+const STATE_FILE: &str = "recent-colors.json";
+
+/// number of recently used colors that are kept
+const MAX_RECENT: usize = 8;
+
+/// Chooses the custom color with a hex entry, HSV sliders, the eyedropper or one of the
+/// recently used colors, which are kept across sessions
+pub struct ColorPicker {
+    color: Color,
+    recent: Vec<Color>,
+    // set while the widgets show a new color, their signals are ignored meanwhile
+    updating: Rc<Cell<bool>>,
+    preview: gtk::Image,
+    hex_entry: gtk::Entry,
+    hue: gtk::Scale,
+    saturation: gtk::Scale,
+    value: gtk::Scale,
+    alpha: gtk::Scale,
+    recent_box: gtk::Box,
+}
+
+#[derive(Debug, Clone)]
+pub enum ColorPickerInput {
+    /// open the dialog with the given color
+    Show(Color),
+    Select(Color),
+    HexChanged(String),
+    SlidersChanged,
+    PickFromImage,
+    /// color of the image clicked with the eyedropper, `None` if picking got canceled
+    Picked(Option<Color>),
+    Submit,
+    Cancel,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum ColorPickerOutput {
+    Selected(Color),
+    /// the next click on the image should pick a color, answered with
+    /// [`ColorPickerInput::Picked`]
+    PickFromImage,
+}
+
+impl ColorPicker {
+    /// Show `self.color` in all widgets without sending their change signals back
+    fn show_color(&self) {
+        self.updating.set(true);
+        let (hue, saturation, value) = to_hsv(self.color);
+        // a gray has no hue, keep the one of the slider
+        if saturation > 0.0 {
+            self.hue.set_value(hue);
+        }
+        if value > 0.0 {
+            self.saturation.set_value(saturation * 100.0);
+        }
+        self.value.set_value(value * 100.0);
+        self.alpha.set_value(self.color.a as f64);
+        let hex = self.color.to_string();
+        if parse_hex(&self.hex_entry.text()) != Some(self.color) {
+            self.hex_entry.set_text(&hex);
+        }
+        self.preview
+            .set_from_pixbuf(Some(&create_icon_pixbuf(self.color)));
+        self.updating.set(false);
+    }
+
+    fn fill_recent_box(&self, sender: &ComponentSender<Self>) {
+        while let Some(child) = self.recent_box.first_child() {
+            self.recent_box.remove(&child);
+        }
+        for &color in &self.recent {
+            self.recent_box.append(&color_button(color, sender));
+        }
+        self.recent_box.set_visible(!self.recent.is_empty());
+    }
+
+    fn remember(&mut self, color: Color) {
+        self.recent.retain(|&c| c != color);
+        self.recent.insert(0, color);
+        self.recent.truncate(MAX_RECENT);
+        if let Err(e) = write_recent(&self.recent) {
+            println!("Error while saving recent colors: {e}");
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for ColorPicker {
+    type Init = ();
+    type Input = ColorPickerInput;
+    type Output = ColorPickerOutput;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Window {
+            set_modal: true,
+            set_hide_on_close: true,
+            set_title: Some("Choose Color"),
+            set_titlebar: Some(&header_bar),
+
+            connect_close_request[sender] => move |_| {
+                sender.input(ColorPickerInput::Cancel);
+                glib::Propagation::Proceed
+            },
+
+            #[wrap(Some)]
+            set_child = &gtk::Grid {
+                set_row_spacing: 10,
+                set_column_spacing: 10,
+                set_margin_all: 12,
+
+                attach[0, 0, 1, 1] = &model.preview.clone(),
+                attach[1, 0, 1, 1] = &gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 6,
+
+                    append = &model.hex_entry.clone(),
+                    gtk::Button {
+                        set_icon_name: "eyedropper-regular",
+                        set_tooltip: "Pick a color from the image",
+                        connect_clicked => ColorPickerInput::PickFromImage,
+                    },
+                },
+
+                attach[0, 1, 1, 1] = &gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_label: "Hue",
+                },
+                attach[1, 1, 1, 1] = &model.hue.clone(),
+                attach[0, 2, 1, 1] = &gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_label: "Saturation",
+                },
+                attach[1, 2, 1, 1] = &model.saturation.clone(),
+                attach[0, 3, 1, 1] = &gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_label: "Value",
+                },
+                attach[1, 3, 1, 1] = &model.value.clone(),
+                attach[0, 4, 1, 1] = &gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_label: "Alpha",
+                },
+                attach[1, 4, 1, 1] = &model.alpha.clone(),
+
+                attach[0, 5, 1, 1] = &gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_label: "Recent",
+                    #[watch]
+                    set_visible: !model.recent.is_empty(),
+                },
+                attach[1, 5, 1, 1] = &model.recent_box.clone(),
+                attach[0, 6, 1, 1] = &gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_label: "Custom",
+                    set_visible: custom_box.first_child().is_some(),
+                },
+                attach[1, 6, 1, 1] = &custom_box.clone(),
+            },
+        }
+    }
+
+    fn init(_: (), root: Self::Root, sender: ComponentSender<Self>) -> ComponentParts<Self> {
+        let slider = |max: f64| {
+            gtk::Scale::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .adjustment(&gtk::Adjustment::new(0.0, 0.0, max, 1.0, 10.0, 0.0))
+                .digits(0)
+                .draw_value(true)
+                .hexpand(true)
+                .width_request(240)
+                .build()
+        };
+        let color_box = || {
+            gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(2)
+                .build()
+        };
+        let color = Color::default();
+        let model = ColorPicker {
+            color,
+            recent: read_recent().unwrap_or_else(|e| {
+                println!("Error while reading recent colors: {e}");
+                Vec::new()
+            }),
+            updating: Rc::new(Cell::new(false)),
+            preview: gtk::Image::from_pixbuf(Some(&create_icon_pixbuf(color))),
+            hex_entry: gtk::Entry::builder()
+                .max_length(9)
+                .width_chars(10)
+                .placeholder_text("#rrggbb")
+                .build(),
+            hue: slider(360.0),
+            saturation: slider(100.0),
+            value: slider(100.0),
+            alpha: slider(255.0),
+            recent_box: color_box(),
+        };
+
+        for slider in [&model.hue, &model.saturation, &model.value, &model.alpha] {
+            let sender = sender.clone();
+            let updating = model.updating.clone();
+            slider.connect_value_changed(move |_| {
+                if !updating.get() {
+                    sender.input(ColorPickerInput::SlidersChanged);
+                }
+            });
+        }
+        {
+            let sender = sender.clone();
+            let updating = model.updating.clone();
+            model.hex_entry.connect_changed(move |entry| {
+                if !updating.get() {
+                    sender.input(ColorPickerInput::HexChanged(entry.text().to_string()));
+                }
+            });
+        }
+
+        let custom_box = color_box();
+        for &color in APP_CONFIG.read().color_palette().custom() {
+            custom_box.append(&color_button(color, &sender));
+        }
+        model.fill_recent_box(&sender);
+        model.show_color();
+
+        // the title bar didn't really work within the view! macro.
+        let title_label = gtk::Label::builder()
+            .label("Choose Color")
+            .margin_start(6)
+            .build();
+
+        let cancel_button = gtk::Button::builder().label("Cancel").build();
+        let sender_clone = sender.clone();
+        cancel_button.connect_clicked(move |_| {
+            sender_clone.input(ColorPickerInput::Cancel);
+        });
+
+        let select_button = gtk::Button::builder().label("Select").build();
+        let sender_clone = sender.clone();
+        select_button.connect_clicked(move |_| {
+            sender_clone.input(ColorPickerInput::Submit);
+        });
+
+        let header_bar = gtk::HeaderBar::builder().show_title_buttons(false).build();
+
+        header_bar.set_title_widget(Some(&title_label));
+        header_bar.pack_start(&cancel_button);
+        header_bar.pack_end(&select_button);
+
+        let widgets = view_output!();
+
+        let key_controller = gtk::EventControllerKey::builder()
+            .propagation_phase(gtk::PropagationPhase::Capture)
+            .build();
+
+        key_controller.connect_key_pressed(move |_, keyval, _, _| {
+            use gtk::gdk::Key;
+            match keyval {
+                Key::Return | Key::KP_Enter => {
+                    sender.input(ColorPickerInput::Submit);
+                    glib::Propagation::Stop
+                }
+                Key::Escape => {
+                    sender.input(ColorPickerInput::Cancel);
+                    glib::Propagation::Stop
+                }
+                _ => glib::Propagation::Proceed,
+            }
+        });
+        root.add_controller(key_controller);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(
+        &mut self,
+        message: ColorPickerInput,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            ColorPickerInput::Show(color) => {
+                self.color = color;
+                self.show_color();
+                root.show();
+            }
+            ColorPickerInput::Select(color) => {
+                self.color = color;
+                self.show_color();
+            }
+            ColorPickerInput::HexChanged(text) => {
+                if let Some(color) = parse_hex(&text) {
+                    self.color = color;
+                    self.show_color();
+                }
+            }
+            ColorPickerInput::SlidersChanged => {
+                self.color = from_hsv(
+                    self.hue.value(),
+                    self.saturation.value() / 100.0,
+                    self.value.value() / 100.0,
+                    self.alpha.value().round() as u8,
+                );
+                self.show_color();
+            }
+            ColorPickerInput::PickFromImage => {
+                // out of the way of the image until a color is clicked
+                root.hide();
+                sender
+                    .output_sender()
+                    .emit(ColorPickerOutput::PickFromImage);
+            }
+            ColorPickerInput::Picked(color) => {
+                if let Some(color) = color {
+                    self.color = color;
+                    self.show_color();
+                }
+                root.show();
+            }
+            ColorPickerInput::Submit => {
+                self.remember(self.color);
+                self.fill_recent_box(&sender);
+                sender
+                    .output_sender()
+                    .emit(ColorPickerOutput::Selected(self.color));
+                root.hide();
+            }
+            ColorPickerInput::Cancel => root.hide(),
+        }
+    }
+}
+
+fn color_button(color: Color, sender: &ComponentSender<ColorPicker>) -> gtk::Button {
+    let button = gtk::Button::builder()
+        .focusable(false)
+        .child(&create_icon(color))
+        .tooltip_text(color.to_string())
+        .build();
+    let sender = sender.clone();
+    button.connect_clicked(move |_| sender.input(ColorPickerInput::Select(color)));
+    button
+}
+
+/// `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, the `#` may be left out
+fn parse_hex(text: &str) -> Option<Color> {
+    let text = text.trim();
+    let text = text.strip_prefix('#').unwrap_or(text);
+    HexColor::parse(&format!("#{text}")).ok().map(Color::from)
+}
+
+/// Hue in degrees, saturation and value from 0 to 1
+fn to_hsv(color: Color) -> (f64, f64, f64) {
+    let (r, g, b, _) = color.to_rgba_f64();
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+    (hue, saturation, max)
+}
+
+fn from_hsv(hue: f64, saturation: f64, value: f64, alpha: u8) -> Color {
+    let chroma = value * saturation;
+    let sector = (hue / 60.0).rem_euclid(6.0);
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let channel = |c: f64| ((c + value - chroma) * 255.0).round() as u8;
+    Color::new(channel(r), channel(g), channel(b), alpha)
+}
+
+fn read_recent() -> Result<Vec<Color>> {
+    match path(false)? {
+        Some(path) if path.exists() => Ok(serde_json::from_slice(&fs::read(path)?)?),
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn write_recent(recent: &[Color]) -> Result<()> {
+    if let Some(path) = path(true)? {
+        fs::write(path, serde_json::to_vec(recent)?)?;
+    }
+    Ok(())
+}
+
+fn path(create: bool) -> Result<Option<PathBuf>> {
+    let dirs = BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
+    Ok(if create {
+        Some(dirs.place_state_file(STATE_FILE)?)
+    } else {
+        dirs.get_state_file(STATE_FILE)
+    })
+}
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use relm4::gtk::{self, prelude::*};
+
+/// Ask for an image file, `on_chosen` gets its path unless the dialog is cancelled
+pub fn show(
+    parent: Option<gtk::Window>,
+    title: &str,
+    accept_label: &str,
+    on_chosen: impl Fn(PathBuf) + 'static,
+) {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("Images"));
+    filter.add_pixbuf_formats();
+    choose(
+        parent,
+        title,
+        gtk::FileChooserAction::Open,
+        accept_label,
+        &filter,
+        on_chosen,
+    );
+}
+
+/// Ask for a JSON file of annotations to open, or where to save one with `save`
+pub fn show_annotations(
+    parent: Option<gtk::Window>,
+    title: &str,
+    save: bool,
+    on_chosen: impl Fn(PathBuf) + 'static,
+) {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("Annotations"));
+    filter.add_mime_type("application/json");
+    filter.add_suffix("json");
+    let (action, accept_label) = if save {
+        (gtk::FileChooserAction::Save, "Export")
+    } else {
+        (gtk::FileChooserAction::Open, "Import")
+    };
+    choose(parent, title, action, accept_label, &filter, on_chosen);
+}
+
+fn choose(
+    parent: Option<gtk::Window>,
+    title: &str,
+    action: gtk::FileChooserAction,
+    accept_label: &str,
+    filter: &gtk::FileFilter,
+    on_chosen: impl Fn(PathBuf) + 'static,
+) {
+    let builder = gtk::FileChooserDialog::builder()
+        .modal(parent.is_some())
+        .title(title)
+        .action(action);
+    let dialog = match parent {
+        Some(w) => builder.transient_for(&w),
+        None => builder,
+    }
+    .build();
+    dialog.add_buttons(&[
+        ("Cancel", gtk::ResponseType::Cancel),
+        (accept_label, gtk::ResponseType::Accept),
+    ]);
+    dialog.add_filter(filter);
+
+    dialog.connect_response(move |dialog, response| {
+        if response == gtk::ResponseType::Accept {
+            if let Some(path) = dialog.file().and_then(|file| file.path()) {
+                on_chosen(path);
+            }
+        }
+        dialog.close();
+    });
+    dialog.show();
+}
@@ -1 +1,10 @@
+pub mod annotation_search;
+pub mod color_picker;
+pub mod image_chooser;
+pub mod recolor_dialog;
+pub mod scanned_codes;
+pub mod snapshot_history;
+pub mod status_bar;
+pub mod toast;
+pub mod toolbar_positions;
 pub mod toolbars;
@@ -0,0 +1,239 @@
+use relm4::{
+    gtk::{gdk::RGBA, glib, prelude::*},
+    prelude::*,
+};
+
+use crate::{configuration::APP_CONFIG, style::Color, ui::toolbars::create_icon};
+
+/// Replaces one color of all annotations with another one, the sketch board previews the
+/// change while the dialog is open
+pub struct RecolorDialog {
+    from: Option<Color>,
+    to: Color,
+    from_box: gtk::Box,
+    to_button: gtk::ColorButton,
+}
+
+#[derive(Debug, Clone)]
+pub enum RecolorDialogInput {
+    /// open the dialog for the colors used by the annotations, `to` is preselected
+    Show {
+        used: Vec<Color>,
+        to: Color,
+    },
+    FromSelected(Color),
+    ToSelected(Color),
+    Submit,
+    Cancel,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum RecolorDialogOutput {
+    Preview(Option<(Color, Color)>),
+    Apply,
+}
+
+impl RecolorDialog {
+    fn fill_from_box(&self, used: &[Color], sender: &ComponentSender<Self>) {
+        while let Some(child) = self.from_box.first_child() {
+            self.from_box.remove(&child);
+        }
+
+        let mut group: Option<gtk::ToggleButton> = None;
+        for &color in used {
+            let button = gtk::ToggleButton::builder()
+                .focusable(false)
+                .child(&create_icon(color))
+                .tooltip_text(color.to_string())
+                .build();
+            button.set_group(group.as_ref());
+            button.set_active(self.from == Some(color));
+
+            let sender = sender.clone();
+            button.connect_toggled(move |button| {
+                if button.is_active() {
+                    sender.input(RecolorDialogInput::FromSelected(color));
+                }
+            });
+            self.from_box.append(&button);
+            group.get_or_insert(button);
+        }
+    }
+
+    fn preview(&self, sender: &ComponentSender<Self>) {
+        let recolor = self.from.map(|from| (from, self.to));
+        sender
+            .output_sender()
+            .emit(RecolorDialogOutput::Preview(recolor));
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for RecolorDialog {
+    type Init = ();
+    type Input = RecolorDialogInput;
+    type Output = RecolorDialogOutput;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Window {
+            set_modal: true,
+            set_hide_on_close: true,
+            set_title: Some("Recolor Annotations"),
+            set_titlebar: Some(&header_bar),
+
+            connect_close_request[sender] => move |_| {
+                sender.input(RecolorDialogInput::Cancel);
+                glib::Propagation::Proceed
+            },
+
+            #[wrap(Some)]
+            set_child = &gtk::Grid {
+                set_row_spacing: 10,
+                set_column_spacing: 10,
+                set_margin_all: 12,
+
+                attach[0, 0, 1, 1] = &gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_label: "Replace",
+                },
+                attach[1, 0, 1, 1] = &model.from_box.clone(),
+                attach[0, 1, 1, 1] = &gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_label: "With",
+                },
+                attach[1, 1, 1, 1] = &model.to_button.clone(),
+            },
+        }
+    }
+
+    fn init(_: (), root: Self::Root, sender: ComponentSender<Self>) -> ComponentParts<Self> {
+        let to = APP_CONFIG.read().color_palette().palette()[0];
+        let model = RecolorDialog {
+            from: None,
+            to,
+            from_box: gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(2)
+                .build(),
+            to_button: gtk::ColorButton::with_rgba(&RGBA::from(to)),
+        };
+
+        model.to_button.set_use_alpha(true);
+        model.to_button.set_halign(gtk::Align::Start);
+        let palette = APP_CONFIG
+            .read()
+            .color_palette()
+            .palette()
+            .iter()
+            .copied()
+            .map(RGBA::from)
+            .collect::<Vec<_>>();
+        if !palette.is_empty() {
+            model.to_button.add_palette(
+                gtk::Orientation::Horizontal,
+                8, // same as the custom color dialog
+                &palette,
+            );
+        }
+        {
+            let sender = sender.clone();
+            model.to_button.connect_color_set(move |button| {
+                sender.input(RecolorDialogInput::ToSelected(Color::from_gdk(
+                    button.rgba(),
+                )));
+            });
+        }
+
+        // the title bar didn't really work within the view! macro.
+        let title_label = gtk::Label::builder()
+            .label("Recolor Annotations")
+            .margin_start(6)
+            .build();
+
+        let cancel_button = gtk::Button::builder().label("Cancel").build();
+        let sender_clone = sender.clone();
+        cancel_button.connect_clicked(move |_| {
+            sender_clone.input(RecolorDialogInput::Cancel);
+        });
+
+        let apply_button = gtk::Button::builder().label("Apply").build();
+        let sender_clone = sender.clone();
+        apply_button.connect_clicked(move |_| {
+            sender_clone.input(RecolorDialogInput::Submit);
+        });
+
+        let header_bar = gtk::HeaderBar::builder().show_title_buttons(false).build();
+
+        header_bar.set_title_widget(Some(&title_label));
+        header_bar.pack_start(&cancel_button);
+        header_bar.pack_end(&apply_button);
+
+        let widgets = view_output!();
+
+        let key_controller = gtk::EventControllerKey::builder()
+            .propagation_phase(gtk::PropagationPhase::Capture)
+            .build();
+
+        key_controller.connect_key_pressed(move |_, keyval, _, _| {
+            use gtk::gdk::Key;
+            match keyval {
+                Key::Return => {
+                    sender.input(RecolorDialogInput::Submit);
+                    glib::Propagation::Stop
+                }
+                Key::Escape => {
+                    sender.input(RecolorDialogInput::Cancel);
+                    glib::Propagation::Stop
+                }
+                _ => glib::Propagation::Proceed,
+            }
+        });
+        root.add_controller(key_controller);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(
+        &mut self,
+        message: RecolorDialogInput,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            RecolorDialogInput::Show { used, to } => {
+                // keep the last choice if it is still in use, recoloring twice in a row is common
+                self.from = self.from.filter(|from| used.contains(from));
+                self.to = to;
+                self.to_button.set_rgba(&RGBA::from(to));
+                self.fill_from_box(&used, &sender);
+                self.preview(&sender);
+                root.show();
+            }
+            RecolorDialogInput::FromSelected(color) => {
+                self.from = Some(color);
+                self.preview(&sender);
+            }
+            RecolorDialogInput::ToSelected(color) => {
+                self.to = color;
+                self.preview(&sender);
+            }
+            RecolorDialogInput::Submit => {
+                if self.from.is_some() {
+                    sender.output_sender().emit(RecolorDialogOutput::Apply);
+                } else {
+                    sender
+                        .output_sender()
+                        .emit(RecolorDialogOutput::Preview(None));
+                }
+                root.hide();
+            }
+            RecolorDialogInput::Cancel => {
+                sender
+                    .output_sender()
+                    .emit(RecolorDialogOutput::Preview(None));
+                root.hide();
+            }
+        }
+    }
+}
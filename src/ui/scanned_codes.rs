@@ -0,0 +1,156 @@
+use relm4::{
+    gtk::{gdk, glib, prelude::*},
+    prelude::*,
+};
+
+use crate::{configuration::APP_CONFIG, notification::log_result};
+
+/// schemes offered to open, others like `WIFI:` are only worth copying
+const OPEN_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Lists the content of the QR codes and barcodes found in the image, each with buttons to
+/// copy it or open it if it is a link
+pub struct ScannedCodesDialog {
+    list: gtk::ListBox,
+}
+
+#[derive(Debug, Clone)]
+pub enum ScannedCodesDialogInput {
+    Show(Vec<String>),
+    Copy(String),
+    Open(String),
+    Close,
+}
+
+impl ScannedCodesDialog {
+    fn fill_list(&self, codes: &[String], sender: &ComponentSender<Self>) {
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+
+        for code in codes {
+            let row = gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(6)
+                .margin_top(6)
+                .margin_bottom(6)
+                .margin_start(6)
+                .margin_end(6)
+                .build();
+            row.append(
+                &gtk::Label::builder()
+                    .label(code)
+                    .selectable(true)
+                    .wrap(true)
+                    .wrap_mode(gtk::pango::WrapMode::WordChar)
+                    .xalign(0.0)
+                    .hexpand(true)
+                    .build(),
+            );
+
+            let copy_button = gtk::Button::builder()
+                .label("Copy")
+                .valign(gtk::Align::Center)
+                .build();
+            let (sender_clone, code_clone) = (sender.clone(), code.clone());
+            copy_button.connect_clicked(move |_| {
+                sender_clone.input(ScannedCodesDialogInput::Copy(code_clone.clone()));
+            });
+            row.append(&copy_button);
+
+            let openable = glib::Uri::peek_scheme(code)
+                .is_some_and(|scheme| OPEN_SCHEMES.contains(&scheme.as_str()));
+            if openable {
+                let open_button = gtk::Button::builder()
+                    .label("Open")
+                    .valign(gtk::Align::Center)
+                    .build();
+                let (sender_clone, code_clone) = (sender.clone(), code.clone());
+                open_button.connect_clicked(move |_| {
+                    sender_clone.input(ScannedCodesDialogInput::Open(code_clone.clone()));
+                });
+                row.append(&open_button);
+            }
+            self.list.append(&row);
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for ScannedCodesDialog {
+    type Init = ();
+    type Input = ScannedCodesDialogInput;
+    type Output = ();
+    type CommandOutput = ();
+
+    view! {
+        gtk::Window {
+            set_modal: true,
+            set_hide_on_close: true,
+            set_title: Some("Scanned Codes"),
+            set_default_width: 400,
+
+            #[wrap(Some)]
+            set_child = &gtk::ScrolledWindow {
+                set_hscrollbar_policy: gtk::PolicyType::Never,
+                set_propagate_natural_height: true,
+                set_max_content_height: 400,
+
+                #[wrap(Some)]
+                set_child = &model.list.clone(),
+            },
+        }
+    }
+
+    fn init(_: (), root: Self::Root, sender: ComponentSender<Self>) -> ComponentParts<Self> {
+        let model = ScannedCodesDialog {
+            list: gtk::ListBox::builder()
+                .selection_mode(gtk::SelectionMode::None)
+                .build(),
+        };
+
+        let widgets = view_output!();
+
+        let key_controller = gtk::EventControllerKey::builder()
+            .propagation_phase(gtk::PropagationPhase::Capture)
+            .build();
+
+        key_controller.connect_key_pressed(move |_, keyval, _, _| {
+            if keyval == gdk::Key::Escape {
+                sender.input(ScannedCodesDialogInput::Close);
+                glib::Propagation::Stop
+            } else {
+                glib::Propagation::Proceed
+            }
+        });
+        root.add_controller(key_controller);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(
+        &mut self,
+        message: ScannedCodesDialogInput,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            ScannedCodesDialogInput::Show(codes) => {
+                self.fill_list(&codes, &sender);
+                root.show();
+            }
+            ScannedCodesDialogInput::Copy(code) => {
+                root.clipboard().set_text(&code);
+                log_result(
+                    "Copied code to clipboard.",
+                    !APP_CONFIG.read().disable_notifications(),
+                );
+            }
+            ScannedCodesDialogInput::Open(uri) => {
+                gtk::show_uri(Some(root), &uri, gdk::CURRENT_TIME);
+                root.hide();
+            }
+            ScannedCodesDialogInput::Close => root.hide(),
+        }
+    }
+}
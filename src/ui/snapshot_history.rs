@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+
+use chrono::Local;
+use relm4::{gtk::prelude::*, prelude::*, RelmRemoveAllExt};
+
+use crate::snapshots::Snapshot;
+
+/// Lists the snapshots taken in the background, activating one exports it
+pub struct SnapshotHistory {
+    snapshots: Vec<Snapshot>,
+    list: gtk::ListBox,
+}
+
+#[derive(Debug, Clone)]
+pub enum SnapshotHistoryInput {
+    Show(Vec<Snapshot>),
+    Activate(usize),
+}
+
+#[derive(Debug, Clone)]
+pub enum SnapshotHistoryOutput {
+    Export(PathBuf),
+}
+
+impl SnapshotHistory {
+    fn fill(&mut self) {
+        self.list.remove_all();
+
+        // newest first, that is what one usually looks for
+        let now = Local::now();
+        for snapshot in self.snapshots.iter().rev() {
+            let minutes = (now - snapshot.time).num_minutes();
+            let age = match minutes {
+                0 => "just now".to_string(),
+                1 => "1 minute ago".to_string(),
+                _ => format!("{minutes} minutes ago"),
+            };
+            let label = gtk::Label::builder()
+                .label(format!("{} ({age})", snapshot.time.format("%H:%M:%S")))
+                .xalign(0.0)
+                .build();
+            self.list.append(&label);
+        }
+    }
+}
+
+#[relm4::component(pub)]
+impl Component for SnapshotHistory {
+    type Init = ();
+    type Input = SnapshotHistoryInput;
+    type Output = SnapshotHistoryOutput;
+    type CommandOutput = ();
+
+    view! {
+        gtk::Popover {
+            set_has_arrow: false,
+            set_position: gtk::PositionType::Bottom,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_spacing: 6,
+
+                gtk::Label {
+                    set_halign: gtk::Align::Start,
+                    set_label: "Export an earlier state",
+                },
+                gtk::ScrolledWindow {
+                    set_hscrollbar_policy: gtk::PolicyType::Never,
+                    set_propagate_natural_height: true,
+                    set_max_content_height: 300,
+
+                    #[local_ref]
+                    list -> gtk::ListBox {
+                        set_activate_on_single_click: true,
+                        connect_row_activated[sender] => move |_, row| {
+                            sender.input(SnapshotHistoryInput::Activate(row.index() as usize));
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    fn init(_: (), root: Self::Root, sender: ComponentSender<Self>) -> ComponentParts<Self> {
+        let model = SnapshotHistory {
+            snapshots: Vec::new(),
+            list: gtk::ListBox::new(),
+        };
+        model
+            .list
+            .set_placeholder(Some(&gtk::Label::new(Some("No snapshots yet"))));
+
+        let list = &model.list;
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(
+        &mut self,
+        message: SnapshotHistoryInput,
+        sender: ComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match message {
+            SnapshotHistoryInput::Show(snapshots) => {
+                self.snapshots = snapshots;
+                self.fill();
+                root.popup();
+            }
+            SnapshotHistoryInput::Activate(row) => {
+                // rows are listed newest first
+                let Some(index) = self.snapshots.len().checked_sub(row + 1) else {
+                    return;
+                };
+                sender.output_sender().emit(SnapshotHistoryOutput::Export(
+                    self.snapshots[index].path.clone(),
+                ));
+                root.popdown();
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use relm4::{
+    gtk::{prelude::*, Align},
+    prelude::*,
+};
+
+use crate::{configuration::APP_CONFIG, math::Vec2D, style::Color};
+
+/// Pointer position, pixel color, zoom and image size in the bottom left corner
+pub struct StatusBar {
+    text: String,
+}
+
+/// What the status bar shows, sent by the sketch board as the pointer moves
+#[derive(Debug, Clone, Copy)]
+pub struct Status {
+    /// pointer in image coordinates, `None` while it is not over the image
+    pub pos: Option<Vec2D>,
+    /// color of the image pixel under the pointer
+    pub color: Option<Color>,
+    /// 1.0 shows the image at its size
+    pub zoom: f32,
+    pub image_size: (i32, i32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum StatusBarInput {
+    Update(Status),
+}
+
+impl Status {
+    fn text(&self) -> String {
+        let pos = match self.pos {
+            Some(pos) => format!("{:.0}, {:.0}", pos.x.floor(), pos.y.floor()),
+            None => "–".to_string(),
+        };
+        let color = self.color.map_or("–".to_string(), |color| {
+            format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+        });
+        format!(
+            "{pos}   {color}   {:.0}%   {} × {}",
+            self.zoom * 100.0,
+            self.image_size.0,
+            self.image_size.1
+        )
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for StatusBar {
+    type Init = ();
+    type Input = StatusBarInput;
+    type Output = ();
+
+    view! {
+        root = gtk::Box {
+            set_valign: Align::End,
+            set_halign: Align::Start,
+            add_css_class: "status-bar",
+            set_visible: APP_CONFIG.read().show_status_bar(),
+            // the canvas below gets all clicks
+            set_can_target: false,
+
+            gtk::Label {
+                #[watch]
+                set_label: &model.text,
+            },
+        }
+    }
+
+    fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+        match message {
+            StatusBarInput::Update(status) => self.text = status.text(),
+        }
+    }
+
+    fn init(
+        _: Self::Init,
+        root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = StatusBar {
+            text: String::new(),
+        };
+        let widgets = view_output!();
+        ComponentParts { model, widgets }
+    }
+}
@@ -0,0 +1,250 @@
+use std::time::Duration;
+
+use relm4::{
+    gtk::{glib, prelude::*, Align},
+    prelude::*,
+};
+
+use crate::configuration::{ToastPosition, APP_CONFIG};
+use crate::theme;
+
+/// messages with an action stay at least this long, so there is time to click it
+const ACTION_DURATION: Duration = Duration::from_secs(5);
+/// how often the progress bar of an operation without known progress moves
+const PULSE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Short status message shown on top of the canvas
+pub struct Toast {
+    text: String,
+    // label of the button next to the message, if any
+    action: Option<String>,
+    visible: bool,
+    // counts shown messages, so outdated timeouts don't hide newer ones
+    generation: u64,
+    // long-running operations not ended yet, their progress is shown once messages expire
+    running: usize,
+    progress: Progress,
+    // the toast shows `progress` instead of a message
+    showing_progress: bool,
+    progress_bar: gtk::ProgressBar,
+    // moves the progress bar while the progress is unknown
+    pulse: Option<glib::SourceId>,
+}
+
+/// Text and completed fraction of the operation running last
+#[derive(Debug, Clone, Default)]
+struct Progress {
+    text: String,
+    /// `None` pulses the bar, for operations that can't tell how far they are
+    fraction: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ToastInput {
+    /// show a message that hides itself after a short time
+    Flash(String),
+    /// show a message with a button, clicking it emits [`ToastOutput::ActionClicked`]
+    Action {
+        text: String,
+        label: String,
+    },
+    ActionClicked,
+    Expire(u64),
+    /// a long-running operation started, a progress bar is shown until it ends
+    ProgressStarted(String),
+    /// update the message and progress bar of the operation running last in place
+    Progress {
+        text: String,
+        fraction: Option<f64>,
+    },
+    /// an operation started with [`ToastInput::ProgressStarted`] is done
+    ProgressEnded,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum ToastOutput {
+    ActionClicked,
+}
+
+impl Toast {
+    fn show(&mut self, text: String, action: Option<String>) {
+        self.text = text;
+        self.action = action;
+        self.visible = true;
+        self.generation += 1;
+        self.hide_progress();
+    }
+
+    fn expire_after(&self, duration: Duration, sender: ComponentSender<Self>) {
+        let generation = self.generation;
+        glib::timeout_add_local_once(duration, move || {
+            sender.input(ToastInput::Expire(generation));
+        });
+    }
+
+    /// Show the progress of the running operations, or hide the toast if there are none
+    fn show_progress(&mut self) {
+        self.action = None;
+        self.generation += 1;
+        if self.running == 0 {
+            self.visible = false;
+            self.hide_progress();
+            return;
+        }
+        self.text = self.progress.text.clone();
+        self.visible = true;
+        self.showing_progress = true;
+        match self.progress.fraction {
+            Some(fraction) => {
+                self.stop_pulsing();
+                self.progress_bar.set_fraction(fraction.clamp(0.0, 1.0));
+            }
+            // without motion the bar stays empty, the text tells what is running
+            None if self.pulse.is_none() && !theme::reduce_motion() => {
+                let progress_bar = self.progress_bar.clone();
+                self.pulse = Some(glib::timeout_add_local(PULSE_INTERVAL, move || {
+                    progress_bar.pulse();
+                    glib::ControlFlow::Continue
+                }));
+            }
+            None => {}
+        }
+    }
+
+    fn hide_progress(&mut self) {
+        self.showing_progress = false;
+        self.stop_pulsing();
+    }
+
+    fn stop_pulsing(&mut self) {
+        if let Some(pulse) = self.pulse.take() {
+            pulse.remove();
+        }
+    }
+
+    fn flash_duration() -> Duration {
+        Duration::from_secs_f32(APP_CONFIG.read().toast_duration())
+    }
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for Toast {
+    type Init = ();
+    type Input = ToastInput;
+    type Output = ToastOutput;
+
+    view! {
+        root = gtk::Box {
+            set_valign: match APP_CONFIG.read().toast_position() {
+                ToastPosition::Top => Align::Start,
+                ToastPosition::Center => Align::Center,
+                ToastPosition::Bottom => Align::End,
+            },
+            set_halign: Align::Center,
+            set_spacing: 6,
+            add_css_class: "toast",
+            add_css_class: match APP_CONFIG.read().toast_position() {
+                ToastPosition::Top => "toast-top",
+                ToastPosition::Center => "toast-center",
+                ToastPosition::Bottom => "toast-bottom",
+            },
+
+            // only catch clicks if there is something to click, the canvas gets them otherwise
+            #[watch]
+            set_can_target: model.action.is_some(),
+            #[watch]
+            set_visible: model.visible,
+
+            gtk::Label {
+                #[watch]
+                set_label: &model.text,
+            },
+            #[local_ref]
+            progress_bar -> gtk::ProgressBar {
+                set_valign: Align::Center,
+                set_width_request: 120,
+
+                #[watch]
+                set_visible: model.showing_progress,
+            },
+            gtk::Button {
+                set_focusable: false,
+                add_css_class: "flat",
+
+                #[watch]
+                set_label: model.action.as_deref().unwrap_or_default(),
+                #[watch]
+                set_visible: model.action.is_some(),
+
+                connect_clicked => ToastInput::ActionClicked,
+            },
+        }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+        match message {
+            ToastInput::Flash(text) => {
+                self.show(text, None);
+                self.expire_after(Self::flash_duration(), sender);
+            }
+            ToastInput::Action { text, label } => {
+                self.show(text, Some(label));
+                self.expire_after(ACTION_DURATION.max(Self::flash_duration()), sender);
+            }
+            ToastInput::ActionClicked => {
+                self.show_progress();
+                sender.output_sender().emit(ToastOutput::ActionClicked);
+            }
+            ToastInput::Expire(generation) => {
+                if generation == self.generation {
+                    self.show_progress();
+                }
+            }
+            ToastInput::ProgressStarted(text) => {
+                self.running += 1;
+                self.progress = Progress {
+                    text,
+                    fraction: None,
+                };
+                // messages with an action stay until they expire
+                if self.action.is_none() {
+                    self.show_progress();
+                }
+            }
+            ToastInput::Progress { text, fraction } => {
+                self.progress = Progress { text, fraction };
+                if self.showing_progress {
+                    self.show_progress();
+                }
+            }
+            ToastInput::ProgressEnded => {
+                self.running = self.running.saturating_sub(1);
+                if self.showing_progress {
+                    self.show_progress();
+                }
+            }
+        }
+    }
+
+    fn init(
+        _: Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = Toast {
+            text: String::new(),
+            action: None,
+            visible: false,
+            generation: 0,
+            running: 0,
+            progress: Progress::default(),
+            showing_progress: false,
+            progress_bar: gtk::ProgressBar::builder().pulse_step(0.1).build(),
+            pulse: None,
+        };
+        let progress_bar = &model.progress_bar;
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+}
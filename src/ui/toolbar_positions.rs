@@ -0,0 +1,134 @@
+use std::{cell::Cell, collections::HashMap, fs, path::PathBuf, rc::Rc};
+
+use anyhow::Result;
+use relm4::gtk::{self, prelude::*, Align};
+use xdg::BaseDirectories;
+
+const STATE_FILE: &str = "toolbar-positions.json";
+
+/// Add a handle that drags `toolbar` around its overlay, e.g. off the part of the image
+/// being annotated. The position is kept under `name` across sessions, a double click on
+/// the handle puts the toolbar back.
+pub fn make_floating(toolbar: &gtk::Box, name: &'static str) {
+    let handle = gtk::Image::from_icon_name("re-order-dots-vertical-regular");
+    handle.set_tooltip_text(Some("Drag to move, double-click to put back"));
+    handle.set_cursor_from_name(Some("grab"));
+    toolbar.prepend(&handle);
+
+    let home = toolbar.valign();
+    if let Some(&position) = read_positions().get(name) {
+        place(toolbar, Some(position), home);
+    }
+
+    // where the toolbar got grabbed, relative to its origin
+    let grab = Rc::new(Cell::new((0.0, 0.0)));
+    let drag = gtk::GestureDrag::new();
+    drag.connect_drag_begin({
+        let toolbar = toolbar.clone();
+        let grab = grab.clone();
+        move |gesture, _, _| {
+            if let Some((x, y)) = pointer_in_parent(gesture, &toolbar) {
+                let allocation = toolbar.allocation();
+                grab.set((x - allocation.x() as f64, y - allocation.y() as f64));
+            }
+        }
+    });
+    drag.connect_drag_update({
+        let toolbar = toolbar.clone();
+        move |gesture, _, _| {
+            let (Some((x, y)), Some(parent)) =
+                (pointer_in_parent(gesture, &toolbar), toolbar.parent())
+            else {
+                return;
+            };
+            // the pointer is taken in the parent, the handle moves with the drag
+            let (grab_x, grab_y) = grab.get();
+            let x = ((x - grab_x) as i32).clamp(0, (parent.width() - toolbar.width()).max(0));
+            let y = ((y - grab_y) as i32).clamp(0, (parent.height() - toolbar.height()).max(0));
+            place(&toolbar, Some((x, y)), home);
+        }
+    });
+    drag.connect_drag_end({
+        let toolbar = toolbar.clone();
+        move |_, _, _| {
+            save_position(name, Some((toolbar.margin_start(), toolbar.margin_top())));
+        }
+    });
+    handle.add_controller(drag);
+
+    let click = gtk::GestureClick::new();
+    click.connect_pressed({
+        let toolbar = toolbar.clone();
+        move |_, n_press, _, _| {
+            if n_press == 2 {
+                place(&toolbar, None, home);
+                save_position(name, None);
+            }
+        }
+    });
+    handle.add_controller(click);
+}
+
+fn pointer_in_parent(gesture: &gtk::GestureDrag, toolbar: &gtk::Box) -> Option<(f64, f64)> {
+    let widget = gesture.widget()?;
+    let (x, y) = gesture.point(None)?;
+    widget.translate_coordinates(&toolbar.parent()?, x, y)
+}
+
+/// Put `toolbar` at `position` in its overlay, back to the center of the `home` edge if `None`
+fn place(toolbar: &gtk::Box, position: Option<(i32, i32)>, home: Align) {
+    match position {
+        Some((x, y)) => {
+            toolbar.set_halign(Align::Start);
+            toolbar.set_valign(Align::Start);
+            toolbar.set_margin_start(x);
+            toolbar.set_margin_top(y);
+            toolbar.add_css_class("toolbar-floating");
+        }
+        None => {
+            toolbar.set_halign(Align::Center);
+            toolbar.set_valign(home);
+            toolbar.set_margin_start(0);
+            toolbar.set_margin_top(0);
+            toolbar.remove_css_class("toolbar-floating");
+        }
+    }
+}
+
+fn save_position(name: &str, position: Option<(i32, i32)>) {
+    let mut positions = read_positions();
+    match position {
+        Some(position) => positions.insert(name.to_string(), position),
+        None => positions.remove(name),
+    };
+    if let Err(e) = write_positions(&positions) {
+        println!("Error while saving toolbar positions: {e}");
+    }
+}
+
+fn read_positions() -> HashMap<String, (i32, i32)> {
+    let result = path(false).and_then(|path| match path {
+        Some(path) if path.exists() => Ok(serde_json::from_slice(&fs::read(path)?)?),
+        _ => Ok(HashMap::new()),
+    });
+    result.unwrap_or_else(|e| {
+        println!("Error while reading toolbar positions: {e}");
+        HashMap::new()
+    })
+}
+
+fn write_positions(positions: &HashMap<String, (i32, i32)>) -> Result<()> {
+    if let Some(path) = path(true)? {
+        fs::write(path, serde_json::to_vec_pretty(positions)?)?;
+    }
+    Ok(())
+}
+
+fn path(create: bool) -> Result<Option<PathBuf>> {
+    let dirs = BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
+    Ok(if create {
+        Some(dirs.place_state_file(STATE_FILE)?)
+    } else {
+        dirs.get_state_file(STATE_FILE)
+    })
+}
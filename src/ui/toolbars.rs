@@ -1,9 +1,11 @@
 use std::{borrow::Cow, collections::HashMap};
 
 use crate::{
-    configuration::APP_CONFIG,
-    style::{Color, Size},
+    configuration::{Action, ExportCanvas, Keybinds, APP_CONFIG},
+    style::{Color, Size, TextAlign, TextFont, ToolStyle},
     tools::Tools,
+    ui::color_picker::{ColorPicker, ColorPickerInput, ColorPickerOutput},
+    ui::toolbar_positions::make_floating,
 };
 
 use gdk_pixbuf::{
@@ -14,10 +16,13 @@ use gdk_pixbuf::{
 use gtk::ToggleButton;
 use relm4::{
     actions::{ActionablePlus, RelmAction, RelmActionGroup},
-    gtk::{gdk::RGBA, prelude::*, Align, ColorChooserDialog, ResponseType, Window},
+    gtk::{prelude::*, Align, Window},
     prelude::*,
 };
 
+/// Zooms offered by the zoom menu of the top toolbar, 1.0 shows the image at its size
+const ZOOM_PRESETS: [f32; 7] = [0.25, 0.5, 0.75, 1.0, 1.5, 2.0, 4.0];
+
 pub struct ToolsToolbar {
     visible: bool,
     active_button: Option<ToggleButton>,
@@ -29,10 +34,21 @@ pub struct StyleToolbar {
     custom_color: Color,
     custom_color_pixbuf: Pixbuf,
     color_action: SimpleAction,
+    size_action: SimpleAction,
+    fill: bool,
+    preset_dropdown: gtk::DropDown,
+    preset: Option<usize>,
+    palette_dropdown: gtk::DropDown,
+    /// index into the configured palettes
+    palette: usize,
+    /// holds the buttons of the selected palette
+    palette_box: gtk::Box,
     visible: bool,
     annotation_size: f32,
     annotation_size_formatted: String,
     annotation_dialog_controller: Option<Controller<AnnotationSizeDialog>>,
+    color_picker: Option<Controller<ColorPicker>>,
+    font: TextFont,
 }
 
 pub struct AnnotationSizeDialog {
@@ -49,11 +65,41 @@ pub enum ToolbarEvent {
     SaveFile,
     CopyClipboard,
     ToggleFill,
+    ToggleShadow,
+    ToggleOutline,
     AnnotationSizeChanged(f32),
     Reset,
     SaveFileAs,
     Resize,
     OriginalScale,
+    ZoomIn,
+    ZoomOut,
+    /// show the image at the zoom, 1.0 shows it at its size
+    Zoom(f32),
+    GrowCanvas,
+    /// take the screenshot again and keep the annotations
+    Recapture,
+    /// show the previous or next of the images given on the command line
+    PreviousImage,
+    NextImage,
+    /// join another image file to the canvas
+    AppendImage,
+    /// show or hide the heatmap of the differences with `--diff`
+    DiffHeatmap(bool),
+    /// save the annotations as JSON, for other images of the same size
+    ExportAnnotations,
+    /// add the annotations of a file exported before
+    ImportAnnotations,
+    ExportCanvasSelected(Option<ExportCanvas>),
+    Recolor,
+    /// propose blurs for the text found in the image
+    AutoRedact,
+    FontSelected(TextFont),
+    TextAlignSelected(TextAlign),
+    /// apply the preset with the given index of the configured ones
+    PresetSelected(usize),
+    /// the next click on the image picks a color for the color dialog
+    PickColor,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -68,10 +114,27 @@ pub enum StyleToolbarInput {
     ColorButtonSelected(ColorButtons),
     ShowColorDialog,
     ColorDialogFinished(Option<Color>),
+    /// the color dialog asks for a color of the image
+    PickColor,
+    /// color of the image clicked for the color dialog, `None` if picking got canceled
+    ColorPicked(Option<Color>),
     SetVisibility(bool),
     ToggleVisibility,
     ShowAnnotationDialog,
     AnnotationDialogFinished(Option<f32>),
+    FontFamilySelected(&'static str),
+    FontBoldToggled(bool),
+    FontItalicToggled(bool),
+    /// 0 follows the annotation size
+    FontSizeChanged(f32),
+    ToggleFill,
+    /// `None` is the first entry of the preset selector, it applies nothing
+    PresetSelected(Option<usize>),
+    /// show the preset applied by a key in the preset selector
+    ShowPreset(usize),
+    /// show the style remembered for the selected tool, the sketch board already uses it
+    ShowToolStyle(ToolStyle),
+    PaletteSelected(usize),
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -88,15 +151,82 @@ pub enum AnnotationSizeDialogOutput {
     AnnotationSizeSubmitted(f32),
 }
 
-fn create_icon_pixbuf(color: Color) -> Pixbuf {
+pub fn create_icon_pixbuf(color: Color) -> Pixbuf {
     let pixbuf = gdk_pixbuf::Pixbuf::new(gdk_pixbuf::Colorspace::Rgb, false, 8, 40, 40).unwrap();
-    pixbuf.fill(color.to_rgba_u32());
+    if color.a == 255 {
+        pixbuf.fill(color.to_rgba_u32());
+        return pixbuf;
+    }
+
+    // translucent colors over a checkerboard
+    const CHECKER: i32 = 10;
+    for y in (0..40).step_by(CHECKER as usize) {
+        for x in (0..40).step_by(CHECKER as usize) {
+            let background = if (x + y) / CHECKER % 2 == 0 {
+                0xcc
+            } else {
+                0x88
+            };
+            let blend = |channel: u8| {
+                ((channel as u32 * color.a as u32 + background * (255 - color.a as u32)) / 255)
+                    as u8
+            };
+            let cell = Color::new(blend(color.r), blend(color.g), blend(color.b), 255);
+            pixbuf
+                .new_subpixbuf(x, y, CHECKER, CHECKER)
+                .fill(cell.to_rgba_u32());
+        }
+    }
     pixbuf
 }
-fn create_icon(color: Color) -> gtk::Image {
+pub fn create_icon(color: Color) -> gtk::Image {
     gtk::Image::from_pixbuf(Some(&create_icon_pixbuf(color)))
 }
 
+/// Rebuild `toolbar` from the configured items, which are the widget names of its children.
+/// Children that are not listed are left out, "separator" adds a separator.
+fn arrange_toolbar(toolbar: &gtk::Box, items: &[String]) {
+    let mut children = HashMap::new();
+    while let Some(child) = toolbar.first_child() {
+        toolbar.remove(&child);
+        if !child.is::<gtk::Separator>() {
+            children.insert(child.widget_name().to_string(), child);
+        }
+    }
+    let mut names: Vec<String> = children.keys().cloned().collect();
+    names.sort();
+
+    for item in items {
+        if item == "separator" {
+            toolbar.append(&gtk::Separator::default());
+        } else if let Some(child) = children.remove(item) {
+            toolbar.append(&child);
+        } else {
+            eprintln!(
+                "Warning: toolbar item `{item}` is unknown or repeated, possible items are {}",
+                names.join(", ")
+            );
+        }
+    }
+}
+
+/// Tooltip of `widget` as "`name` — key", the key looked up from the keybindings each time
+/// the tooltip is shown so it follows changes to them
+fn shortcut_tooltip(
+    widget: &impl IsA<gtk::Widget>,
+    name: &'static str,
+    shortcut: impl Fn(&Keybinds) -> Option<String> + 'static,
+) {
+    widget.set_has_tooltip(true);
+    widget.connect_query_tooltip(move |_, _, _, _, tooltip| {
+        match shortcut(APP_CONFIG.read().keybinds()) {
+            Some(key) => tooltip.set_text(Some(&format!("{name} — {key}"))),
+            None => tooltip.set_text(Some(name)),
+        }
+        true
+    });
+}
+
 #[relm4::component(pub)]
 impl SimpleComponent for ToolsToolbar {
     type Init = ();
@@ -115,26 +245,153 @@ impl SimpleComponent for ToolsToolbar {
             #[watch]
             set_visible: model.visible,
 
+            #[name(original_scale_button)]
             gtk::Button {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "original-scale",
                 set_icon_name: "resize-large-regular",
-                set_tooltip: "1:1",
+                // tooltip set programatically
                 connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::OriginalScale);},
             },
+            #[name(resize_button)]
             gtk::Button {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "resize",
                 set_icon_name: "page-fit-regular",
-                set_tooltip: "Resize",
+                // tooltip set programatically
                 connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::Resize);},
             },
+            #[name(zoom_out_button)]
+            gtk::Button {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "zoom-out",
+                set_icon_name: "zoom-out-regular",
+                // tooltip set programatically
+                connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::ZoomOut);},
+            },
+            gtk::MenuButton {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "zoom-presets",
+                set_icon_name: "zoom",
+                set_tooltip: "Zoom",
+                #[wrap(Some)]
+                #[name(zoom_popover)]
+                set_popover = &gtk::Popover {
+                    #[name(zoom_presets_box)]
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+                },
+            },
+            #[name(zoom_in_button)]
+            gtk::Button {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "zoom-in",
+                set_icon_name: "zoom-in-regular",
+                // tooltip set programatically
+                connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::ZoomIn);},
+            },
             gtk::Button {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "grow-canvas",
+                set_icon_name: "arrow-maximize-regular",
+                set_tooltip: "Grow canvas",
+                connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::GrowCanvas);},
+            },
+            #[name(previous_image_button)]
+            gtk::Button {
+                set_focusable: false,
+                set_hexpand: false,
+                set_visible: APP_CONFIG.read().has_several_inputs(),
+
+                set_widget_name: "previous-image",
+                set_icon_name: "arrow-previous-regular",
+                // tooltip set programatically
+                connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::PreviousImage);},
+            },
+            #[name(next_image_button)]
+            gtk::Button {
+                set_focusable: false,
+                set_hexpand: false,
+                set_visible: APP_CONFIG.read().has_several_inputs(),
+
+                set_widget_name: "next-image",
+                set_icon_name: "arrow-next-regular",
+                // tooltip set programatically
+                connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::NextImage);},
+            },
+            gtk::Button {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "append-image",
+                set_icon_name: "image-multiple-regular",
+                set_tooltip: "Append image",
+                connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::AppendImage);},
+            },
+            gtk::ToggleButton {
+                set_focusable: false,
+                set_hexpand: false,
+                set_visible: APP_CONFIG.read().diff_mode(),
+
+                set_widget_name: "diff-heatmap",
+                set_icon_name: "column-single-compare-regular",
+                set_tooltip: "Heatmap of the differences",
+                connect_toggled[sender] => move |button| {sender.output_sender().emit(ToolbarEvent::DiffHeatmap(button.is_active()));},
+            },
+            #[name(recapture_button)]
+            gtk::Button {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "recapture",
+                set_icon_name: "camera-regular",
+                // tooltip set programatically
+                connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::Recapture);},
+            },
+            gtk::DropDown::from_strings(&canvas_labels) {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "export-canvas",
+                set_tooltip: "Export canvas size",
+                set_selected: initial_canvas_index,
+                connect_selected_notify[sender, canvas_sizes] => move |dropdown| {
+                    // the first entry keeps the rendered size
+                    let size = (dropdown.selected() as usize)
+                        .checked_sub(1)
+                        .and_then(|i| canvas_sizes.get(i))
+                        .copied();
+                    sender.output_sender().emit(ToolbarEvent::ExportCanvasSelected(size));
+                },
+            },
+            #[name(auto_redact_button)]
+            gtk::Button {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "auto-redact",
+                set_icon_name: "scan-text-regular",
+                // tooltip set programatically
+                connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::AutoRedact);},
+            },
+            gtk::Button {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "reset",
                 set_icon_name: "recycling-bin",
                 set_tooltip: "Reset",
                 connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::Reset);},
@@ -144,16 +401,18 @@ impl SimpleComponent for ToolsToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "undo",
                 set_icon_name: "arrow-undo-filled",
-                set_tooltip: "Undo (Ctrl-Z)",
+                set_tooltip: "Undo — Ctrl+Z",
                 connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::Undo);},
             },
             gtk::Button {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "redo",
                 set_icon_name: "arrow-redo-filled",
-                set_tooltip: "Redo (Ctrl-Y)",
+                set_tooltip: "Redo — Ctrl+Y",
                 connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::Redo);},
             },
             gtk::Separator {},
@@ -162,15 +421,27 @@ impl SimpleComponent for ToolsToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "pointer",
                 set_icon_name: "cursor-regular",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Pointer,
             },
+            #[name(select_button)]
+            gtk::ToggleButton {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "select",
+                set_icon_name: "select",
+                // tooltip set programatically
+                ActionablePlus::set_action::<ToolsAction>: Tools::Select,
+            },
             #[name(crop_button)]
             gtk::ToggleButton {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "crop",
                 set_icon_name: "crop-filled",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Crop,
@@ -180,6 +451,7 @@ impl SimpleComponent for ToolsToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "brush",
                 set_icon_name: "pen-regular",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Brush,
@@ -189,6 +461,7 @@ impl SimpleComponent for ToolsToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "line",
                 set_icon_name: "minus-large",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Line,
@@ -198,6 +471,7 @@ impl SimpleComponent for ToolsToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "arrow",
                 set_icon_name: "arrow-up-right-filled",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Arrow,
@@ -207,6 +481,7 @@ impl SimpleComponent for ToolsToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "rectangle",
                 set_icon_name: "checkbox-unchecked-regular",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Rectangle,
@@ -216,15 +491,27 @@ impl SimpleComponent for ToolsToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "ellipse",
                 set_icon_name: "circle-regular",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Ellipse,
             },
+            #[name(table_button)]
+            gtk::ToggleButton {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "table",
+                set_icon_name: "table-regular",
+                // tooltip set programatically
+                ActionablePlus::set_action::<ToolsAction>: Tools::Table,
+            },
             #[name(text_button)]
             gtk::ToggleButton {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "text",
                 set_icon_name: "text-case-title-regular",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Text,
@@ -234,6 +521,7 @@ impl SimpleComponent for ToolsToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "marker",
                 set_icon_name: "number-circle-1-regular",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Marker,
@@ -243,6 +531,7 @@ impl SimpleComponent for ToolsToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "blur",
                 set_icon_name: "drop-regular",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Blur,
@@ -252,37 +541,92 @@ impl SimpleComponent for ToolsToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "highlight",
                 set_icon_name: "highlight-regular",
                 // tooltip set programatically
                 ActionablePlus::set_action::<ToolsAction>: Tools::Highlight,
             },
+            #[name(ruler_button)]
+            gtk::ToggleButton {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "ruler",
+                set_icon_name: "ruler-regular",
+                // tooltip set programatically
+                ActionablePlus::set_action::<ToolsAction>: Tools::Ruler,
+            },
+            #[name(brace_button)]
+            gtk::ToggleButton {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "brace",
+                set_icon_name: "braces-regular",
+                // tooltip set programatically
+                ActionablePlus::set_action::<ToolsAction>: Tools::Brace,
+            },
+            #[name(eraser_button)]
+            gtk::ToggleButton {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "eraser",
+                set_icon_name: "eraser",
+                // tooltip set programatically
+                ActionablePlus::set_action::<ToolsAction>: Tools::Eraser,
+            },
             gtk::Separator {},
+            #[name(copy_button)]
             gtk::Button {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "copy",
                 set_icon_name: "copy-regular",
-                set_tooltip: "Copy to clipboard (Ctrl+C)",
+                // tooltip set programatically
                 connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::CopyClipboard);},
             },
+            #[name(save_button)]
             gtk::Button {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "save",
                 set_icon_name: "save-regular",
-                set_tooltip: "Save (Ctrl+S)",
+                // tooltip set programatically
                 connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::SaveFile);},
 
                 set_visible: APP_CONFIG.read().output_filename().is_some()
             },
+            #[name(save_as_button)]
             gtk::Button {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "save-as",
                 set_icon_name: "save-multiple-regular",
-                set_tooltip: "Save as (Ctrl+Shift+S)",
+                // tooltip set programatically
                 connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::SaveFileAs);},
             },
+            gtk::Button {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "export-annotations",
+                set_icon_name: "document-arrow-up-regular",
+                set_tooltip: "Export annotations",
+                connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::ExportAnnotations);},
+            },
+            gtk::Button {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "import-annotations",
+                set_icon_name: "document-arrow-down-regular",
+                set_tooltip: "Import annotations",
+                connect_clicked[sender] => move |_| {sender.output_sender().emit(ToolbarEvent::ImportAnnotations);},
+            },
         },
     }
 
@@ -320,6 +664,20 @@ impl SimpleComponent for ToolsToolbar {
             },
         );
 
+        // canvas sizes offered for export, the one selected at startup is always available
+        let initial_canvas = APP_CONFIG.read().export_canvas();
+        let mut canvas_sizes = APP_CONFIG.read().export_canvas_presets().to_vec();
+        if let Some(canvas) = initial_canvas.filter(|c| !canvas_sizes.contains(c)) {
+            canvas_sizes.push(canvas);
+        }
+        let canvas_labels: Vec<String> = std::iter::once("Original size".to_string())
+            .chain(canvas_sizes.iter().map(ToString::to_string))
+            .collect();
+        let canvas_labels: Vec<&str> = canvas_labels.iter().map(String::as_str).collect();
+        let initial_canvas_index = initial_canvas
+            .and_then(|c| canvas_sizes.iter().position(|s| *s == c))
+            .map_or(0, |i| i as u32 + 1);
+
         let mut model = ToolsToolbar {
             visible: !APP_CONFIG.read().default_hide_toolbars(),
             active_button: None,
@@ -327,6 +685,10 @@ impl SimpleComponent for ToolsToolbar {
             tool_action: tool_action.clone().into(),
         };
         let widgets = view_output!();
+        if let Some(items) = APP_CONFIG.read().toolbars().top() {
+            arrange_toolbar(&widgets.root, items);
+        }
+        make_floating(&widgets.root, "top");
 
         model.tool_buttons = HashMap::from([
             (Tools::Pointer, widgets.pointer_button.clone()),
@@ -340,30 +702,64 @@ impl SimpleComponent for ToolsToolbar {
             (Tools::Marker, widgets.marker_button.clone()),
             (Tools::Blur, widgets.blur_button.clone()),
             (Tools::Highlight, widgets.highlight_button.clone()),
+            (Tools::Ruler, widgets.ruler_button.clone()),
+            (Tools::Table, widgets.table_button.clone()),
+            (Tools::Brace, widgets.brace_button.clone()),
+            (Tools::Eraser, widgets.eraser_button.clone()),
+            (Tools::Select, widgets.select_button.clone()),
         ]);
 
-        // reverse shortcuts mapping
-        let config = APP_CONFIG.read();
-        let tool_to_key_map: HashMap<&Tools, &char> = config
-            .keybinds()
-            .shortcuts()
-            .iter()
-            .inspect(|(hotkey, tool)| if hotkey.is_ascii_digit() {
+        for (hotkey, tool) in APP_CONFIG.read().keybinds().shortcuts() {
+            if hotkey.is_ascii_digit() {
                 eprintln!("Warning: hotkey `{}` for tool `{}` overrides built-in hotkey to select a color from the palette", hotkey, tool);
-            })
-            .map(|(k, v)| (v, k))
-            .collect();
-
-        // Update tooltips based on configured keybinds
-        for (tool, button) in &model.tool_buttons {
-            let display_name = tool.display_name();
+            }
+        }
 
-            let tooltip = if let Some(key) = tool_to_key_map.get(tool) {
-                &format!("{} ({})", display_name, key.to_uppercase())
-            } else {
-                display_name
-            };
-            button.set_tooltip_text(Some(tooltip));
+        // Tooltips name the keys currently bound
+        for (&tool, button) in &model.tool_buttons {
+            shortcut_tooltip(button, tool.display_name(), move |keybinds| {
+                keybinds
+                    .tool_key(tool)
+                    .map(|key| key.to_uppercase().to_string())
+            });
+        }
+        shortcut_tooltip(&widgets.auto_redact_button, "Blur text", |keybinds| {
+            Some(keybinds.auto_redact().to_uppercase().to_string())
+        });
+        shortcut_tooltip(&widgets.copy_button, "Copy to clipboard", |keybinds| {
+            keybinds.actions_shortcut(&[Action::SaveToClipboard])
+        });
+        shortcut_tooltip(&widgets.save_button, "Save", |keybinds| {
+            keybinds.actions_shortcut(&[Action::SaveToFile(None)])
+        });
+        shortcut_tooltip(&widgets.save_as_button, "Save as", |keybinds| {
+            keybinds.actions_shortcut(&[Action::SaveToFileAs])
+        });
+        // the zoom, recapture and image keys are built in
+        for (button, name, key) in [
+            (&widgets.original_scale_button, "Original scale", "Ctrl+1"),
+            (&widgets.resize_button, "Fit to window", "Ctrl+0"),
+            (&widgets.zoom_in_button, "Zoom in", "Ctrl++"),
+            (&widgets.zoom_out_button, "Zoom out", "Ctrl+-"),
+            (&widgets.recapture_button, "Capture again", "Ctrl+R"),
+            (
+                &widgets.previous_image_button,
+                "Previous image",
+                "Ctrl+Page Up",
+            ),
+            (&widgets.next_image_button, "Next image", "Ctrl+Page Down"),
+        ] {
+            shortcut_tooltip(button, name, move |_| Some(key.to_string()));
+        }
+        for zoom in ZOOM_PRESETS {
+            let button = gtk::Button::with_label(&format!("{:.0}%", zoom * 100.0));
+            button.add_css_class("flat");
+            let (sender, popover) = (sender.clone(), widgets.zoom_popover.clone());
+            button.connect_clicked(move |_| {
+                popover.popdown();
+                sender.output_sender().emit(ToolbarEvent::Zoom(zoom));
+            });
+            widgets.zoom_presets_box.append(&button);
         }
 
         // Set initial active button correctly
@@ -387,61 +783,86 @@ pub enum ColorButtons {
 }
 
 impl StyleToolbar {
-    fn show_color_dialog(&self, sender: ComponentSender<StyleToolbar>, root: Option<Window>) {
-        let current_color: RGBA = self.custom_color.into();
-        relm4::spawn_local(async move {
-            let mut builder = ColorChooserDialog::builder()
-                .modal(true)
-                .title("Choose Color")
-                .hide_on_close(true)
-                .rgba(&current_color);
+    fn emit_font(&self, sender: &ComponentSender<StyleToolbar>) {
+        sender
+            .output_sender()
+            .emit(ToolbarEvent::FontSelected(self.font));
+    }
 
+    fn show_color_dialog(&mut self, sender: ComponentSender<StyleToolbar>, root: Option<Window>) {
+        if self.color_picker.is_none() {
+            let mut builder = ColorPicker::builder();
             if let Some(w) = root {
                 builder = builder.transient_for(&w);
             }
 
-            // build dialog and configure further
-            let dialog = builder.build();
-            dialog.set_use_alpha(true);
-
-            let custom_colors = APP_CONFIG
-                .read()
-                .color_palette()
-                .custom()
-                .iter()
-                .copied()
-                .map(RGBA::from)
-                .collect::<Vec<_>>();
-
-            if !custom_colors.is_empty() {
-                dialog.add_palette(
-                    gtk::Orientation::Horizontal,
-                    8, // A more or less arbitrary, but reasonable value.
-                    &custom_colors,
-                );
-            }
+            let mut controller =
+                builder
+                    .launch(())
+                    .forward(sender.input_sender(), |output| match output {
+                        ColorPickerOutput::Selected(color) => {
+                            StyleToolbarInput::ColorDialogFinished(Some(color))
+                        }
+                        ColorPickerOutput::PickFromImage => StyleToolbarInput::PickColor,
+                    });
 
-            // set callback for result
-            let dialog_copy = dialog.clone();
-            dialog.connect_response(move |_, r| {
-                if r == ResponseType::Ok {
-                    dialog_copy.hide();
-                    let color = Color::from_gdk(dialog_copy.rgba());
-                    sender.input(StyleToolbarInput::ColorDialogFinished(Some(color)));
-                } else if r == ResponseType::Cancel || r == ResponseType::Close {
-                    dialog_copy.hide();
-                }
-            });
+            controller.detach_runtime();
+            self.color_picker = Some(controller);
+        }
 
-            dialog.show();
-        });
+        let ctrl = self.color_picker.as_ref().unwrap();
+        ctrl.emit(ColorPickerInput::Show(self.custom_color));
     }
 
-    fn map_button_to_color(&self, button: ColorButtons) -> Color {
+    /// `None` for number keys beyond the selected palette
+    fn map_button_to_color(&self, button: ColorButtons) -> Option<Color> {
         let config = APP_CONFIG.read();
         match button {
-            ColorButtons::Palette(n) => config.color_palette().palette()[n as usize],
-            ColorButtons::Custom => self.custom_color,
+            ColorButtons::Palette(n) => config.color_palette().palettes()[self.palette]
+                .colors()
+                .get(n as usize)
+                .copied(),
+            ColorButtons::Custom => Some(self.custom_color),
+        }
+    }
+
+    /// Activate the button of `color`, the custom color button takes colors not in the palette
+    fn show_color(&mut self, color: Color) {
+        let palette_index = APP_CONFIG.read().color_palette().palettes()[self.palette]
+            .colors()
+            .iter()
+            .position(|&c| c == color);
+        let button = match palette_index {
+            Some(i) => ColorButtons::Palette(i as u64),
+            None => {
+                self.custom_color = color;
+                self.custom_color_pixbuf = create_icon_pixbuf(color);
+                ColorButtons::Custom
+            }
+        };
+        // changing the state doesn't activate the actions, nothing is sent back
+        self.color_action.change_state(&button.to_variant());
+    }
+
+    /// Fill the palette box with the buttons of the selected palette
+    fn show_palette_buttons(&self) {
+        while let Some(child) = self.palette_box.first_child() {
+            self.palette_box.remove(&child);
+        }
+        let config = APP_CONFIG.read();
+        let palette = &config.color_palette().palettes()[self.palette];
+        for (i, &color) in palette.colors().iter().enumerate() {
+            let btn = gtk::ToggleButton::builder()
+                .focusable(false)
+                .hexpand(false)
+                .child(&create_icon(color))
+                .build();
+            match palette.color_name(i) {
+                Some(name) => btn.set_tooltip_text(Some(name)),
+                None => btn.set_tooltip_text(Some(&color.to_string())),
+            }
+            btn.set_action::<ColorAction>(ColorButtons::Palette(i as u64));
+            self.palette_box.append(&btn);
         }
     }
 
@@ -492,10 +913,29 @@ impl Component for StyleToolbar {
             #[watch]
             set_visible: model.visible,
 
+            #[local_ref]
+            palette_box -> gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 2,
+                set_widget_name: "palette",
+            },
+            #[local_ref]
+            palette_dropdown -> gtk::DropDown {
+                set_focusable: false,
+                set_hexpand: false,
+                set_visible: APP_CONFIG.read().color_palette().palettes().len() > 1,
+
+                set_widget_name: "palette-selector",
+                set_tooltip: "Color palette",
+                connect_selected_notify[sender] => move |dropdown| {
+                    sender.input(StyleToolbarInput::PaletteSelected(dropdown.selected() as usize));
+                },
+            },
             gtk::Separator {},
             gtk::ToggleButton {
                 set_focusable: false,
                 set_hexpand: false,
+                set_widget_name: "custom-color",
 
                 gtk::Image::from_pixbuf(Some(&model.custom_color_pixbuf)) {
                     #[watch]
@@ -508,6 +948,7 @@ impl Component for StyleToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "color-picker",
                 set_icon_name: "color-regular",
                 set_tooltip: "Pick custom color",
 
@@ -518,6 +959,7 @@ impl Component for StyleToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "size-small",
                 set_label: "S",
                 set_tooltip: "Small size",
                 ActionablePlus::set_action::<SizeAction>: Size::Small,
@@ -526,6 +968,7 @@ impl Component for StyleToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "size-medium",
                 set_label: "M",
                 set_tooltip: "Medium size",
                 ActionablePlus::set_action::<SizeAction>: Size::Medium,
@@ -534,45 +977,180 @@ impl Component for StyleToolbar {
                 set_focusable: false,
                 set_hexpand: false,
 
+                set_widget_name: "size-large",
                 set_label: "L",
                 set_tooltip: "Large size",
                 ActionablePlus::set_action::<SizeAction>: Size::Large,
             },
-            gtk::Label {
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 2,
+                set_widget_name: "annotation-size",
+
+                gtk::Label {
+                    set_focusable: false,
+                    set_hexpand: false,
+
+                    set_text: "x",
+                },
+                gtk::Button {
+                    set_focusable: false,
+                    set_hexpand: false,
+
+                    #[watch]
+                    set_label: &model.annotation_size_formatted,
+                    set_tooltip: "Edit Annotation Size Factor",
+
+                    connect_clicked => StyleToolbarInput::ShowAnnotationDialog
+                },
+            },
+            gtk::MenuButton {
                 set_focusable: false,
                 set_hexpand: false,
-
-                set_text: "x",
+                set_direction: gtk::ArrowType::Up,
+
+                set_widget_name: "font",
+                set_icon_name: "text-font-regular",
+                set_tooltip: "Text font",
+
+                #[wrap(Some)]
+                set_popover = &gtk::Popover {
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 2,
+
+                        #[name = "font_button"]
+                        gtk::FontButton {
+                            set_level: gtk::FontChooserLevel::FAMILY,
+                            set_use_font: true,
+                            set_tooltip: "Font family",
+                            connect_font_set[sender] => move |button| {
+                                if let Some(family) = button.font_desc().and_then(|d| d.family()) {
+                                    sender.input(StyleToolbarInput::FontFamilySelected(
+                                        TextFont::intern_family(&family),
+                                    ));
+                                }
+                            },
+                        },
+                        gtk::ToggleButton {
+                            set_icon_name: "text-bold-regular",
+                            set_tooltip: "Bold",
+                            connect_toggled[sender] => move |button| {
+                                sender.input(StyleToolbarInput::FontBoldToggled(button.is_active()));
+                            },
+                        },
+                        gtk::ToggleButton {
+                            set_icon_name: "text-italic-regular",
+                            set_tooltip: "Italic",
+                            connect_toggled[sender] => move |button| {
+                                sender.input(StyleToolbarInput::FontItalicToggled(button.is_active()));
+                            },
+                        },
+                        #[name = "align_left"]
+                        gtk::ToggleButton {
+                            set_icon_name: "text-align-left-regular",
+                            set_tooltip: "Align left",
+                            set_active: true,
+                            connect_toggled[sender] => move |button| {
+                                if button.is_active() {
+                                    sender.output_sender().emit(ToolbarEvent::TextAlignSelected(TextAlign::Left));
+                                }
+                            },
+                        },
+                        gtk::ToggleButton {
+                            set_icon_name: "text-align-center-regular",
+                            set_tooltip: "Align center",
+                            set_group: Some(&align_left),
+                            connect_toggled[sender] => move |button| {
+                                if button.is_active() {
+                                    sender.output_sender().emit(ToolbarEvent::TextAlignSelected(TextAlign::Center));
+                                }
+                            },
+                        },
+                        gtk::ToggleButton {
+                            set_icon_name: "text-align-right-regular",
+                            set_tooltip: "Align right",
+                            set_group: Some(&align_left),
+                            connect_toggled[sender] => move |button| {
+                                if button.is_active() {
+                                    sender.output_sender().emit(ToolbarEvent::TextAlignSelected(TextAlign::Right));
+                                }
+                            },
+                        },
+                        gtk::SpinButton {
+                            set_range: (0.0, 1000.0),
+                            set_increments: (1.0, 10.0),
+                            set_value: model.font.size.unwrap_or(0.0) as f64,
+                            set_tooltip: "Text size in pixels, 0 follows the size buttons",
+                            connect_value_changed[sender] => move |button| {
+                                sender.input(StyleToolbarInput::FontSizeChanged(button.value() as f32));
+                            },
+                        },
+                    },
+                },
             },
+            gtk::Separator {},
             gtk::Button {
                 set_focusable: false,
                 set_hexpand: false,
 
                 #[watch]
-                set_label: &model.annotation_size_formatted,
-                set_tooltip: "Edit Annotation Size Factor",
+                set_icon_name: if model.fill {
+                    "paint-bucket-filled"
+                } else {
+                    "paint-bucket-regular"
+                },
+                set_widget_name: "fill",
+                set_tooltip: "Fill shape, text gets a background",
+                connect_clicked => StyleToolbarInput::ToggleFill,
+            },
+            gtk::ToggleButton {
+                set_focusable: false,
+                set_hexpand: false,
 
-                connect_clicked => StyleToolbarInput::ShowAnnotationDialog
+                set_widget_name: "shadow",
+                set_icon_name: "layer-diagonal-regular",
+                set_tooltip: "Drop shadow",
+                set_active: APP_CONFIG.read().default_shadow(),
+                connect_toggled[sender] => move |_| {
+                    sender.output_sender().emit(ToolbarEvent::ToggleShadow);
+                },
+            },
+            gtk::ToggleButton {
+                set_focusable: false,
+                set_hexpand: false,
+
+                set_widget_name: "outline",
+                set_icon_name: "border-all-regular",
+                set_tooltip: "Contrasting outline",
+                set_active: APP_CONFIG.read().default_outline(),
+                connect_toggled[sender] => move |_| {
+                    sender.output_sender().emit(ToolbarEvent::ToggleOutline);
+                },
             },
-            gtk::Separator {},
             gtk::Button {
                 set_focusable: false,
                 set_hexpand: false,
 
-                set_icon_name: if APP_CONFIG.read().default_fill_shapes() {
-                    "paint-bucket-filled"
-                } else {
-                    "paint-bucket-regular"
+                set_widget_name: "recolor",
+                set_icon_name: "color-fill-regular",
+                set_tooltip: "Replace a color of all annotations",
+                connect_clicked[sender] => move |_| {
+                    sender.output_sender().emit(ToolbarEvent::Recolor);
                 },
-                set_tooltip: "Fill shape",
-                connect_clicked[sender] => move |button| {
-                    sender.output_sender().emit(ToolbarEvent::ToggleFill);
-                    let new_icon = if button.icon_name() == Some("paint-bucket-regular".into()) {
-                        "paint-bucket-filled"
-                    } else {
-                        "paint-bucket-regular"
-                    };
-                    button.set_icon_name(new_icon);
+            },
+            #[local_ref]
+            preset_dropdown -> gtk::DropDown {
+                set_focusable: false,
+                set_hexpand: false,
+                set_visible: !APP_CONFIG.read().presets().is_empty(),
+
+                set_widget_name: "presets",
+                set_tooltip: "Tool presets",
+                connect_selected_notify[sender] => move |dropdown| {
+                    // the first entry applies no preset
+                    let preset = (dropdown.selected() as usize).checked_sub(1);
+                    sender.input(StyleToolbarInput::PresetSelected(preset));
                 },
             },
         },
@@ -598,12 +1176,36 @@ impl Component for StyleToolbar {
                         .emit(ToolbarEvent::ColorSelected(color));
                 }
             }
+            StyleToolbarInput::PickColor => {
+                sender.output_sender().emit(ToolbarEvent::PickColor);
+            }
+            StyleToolbarInput::ColorPicked(color) => {
+                if let Some(ctrl) = &self.color_picker {
+                    ctrl.emit(ColorPickerInput::Picked(color));
+                }
+            }
             StyleToolbarInput::ColorButtonSelected(button) => {
-                let color = self.map_button_to_color(button);
-                self.color_action.change_state(&button.to_variant());
-                sender
-                    .output_sender()
-                    .emit(ToolbarEvent::ColorSelected(color));
+                if let Some(color) = self.map_button_to_color(button) {
+                    self.color_action.change_state(&button.to_variant());
+                    sender
+                        .output_sender()
+                        .emit(ToolbarEvent::ColorSelected(color));
+                }
+            }
+            StyleToolbarInput::PaletteSelected(index) => {
+                if index != self.palette {
+                    // the color in use stays, only the buttons change
+                    let color = self
+                        .color_action
+                        .state()
+                        .and_then(|state| ColorButtons::from_variant(&state))
+                        .and_then(|button| self.map_button_to_color(button));
+                    self.palette = index;
+                    self.show_palette_buttons();
+                    if let Some(color) = color {
+                        self.show_color(color);
+                    }
+                }
             }
 
             StyleToolbarInput::ShowAnnotationDialog => {
@@ -621,6 +1223,48 @@ impl Component for StyleToolbar {
                 }
             }
 
+            StyleToolbarInput::FontFamilySelected(family) => {
+                self.font.family = Some(family);
+                self.emit_font(&sender);
+            }
+            StyleToolbarInput::FontBoldToggled(bold) => {
+                self.font.bold = bold;
+                self.emit_font(&sender);
+            }
+            StyleToolbarInput::FontItalicToggled(italic) => {
+                self.font.italic = italic;
+                self.emit_font(&sender);
+            }
+            StyleToolbarInput::FontSizeChanged(size) => {
+                self.font.size = (size > 0.0).then_some(size);
+                self.emit_font(&sender);
+            }
+
+            StyleToolbarInput::PresetSelected(preset) => {
+                if preset != self.preset {
+                    self.preset = preset;
+                    if let Some(index) = preset {
+                        sender
+                            .output_sender()
+                            .emit(ToolbarEvent::PresetSelected(index));
+                    }
+                }
+            }
+            StyleToolbarInput::ShowPreset(index) => {
+                // selecting the same preset again doesn't send it back
+                self.preset = Some(index);
+                self.preset_dropdown.set_selected(index as u32 + 1);
+            }
+            StyleToolbarInput::ToggleFill => {
+                self.fill = !self.fill;
+                sender.output_sender().emit(ToolbarEvent::ToggleFill);
+            }
+            StyleToolbarInput::ShowToolStyle(tool_style) => {
+                self.show_color(tool_style.color);
+                self.size_action.change_state(&tool_style.size.to_variant());
+                self.fill = tool_style.fill;
+            }
+
             StyleToolbarInput::SetVisibility(visible) => self.visible = visible,
             StyleToolbarInput::ToggleVisibility => {
                 self.visible = !self.visible;
@@ -633,23 +1277,6 @@ impl Component for StyleToolbar {
         root: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        for (i, &color) in APP_CONFIG
-            .read()
-            .color_palette()
-            .palette()
-            .iter()
-            .enumerate()
-            .rev()
-        {
-            let btn = gtk::ToggleButton::builder()
-                .focusable(false)
-                .hexpand(false)
-                .child(&create_icon(color))
-                .build();
-            btn.set_action::<ColorAction>(ColorButtons::Palette(i as u64));
-            root.prepend(&btn);
-        }
-
         // Color Action for selecting colors
         let sender_tmp: ComponentSender<StyleToolbar> = sender.clone();
         let color_action: RelmAction<ColorAction> = RelmAction::new_stateful_with_target_value(
@@ -680,11 +1307,37 @@ impl Component for StyleToolbar {
             .unwrap_or(Color::red());
         let custom_color_pixbuf = create_icon_pixbuf(custom_color);
 
+        let preset_labels: Vec<String> = std::iter::once("No preset".to_string())
+            .chain(
+                APP_CONFIG
+                    .read()
+                    .presets()
+                    .iter()
+                    .map(|p| p.name().to_string()),
+            )
+            .collect();
+        let preset_labels: Vec<&str> = preset_labels.iter().map(String::as_str).collect();
+        let palette_labels: Vec<String> = APP_CONFIG
+            .read()
+            .color_palette()
+            .palettes()
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect();
+        let palette_labels: Vec<&str> = palette_labels.iter().map(String::as_str).collect();
+
         // create model
         let model = StyleToolbar {
             custom_color,
             custom_color_pixbuf,
             color_action: SimpleAction::from(color_action.clone()),
+            size_action: SimpleAction::from(size_action.clone()),
+            fill: APP_CONFIG.read().default_fill_shapes(),
+            preset_dropdown: gtk::DropDown::from_strings(&preset_labels),
+            preset: None,
+            palette_dropdown: gtk::DropDown::from_strings(&palette_labels),
+            palette: 0,
+            palette_box: gtk::Box::default(),
             visible: !APP_CONFIG.read().default_hide_toolbars(),
             annotation_size: APP_CONFIG.read().annotation_size_factor(),
             annotation_size_formatted: format!(
@@ -692,10 +1345,23 @@ impl Component for StyleToolbar {
                 APP_CONFIG.read().annotation_size_factor()
             ),
             annotation_dialog_controller: None,
+            color_picker: None,
+            font: TextFont::default(),
         };
 
         // create widgets
+        let preset_dropdown = &model.preset_dropdown;
+        let palette_dropdown = &model.palette_dropdown;
+        let palette_box = &model.palette_box;
         let widgets = view_output!();
+        if let Some(family) = APP_CONFIG.read().font().family() {
+            widgets.font_button.set_font(family);
+        }
+        model.show_palette_buttons();
+        if let Some(items) = APP_CONFIG.read().toolbars().bottom() {
+            arrange_toolbar(&widgets.root, items);
+        }
+        make_floating(&widgets.root, "bottom");
 
         let mut group = RelmActionGroup::<StyleToolbarActionGroup>::new();
         group.add_action(color_action);
@@ -726,6 +1392,12 @@ impl Clone for ColorAction {
 
 relm4::new_stateful_action!(SizeAction, StyleToolbarActionGroup, "sizes", Size, Size);
 
+impl Clone for SizeAction {
+    fn clone(&self) -> Self {
+        Self {}
+    }
+}
+
 impl StaticVariantType for ColorButtons {
     fn static_variant_type() -> Cow<'static, VariantTy> {
         Cow::Borrowed(VariantTy::UINT64)
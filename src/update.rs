@@ -0,0 +1,75 @@
+use std::{fs, time::SystemTime};
+
+use anyhow::{anyhow, Result};
+use xdg::BaseDirectories;
+
+use crate::upload::curl;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/n0kkster/sattyd/releases/latest";
+
+/// records when the last automatic check happened
+const STATE_FILE: &str = "update-check";
+
+/// automatic checks happen at most once per day
+const CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub version: String,
+    /// page with the release notes
+    pub url: String,
+}
+
+/// Whether the automatic check is due, a due check counts as done from now on
+pub fn due() -> bool {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let dirs = BaseDirectories::with_prefix(env!("CARGO_PKG_NAME"));
+    let last = dirs
+        .get_state_file(STATE_FILE)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| content.trim().parse::<u64>().ok());
+    if last.is_some_and(|last| now.saturating_sub(last) < CHECK_INTERVAL_SECS) {
+        return false;
+    }
+    if let Ok(path) = dirs.place_state_file(STATE_FILE) {
+        let _ = fs::write(path, now.to_string());
+    }
+    true
+}
+
+/// The latest release, `None` if it is not newer than this build
+pub fn check() -> Result<Option<Release>> {
+    let response = curl(&format!(
+        "url = \"{LATEST_RELEASE_URL}\"\nheader = \"Accept: application/vnd.github+json\"\n\
+         max-time = 10\n"
+    ))?;
+    let response: serde_json::Value = serde_json::from_slice(&response)?;
+    let (Some(tag), Some(url)) = (response["tag_name"].as_str(), response["html_url"].as_str())
+    else {
+        return Err(match response["message"].as_str() {
+            Some(message) => anyhow!("Update check failed: {message}"),
+            None => anyhow!("Update check failed: unexpected response {response}"),
+        });
+    };
+
+    let version = tag.trim_start_matches('v');
+    Ok(
+        (version_parts(version) > version_parts(env!("CARGO_PKG_VERSION"))).then(|| Release {
+            version: version.to_string(),
+            url: url.to_string(),
+        }),
+    )
+}
+
+/// `1.2.3-rc1` as `[1, 2, 3]`, for comparing versions
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
@@ -0,0 +1,223 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicU32, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::{
+    configuration::{Upload, UploadProvider},
+    secrets,
+};
+
+/// Upload `png_data` as configured in `upload` and return a Markdown image link to it. The
+/// token comes from the environment or the Secret Service. The request is made by `curl`,
+/// the token is passed on its stdin to keep it out of the process list.
+pub fn upload(upload: &Upload, png_data: &[u8]) -> Result<String> {
+    let token = match std::env::var(upload.token_env()) {
+        Ok(token) => token,
+        Err(_) => secrets::lookup(upload.token_service())?.with_context(|| {
+            format!(
+                "No access token in ${} or stored with `satty auth set {}`",
+                upload.token_env(),
+                upload.token_service()
+            )
+        })?,
+    };
+    let token = token.trim();
+    // it ends up in a header, a line break would start another one
+    if token.chars().any(char::is_control) {
+        return Err(anyhow!("The access token contains control characters"));
+    }
+    let name = chrono::Local::now().format(upload.path()).to_string();
+    let file_name = Path::new(&name)
+        .file_name()
+        .map_or(name.clone(), |n| n.to_string_lossy().into_owned());
+
+    // curl reads the request body from a file, its stdin takes the config with the token
+    let body = TempFile::create(png_data)?;
+    let response = match upload.provider() {
+        UploadProvider::GitLab => curl(&format!(
+            "url = {}\nheader = {}\nform = {}\n",
+            quote(&format!(
+                "{}/api/v4/projects/{}/uploads",
+                upload.url(),
+                percent_encode(upload.project())
+            )),
+            quote(&format!("PRIVATE-TOKEN: {token}")),
+            quote(&format!(
+                "file=@{};filename={};type=image/png",
+                form_quote(&body.0.to_string_lossy()),
+                form_quote(&file_name)
+            )),
+        ))?,
+        UploadProvider::GitHub => {
+            let upload_url = github_upload_url(upload, token)?;
+            curl(&format!(
+                "url = {}\nheader = {}\nheader = {}\n\
+                 header = \"Content-Type: image/png\"\ndata-binary = {}\n",
+                quote(&format!("{upload_url}?name={}", percent_encode(&file_name))),
+                quote(&format!("Authorization: Bearer {token}")),
+                quote("Accept: application/vnd.github+json"),
+                quote(&format!("@{}", body.0.to_string_lossy())),
+            ))?
+        }
+    };
+    let response: serde_json::Value = serde_json::from_slice(&response)?;
+
+    match upload.provider() {
+        // relative to the project, as pasted by GitLab itself
+        UploadProvider::GitLab => response["markdown"].as_str().map(str::to_string),
+        UploadProvider::GitHub => response["browser_download_url"]
+            .as_str()
+            .map(|url| format!("![{file_name}]({url})")),
+    }
+    .ok_or_else(|| upload_error(&response))
+}
+
+/// Where assets of the configured GitHub release are uploaded to
+fn github_upload_url(upload: &Upload, token: &str) -> Result<String> {
+    let response = curl(&format!(
+        "url = {}\nheader = {}\nheader = {}\n",
+        quote(&format!(
+            "{}/repos/{}/releases/tags/{}",
+            upload.url(),
+            upload.project(),
+            percent_encode(upload.release())
+        )),
+        quote(&format!("Authorization: Bearer {token}")),
+        quote("Accept: application/vnd.github+json"),
+    ))?;
+    let response: serde_json::Value = serde_json::from_slice(&response)?;
+    // a URI template like ".../assets{?name,label}"
+    response["upload_url"]
+        .as_str()
+        .map(|url| url.split('{').next().unwrap_or(url).to_string())
+        .ok_or_else(|| upload_error(&response))
+}
+
+fn upload_error(response: &serde_json::Value) -> anyhow::Error {
+    match response["message"].as_str() {
+        Some(message) => anyhow!("Upload failed: {message}"),
+        None => anyhow!("Upload failed: unexpected response {response}"),
+    }
+}
+
+/// Run `curl` with `config` as its config file, returns the response body
+pub fn curl(config: &str) -> Result<Vec<u8>> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Cannot run curl")?;
+    child.stdin.take().unwrap().write_all(config.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "curl failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// `value` as a quoted string of a curl config file
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// `value` as a quoted part of a curl form field, so `;` and `,` don't end it
+fn form_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `value` as a single segment or query value of a URL
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+/// A file only this user can read, removed again when dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+    /// Write `data` to a new file. Creating it fails rather than following a link or reusing
+    /// a file someone else put there, another name is tried then.
+    fn create(data: &[u8]) -> Result<Self> {
+        // uploads of the daemon run in the same process
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        for _ in 0..16 {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.subsec_nanos());
+            let path = std::env::temp_dir().join(format!(
+                "satty-upload-{}-{}-{nanos:08x}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    let temp = Self(path);
+                    file.write_all(data)?;
+                    return Ok(temp);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e).context("Cannot create a file for the upload"),
+            }
+        }
+        Err(anyhow!("Cannot create a file for the upload"))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_values_are_escaped() {
+        assert_eq!(quote("a\"b\\c\nd"), r#""a\"b\\c\nd""#);
+        assert_eq!(form_quote("x\";y"), r#""x\";y""#);
+        assert_eq!(percent_encode("group/my project"), "group%2Fmy%20project");
+    }
+}